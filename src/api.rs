@@ -0,0 +1,784 @@
+// 局域网 HTTP/WebSocket 远程控制 API（Config.http_api.enabled 开启）
+//
+// HTTP 工作线程负责解析请求和校验令牌，命令通过通道交给 UI 线程执行；UI 线程定时检查播放
+// 状态变化，连同 LibraryEvent 一起交给广播线程，再放入每个 WebSocket 连接各自的发送队列。
+// 每个连接由独立线程收发，回复 Ping/Close，跟不上的客户端直接断开，不影响其他连接。
+//
+// 默认只监听 127.0.0.1，局域网访问需要在配置中把地址改为 0.0.0.0。
+//
+// 令牌通过 `Authorization: Bearer <token>` 或 `?token=<token>`（浏览器 WebSocket）传递。
+
+use gpui::*;
+use serde::Serialize;
+use serde_json::{Value, json};
+use socket2::{Domain, Socket as RawSocket, Type};
+use std::{
+    collections::HashMap,
+    io::{self, Read},
+    net::{TcpListener, ToSocketAddrs},
+    rc::Rc,
+    sync::{
+        Arc, RwLock,
+        mpsc::{self, Receiver, Sender, SyncSender, TryRecvError, TrySendError},
+    },
+    thread,
+    time::Duration,
+};
+use tiny_http::{Header, Method, ReadWrite, Request, Response, Server};
+use tracing::{info, warn};
+use tungstenite::{Message, WebSocket, handshake::derive_accept_key, protocol::Role};
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    db::{
+        database::DB,
+        dbstate::{LibraryEvent, LibraryState},
+        metadata::AlbumInfo,
+        table::Table,
+    },
+    play::player::Player,
+    remote::PlayerStatus,
+    ui::search,
+};
+
+/// 处理 HTTP 请求的线程数
+const WORKER_THREADS: usize = 4;
+/// UI 线程处理请求、检查播放状态的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// /api/library 默认和最大分页大小
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+/// 请求体上限（字节）
+const MAX_BODY: u64 = 64 * 1024;
+/// 连接读超时：WebSocket 线程按这个间隔在读取客户端帧和发送排队消息之间切换
+const READ_TIMEOUT: Duration = Duration::from_millis(200);
+/// 连接写超时：客户端长时间不读取时断开
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// 每个 WebSocket 连接的发送队列长度，写满说明客户端跟不上
+const CLIENT_QUEUE_LEN: usize = 64;
+
+/// 需要在 UI 线程执行的命令
+#[derive(Debug)]
+enum ApiCommand {
+    Status,
+    /// 播放指定歌曲，None 表示继续播放
+    Play(Option<Uuid>),
+    Pause,
+    Toggle,
+    Next,
+    Previous,
+    /// 跳转到指定位置（秒）
+    Seek(u64),
+    Volume(f32),
+    Queue,
+    Enqueue(Vec<Uuid>),
+    ClearQueue,
+    Library {
+        query: Option<String>,
+        offset: usize,
+        limit: usize,
+    },
+    Favorites,
+    History,
+}
+
+/// 请求失败：HTTP 状态码和错误信息
+#[derive(Debug)]
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(400, message)
+    }
+}
+
+type ApiResult = Result<Value, ApiError>;
+
+/// 等待 UI 线程处理的请求
+struct ApiRequest {
+    command: ApiCommand,
+    reply: Sender<ApiResult>,
+}
+
+/// 歌曲的 JSON 表示（不含封面数据）
+#[derive(Serialize)]
+struct TrackJson {
+    id: Uuid,
+    title: String,
+    artist: String,
+    album: String,
+    duration: u64,
+    genre: Option<String>,
+}
+
+impl From<&AlbumInfo> for TrackJson {
+    fn from(item: &AlbumInfo) -> Self {
+        Self {
+            id: item.id(),
+            title: item.title().to_string(),
+            artist: item.artist().to_string(),
+            album: item.album().to_string(),
+            duration: item.duration(),
+            genre: item.genre().map(|g| g.to_string()),
+        }
+    }
+}
+
+fn tracks_json<'a>(items: impl IntoIterator<Item = &'a AlbumInfo>) -> Value {
+    json!(items.into_iter().map(TrackJson::from).collect::<Vec<_>>())
+}
+
+type Socket = WebSocket<Box<dyn ReadWrite + Send>>;
+
+/// 广播线程的输入
+enum Broadcast {
+    /// 新的 WebSocket 连接的发送队列
+    Connect(SyncSender<String>),
+    Message(String),
+}
+
+/// 运行中的服务使用的令牌，设置页重新生成后立即生效
+type SharedToken = Arc<RwLock<String>>;
+
+struct ApiToken(SharedToken);
+
+impl Global for ApiToken {}
+
+/// API 服务端：HTTP 线程把请求放入通道，由 `init` 在 UI 线程处理
+struct ApiServer {
+    requests: Receiver<ApiRequest>,
+    broadcast: Sender<Broadcast>,
+}
+
+impl ApiServer {
+    fn start(address: &str, token: SharedToken) -> io::Result<Self> {
+        let server =
+            Arc::new(Server::from_listener(bind(address)?, None).map_err(io::Error::other)?);
+        let (request_sender, requests) = mpsc::channel();
+        let (broadcast, broadcast_receiver) = mpsc::channel();
+
+        for i in 0..WORKER_THREADS {
+            let server = server.clone();
+            let token = token.clone();
+            let requests = request_sender.clone();
+            let broadcast = broadcast.clone();
+            thread::Builder::new()
+                .name(format!("zotu-api-{}", i))
+                .spawn(move || {
+                    for request in server.incoming_requests() {
+                        handle_request(request, &token, &requests, &broadcast);
+                    }
+                })?;
+        }
+
+        thread::Builder::new()
+            .name("zotu-api-broadcast".into())
+            .spawn(move || run_broadcast(broadcast_receiver))?;
+
+        Ok(Self {
+            requests,
+            broadcast,
+        })
+    }
+
+    fn pending_requests(&self) -> Vec<ApiRequest> {
+        self.requests.try_iter().collect()
+    }
+
+    fn broadcast(&self, message: Value) {
+        self.broadcast
+            .send(Broadcast::Message(message.to_string()))
+            .ok();
+    }
+}
+
+/// 监听地址，并设置读写超时（由 accept 得到的连接继承），慢速或停滞的客户端不会一直占住线程
+fn bind(address: &str) -> io::Result<TcpListener> {
+    let address = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "无效的监听地址"))?;
+    let socket = RawSocket::new(Domain::for_address(address), Type::STREAM, None)?;
+    socket.set_reuse_address(true)?;
+    socket.set_read_timeout(Some(READ_TIMEOUT))?;
+    socket.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    socket.bind(&address.into())?;
+    socket.listen(128)?;
+    Ok(socket.into())
+}
+
+/// 把消息放入所有 WebSocket 连接的发送队列，队列已满或连接已结束的客户端被移除
+fn run_broadcast(receiver: Receiver<Broadcast>) {
+    let mut clients: Vec<SyncSender<String>> = Vec::new();
+    for broadcast in receiver {
+        match broadcast {
+            Broadcast::Connect(client) => clients.push(client),
+            Broadcast::Message(text) => {
+                clients.retain(|client| match client.try_send(text.clone()) {
+                    Ok(()) => true,
+                    Err(TrySendError::Full(_)) => {
+                        warn!("WebSocket 客户端接收过慢，已断开");
+                        false
+                    }
+                    Err(TrySendError::Disconnected(_)) => false,
+                });
+            }
+        }
+    }
+}
+
+/// 单个 WebSocket 连接：读取客户端帧（自动回复 Ping/Close），在读超时间隙发送排队的消息
+fn run_client(mut socket: Socket, first: String, outgoing: Receiver<String>) {
+    if socket.send(Message::text(first)).is_err() {
+        return;
+    }
+    loop {
+        match socket.read() {
+            // 客户端发来的消息不需要处理，Pong/Close 回复由 tungstenite 排队，下面 flush 时写出
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(_) => return,
+        }
+
+        loop {
+            match outgoing.try_recv() {
+                Ok(text) => {
+                    if socket.send(Message::text(text)).is_err() {
+                        return;
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                // 广播线程已移除该连接（接收过慢或服务停止）
+                Err(TryRecvError::Disconnected) => {
+                    socket.close(None).ok();
+                    socket.flush().ok();
+                    return;
+                }
+            }
+        }
+        if socket.flush().is_err() {
+            return;
+        }
+    }
+}
+
+/// 交给 UI 线程执行并等待结果
+fn dispatch(requests: &Sender<ApiRequest>, command: ApiCommand) -> ApiResult {
+    let (reply, result) = mpsc::channel();
+    requests
+        .send(ApiRequest { command, reply })
+        .map_err(|_| ApiError::new(503, "Zotu 正在退出"))?;
+    result
+        .recv()
+        .unwrap_or_else(|_| Err(ApiError::new(503, "Zotu 正在退出")))
+}
+
+fn handle_request(
+    mut request: Request,
+    token: &RwLock<String>,
+    requests: &Sender<ApiRequest>,
+    broadcast: &Sender<Broadcast>,
+) {
+    let (path, query) = match request.url().split_once('?') {
+        Some((path, query)) => (path.to_string(), parse_query(query)),
+        None => (request.url().to_string(), HashMap::new()),
+    };
+
+    // CORS 预检不带令牌
+    if *request.method() == Method::Options {
+        respond(request, Response::empty(204));
+        return;
+    }
+
+    let authorized = {
+        let token = token.read().unwrap_or_else(|e| e.into_inner());
+        authorized(&request, &query, &token)
+    };
+    if !authorized {
+        respond_json(request, Err(ApiError::new(401, "令牌无效")));
+        return;
+    }
+
+    if path == "/api/ws" {
+        upgrade_websocket(request, requests, broadcast);
+        return;
+    }
+
+    let mut body = String::new();
+    if let Err(e) = request
+        .as_reader()
+        .take(MAX_BODY + 1)
+        .read_to_string(&mut body)
+    {
+        respond_json(request, Err(ApiError::bad_request(e.to_string())));
+        return;
+    }
+    if body.len() as u64 > MAX_BODY {
+        respond_json(request, Err(ApiError::new(413, "请求体过大")));
+        return;
+    }
+
+    let result =
+        route(request.method(), &path, &query, &body).and_then(|cmd| dispatch(requests, cmd));
+    respond_json(request, result);
+}
+
+fn authorized(request: &Request, query: &HashMap<String, String>, token: &str) -> bool {
+    let bearer = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer "));
+    bearer
+        .or(query.get("token").map(String::as_str))
+        .is_some_and(|given| {
+            !token.is_empty() && constant_time_eq(given.as_bytes(), token.as_bytes())
+        })
+}
+
+/// 比较耗时与内容无关，避免通过响应时间逐字节猜出令牌
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y));
+    std::hint::black_box(diff) == 0
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    url::form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect()
+}
+
+/// 解析路由和参数
+fn route(
+    method: &Method,
+    path: &str,
+    query: &HashMap<String, String>,
+    body: &str,
+) -> Result<ApiCommand, ApiError> {
+    let body: Value = if body.trim().is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_str(body).map_err(|e| ApiError::bad_request(e.to_string()))?
+    };
+
+    let command = match (method, path) {
+        (Method::Get, "/api/status") => ApiCommand::Status,
+        (Method::Post, "/api/play") => ApiCommand::Play(match body.get("id") {
+            Some(id) => Some(parse_uuid(id)?),
+            None => None,
+        }),
+        (Method::Post, "/api/pause") => ApiCommand::Pause,
+        (Method::Post, "/api/toggle") => ApiCommand::Toggle,
+        (Method::Post, "/api/next") => ApiCommand::Next,
+        (Method::Post, "/api/previous") => ApiCommand::Previous,
+        (Method::Post, "/api/seek") => ApiCommand::Seek(
+            body.get("position")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| ApiError::bad_request("缺少 position（秒）"))?,
+        ),
+        (Method::Post, "/api/volume") => ApiCommand::Volume(
+            body.get("volume")
+                .and_then(Value::as_f64)
+                .ok_or_else(|| ApiError::bad_request("缺少 volume（0.0 ~ 1.0）"))?
+                as f32,
+        ),
+        (Method::Get, "/api/queue") => ApiCommand::Queue,
+        (Method::Post, "/api/queue") => ApiCommand::Enqueue(
+            body.get("ids")
+                .and_then(Value::as_array)
+                .ok_or_else(|| ApiError::bad_request("缺少 ids"))?
+                .iter()
+                .map(parse_uuid)
+                .collect::<Result<_, _>>()?,
+        ),
+        (Method::Delete, "/api/queue") => ApiCommand::ClearQueue,
+        (Method::Get, "/api/library") => ApiCommand::Library {
+            query: query
+                .get("q")
+                .map(|q| q.trim().to_lowercase())
+                .filter(|q| !q.is_empty()),
+            offset: parse_number(query, "offset", 0)?,
+            limit: parse_number(query, "limit", DEFAULT_PAGE_SIZE)?.min(MAX_PAGE_SIZE),
+        },
+        (Method::Get, "/api/favorites") => ApiCommand::Favorites,
+        (Method::Get, "/api/history") => ApiCommand::History,
+        _ => return Err(ApiError::new(404, format!("未知接口: {} {}", method, path))),
+    };
+    Ok(command)
+}
+
+fn parse_uuid(value: &Value) -> Result<Uuid, ApiError> {
+    value
+        .as_str()
+        .and_then(|s| Uuid::parse_str(s).ok())
+        .ok_or_else(|| ApiError::bad_request(format!("无效的歌曲 ID: {}", value)))
+}
+
+fn parse_number(
+    query: &HashMap<String, String>,
+    key: &str,
+    default: usize,
+) -> Result<usize, ApiError> {
+    match query.get(key) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| ApiError::bad_request(format!("无效的 {}: {}", key, value))),
+        None => Ok(default),
+    }
+}
+
+fn upgrade_websocket(
+    request: Request,
+    requests: &Sender<ApiRequest>,
+    broadcast: &Sender<Broadcast>,
+) {
+    let key = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Sec-WebSocket-Key"))
+        .map(|h| derive_accept_key(h.value.as_bytes()));
+    let Some(accept) = key else {
+        respond_json(request, Err(ApiError::bad_request("不是 WebSocket 请求")));
+        return;
+    };
+
+    // 首条消息是当前播放状态
+    let first = match dispatch(requests, ApiCommand::Status) {
+        Ok(status) => json!({ "type": "status", "status": status }).to_string(),
+        Err(e) => {
+            respond_json(request, Err(e));
+            return;
+        }
+    };
+
+    // Upgrade / Connection 由 tiny_http 添加
+    let response = Response::empty(101).with_header(header("Sec-WebSocket-Accept", &accept));
+    let stream = request.upgrade("websocket", response);
+    let socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+    let (client, outgoing) = mpsc::sync_channel(CLIENT_QUEUE_LEN);
+    let spawned = thread::Builder::new()
+        .name("zotu-api-ws".into())
+        .spawn(move || run_client(socket, first, outgoing));
+    match spawned {
+        Ok(_) => {
+            broadcast.send(Broadcast::Connect(client)).ok();
+        }
+        Err(e) => warn!("创建 WebSocket 线程失败: {}", e),
+    }
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).expect("header 只包含 ASCII")
+}
+
+fn respond<R: Read>(request: Request, response: Response<R>) {
+    let response = response
+        .with_header(header("Access-Control-Allow-Origin", "*"))
+        .with_header(header(
+            "Access-Control-Allow-Headers",
+            "Authorization, Content-Type",
+        ))
+        .with_header(header(
+            "Access-Control-Allow-Methods",
+            "GET, POST, DELETE, OPTIONS",
+        ));
+    if let Err(e) = request.respond(response) {
+        warn!("发送 API 响应失败: {}", e);
+    }
+}
+
+fn respond_json(request: Request, result: ApiResult) {
+    let (status, body) = match result {
+        Ok(value) => (200, value),
+        Err(e) => (e.status, json!({ "error": e.message })),
+    };
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json; charset=utf-8"));
+    respond(request, response);
+}
+
+/// 在 UI 线程执行命令；播放控制类命令返回执行后的状态
+fn execute(command: ApiCommand, cx: &mut App) -> ApiResult {
+    let db = cx.global::<DB>();
+    match command {
+        ApiCommand::Queue => return Ok(tracks_json(cx.global::<Player>().queue())),
+        ApiCommand::Library {
+            query,
+            offset,
+            limit,
+        } => {
+            let items = match query {
+                Some(query) => db
+                    .load_all_albums()
+                    .into_iter()
+                    .filter(|item| {
+                        search::matches(&item.title(), &query)
+                            || search::matches(&item.artist(), &query)
+                            || search::matches(&item.album(), &query)
+                    })
+                    .skip(offset)
+                    .take(limit)
+                    .collect(),
+                None => db
+                    .load_albums_paginated(offset as i64, limit as i64)
+                    .map_err(|e| ApiError::new(500, e.to_string()))?,
+            };
+            return Ok(tracks_json(&items));
+        }
+        ApiCommand::Favorites | ApiCommand::History => {
+            let table = match command {
+                ApiCommand::Favorites => Table::Favorite,
+                _ => Table::History,
+            };
+            let items: Vec<AlbumInfo> = db
+                .get_all_uuids(table)
+                .iter()
+                .filter_map(|id| db.load_album_by_uuid(id).ok().flatten())
+                .collect();
+            return Ok(tracks_json(&items));
+        }
+        ApiCommand::Play(Some(id)) => {
+            let item = db
+                .load_album_by_uuid(&id)
+                .map_err(|e| ApiError::new(500, e.to_string()))?
+                .ok_or_else(|| ApiError::new(404, format!("歌曲不存在: {}", id)))?;
+            cx.global_mut::<Player>().play_track(&item);
+        }
+        ApiCommand::Enqueue(ids) => {
+            let mut items = Vec::with_capacity(ids.len());
+            for id in ids {
+                match db.load_album_by_uuid(&id) {
+                    Ok(Some(item)) => items.push(item),
+                    Ok(None) => return Err(ApiError::new(404, format!("歌曲不存在: {}", id))),
+                    Err(e) => return Err(ApiError::new(500, e.to_string())),
+                }
+            }
+            cx.global_mut::<Player>().enqueue(items);
+        }
+        ApiCommand::Volume(volume) => {
+            let volume = cx.update_global::<Player, _>(|player, _cx| {
+                player.set_volume(volume);
+                player.volume()
+            });
+            cx.global_mut::<Config>().play_info.volume = volume;
+        }
+        command => cx.update_global::<Player, _>(|player, _cx| match command {
+            ApiCommand::Play(None) if player.is_paused() => player.toggle_play(),
+            ApiCommand::Pause if player.is_playing() => player.toggle_play(),
+            ApiCommand::Toggle => player.toggle_play(),
+            ApiCommand::Next => player.next(),
+            ApiCommand::Previous => player.previous(),
+            ApiCommand::Seek(position) => player.seek(position),
+            ApiCommand::ClearQueue => player.clear_queue(),
+            _ => {}
+        }),
+    }
+    serde_json::to_value(PlayerStatus::from_app(cx)).map_err(|e| ApiError::new(500, e.to_string()))
+}
+
+/// 不含播放进度的状态，用于判断是否需要推送
+fn status_key(status: &PlayerStatus) -> Value {
+    let mut value = serde_json::to_value(status).unwrap_or_default();
+    if let Some(map) = value.as_object_mut() {
+        map.remove("elapsed");
+    }
+    value
+}
+
+fn library_event_json(event: &LibraryEvent) -> Value {
+    let (name, id) = match event {
+        LibraryEvent::FavoriteAdded(id) => ("favorite_added", Some(id)),
+        LibraryEvent::FavoriteRemoved(id) => ("favorite_removed", Some(id)),
+        LibraryEvent::HistoryAdded(id) => ("history_added", Some(id)),
+        LibraryEvent::RatingsChanged => ("ratings_changed", None),
+        LibraryEvent::LibraryUpdated => ("library_updated", None),
+        LibraryEvent::PlayabilityChanged => ("playability_changed", None),
+        LibraryEvent::PlaysRecorded => ("plays_recorded", None),
+    };
+    json!({ "type": "library", "event": name, "id": id })
+}
+
+/// 重新生成访问令牌并立即保存配置，运行中的服务随即只接受新令牌
+pub fn regenerate_token(cx: &mut App) {
+    let token = Uuid::new_v4().simple().to_string();
+    let config = cx.global_mut::<Config>();
+    config.http_api.token = token.clone();
+    if let Err(e) = config.persist() {
+        warn!("保存 API 令牌失败: {}", e);
+    }
+    if let Some(ApiToken(shared)) = cx.try_global::<ApiToken>() {
+        *shared.write().unwrap_or_else(|e| e.into_inner()) = token;
+    }
+}
+
+/// 按配置启动 API 服务（未开启时什么也不做），令牌为空时自动生成并写入配置
+pub fn init(library_state: &Entity<LibraryState>, cx: &mut App) {
+    let config = &cx.global::<Config>().http_api;
+    if !config.enabled {
+        return;
+    }
+    if config.token.is_empty() {
+        regenerate_token(cx);
+    }
+    let config = &cx.global::<Config>().http_api;
+    let address = config.address.clone();
+    let token = Arc::new(RwLock::new(config.token.clone()));
+
+    let server = match ApiServer::start(&address, token.clone()) {
+        Ok(server) => Rc::new(server),
+        Err(e) => {
+            warn!("远程控制 API 启动失败 ({}): {}", address, e);
+            return;
+        }
+    };
+    info!("远程控制 API 已启动: http://{}", address);
+    cx.set_global(ApiToken(token));
+
+    let library_server = server.clone();
+    cx.subscribe(library_state, move |_state, event: &LibraryEvent, _cx| {
+        library_server.broadcast(library_event_json(event));
+    })
+    .detach();
+
+    cx.spawn(async move |cx: &mut AsyncApp| {
+        let mut last_status = None;
+        loop {
+            cx.background_executor().timer(POLL_INTERVAL).await;
+
+            let result = cx.update(|cx: &mut App| {
+                let requests = server.pending_requests();
+                let handled = !requests.is_empty();
+                for request in requests {
+                    request.reply.send(execute(request.command, cx)).ok();
+                }
+                // 外部控制后刷新界面（播放栏只在播放中自动刷新）
+                if handled {
+                    cx.refresh_windows();
+                }
+                PlayerStatus::from_app(cx)
+            });
+            let Ok(status) = result else {
+                break;
+            };
+
+            let key = status_key(&status);
+            if last_status.as_ref() != Some(&key) {
+                if last_status.is_some() {
+                    server.broadcast(json!({ "type": "status", "status": status }));
+                }
+                last_status = Some(key);
+            }
+        }
+    })
+    .detach();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Broadcast, CLIENT_QUEUE_LEN, ReadWrite, Role, Socket, WebSocket, bind, run_broadcast,
+        run_client,
+    };
+    use std::{
+        net::{SocketAddr, TcpListener, TcpStream},
+        sync::mpsc,
+        thread,
+        time::{Duration, Instant},
+    };
+    use tungstenite::{Message, client::client};
+
+    /// 建立一条 WebSocket 连接：返回（服务端 socket, 客户端 socket）
+    fn pair(listener: &TcpListener, addr: SocketAddr) -> (Socket, WebSocket<TcpStream>) {
+        let stream = TcpStream::connect(addr).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        let server_side = thread::spawn(move || {
+            let stream: Box<dyn ReadWrite + Send> = Box::new(server);
+            tungstenite::accept(stream).unwrap()
+        });
+        let (client, _) = client(format!("ws://{}/api/ws", addr), stream).unwrap();
+        let server = server_side.join().unwrap();
+        let server = WebSocket::from_raw_socket(server.into_inner(), Role::Server, None);
+        (server, client)
+    }
+
+    fn connect(broadcast: &mpsc::Sender<Broadcast>, socket: Socket) -> thread::JoinHandle<()> {
+        let (client, outgoing) = mpsc::sync_channel(CLIENT_QUEUE_LEN);
+        broadcast.send(Broadcast::Connect(client)).unwrap();
+        thread::spawn(move || run_client(socket, "hello".into(), outgoing))
+    }
+
+    #[test]
+    fn stalled_client_does_not_block_others() {
+        let listener = bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (broadcast, receiver) = mpsc::channel();
+        thread::spawn(move || run_broadcast(receiver));
+
+        // 连上后从不读取
+        let (stalled_server, _stalled) = pair(&listener, addr);
+        connect(&broadcast, stalled_server);
+
+        let (server, mut active) = pair(&listener, addr);
+        connect(&broadcast, server);
+        assert_eq!(active.read().unwrap(), Message::text("hello"));
+
+        // 足够填满停滞连接的 socket 缓冲区和发送队列
+        let payload = "x".repeat(64 * 1024);
+        let batch = CLIENT_QUEUE_LEN / 2;
+        let start = Instant::now();
+        for _ in 0..8 {
+            for _ in 0..batch {
+                broadcast.send(Broadcast::Message(payload.clone())).unwrap();
+            }
+            for _ in 0..batch {
+                let message = active.read().unwrap();
+                assert_eq!(message.into_text().unwrap().len(), payload.len());
+            }
+        }
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn ping_is_answered() {
+        let listener = bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (broadcast, receiver) = mpsc::channel();
+        thread::spawn(move || run_broadcast(receiver));
+
+        let (server, mut client) = pair(&listener, addr);
+        let handle = connect(&broadcast, server);
+        assert_eq!(client.read().unwrap(), Message::text("hello"));
+
+        client
+            .send(Message::Ping(b"alive".to_vec().into()))
+            .unwrap();
+        assert_eq!(
+            client.read().unwrap(),
+            Message::Pong(b"alive".to_vec().into())
+        );
+
+        // 客户端关闭后连接线程结束
+        client.close(None).unwrap();
+        while client.read().is_ok() {}
+        handle.join().unwrap();
+    }
+}
@@ -1,50 +1,136 @@
 use gpui::{prelude::FluentBuilder, *};
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::UNIX_EPOCH};
+use tracing::warn;
 
 use crate::{
     components::{
         now_playing::PlayerDetail,
         playbar::{PlayBar, PlayBarMessage},
-        setting::Setting,
-        sidebar::{SideBar, SidebarItem},
+        setting::{Setting, SettingEvent},
+        sidebar::{SideBar, SidebarAction, SidebarItem},
         songview::{AlbumList, ViewType},
+        stats::StatsView,
         titlebar::TitleBar,
     },
-    db::{database::DB, dbstate::LibraryState, table::Table},
-    play::player::Player,
+    config::Config,
+    db::{
+        database::DB,
+        dbstate::{LibraryEvent, LibraryState},
+        table::Table,
+    },
+    keymap::*,
+    notify::{self, Level},
+    play::player::{PlayEvent, PlayabilityChange, Player},
+    remote::{PlayerStatus, RemoteCommand, RemoteResponse},
     theme::*,
-    ui::search::{ClearSearchEvent, SearchEvent},
+    ui::{
+        notification::NotificationCenter,
+        palette::{CommandPalette, PaletteEvent},
+        search::{ClearSearchEvent, SearchEvent},
+        smart_editor::{SmartPlaylistEditor, SmartPlaylistEvent},
+    },
 };
 
+/// 每次快捷键调整的音量步长
+const VOLUME_STEP: f32 = 0.05;
+/// 每次快捷键快进/快退的秒数
+const SEEK_STEP_SECS: i64 = 5;
+/// 每次快捷键调整的播放速度
+const PLAYBACK_RATE_STEP: f32 = 0.1;
+/// 解码失败通知中最多列出的文件数
+const MAX_LISTED_FAILURES: usize = 5;
+
 // 主应用结构
 pub struct Zotu {
     view_type: SidebarItem,
     song_view: Entity<AlbumList>,
     setting: Entity<Setting>,
+    stats: Entity<StatsView>,
     play_bar: Entity<PlayBar>,
     title_bar: Entity<TitleBar>,
     sidebar: Entity<SideBar>,
     now_playing: Entity<PlayerDetail>,
+    palette: Entity<CommandPalette>,
+    smart_editor: Entity<SmartPlaylistEditor>,
+    library_state: Entity<LibraryState>,
+    notifications: Entity<NotificationCenter>,
+    focus_handle: FocusHandle,
 }
 
 impl Zotu {
-    pub fn new(_window: &mut Window, cx: &mut Context<Self>) -> Self {
+    pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
         // 从数据库加载初始数据
         let library_list = cx.global::<DB>().load_all_albums();
         let favorite_uuid_list = cx.global::<DB>().get_all_uuids(Table::Favorite);
         let history_uuid_list = cx.global::<DB>().get_all_uuids(Table::History);
+        let ratings = cx.global::<DB>().load_ratings().unwrap_or_else(|e| {
+            warn!("加载评分失败: {}", e);
+            HashMap::new()
+        });
+        let unplayable = cx.global::<DB>().load_unplayable().unwrap_or_else(|e| {
+            warn!("加载无法播放的歌曲失败: {}", e);
+            HashMap::new()
+        });
+        cx.global_mut::<Player>()
+            .set_unplayable(unplayable.keys().copied().collect());
 
         // 创建 LibraryState Entity - 作为唯一的数据源
-        let library_state =
-            cx.new(|_cx| LibraryState::new(library_list, favorite_uuid_list, history_uuid_list));
+        let library_state = cx.new(|_cx| {
+            LibraryState::new(
+                library_list,
+                favorite_uuid_list,
+                history_uuid_list,
+                ratings,
+                unplayable,
+            )
+        });
+
+        // 加权随机使用评分和播放次数：评分或曲库变化时重新加载，每次播放结束时累加
+        Self::load_track_stats(cx);
+        cx.subscribe(
+            &library_state,
+            |this, _state, evt: &LibraryEvent, cx| match evt {
+                LibraryEvent::RatingsChanged | LibraryEvent::LibraryUpdated => {
+                    Self::load_track_stats(cx);
+                }
+                // 正在查看的统计页随播放记录刷新
+                LibraryEvent::PlaysRecorded if this.view_type == SidebarItem::Stats => {
+                    this.stats.update(cx, |stats, cx| stats.reload(cx));
+                }
+                _ => {}
+            },
+        )
+        .detach();
 
         // 创建歌曲列表视图，持有 LibraryState
-        let song_view = cx.new(|cx| AlbumList::new(library_state, cx));
+        let song_view = cx.new(|cx| AlbumList::new(library_state.clone(), window, cx));
 
         let play_bar = cx.new(|cx| PlayBar::new(cx));
         let title_bar = cx.new(|cx| TitleBar::new(cx));
-        let sidebar = cx.new(|_| SideBar::new());
-        let setting = cx.new(|_| Setting);
-        let player_detail = cx.new(|_| PlayerDetail::new());
+        let smart_playlists = Self::load_smart_playlists(cx);
+        let custom_playlists = Self::load_custom_playlists(cx);
+        let sidebar = cx.new(|_| {
+            let mut sidebar = SideBar::new();
+            sidebar.set_smart_playlists(smart_playlists);
+            sidebar.set_custom_playlists(custom_playlists);
+            sidebar
+        });
+
+        let setting = cx.new(|cx| Setting::new(library_state.clone(), cx));
+        let stats = cx.new(StatsView::new);
+        let player_detail = cx.new(|cx| PlayerDetail::new(library_state.clone(), cx));
+
+        // 导入歌单后刷新侧边栏
+        cx.subscribe(&setting, |this, _that, evt: &SettingEvent, cx| match evt {
+            SettingEvent::PlaylistsChanged => {
+                let playlists = Self::load_custom_playlists(cx);
+                this.sidebar.update(cx, |sidebar, cx| {
+                    sidebar.set_custom_playlists(playlists);
+                    cx.notify();
+                });
+            }
+        })
+        .detach();
 
         // 订阅标题栏搜索事件
         cx.subscribe(&title_bar, |this, _that, evt: &SearchEvent, cx| {
@@ -57,11 +143,19 @@ impl Zotu {
         })
         .detach();
 
-        // 订阅 playbar 事件（点击封面打开详情页）
-        cx.subscribe(&play_bar, |this, _that, _evt: &PlayBarMessage, cx| {
-            this.now_playing.update(cx, |now_playing, cx| {
-                now_playing.show(cx);
-            });
+        // 订阅 playbar 事件（点击封面打开详情页、解码失败提示）
+        cx.subscribe(&play_bar, |this, _that, evt: &PlayBarMessage, cx| {
+            match evt {
+                PlayBarMessage::NowPlayingClick => {
+                    this.now_playing.update(cx, |now_playing, cx| {
+                        now_playing.show(cx);
+                    });
+                }
+                PlayBarMessage::PlayabilityChanged(changes) => {
+                    this.apply_playability_changes(changes, cx);
+                }
+                PlayBarMessage::Played(events) => this.record_play_events(events, cx),
+            }
             cx.notify();
         })
         .detach();
@@ -79,56 +173,641 @@ impl Zotu {
         .detach();
 
         // 订阅侧边栏消息
-        cx.subscribe(&sidebar, |this, _that, evt, cx| match evt {
-            SidebarItem::Settings => {
-                this.view_type = SidebarItem::Settings;
-                cx.notify();
+        cx.subscribe(&sidebar, |this, _that, evt: &SidebarItem, cx| {
+            this.navigate(*evt, cx);
+        })
+        .detach();
+
+        // 侧边栏新建、右键编辑智能歌单
+        let smart_editor = cx.new(SmartPlaylistEditor::new);
+        cx.subscribe(&sidebar, |this, _that, evt: &SidebarAction, cx| match evt {
+            SidebarAction::NewSmartPlaylist => {
+                this.smart_editor
+                    .update(cx, |editor, cx| editor.open_new(cx));
             }
-            SidebarItem::Library => {
-                this.view_type = SidebarItem::Library;
-                let list = this
-                    .song_view
-                    .update(cx, |view, cx| view.set_view_type(ViewType::Library, cx));
-                cx.global_mut::<Player>().set_playlist(list);
-                cx.notify();
+            SidebarAction::EditSmartPlaylist(id) => {
+                match cx.global::<DB>().load_smart_playlist(*id) {
+                    Ok(Some(playlist)) => this
+                        .smart_editor
+                        .update(cx, |editor, cx| editor.open(&playlist, cx)),
+                    Ok(None) => {}
+                    Err(e) => warn!("加载智能歌单失败: {}", e),
+                }
             }
-            SidebarItem::Favorite => {
-                this.view_type = SidebarItem::Favorite;
-                let list = this
-                    .song_view
-                    .update(cx, |view, cx| view.set_view_type(ViewType::Favorite, cx));
-                cx.global_mut::<Player>().set_playlist(list);
+        })
+        .detach();
+        cx.subscribe(
+            &smart_editor,
+            |this, _that, evt: &SmartPlaylistEvent, cx| {
+                this.apply_smart_playlist_event(evt, cx);
+            },
+        )
+        .detach();
+
+        // 命令面板中选择歌曲、专辑、歌单
+        let palette = cx.new(|cx| CommandPalette::new(library_state.clone(), cx));
+        cx.subscribe(&palette, |this, _that, evt: &PaletteEvent, cx| match evt {
+            PaletteEvent::PlayTrack(item) => {
+                this.song_view
+                    .update(cx, |view, cx| view.play_item(item, cx));
                 cx.notify();
             }
-            SidebarItem::History => {
-                this.view_type = SidebarItem::History;
-                let list = this
-                    .song_view
-                    .update(cx, |view, cx| view.set_view_type(ViewType::History, cx));
-                cx.global_mut::<Player>().set_playlist(list);
+            PaletteEvent::PlayAlbum(items) => {
+                let items = Arc::new(items.clone());
+                cx.update_global::<Player, _>(|player, _cx| player.play_list(items));
                 cx.notify();
             }
-            SidebarItem::Custom(_) => {
-                // TODO: 处理自定义歌单
+            PaletteEvent::OpenView(item) => {
+                this.sidebar
+                    .update(cx, |sidebar, cx| sidebar.select(*item, cx));
+                this.navigate(*item, cx);
             }
         })
         .detach();
 
+        let notifications = cx.new(NotificationCenter::new);
+
+        // 退出时记下正在收听的这一次播放
+        cx.on_app_quit(|this, cx| {
+            let events = cx.update_global::<Player, _>(|player, _cx| {
+                player.flush_listen();
+                player.take_play_events()
+            });
+            this.record_play_events(&events, cx);
+            async {}
+        })
+        .detach();
+
+        // 启动时让歌曲列表获得焦点，方向键可直接使用
+        window.focus(&song_view.focus_handle(cx));
+
         Self {
             view_type: SidebarItem::Library,
             song_view,
             setting,
+            stats,
             play_bar,
             title_bar,
             sidebar,
             now_playing: player_detail,
+            palette,
+            smart_editor,
+            library_state,
+            notifications,
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    /// 把解码失败或恢复写入曲库状态，失败的文件汇总为一条错误通知
+    fn apply_playability_changes(&mut self, changes: &[PlayabilityChange], cx: &mut Context<Self>) {
+        let mut failed = Vec::new();
+        for change in changes {
+            match change {
+                PlayabilityChange::Failed(track, error) => {
+                    let reason = error.to_string();
+                    self.library_state.update(cx, |state, cx| {
+                        state.set_unplayable(&track.id(), Some(reason.clone()), cx)
+                    });
+                    failed.push(format!("{} — {}", track.path().display(), reason));
+                }
+                PlayabilityChange::Recovered(id) => {
+                    self.library_state
+                        .update(cx, |state, cx| state.set_unplayable(id, None, cx));
+                }
+            }
+        }
+        if failed.is_empty() {
+            return;
+        }
+
+        let title = format!("{} 个文件无法播放，已跳过", failed.len());
+        let hidden = failed.len().saturating_sub(MAX_LISTED_FAILURES);
+        failed.truncate(MAX_LISTED_FAILURES);
+        if hidden > 0 {
+            failed.push(format!("还有 {} 个文件", hidden));
+        }
+        notify::post(Level::Error, title, Some(failed.join("\n")));
+    }
+
+    /// 写入播放历史和播放统计：开始播放时加入历史，结束时按实际收听时长记一次播放
+    fn record_play_events(&mut self, events: &[PlayEvent], cx: &mut Context<Self>) {
+        for event in events {
+            match event {
+                PlayEvent::Started(track) => {
+                    if let Err(e) = cx.global::<DB>().add_to_table(Table::History, &track.id()) {
+                        warn!("写入播放历史失败: {}", e);
+                    }
+                    self.library_state
+                        .update(cx, |state, cx| state.add_to_history(&track.id(), cx));
+                }
+                PlayEvent::Finished {
+                    track,
+                    started_at,
+                    played,
+                } => {
+                    let started_at = started_at
+                        .duration_since(UNIX_EPOCH)
+                        .map(|d| d.as_secs() as i64)
+                        .unwrap_or(0);
+                    self.library_state.update(cx, |state, cx| {
+                        state.record_play(&track.id(), started_at, played.as_secs(), cx)
+                    });
+                    cx.global_mut::<Player>().count_play(&track.id());
+                }
+            }
+        }
+    }
+
+    /// 保存或删除智能歌单，刷新侧边栏；正在查看的歌单随之刷新或返回曲库
+    fn apply_smart_playlist_event(&mut self, evt: &SmartPlaylistEvent, cx: &mut Context<Self>) {
+        let db = cx.global::<DB>();
+        let (result, show) = match evt {
+            SmartPlaylistEvent::Saved(playlist) if playlist.id == 0 => {
+                match db.add_smart_playlist(playlist) {
+                    Ok(id) => (Ok(()), Some(SidebarItem::Smart(id))),
+                    Err(e) => (Err(e), None),
+                }
+            }
+            SmartPlaylistEvent::Saved(playlist) => {
+                let current = self.view_type == SidebarItem::Smart(playlist.id);
+                (
+                    db.update_smart_playlist(playlist),
+                    current.then_some(SidebarItem::Smart(playlist.id)),
+                )
+            }
+            SmartPlaylistEvent::Deleted(id) => {
+                let current = self.view_type == SidebarItem::Smart(*id);
+                (
+                    db.remove_smart_playlist(*id),
+                    current.then_some(SidebarItem::Library),
+                )
+            }
+        };
+        if let Err(e) = result {
+            warn!("保存智能歌单失败: {}", e);
+            notify::post(Level::Error, "保存智能歌单失败", Some(e.to_string()));
+            return;
+        }
+
+        let playlists = Self::load_smart_playlists(cx);
+        self.sidebar.update(cx, |sidebar, cx| {
+            sidebar.set_smart_playlists(playlists);
+            cx.notify();
+        });
+        if let Some(item) = show {
+            self.show_view(item, cx);
+        }
+    }
+
+    pub fn library_state(&self) -> &Entity<LibraryState> {
+        &self.library_state
+    }
+
+    /// 重新从数据库加载曲库（后台任务修改了曲库之后调用）
+    pub fn refresh_library(&mut self, cx: &mut Context<Self>) {
+        self.song_view.update(cx, |view, cx| view.refresh_library(cx));
+    }
+
+    /// 切换到侧边栏对应的视图，并把该列表设为播放列表
+    fn navigate(&mut self, item: SidebarItem, cx: &mut Context<Self>) {
+        self.view_type = item;
+        let view_type = match item {
+            SidebarItem::Settings => {
+                cx.notify();
+                return;
+            }
+            // 统计页不改变播放列表，每次打开时重新统计
+            SidebarItem::Stats => {
+                self.stats.update(cx, |stats, cx| stats.reload(cx));
+                cx.notify();
+                return;
+            }
+            SidebarItem::Library => ViewType::Library,
+            SidebarItem::Favorite => ViewType::Favorite,
+            SidebarItem::History => ViewType::History,
+            SidebarItem::Smart(id) => ViewType::Smart(id),
+            SidebarItem::Custom(id) => ViewType::Custom(id),
+        };
+        let list = self
+            .song_view
+            .update(cx, |view, cx| view.set_view_type(view_type, cx));
+        cx.global_mut::<Player>().set_playlist(list);
+        cx.notify();
+    }
+
+    /// 快捷键、命令面板切换视图时同步侧边栏选中状态
+    fn show_view(&mut self, item: SidebarItem, cx: &mut Context<Self>) {
+        self.now_playing
+            .update(cx, |now_playing, cx| now_playing.hide(cx));
+        self.sidebar
+            .update(cx, |sidebar, cx| sidebar.select(item, cx));
+        self.navigate(item, cx);
+    }
+
+    // ========== 快捷键 ==========
+
+    fn play_pause(&mut self, _: &PlayPause, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.update_global::<Player, _>(|player, _cx| player.toggle_play());
+        cx.notify();
+    }
+
+    fn next_track(&mut self, _: &NextTrack, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.update_global::<Player, _>(|player, _cx| player.next());
+        cx.notify();
+    }
+
+    fn previous_track(&mut self, _: &PreviousTrack, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.update_global::<Player, _>(|player, _cx| player.previous());
+        cx.notify();
+    }
+
+    fn seek_forward(&mut self, _: &SeekForward, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.update_global::<Player, _>(|player, _cx| player.seek_by(SEEK_STEP_SECS));
+        cx.notify();
+    }
+
+    fn seek_backward(&mut self, _: &SeekBackward, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.update_global::<Player, _>(|player, _cx| player.seek_by(-SEEK_STEP_SECS));
+        cx.notify();
+    }
+
+    fn volume_up(&mut self, _: &VolumeUp, _window: &mut Window, cx: &mut Context<Self>) {
+        self.change_volume(VOLUME_STEP, cx);
+    }
+
+    fn volume_down(&mut self, _: &VolumeDown, _window: &mut Window, cx: &mut Context<Self>) {
+        self.change_volume(-VOLUME_STEP, cx);
+    }
+
+    /// 调整音量并写入配置（退出时保存）
+    fn change_volume(&mut self, delta: f32, cx: &mut Context<Self>) {
+        let volume = cx.update_global::<Player, _>(|player, _cx| {
+            player.set_volume(player.volume() + delta);
+            player.volume()
+        });
+        cx.global_mut::<Config>().play_info.volume = volume;
+        cx.notify();
+    }
+
+    fn playback_faster(
+        &mut self,
+        _: &PlaybackFaster,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let rate = cx.global::<Player>().playback_rate() + PLAYBACK_RATE_STEP;
+        self.change_playback_rate(rate, cx);
+    }
+
+    fn playback_slower(
+        &mut self,
+        _: &PlaybackSlower,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let rate = cx.global::<Player>().playback_rate() - PLAYBACK_RATE_STEP;
+        self.change_playback_rate(rate, cx);
+    }
+
+    fn reset_playback_rate(
+        &mut self,
+        _: &ResetPlaybackRate,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.change_playback_rate(1.0, cx);
+    }
+
+    fn change_playback_rate(&mut self, rate: f32, cx: &mut Context<Self>) {
+        // 按步长取整，避免浮点误差累积
+        let rate = (rate * 100.0).round() / 100.0;
+        let rate = cx.update_global::<Player, _>(|player, _cx| {
+            player.set_playback_rate(rate);
+            player.playback_rate()
+        });
+        cx.global_mut::<Config>().play_info.playback_rate = rate;
+        cx.notify();
+    }
+
+    fn toggle_shuffle(&mut self, _: &ToggleShuffle, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.update_global::<Player, _>(|player, _cx| player.toggle_shuffle());
+        cx.notify();
+    }
+
+    fn toggle_loop_mode(
+        &mut self,
+        _: &ToggleLoopMode,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        cx.update_global::<Player, _>(|player, _cx| player.toggle_loop_mode());
+        cx.notify();
+    }
+
+    fn cycle_shuffle_mode(
+        &mut self,
+        _: &CycleShuffleMode,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let mode = cx.update_global::<Player, _>(|player, _cx| {
+            player.set_shuffle_mode(player.shuffle_mode().next());
+            player.shuffle_mode()
+        });
+        cx.global_mut::<Config>().play_info.shuffle_mode = mode;
+        cx.notify();
+    }
+
+    fn toggle_stop_after_current(
+        &mut self,
+        _: &ToggleStopAfterCurrent,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        cx.global_mut::<Player>().toggle_stop_after_current();
+        cx.notify();
+    }
+
+    fn cycle_ab_loop(&mut self, _: &CycleAbLoop, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.global_mut::<Player>().cycle_ab_loop();
+        cx.notify();
+    }
+
+    fn focus_search(&mut self, _: &FocusSearch, window: &mut Window, cx: &mut Context<Self>) {
+        // 详情页、设置页和统计页中没有搜索框，先回到曲库
+        self.now_playing
+            .update(cx, |now_playing, cx| now_playing.hide(cx));
+        if matches!(self.view_type, SidebarItem::Settings | SidebarItem::Stats) {
+            self.view_type = SidebarItem::Library;
+            let list = self
+                .song_view
+                .update(cx, |view, cx| view.set_view_type(ViewType::Library, cx));
+            cx.global_mut::<Player>().set_playlist(list);
         }
+        self.title_bar
+            .update(cx, |title_bar, cx| title_bar.focus_search(window, cx));
+        cx.notify();
+    }
+
+    fn open_now_playing(
+        &mut self,
+        _: &OpenNowPlaying,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.now_playing
+            .update(cx, |now_playing, cx| now_playing.show(cx));
+        cx.notify();
+    }
+
+    fn show_library(&mut self, _: &ShowLibrary, _window: &mut Window, cx: &mut Context<Self>) {
+        self.show_view(SidebarItem::Library, cx);
+    }
+
+    fn show_favorites(&mut self, _: &ShowFavorites, _window: &mut Window, cx: &mut Context<Self>) {
+        self.show_view(SidebarItem::Favorite, cx);
+    }
+
+    fn show_history(&mut self, _: &ShowHistory, _window: &mut Window, cx: &mut Context<Self>) {
+        self.show_view(SidebarItem::History, cx);
+    }
+
+    fn show_stats(&mut self, _: &ShowStats, _window: &mut Window, cx: &mut Context<Self>) {
+        self.show_view(SidebarItem::Stats, cx);
+    }
+
+    fn open_settings(&mut self, _: &OpenSettings, _window: &mut Window, cx: &mut Context<Self>) {
+        self.show_view(SidebarItem::Settings, cx);
+    }
+
+    /// 重新扫描配置中的音乐文件夹
+    fn rescan_library(&mut self, _: &RescanLibrary, _window: &mut Window, cx: &mut Context<Self>) {
+        let path = cx.global::<Config>().media_file.music_directory.to_string();
+        let scan = cx.global::<DB>().scan_in_background(path, cx);
+        cx.spawn(async move |this: WeakEntity<Zotu>, cx: &mut AsyncApp| {
+            if scan.await.is_ok() {
+                this.update(cx, |this, cx| {
+                    this.song_view
+                        .update(cx, |view, cx| view.refresh_library(cx));
+                })
+                .ok();
+            }
+        })
+        .detach();
+    }
+
+    fn clear_history(&mut self, _: &ClearHistory, _window: &mut Window, cx: &mut Context<Self>) {
+        if let Err(e) = cx.global::<DB>().clear_table(Table::History) {
+            warn!("清空历史失败: {}", e);
+            return;
+        }
+        self.library_state
+            .update(cx, |state, cx| state.clear_history(cx));
+        if self.view_type == SidebarItem::History {
+            self.navigate(SidebarItem::History, cx);
+        }
+    }
+
+    // ========== 远程控制 ==========
+
+    /// 执行命令行转发来的命令；扫描和 play / enqueue 的导入在后台进行，立即回复已开始，结果显示为通知
+    pub fn handle_remote(
+        &mut self,
+        command: RemoteCommand,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> RemoteResponse {
+        match command {
+            RemoteCommand::Raise => {
+                window.activate_window();
+                RemoteResponse::done("已激活窗口")
+            }
+            RemoteCommand::Play { path } => {
+                let message = format!("正在读取 {}，完成后开始播放", path.display());
+                self.load_tracks(path, false, cx);
+                RemoteResponse::done(message)
+            }
+            RemoteCommand::Enqueue { path } => {
+                let message = format!("正在读取 {}，完成后加入队列", path.display());
+                self.load_tracks(path, true, cx);
+                RemoteResponse::done(message)
+            }
+            RemoteCommand::Toggle => {
+                let player = cx.global_mut::<Player>();
+                if player.current_track().is_none() {
+                    return RemoteResponse::error("当前没有播放的歌曲");
+                }
+                player.toggle_play();
+                if player.is_playing() {
+                    RemoteResponse::done("继续播放")
+                } else {
+                    RemoteResponse::done("已暂停")
+                }
+            }
+            RemoteCommand::Next => {
+                let player = cx.global_mut::<Player>();
+                player.next();
+                match player.current_track() {
+                    Some(track) => RemoteResponse::done(format!(
+                        "正在播放: {} - {}",
+                        track.title(),
+                        track.artist()
+                    )),
+                    None => RemoteResponse::error("播放列表为空"),
+                }
+            }
+            RemoteCommand::Status => RemoteResponse::Status(PlayerStatus::from_app(cx)),
+            RemoteCommand::Scan { path } => {
+                let scan = cx
+                    .global::<DB>()
+                    .scan_in_background(path.to_string_lossy().to_string(), cx);
+                cx.spawn(async move |this: WeakEntity<Zotu>, cx: &mut AsyncApp| {
+                    if scan.await.is_ok() {
+                        this.update(cx, |this, cx| {
+                            this.song_view
+                                .update(cx, |view, cx| view.refresh_library(cx));
+                        })
+                        .ok();
+                    }
+                })
+                .detach();
+                RemoteResponse::done(format!("已开始扫描 {}", path.display()))
+            }
+        }
+    }
+
+    /// 在后台读取文件或文件夹中的歌曲（不在曲库中的先导入），完成后播放或加入队列；
+    /// 失败时显示为通知
+    fn load_tracks(&mut self, path: PathBuf, enqueue: bool, cx: &mut Context<Self>) {
+        let scan = cx
+            .global::<DB>()
+            .scan_in_background(path.to_string_lossy().to_string(), cx);
+        cx.spawn(async move |this: WeakEntity<Zotu>, cx: &mut AsyncApp| {
+            let Ok(added) = scan.await else {
+                // 扫描失败已由扫描任务显示通知
+                return;
+            };
+            let Ok(load) = cx.update(|cx: &mut App| {
+                cx.global::<DB>()
+                    .load_tracks_in_background(path.clone(), cx)
+            }) else {
+                return;
+            };
+            let tracks = match load.await {
+                Ok(tracks) if tracks.is_empty() => {
+                    let detail = format!("{:?} 中没有可播放的音频文件", path);
+                    notify::post(Level::Error, "无法播放", Some(detail));
+                    return;
+                }
+                Ok(tracks) => tracks,
+                Err(e) => {
+                    notify::post(Level::Error, format!("读取 {:?} 失败", path), Some(e));
+                    return;
+                }
+            };
+
+            this.update(cx, |this, cx| {
+                if added > 0 {
+                    this.song_view
+                        .update(cx, |view, cx| view.refresh_library(cx));
+                }
+                cx.update_global::<Player, _>(|player, _cx| {
+                    if enqueue {
+                        player.enqueue(tracks);
+                        // 没有在播放时直接从队列开始
+                        if player.current_track().is_none() {
+                            player.next();
+                        }
+                    } else {
+                        player.play_list(Arc::new(tracks));
+                    }
+                });
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn toggle_notifications(
+        &mut self,
+        _: &ToggleNotifications,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.notifications
+            .update(cx, |notifications, cx| notifications.toggle_history(cx));
+    }
+
+    fn toggle_command_palette(
+        &mut self,
+        _: &ToggleCommandPalette,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.palette
+            .update(cx, |palette, cx| palette.toggle(window, cx));
+    }
+
+    /// 从数据库加载加权随机使用的评分和播放次数
+    fn load_track_stats(cx: &mut App) {
+        match cx.global::<DB>().load_track_stats() {
+            Ok(stats) => cx.global_mut::<Player>().set_track_stats(stats),
+            Err(e) => warn!("加载播放统计失败: {}", e),
+        }
+    }
+
+    /// 从数据库加载智能歌单（ID, 名称）
+    fn load_smart_playlists(cx: &App) -> Vec<(i64, SharedString)> {
+        match cx.global::<DB>().load_smart_playlists() {
+            Ok(playlists) => playlists.into_iter().map(|p| (p.id, p.name)).collect(),
+            Err(e) => {
+                warn!("加载智能歌单失败: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// 从数据库加载自定义歌单（ID, 名称）
+    fn load_custom_playlists(cx: &App) -> Vec<(i64, SharedString)> {
+        cx.global::<DB>().load_playlists().unwrap_or_else(|e| {
+            warn!("加载歌单失败: {}", e);
+            Vec::new()
+        })
     }
 }
 
 impl Render for Zotu {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         div()
+            .key_context("Zotu")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::play_pause))
+            .on_action(cx.listener(Self::next_track))
+            .on_action(cx.listener(Self::previous_track))
+            .on_action(cx.listener(Self::seek_forward))
+            .on_action(cx.listener(Self::seek_backward))
+            .on_action(cx.listener(Self::volume_up))
+            .on_action(cx.listener(Self::volume_down))
+            .on_action(cx.listener(Self::playback_faster))
+            .on_action(cx.listener(Self::playback_slower))
+            .on_action(cx.listener(Self::reset_playback_rate))
+            .on_action(cx.listener(Self::toggle_shuffle))
+            .on_action(cx.listener(Self::toggle_loop_mode))
+            .on_action(cx.listener(Self::cycle_shuffle_mode))
+            .on_action(cx.listener(Self::toggle_stop_after_current))
+            .on_action(cx.listener(Self::cycle_ab_loop))
+            .on_action(cx.listener(Self::focus_search))
+            .on_action(cx.listener(Self::open_now_playing))
+            .on_action(cx.listener(Self::show_library))
+            .on_action(cx.listener(Self::show_favorites))
+            .on_action(cx.listener(Self::show_history))
+            .on_action(cx.listener(Self::show_stats))
+            .on_action(cx.listener(Self::open_settings))
+            .on_action(cx.listener(Self::rescan_library))
+            .on_action(cx.listener(Self::clear_history))
+            .on_action(cx.listener(Self::toggle_command_palette))
+            .on_action(cx.listener(Self::toggle_notifications))
             .size_full()
             .flex()
             .flex_row()
@@ -149,9 +828,13 @@ impl Render for Zotu {
                             .child(self.title_bar.clone())
                             .map(|parent| match self.view_type {
                                 SidebarItem::Settings => parent.child(self.setting.clone()),
+                                SidebarItem::Stats => parent
+                                    .child(self.stats.clone())
+                                    .child(self.play_bar.clone()),
                                 SidebarItem::Library
                                 | SidebarItem::Favorite
                                 | SidebarItem::History
+                                | SidebarItem::Smart(_)
                                 | SidebarItem::Custom(_) => parent
                                     .child(self.song_view.clone())
                                     .child(self.play_bar.clone()),
@@ -159,5 +842,11 @@ impl Render for Zotu {
                     )
                 },
             )
+            // 智能歌单编辑弹窗
+            .child(self.smart_editor.clone())
+            // 通知提示和历史面板
+            .child(self.notifications.clone())
+            // 命令面板覆盖在最上层
+            .child(self.palette.clone())
     }
 }
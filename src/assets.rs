@@ -1,37 +1,56 @@
 use gpui::{AssetSource, SharedString};
-use std::{borrow::Cow, fs, path::PathBuf};
+use std::borrow::Cow;
 
-pub struct Assets {
-    base: PathBuf,
+/// 把 assets 目录下的文件编译进程序，列表中的路径相对于 assets 目录
+macro_rules! embed_assets {
+    ($($path:literal),* $(,)?) => {
+        &[$(($path, include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/", $path)))),*]
+    };
 }
 
-impl Assets {
-    pub fn new(base: &str) -> Self {
-        Self {
-            base: PathBuf::from(base),
-        }
-    }
-}
+/// 内嵌的资源文件（路径, 内容）
+static EMBEDDED: &[(&str, &[u8])] = embed_assets![
+    "svg/album.svg",
+    "svg/bell.svg",
+    "svg/chart.svg",
+    "svg/close.svg",
+    "svg/folder.svg",
+    "svg/heart.svg",
+    "svg/history.svg",
+    "svg/last.svg",
+    "svg/library.svg",
+    "svg/list.svg",
+    "svg/minus.svg",
+    "svg/moon.svg",
+    "svg/next.svg",
+    "svg/pause.svg",
+    "svg/play.svg",
+    "svg/random.svg",
+    "svg/search.svg",
+    "svg/setting.svg",
+    "svg/single.svg",
+    "svg/stack.svg",
+    "svg/star-fill.svg",
+    "svg/star.svg",
+];
+
+/// 内嵌在程序中的资源，不依赖运行时的工作目录
+pub struct Assets;
 
 impl AssetSource for Assets {
     fn load(&self, path: &str) -> gpui::Result<Option<Cow<'static, [u8]>>> {
-        fs::read(self.base.join(path))
-            .map(|data| Some(Cow::Owned(data)))
-            .map_err(|err| err.into())
+        Ok(EMBEDDED
+            .iter()
+            .find(|(name, _)| *name == path)
+            .map(|(_, data)| Cow::Borrowed(*data)))
     }
 
     fn list(&self, path: &str) -> gpui::Result<Vec<SharedString>> {
-        fs::read_dir(self.base.join(path))
-            .map(|entries| {
-                entries
-                    .filter_map(|entry| {
-                        entry
-                            .ok()
-                            .and_then(|entry| entry.file_name().into_string().ok())
-                            .map(SharedString::from)
-                    })
-                    .collect()
-            })
-            .map_err(|err| err.into())
+        let prefix = path.trim_end_matches('/');
+        Ok(EMBEDDED
+            .iter()
+            .filter_map(|(name, _)| name.strip_prefix(prefix)?.strip_prefix('/'))
+            .map(|name| SharedString::from(name.to_string()))
+            .collect())
     }
 }
@@ -0,0 +1,254 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use crate::{
+    config::Config,
+    db::database::DB,
+    paths,
+    play::player::LoopMode,
+    remote::{self, PlayerStatus, RemoteCommand, RemoteResponse},
+    util::format_duration,
+};
+
+pub const USAGE: &str = "用法: zotu [--config <文件>] [--data-dir <目录>] [--db <文件>] [命令]
+
+选项:
+  --config <文件>         配置文件，默认 $XDG_CONFIG_HOME/zotu/config.json
+  --data-dir <目录>       数据目录（数据库、日志和缓存），默认 $XDG_DATA_HOME/zotu
+  --db <文件>             数据库文件，默认为数据目录下的 metadata.db
+
+命令:
+  play <文件|文件夹>      播放文件或文件夹中的歌曲
+  enqueue <文件|文件夹>   加入播放队列
+  toggle                  播放/暂停
+  next                    下一首
+  status [--json]         查看播放状态
+  scan <文件夹>           扫描文件夹并加入曲库
+  help                    显示本帮助
+
+已有实例在运行时命令会转发给它；scan 和 status 在没有实例时直接读写数据库。";
+
+/// 命令行参数
+#[derive(Debug, PartialEq)]
+pub struct Cli {
+    pub config_path: PathBuf,
+    pub db_path: PathBuf,
+    /// --data-dir 指定的数据目录
+    pub data_dir: Option<PathBuf>,
+    /// 未指定命令时启动（或激活）窗口
+    pub command: Option<RemoteCommand>,
+    /// status 以 JSON 输出
+    pub json: bool,
+    pub help: bool,
+}
+
+impl Default for Cli {
+    fn default() -> Self {
+        Cli {
+            config_path: paths::default_config_path(),
+            db_path: paths::default_db_path(),
+            data_dir: None,
+            command: None,
+            json: false,
+            help: false,
+        }
+    }
+}
+
+impl Cli {
+    /// 解析参数（不含程序名），路径参数转换为绝对路径以便转发给其他进程
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut cli = Cli::default();
+        let mut args = args.into_iter();
+        let mut db_path = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => cli.config_path = PathBuf::from(expect_value(&mut args, &arg)?),
+                "--db" => db_path = Some(PathBuf::from(expect_value(&mut args, &arg)?)),
+                "--data-dir" => {
+                    let dir = PathBuf::from(expect_value(&mut args, &arg)?);
+                    cli.data_dir = Some(
+                        std::path::absolute(&dir)
+                            .map_err(|e| format!("无法解析路径 {:?}: {}", dir, e))?,
+                    );
+                }
+                "--json" => cli.json = true,
+                "-h" | "--help" | "help" => cli.help = true,
+                flag if flag.starts_with('-') => return Err(format!("未知选项: {}", flag)),
+                name => {
+                    if cli.command.is_some() {
+                        return Err(format!("多余的参数: {}", name));
+                    }
+                    let command = match name {
+                        "play" => RemoteCommand::Play {
+                            path: expect_path(&mut args, name)?,
+                        },
+                        "enqueue" => RemoteCommand::Enqueue {
+                            path: expect_path(&mut args, name)?,
+                        },
+                        "toggle" => RemoteCommand::Toggle,
+                        "next" => RemoteCommand::Next,
+                        "status" => RemoteCommand::Status,
+                        "scan" => RemoteCommand::Scan {
+                            path: expect_path(&mut args, name)?,
+                        },
+                        _ => return Err(format!("未知命令: {}", name)),
+                    };
+                    cli.command = Some(command);
+                }
+            }
+        }
+
+        if cli.json && cli.command != Some(RemoteCommand::Status) {
+            return Err("--json 只能用于 status".to_string());
+        }
+        cli.db_path = match (db_path, &cli.data_dir) {
+            (Some(path), _) => path,
+            (None, Some(dir)) => dir.join(paths::DB_FILE),
+            (None, None) => cli.db_path,
+        };
+        Ok(cli)
+    }
+
+    /// 配置、数据目录和数据库都使用默认位置（只有这时才迁移旧版本的文件）
+    pub fn uses_default_paths(&self) -> bool {
+        self.data_dir.is_none()
+            && self.config_path == paths::default_config_path()
+            && self.db_path == paths::default_db_path()
+    }
+}
+
+fn expect_value(args: &mut impl Iterator<Item = String>, name: &str) -> Result<String, String> {
+    args.next().ok_or_else(|| format!("{} 缺少参数", name))
+}
+
+fn expect_path(args: &mut impl Iterator<Item = String>, name: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(expect_value(args, name)?);
+    if !path.exists() {
+        return Err(format!("路径不存在: {:?}", path));
+    }
+    std::path::absolute(&path).map_err(|e| format!("无法解析路径 {:?}: {}", path, e))
+}
+
+/// 执行命令行：转发给运行中的实例，或以无头模式执行；返回 None 表示需要启动窗口
+pub fn run(cli: &Cli) -> Option<ExitCode> {
+    if cli.help {
+        println!("{}", USAGE);
+        return Some(ExitCode::SUCCESS);
+    }
+
+    let command = cli.command.clone().unwrap_or(RemoteCommand::Raise);
+    match remote::send(&remote::socket_path(&cli.db_path), &command) {
+        Ok(Some(response)) => return Some(print_response(&response, cli.json)),
+        Ok(None) => {}
+        Err(e) => eprintln!("[WARN] 连接运行中的实例失败: {}", e),
+    }
+
+    match command {
+        // 启动窗口后再执行 play / enqueue
+        RemoteCommand::Raise | RemoteCommand::Play { .. } | RemoteCommand::Enqueue { .. } => None,
+        RemoteCommand::Toggle | RemoteCommand::Next => {
+            eprintln!("没有正在运行的 Zotu 实例");
+            Some(ExitCode::FAILURE)
+        }
+        RemoteCommand::Status => Some(run_headless(cli, |db| {
+            // 只读取，不为一次查询创建配置文件
+            let config = Config::load(&cli.config_path)
+                .unwrap_or_else(|e| {
+                    eprintln!("[WARN] 加载配置文件失败，使用默认配置: {}", e);
+                    None
+                })
+                .unwrap_or_default();
+            RemoteResponse::Status(PlayerStatus::headless(&config, db))
+        })),
+        RemoteCommand::Scan { path } => Some(run_headless(cli, |db| scan_directory(db, &path))),
+    }
+}
+
+/// 不启动窗口，直接在数据库上执行
+fn run_headless(cli: &Cli, f: impl FnOnce(&DB) -> RemoteResponse) -> ExitCode {
+    // 没有实例在运行，先迁移旧文件，免得在默认位置建出空数据库后再也迁移不了
+    if cli.uses_default_paths() {
+        paths::migrate_legacy_files();
+    }
+    match DB::new(&cli.db_path.to_string_lossy()) {
+        Ok(db) => print_response(&f(&db), cli.json),
+        Err(e) => {
+            eprintln!("[FATAL] 数据库连接失败: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn scan_directory(db: &DB, path: &Path) -> RemoteResponse {
+    match db.add_metadata_to_library(&path.to_string_lossy()) {
+        Ok(added) => RemoteResponse::done(format!("扫描完成，新增 {} 首歌曲", added)),
+        Err(e) => RemoteResponse::error(format!("扫描失败: {}", e)),
+    }
+}
+
+/// 打印响应，错误输出到 stderr
+pub fn print_response(response: &RemoteResponse, json: bool) -> ExitCode {
+    match response {
+        RemoteResponse::Done { message } => {
+            println!("{}", message);
+            ExitCode::SUCCESS
+        }
+        RemoteResponse::Error { message } => {
+            eprintln!("{}", message);
+            ExitCode::FAILURE
+        }
+        RemoteResponse::Status(status) if json => match serde_json::to_string_pretty(status) {
+            Ok(text) => {
+                println!("{}", text);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("序列化状态失败: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+        RemoteResponse::Status(status) => {
+            print_status(status);
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+fn print_status(status: &PlayerStatus) {
+    let state = match status.state.as_str() {
+        _ if !status.running => "未运行",
+        "playing" => "播放中",
+        "paused" => "已暂停",
+        _ => "已停止",
+    };
+    println!("状态: {}", state);
+    if let Some(track) = &status.track {
+        println!(
+            "歌曲: {} - {}《{}》",
+            track.title, track.artist, track.album
+        );
+        println!(
+            "进度: {} / {}",
+            format_duration(status.elapsed),
+            format_duration(track.duration)
+        );
+    }
+    let loop_mode = match status.loop_mode {
+        LoopMode::List => "列表循环".to_string(),
+        LoopMode::Single => "单曲循环".to_string(),
+        LoopMode::Random => format!("随机播放（{}）", status.shuffle_mode.label()),
+    };
+    println!("音量: {:.0}%", status.volume * 100.0);
+    println!("循环: {}", loop_mode);
+    if status.running {
+        println!("队列: {} 首", status.queue_len);
+    }
+    println!(
+        "曲库: {} 首（收藏 {}，历史 {}）",
+        status.library_size, status.favorite_count, status.history_count
+    );
+}
@@ -3,4 +3,5 @@ pub mod now_playing;
 pub mod setting;
 pub mod sidebar;
 pub mod songview;
+pub mod stats;
 pub mod titlebar;
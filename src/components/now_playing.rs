@@ -1,20 +1,68 @@
 use crate::{
-    db::metadata::AlbumInfo,
-    play::player::{LoopMode, PlayState, Player},
+    db::{
+        cover::{self, CoverSize},
+        database::DB,
+        dbstate::LibraryState,
+        metadata::AlbumInfo,
+    },
+    play::{
+        ab_loop::{AbLoop, LoopRegion},
+        analyzer::{self, FFT_LEN},
+        player::{LoopMode, PlayState, Player},
+    },
     theme::*,
+    ui::{
+        loop_region::render_loop_region,
+        rating::render_rating,
+        visualizer::{render_spectrum, render_waveform},
+    },
     util::format_duration,
 };
 use gpui::{prelude::FluentBuilder, *};
+use tracing::warn;
+use uuid::Uuid;
+
+/// 频谱柱数量
+const SPECTRUM_BANDS: usize = 48;
+/// 频谱柱每帧最多回落的高度比例
+const SPECTRUM_FALLOFF: f32 = 0.03;
+/// 波形显示的采样数
+const WAVEFORM_LEN: usize = 1024;
 
 /// 播放器详情页状态
 pub struct PlayerDetail {
     /// 是否显示详情页
     show: bool,
+    /// LibraryState 引用（读取和设置评分）
+    library_state: Entity<LibraryState>,
+    /// 当前歌曲已保存的循环片段
+    loop_regions: Vec<LoopRegion>,
+    /// loop_regions 对应的歌曲（歌曲变化时重新加载）
+    loop_regions_track: Option<Uuid>,
+    /// 正在输入的片段名称（None 表示未在保存）
+    naming: Option<String>,
+    /// 打开名称输入框后下一次渲染时获取焦点
+    pending_focus: bool,
+    focus_handle: FocusHandle,
+    /// 是否显示频谱和波形
+    show_visualizer: bool,
+    /// 各频段当前显示的高度（带回落效果）
+    spectrum: Vec<f32>,
 }
 
 impl PlayerDetail {
-    pub fn new() -> Self {
-        PlayerDetail { show: false }
+    pub fn new(library_state: Entity<LibraryState>, cx: &mut Context<Self>) -> Self {
+        PlayerDetail {
+            show: false,
+            library_state,
+            loop_regions: Vec::new(),
+            loop_regions_track: None,
+            naming: None,
+            pending_focus: false,
+            focus_handle: cx.focus_handle(),
+            show_visualizer: false,
+            spectrum: vec![0.0; SPECTRUM_BANDS],
+        }
     }
 
     /// 是否正在显示
@@ -29,13 +77,91 @@ impl PlayerDetail {
     }
 
     /// 隐藏详情页
-    fn hide(&mut self, cx: &mut Context<Self>) {
+    pub fn hide(&mut self, cx: &mut Context<Self>) {
         self.show = false;
         cx.notify();
     }
 
+    /// 歌曲变化时重新加载已保存的循环片段
+    fn refresh_loop_regions(&mut self, track: Option<Uuid>, cx: &mut Context<Self>) {
+        if track == self.loop_regions_track {
+            return;
+        }
+        self.loop_regions_track = track;
+        self.naming = None;
+        self.loop_regions = match track {
+            Some(id) => cx
+                .global::<DB>()
+                .load_loop_regions(&id)
+                .unwrap_or_else(|e| {
+                    warn!("加载循环片段失败: {}", e);
+                    Vec::new()
+                }),
+            None => Vec::new(),
+        };
+    }
+
+    /// 开始为当前循环区间命名
+    fn start_naming(&mut self, cx: &mut Context<Self>) {
+        self.naming = Some(format!("片段 {}", self.loop_regions.len() + 1));
+        self.pending_focus = true;
+        cx.notify();
+    }
+
+    /// 保存当前循环区间为命名片段
+    fn save_loop_region(&mut self, cx: &mut Context<Self>) {
+        let Some(name) = self.naming.take() else {
+            return;
+        };
+        let player = cx.global::<Player>();
+        let track = player.current_track().map(|t| t.id());
+        let region = player.ab_loop().and_then(|ab| ab.region());
+        if let (Some(track), Some((start, end))) = (track, region) {
+            let name = name.trim();
+            let name = if name.is_empty() {
+                "未命名片段"
+            } else {
+                name
+            };
+            if let Err(e) = cx.global::<DB>().add_loop_region(&track, name, start, end) {
+                warn!("保存循环片段失败: {}", e);
+            }
+            self.loop_regions_track = None;
+            self.refresh_loop_regions(Some(track), cx);
+        }
+        cx.notify();
+    }
+
+    fn remove_loop_region(&mut self, id: i64, cx: &mut Context<Self>) {
+        if let Err(e) = cx.global::<DB>().remove_loop_region(id) {
+            warn!("删除循环片段失败: {}", e);
+        }
+        self.loop_regions.retain(|region| region.id != id);
+        cx.notify();
+    }
+
+    fn handle_naming_key(&mut self, evt: &KeyDownEvent, cx: &mut Context<Self>) {
+        let Some(name) = &mut self.naming else {
+            return;
+        };
+        match evt.keystroke.key.as_str() {
+            "escape" => self.naming = None,
+            "enter" => self.save_loop_region(cx),
+            "backspace" => {
+                name.pop();
+            }
+            _ => {
+                if let Some(key_char) = &evt.keystroke.key_char {
+                    name.push_str(key_char);
+                }
+            }
+        }
+        cx.notify();
+    }
+
     /// 渲染封面区域（优先使用原始封面文件，回退到缩略图）
-    fn render_cover(&self, track: Option<&AlbumInfo>) -> impl IntoElement {
+    fn render_cover(&self, track: Option<&AlbumInfo>, window: &Window) -> impl IntoElement {
+        let cover_size = CoverSize::fit(COVER_LARGE_SIZE * window.scale_factor());
         div().flex().items_center().justify_center().child(
             div()
                 .size(Pixels::from(COVER_LARGE_SIZE))
@@ -46,11 +172,10 @@ impl PlayerDetail {
                 .items_center()
                 .justify_center()
                 .overflow_hidden()
-                .when_some(track.and_then(|t| t.cover_path()), |this, cover_path| {
-                    // 使用原始封面文件显示大图
-                    this.child(img(cover_path.replace("D:/Code/zotu/assets", "")).size_full())
+                .when_some(track.and_then(|t| t.cover()), |this, cover| {
+                    this.child(img(cover::image(cover, cover_size)).size_full())
                 })
-                .when(track.and_then(|t| t.cover_path()).is_none(), |this| {
+                .when(track.and_then(|t| t.cover()).is_none(), |this| {
                     this.child(
                         svg()
                             .path("svg/album.svg")
@@ -61,6 +186,50 @@ impl PlayerDetail {
         )
     }
 
+    /// 渲染频谱和波形：读取最近播放的采样，播放中每帧刷新
+    fn render_visualizer(&mut self, window: &mut Window, cx: &Context<Self>) -> impl IntoElement {
+        let player = cx.global::<Player>();
+        let tap = player.sample_tap();
+        let mut samples = vec![0.0; FFT_LEN];
+        match player.play_state() {
+            PlayState::Stopped => {}
+            state => {
+                tap.snapshot(&mut samples);
+                if state == PlayState::Play {
+                    window.request_animation_frame();
+                }
+            }
+        }
+
+        let levels = analyzer::spectrum(&samples, tap.sample_rate(), SPECTRUM_BANDS);
+        for (shown, level) in self.spectrum.iter_mut().zip(levels) {
+            *shown = level.max(*shown - SPECTRUM_FALLOFF);
+        }
+
+        // 从前半段第一个上升过零点开始显示，使波形相对稳定
+        let trigger = (1..FFT_LEN - WAVEFORM_LEN)
+            .find(|&i| samples[i - 1] < 0.0 && samples[i] >= 0.0)
+            .unwrap_or(0);
+        let waveform = samples[trigger..trigger + WAVEFORM_LEN]
+            .iter()
+            .step_by(2)
+            .copied()
+            .collect();
+
+        div()
+            .w(px(COVER_LARGE_SIZE * 1.6))
+            .h(px(COVER_LARGE_SIZE))
+            .flex()
+            .flex_col()
+            .gap_3()
+            .child(div().flex_1().child(render_spectrum(self.spectrum.clone())))
+            .child(
+                div()
+                    .h(px(COVER_LARGE_SIZE / 4.0))
+                    .child(render_waveform(waveform)),
+            )
+    }
+
     /// 渲染歌曲信息
     fn render_track_info(&self, track: Option<&AlbumInfo>) -> impl IntoElement {
         let (title, artist, album) = match track {
@@ -89,6 +258,21 @@ impl PlayerDetail {
             .child(div().text_sm().text_color(text_placeholder()).child(album))
     }
 
+    /// 渲染当前歌曲的评分
+    fn render_rating(&self, track: Option<&AlbumInfo>, cx: &Context<Self>) -> impl IntoElement {
+        div().mt_3().when_some(track.map(|t| t.id()), |this, id| {
+            let rating = self.library_state.read(cx).rating(&id);
+            this.child(render_rating(rating, 20.0, |stars| {
+                cx.listener(move |this, _evt: &MouseDownEvent, _window, cx| {
+                    this.library_state.update(cx, |state, cx| {
+                        state.set_rating(&[id], stars, cx);
+                    });
+                    cx.notify();
+                })
+            }))
+        })
+    }
+
     /// 渲染播放进度
     fn render_progress(&self, cx: &Context<Self>) -> impl IntoElement {
         let player = cx.global::<Player>();
@@ -112,6 +296,7 @@ impl PlayerDetail {
             .child(
                 // 进度条
                 div()
+                    .relative()
                     .w_full()
                     .h(Pixels::from(4.0))
                     .bg(bg_hover())
@@ -123,7 +308,11 @@ impl PlayerDetail {
                             .w(relative(progress_pct))
                             .bg(accent_blue())
                             .rounded_full(),
-                    ),
+                    )
+                    .children(render_loop_region(
+                        player.ab_loop(),
+                        progress.as_ref().map(|p| p.duration).unwrap_or(0),
+                    )),
             )
             .child(
                 // 时间标签
@@ -147,6 +336,162 @@ impl PlayerDetail {
             )
     }
 
+    /// 渲染 A–B 循环控制和已保存的片段
+    fn render_ab_loop(&self, cx: &Context<Self>) -> impl IntoElement {
+        let player = cx.global::<Player>();
+        let has_track = player.current_track().is_some();
+        let ab_loop = player.ab_loop();
+        let looping = ab_loop.and_then(|ab| ab.region()).is_some();
+
+        let status = match ab_loop {
+            None => "A–B 循环".to_string(),
+            Some(AbLoop { a, b: None }) => {
+                format!("A {} – 等待 B 点", format_duration(a.as_secs()))
+            }
+            Some(AbLoop { a, b: Some(b) }) => format!(
+                "循环 {} – {}",
+                format_duration(a.as_secs()),
+                format_duration(b.as_secs())
+            ),
+        };
+
+        div()
+            .w_full()
+            .mt_6()
+            .px_12()
+            .flex()
+            .flex_col()
+            .items_center()
+            .gap_3()
+            .when(has_track, |this| {
+                this.child(
+                    div()
+                        .flex()
+                        .flex_row()
+                        .items_center()
+                        .gap_2()
+                        .child(
+                            div()
+                                .mr_2()
+                                .text_sm()
+                                .text_color(text_tertiary())
+                                .child(status),
+                        )
+                        .child(render_text_button("设为 A").on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(|_this, _evt, _window, cx| {
+                                cx.global_mut::<Player>().set_loop_a();
+                                cx.notify();
+                            }),
+                        ))
+                        .when(ab_loop.is_some(), |this| {
+                            this.child(render_text_button("设为 B").on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|_this, _evt, _window, cx| {
+                                    cx.global_mut::<Player>().set_loop_b();
+                                    cx.notify();
+                                }),
+                            ))
+                            .child(
+                                render_text_button("清除").on_mouse_down(
+                                    MouseButton::Left,
+                                    cx.listener(|this, _evt, _window, cx| {
+                                        this.naming = None;
+                                        cx.global_mut::<Player>().clear_ab_loop();
+                                        cx.notify();
+                                    }),
+                                ),
+                            )
+                        })
+                        .when(looping && self.naming.is_none(), |this| {
+                            this.child(render_text_button("保存片段").on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|this, _evt, _window, cx| {
+                                    this.start_naming(cx);
+                                }),
+                            ))
+                        })
+                        // 片段名称输入框（回车保存，Esc 取消）
+                        .when_some(self.naming.clone(), |this, name| {
+                            this.child(
+                                div()
+                                    .key_context("LoopNameInput")
+                                    .track_focus(&self.focus_handle)
+                                    .w(px(160.0))
+                                    .px_2()
+                                    .py_1()
+                                    .rounded_md()
+                                    .bg(bg_input())
+                                    .border_1()
+                                    .border_color(input_focus_ring(true))
+                                    .text_sm()
+                                    .text_color(text_primary())
+                                    .child(name)
+                                    .on_key_down(cx.listener(
+                                        |this, evt: &KeyDownEvent, _window, cx| {
+                                            this.handle_naming_key(evt, cx);
+                                        },
+                                    )),
+                            )
+                        }),
+                )
+            })
+            // 已保存的片段：点击开始循环
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .flex_wrap()
+                    .justify_center()
+                    .gap_2()
+                    .children(self.loop_regions.iter().map(|region| {
+                        let (id, start, end) = (region.id, region.start, region.end);
+                        let active = ab_loop.and_then(|ab| ab.region()) == Some((start, end));
+                        div()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap_1()
+                            .px_3()
+                            .py_1()
+                            .rounded_lg()
+                            .text_xs()
+                            .text_color(text_secondary())
+                            .bg(if active { bg_active() } else { bg_card() })
+                            .border_1()
+                            .border_color(border_default())
+                            .cursor_pointer()
+                            .hover(|s| s.bg(bg_hover()))
+                            .child(format!(
+                                "{}（{} – {}）",
+                                region.name,
+                                format_duration(start.as_secs()),
+                                format_duration(end.as_secs())
+                            ))
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |_this, _evt, _window, cx| {
+                                    cx.global_mut::<Player>().set_ab_loop(start, end);
+                                    cx.notify();
+                                }),
+                            )
+                            .child(
+                                svg()
+                                    .path("svg/close.svg")
+                                    .size_3()
+                                    .text_color(text_tertiary())
+                                    .on_mouse_down(
+                                        MouseButton::Left,
+                                        cx.listener(move |this, _evt, _window, cx| {
+                                            cx.stop_propagation();
+                                            this.remove_loop_region(id, cx);
+                                        }),
+                                    ),
+                            )
+                    })),
+            )
+    }
+
     /// 渲染播放控制区域
     fn render_controls(&self, cx: &Context<Self>) -> impl IntoElement {
         let player = cx.global::<Player>();
@@ -237,10 +582,29 @@ impl PlayerDetail {
     }
 }
 
+/// 详情页中的文字按钮
+fn render_text_button(label: &'static str) -> Div {
+    div()
+        .px_3()
+        .py_1()
+        .rounded_lg()
+        .cursor_pointer()
+        .text_sm()
+        .text_color(text_secondary())
+        .bg(bg_card())
+        .hover(|s| s.bg(bg_hover()))
+        .child(label)
+}
+
 impl Render for PlayerDetail {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let player = cx.global::<Player>();
         let current_track = player.current_track().cloned();
+        self.refresh_loop_regions(current_track.as_ref().map(|t| t.id()), cx);
+        if self.naming.is_some() && self.pending_focus {
+            window.focus(&self.focus_handle);
+            self.pending_focus = false;
+        }
 
         div()
             .id("player-detail")
@@ -249,24 +613,44 @@ impl Render for PlayerDetail {
             .bg(bg_content())
             .flex()
             .flex_col()
-            // 关闭按钮
+            // 关闭按钮和可视化开关
             .child(
-                div().flex().items_start().justify_start().p_4().child(
-                    svg()
-                        .path("svg/close.svg")
-                        .id("close")
-                        .size_6()
-                        .text_color(text_tertiary())
-                        .rounded_full()
-                        .cursor_pointer()
-                        .hover(|s| s.bg(bg_active()))
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_start()
+                    .justify_between()
+                    .p_4()
+                    .child(
+                        svg()
+                            .path("svg/close.svg")
+                            .id("close")
+                            .size_6()
+                            .text_color(text_tertiary())
+                            .rounded_full()
+                            .cursor_pointer()
+                            .hover(|s| s.bg(bg_active()))
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _evt, _window, cx| {
+                                    this.hide(cx);
+                                }),
+                            ),
+                    )
+                    .child(
+                        render_text_button(if self.show_visualizer {
+                            "隐藏频谱"
+                        } else {
+                            "显示频谱"
+                        })
                         .on_mouse_down(
                             MouseButton::Left,
-                            cx.listener(move |this, _evt, _window, cx| {
-                                this.hide(cx);
+                            cx.listener(|this, _evt, _window, cx| {
+                                this.show_visualizer = !this.show_visualizer;
+                                cx.notify();
                             }),
                         ),
-                ),
+                    ),
             )
             // 主内容区域
             .child(
@@ -276,14 +660,28 @@ impl Render for PlayerDetail {
                     .items_center()
                     .justify_center()
                     .flex_1()
-                    // 封面
-                    .child(self.render_cover(current_track.as_ref()))
+                    // 封面和可视化
+                    .child(
+                        div()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap_8()
+                            .child(self.render_cover(current_track.as_ref(), window))
+                            .when(self.show_visualizer, |this| {
+                                this.child(self.render_visualizer(window, cx))
+                            }),
+                    )
                     // 歌曲信息
                     .child(self.render_track_info(current_track.as_ref()))
+                    // 评分
+                    .child(self.render_rating(current_track.as_ref(), cx))
                     // 进度条
                     .child(self.render_progress(cx))
                     // 播放控制
-                    .child(self.render_controls(cx)),
+                    .child(self.render_controls(cx))
+                    // A–B 循环
+                    .child(self.render_ab_loop(cx)),
             )
     }
 }
@@ -1,18 +1,69 @@
 use crate::{
-    play::player::{LoopMode, PlayState, Player},
+    config::Config,
+    db::{
+        cover::{self, CoverSize},
+        database::DB,
+        metadata::AlbumInfo,
+    },
+    play::{
+        ab_loop::AbLoop,
+        player::{LoopMode, PlayEvent, PlayState, PlayabilityChange, Player, SleepTimer},
+        waveform::{self, FileStamp},
+    },
     theme::*,
+    ui::{
+        loop_region::render_loop_region,
+        seekbar::{render_seekbar, seek_fraction},
+    },
     util::format_duration,
 };
 use gpui::{prelude::FluentBuilder, *};
-use std::sync::Arc;
+use std::{
+    cell::Cell,
+    rc::Rc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tracing::warn;
+use uuid::Uuid;
+
+/// 睡眠定时可选时长（分钟）
+const SLEEP_MINUTES: [u64; 5] = [15, 30, 45, 60, 90];
+/// 睡眠定时可选首数
+const SLEEP_TRACKS: [u32; 2] = [3, 5];
+/// 可选播放速度
+const PLAYBACK_RATES: [f32; 7] = [0.5, 0.75, 1.0, 1.25, 1.5, 1.75, 2.0];
 
 pub enum PlayBarMessage {
     NowPlayingClick,
+    /// 有歌曲解码失败被跳过，或重新变为可播放
+    PlayabilityChanged(Vec<PlayabilityChange>),
+    /// 歌曲开始或结束播放
+    Played(Vec<PlayEvent>),
 }
 
 pub struct PlayBar {
     /// 用于定时刷新 UI 的异步任务
     _poll_task: Option<Task<()>>,
+    /// 睡眠定时菜单的位置（None 表示未打开）
+    sleep_menu: Option<Point<Pixels>>,
+    /// 播放速度菜单的位置（None 表示未打开）
+    rate_menu: Option<Point<Pixels>>,
+    /// 波形对应的歌曲
+    waveform_track: Option<Uuid>,
+    /// 当前歌曲的波形峰值（None 表示仍在计算）
+    waveform: Option<Arc<Vec<u8>>>,
+    /// 后台计算波形的任务（切歌时丢弃）
+    _waveform_task: Option<Task<()>>,
+    /// 取消正在进行的波形解码（切歌时置位）
+    waveform_cancel: Arc<AtomicBool>,
+    /// 进度条区域（绘制时更新）
+    seekbar_bounds: Rc<Cell<Bounds<Pixels>>>,
+    /// 拖动进度条时预览的位置比例
+    seek_preview: Option<f32>,
 }
 
 impl EventEmitter<PlayBarMessage> for PlayBar {}
@@ -26,16 +77,36 @@ impl PlayBar {
                     .timer(std::time::Duration::from_millis(250))
                     .await;
 
-                // 更新 Player 状态（检查自动下一首）
-                let should_notify = cx
+                // 更新 Player 状态（检查自动下一首和睡眠定时）
+                let (should_notify, changes, events) = cx
                     .update(|cx: &mut App| {
                         cx.update_global::<Player, _>(|player: &mut Player, _cx: &mut App| {
+                            let state = player.play_state();
                             player.check_and_auto_next();
-                            // 只在播放时通知 UI 刷新（节省资源）
-                            player.is_playing()
+                            // 只在播放时或播放状态变化时通知 UI 刷新（节省资源）
+                            let should_notify = player.is_playing() || player.play_state() != state;
+                            (
+                                should_notify,
+                                player.take_playability_changes(),
+                                player.take_play_events(),
+                            )
                         })
                     })
-                    .unwrap_or(false);
+                    .unwrap_or_default();
+
+                if !changes.is_empty() || !events.is_empty() {
+                    let result = this.update(cx, |_this, cx| {
+                        if !changes.is_empty() {
+                            cx.emit(PlayBarMessage::PlayabilityChanged(changes));
+                        }
+                        if !events.is_empty() {
+                            cx.emit(PlayBarMessage::Played(events));
+                        }
+                    });
+                    if result.is_err() {
+                        break;
+                    }
+                }
 
                 if should_notify {
                     let result =
@@ -52,12 +123,331 @@ impl PlayBar {
 
         PlayBar {
             _poll_task: Some(task),
+            sleep_menu: None,
+            rate_menu: None,
+            waveform_track: None,
+            waveform: None,
+            _waveform_task: None,
+            waveform_cancel: Arc::new(AtomicBool::new(false)),
+            seekbar_bounds: Rc::new(Cell::new(Bounds::default())),
+            seek_preview: None,
+        }
+    }
+
+    /// 切歌时加载波形：优先读取缓存，没有缓存时在后台解码计算
+    fn refresh_waveform(&mut self, track: Option<&AlbumInfo>, cx: &mut Context<Self>) {
+        let id = track.map(|t| t.id());
+        if id == self.waveform_track {
+            return;
+        }
+        self.waveform_track = id;
+        self.waveform = None;
+        self._waveform_task = None;
+        // 丢弃任务不会打断已在后台线程上运行的解码，需要通知它停止
+        self.waveform_cancel.store(true, Ordering::Relaxed);
+        let Some(track) = track else {
+            return;
+        };
+
+        // 文件被替换或修改后缓存的波形作废，读不到文件信息时只计算不缓存
+        let (id, path) = (track.id(), track.path());
+        let stamp = FileStamp::of(&path)
+            .inspect_err(|e| warn!("读取文件信息失败: {}: {}", path.display(), e))
+            .ok();
+        if let Some(stamp) = stamp {
+            match cx.global::<DB>().load_waveform(&id, stamp) {
+                Ok(Some(peaks)) => {
+                    self.waveform = Some(Arc::new(peaks));
+                    return;
+                }
+                Ok(None) => {}
+                Err(e) => warn!("读取波形缓存失败: {}", e),
+            }
+        }
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.waveform_cancel = cancel.clone();
+        let compute = cx
+            .background_executor()
+            .spawn(async move { waveform::compute_peaks(&path, &cancel) });
+        self._waveform_task = Some(cx.spawn(
+            async move |this: WeakEntity<PlayBar>, cx: &mut AsyncApp| {
+                let peaks = match compute.await {
+                    Ok(Some(peaks)) => peaks,
+                    Ok(None) => return,
+                    Err(e) => {
+                        warn!("计算波形失败: {}", e);
+                        return;
+                    }
+                };
+                this.update(cx, |this, cx| {
+                    if let Some(stamp) = stamp
+                        && let Err(e) = cx.global::<DB>().save_waveform(&id, stamp, &peaks)
+                    {
+                        warn!("保存波形缓存失败: {}", e);
+                    }
+                    if this.waveform_track == Some(id) {
+                        this.waveform = Some(Arc::new(peaks));
+                        cx.notify();
+                    }
+                })
+                .ok();
+            },
+        ));
+    }
+
+    /// 松开鼠标时跳转到预览位置
+    fn commit_seek(&mut self, cx: &mut Context<Self>) {
+        let Some(fraction) = self.seek_preview.take() else {
+            return;
+        };
+        let player = cx.global_mut::<Player>();
+        if let Some(progress) = player.progress() {
+            player.seek_precise(Duration::from_secs(progress.duration).mul_f32(fraction));
         }
+        cx.notify();
+    }
+
+    fn close_rate_menu(&mut self, cx: &mut Context<Self>) {
+        self.rate_menu = None;
+        cx.notify();
+    }
+
+    /// 修改播放速度和变调，并保存到配置
+    fn set_rate_and_pitch(&mut self, rate: f32, semitones: f32, cx: &mut Context<Self>) {
+        let (rate, semitones) = cx.update_global::<Player, _>(|player, _cx| {
+            player.set_playback_rate(rate);
+            player.set_pitch_semitones(semitones);
+            (player.playback_rate(), player.pitch_semitones())
+        });
+        let play_info = &mut cx.global_mut::<Config>().play_info;
+        play_info.playback_rate = rate;
+        play_info.pitch_semitones = semitones;
+        cx.notify();
+    }
+
+    /// 播放速度和变调菜单
+    fn render_rate_menu(
+        &self,
+        position: Point<Pixels>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let player = cx.global::<Player>();
+        let current_rate = player.playback_rate();
+        let pitch = player.pitch_semitones();
+
+        let pitch_button = |label: &'static str, semitones: f32| {
+            div()
+                .w(px(24.0))
+                .text_center()
+                .rounded_md()
+                .cursor_pointer()
+                .hover(|s| s.bg(bg_active()))
+                .child(label)
+                .on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(move |this, _evt, _window, cx| {
+                        this.set_rate_and_pitch(current_rate, semitones, cx);
+                    }),
+                )
+        };
+
+        deferred(
+            anchored()
+                .position(position)
+                .anchor(Corner::BottomLeft)
+                .snap_to_window()
+                .child(
+                    render_popup("rate-menu")
+                        .on_mouse_down_out(cx.listener(|this, _evt, _window, cx| {
+                            this.close_rate_menu(cx);
+                        }))
+                        .children(PLAYBACK_RATES.into_iter().map(|rate| {
+                            render_menu_item(format_rate(rate), (rate - current_rate).abs() < 0.01)
+                                .on_mouse_down(
+                                    MouseButton::Left,
+                                    cx.listener(move |this, _evt, _window, cx| {
+                                        this.set_rate_and_pitch(rate, pitch, cx);
+                                        this.close_rate_menu(cx);
+                                    }),
+                                )
+                        }))
+                        .child(div().my_1().h(px(1.0)).bg(border_light()))
+                        // 变调：点击数值恢复原调
+                        .child(
+                            div()
+                                .px_3()
+                                .py_2()
+                                .flex()
+                                .flex_row()
+                                .items_center()
+                                .gap_1()
+                                .text_sm()
+                                .text_color(text_secondary())
+                                .child(div().mr_auto().child("变调"))
+                                .child(pitch_button("−", pitch - 1.0))
+                                .child(
+                                    div()
+                                        .w(px(32.0))
+                                        .text_center()
+                                        .cursor_pointer()
+                                        .child(if pitch == 0.0 {
+                                            "0".to_string()
+                                        } else {
+                                            format!("{:+}", pitch)
+                                        })
+                                        .on_mouse_down(
+                                            MouseButton::Left,
+                                            cx.listener(move |this, _evt, _window, cx| {
+                                                this.set_rate_and_pitch(current_rate, 0.0, cx);
+                                            }),
+                                        ),
+                                )
+                                .child(pitch_button("+", pitch + 1.0)),
+                        ),
+                ),
+        )
+    }
+
+    fn close_sleep_menu(&mut self, cx: &mut Context<Self>) {
+        self.sleep_menu = None;
+        cx.notify();
+    }
+
+    /// 睡眠定时菜单
+    fn render_sleep_menu(
+        &self,
+        position: Point<Pixels>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let player = cx.global::<Player>();
+        let timer = player.sleep_timer();
+        let fade = player.sleep_fade();
+        let stop_after_current = player.stop_after_current();
+
+        deferred(
+            anchored()
+                .position(position)
+                .anchor(Corner::BottomLeft)
+                .snap_to_window()
+                .child(
+                    render_popup("sleep-menu")
+                        .on_mouse_down_out(cx.listener(|this, _evt, _window, cx| {
+                            this.close_sleep_menu(cx);
+                        }))
+                        .child(
+                            render_menu_item("播完本首后停止", stop_after_current).on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|this, _evt, _window, cx| {
+                                    cx.global_mut::<Player>().toggle_stop_after_current();
+                                    this.close_sleep_menu(cx);
+                                }),
+                            ),
+                        )
+                        .child(div().my_1().h(px(1.0)).bg(border_light()))
+                        .children(SLEEP_MINUTES.into_iter().map(|minutes| {
+                            render_menu_item(format!("{} 分钟后", minutes), false).on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _evt, _window, cx| {
+                                    cx.global_mut::<Player>().sleep_after_minutes(minutes);
+                                    this.close_sleep_menu(cx);
+                                }),
+                            )
+                        }))
+                        .child(
+                            render_menu_item("本首结束时", timer == Some(SleepTimer::EndOfTrack))
+                                .on_mouse_down(
+                                    MouseButton::Left,
+                                    cx.listener(|this, _evt, _window, cx| {
+                                        cx.global_mut::<Player>()
+                                            .set_sleep_timer(Some(SleepTimer::EndOfTrack));
+                                        this.close_sleep_menu(cx);
+                                    }),
+                                ),
+                        )
+                        .children(SLEEP_TRACKS.into_iter().map(|tracks| {
+                            render_menu_item(format!("再播放 {} 首", tracks), false).on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _evt, _window, cx| {
+                                    cx.global_mut::<Player>()
+                                        .set_sleep_timer(Some(SleepTimer::Tracks(tracks)));
+                                    this.close_sleep_menu(cx);
+                                }),
+                            )
+                        }))
+                        .child(div().my_1().h(px(1.0)).bg(border_light()))
+                        .child(render_menu_item("结束前淡出", fade).on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |this, _evt, _window, cx| {
+                                cx.global_mut::<Player>().set_sleep_fade(!fade);
+                                cx.global_mut::<Config>().play_info.sleep_fade = !fade;
+                                this.close_sleep_menu(cx);
+                            }),
+                        ))
+                        .when(timer.is_some(), |this| {
+                            this.child(render_menu_item("取消定时", false).on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|this, _evt, _window, cx| {
+                                    cx.global_mut::<Player>().set_sleep_timer(None);
+                                    this.close_sleep_menu(cx);
+                                }),
+                            ))
+                        }),
+                ),
+        )
+    }
+}
+
+/// 弹出菜单容器
+fn render_popup(id: &'static str) -> Stateful<Div> {
+    div()
+        .id(id)
+        .min_w(px(160.0))
+        .bg(bg_content())
+        .rounded_lg()
+        .shadow_md()
+        .border_1()
+        .border_color(border_default())
+        .py_1()
+}
+
+/// 弹出菜单项，checked 时在右侧显示对勾
+fn render_menu_item(label: impl Into<SharedString>, checked: bool) -> Div {
+    div()
+        .px_3()
+        .py_2()
+        .flex()
+        .flex_row()
+        .justify_between()
+        .gap_4()
+        .text_sm()
+        .text_color(text_secondary())
+        .cursor_pointer()
+        .hover(|s| s.bg(bg_active()))
+        .child(label.into())
+        .when(checked, |this| this.child("✓"))
+}
+
+/// 播放速度文字，如 1.0×、1.25×
+fn format_rate(rate: f32) -> String {
+    let text = format!("{:.2}", rate);
+    format!("{}×", text.strip_suffix('0').unwrap_or(&text))
+}
+
+/// 睡眠定时倒计时（按首数计时显示剩余首数）
+fn sleep_countdown(player: &Player) -> Option<String> {
+    match player.sleep_timer()? {
+        SleepTimer::Tracks(n) if n > 1 => Some(format!("剩 {} 首", n)),
+        _ => player
+            .sleep_remaining()
+            .map(|remaining| format_duration(remaining.as_secs())),
     }
 }
 
 impl Render for PlayBar {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let track = cx.global::<Player>().current_track().cloned();
+        self.refresh_waveform(track.as_ref(), cx);
         let player = cx.global::<Player>();
 
         // 获取当前播放信息
@@ -78,11 +468,22 @@ impl Render for PlayBar {
             LoopMode::Random => "svg/random.svg",
         };
 
-        let cover_64 = current_track.as_ref().and_then(|t| t.cover_64());
+        let cover = current_track.as_ref().and_then(|t| t.cover());
+        let cover_size = CoverSize::fit(COVER_THUMB_SIZE * window.scale_factor());
 
         // 获取播放进度
         let progress = player.progress();
 
+        let sleep_active = player.sleep_timer().is_some() || player.stop_after_current();
+        let countdown = sleep_countdown(player);
+        let rate_changed = player.playback_rate() != 1.0 || player.pitch_semitones() != 0.0;
+        let rate_label = format_rate(player.playback_rate());
+        let ab_loop = player.ab_loop();
+        let ab_label = match ab_loop {
+            Some(AbLoop { b: None, .. }) => "A–",
+            _ => "A–B",
+        };
+
         div()
             .w_full()
             .h(Pixels::from(PLAYBAR_HEIGHT))
@@ -92,21 +493,51 @@ impl Render for PlayBar {
             .flex_shrink_0()
             .border_t_1()
             .border_color(border_light())
-            // 进度条
+            // 进度条（点击或拖动跳转）
             .child(
                 div()
+                    .id("seekbar")
+                    .relative()
                     .w_full()
-                    .h(Pixels::from(3.0))
-                    .bg(bg_hover())
+                    .h(Pixels::from(SEEKBAR_HEIGHT))
                     .flex_shrink_0()
-                    .child(
-                        div()
-                            .h_full()
-                            .w(relative(
-                                progress.as_ref().map(|p| p.progress).unwrap_or(0.0),
-                            ))
-                            .bg(accent_blue()),
-                    ),
+                    .when(progress.is_some(), |this| this.cursor_pointer())
+                    .child(render_seekbar(
+                        self.waveform.clone(),
+                        self.seek_preview
+                            .or(progress.as_ref().map(|p| p.progress))
+                            .unwrap_or(0.0),
+                        self.seekbar_bounds.clone(),
+                    ))
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, evt: &MouseDownEvent, _window, cx| {
+                            if cx.global::<Player>().progress().is_some() {
+                                this.seek_preview =
+                                    Some(seek_fraction(this.seekbar_bounds.get(), evt.position));
+                                cx.notify();
+                            }
+                        }),
+                    )
+                    .on_mouse_move(cx.listener(|this, evt: &MouseMoveEvent, _window, cx| {
+                        if this.seek_preview.is_some() && evt.dragging() {
+                            this.seek_preview =
+                                Some(seek_fraction(this.seekbar_bounds.get(), evt.position));
+                            cx.notify();
+                        }
+                    }))
+                    .on_mouse_up(
+                        MouseButton::Left,
+                        cx.listener(|this, _evt, _window, cx| this.commit_seek(cx)),
+                    )
+                    .on_mouse_up_out(
+                        MouseButton::Left,
+                        cx.listener(|this, _evt, _window, cx| this.commit_seek(cx)),
+                    )
+                    .children(render_loop_region(
+                        ab_loop,
+                        progress.as_ref().map(|p| p.duration).unwrap_or(0),
+                    )),
             )
             // 主内容区
             .child(
@@ -136,15 +567,12 @@ impl Render for PlayBar {
                                     .rounded_md()
                                     .cursor_pointer()
                                     .when_else(
-                                        cover_64.is_some(),
+                                        cover.is_some(),
                                         |this| {
                                             this.child(
-                                                img(Arc::new(Image::from_bytes(
-                                                    ImageFormat::Jpeg,
-                                                    cover_64.unwrap().to_vec(),
-                                                )))
-                                                .size_full()
-                                                .rounded_md(),
+                                                img(cover::image(cover.unwrap(), cover_size))
+                                                    .size_full()
+                                                    .rounded_md(),
                                             )
                                         },
                                         |this| {
@@ -209,6 +637,77 @@ impl Render for PlayBar {
                             .items_center()
                             .gap_5()
                             .mr_5()
+                            // 播放速度和变调
+                            .child(
+                                div()
+                                    .w(px(40.0))
+                                    .text_sm()
+                                    .text_center()
+                                    .cursor_pointer()
+                                    .text_color(if rate_changed {
+                                        accent_blue()
+                                    } else {
+                                        text_secondary()
+                                    })
+                                    .child(rate_label)
+                                    .on_mouse_down(
+                                        MouseButton::Left,
+                                        cx.listener(|this, evt: &MouseDownEvent, _window, cx| {
+                                            this.rate_menu = Some(evt.position);
+                                            cx.notify();
+                                        }),
+                                    ),
+                            )
+                            // A–B 循环：依次设置 A 点、B 点、清除
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .cursor_pointer()
+                                    .text_color(if ab_loop.is_some() {
+                                        accent_blue()
+                                    } else {
+                                        text_secondary()
+                                    })
+                                    .child(ab_label)
+                                    .on_mouse_down(
+                                        MouseButton::Left,
+                                        cx.listener(|_this, _evt, _window, cx| {
+                                            cx.global_mut::<Player>().cycle_ab_loop();
+                                            cx.notify();
+                                        }),
+                                    ),
+                            )
+                            // 睡眠定时（右侧显示倒计时）
+                            .child(
+                                div()
+                                    .flex()
+                                    .flex_row()
+                                    .items_center()
+                                    .gap_1()
+                                    .cursor_pointer()
+                                    .child(svg().path("svg/moon.svg").size_5().text_color(
+                                        if sleep_active {
+                                            accent_blue()
+                                        } else {
+                                            text_secondary()
+                                        },
+                                    ))
+                                    .when_some(countdown, |this, countdown| {
+                                        this.child(
+                                            div()
+                                                .text_xs()
+                                                .text_color(text_tertiary())
+                                                .child(countdown),
+                                        )
+                                    })
+                                    .on_mouse_down(
+                                        MouseButton::Left,
+                                        cx.listener(|this, evt: &MouseDownEvent, _window, cx| {
+                                            this.sleep_menu = Some(evt.position);
+                                            cx.notify();
+                                        }),
+                                    ),
+                            )
                             // 循环模式
                             .child(
                                 svg()
@@ -275,5 +774,11 @@ impl Render for PlayBar {
                             ),
                     ),
             )
+            .when_some(self.sleep_menu, |this, position| {
+                this.child(self.render_sleep_menu(position, cx))
+            })
+            .when_some(self.rate_menu, |this, position| {
+                this.child(self.render_rate_menu(position, cx))
+            })
     }
 }
@@ -1,14 +1,187 @@
-use gpui::*;
+use gpui::{prelude::FluentBuilder, *};
 use rfd::AsyncFileDialog;
+use std::sync::Arc;
 
-use crate::{config::Config, db::database::DB, theme::*};
+use crate::{
+    api,
+    config::Config,
+    db::{
+        database::DB,
+        dbstate::LibraryState,
+        metadata::AlbumInfo,
+        playlist::{self, ImportReport, PathStyle, PlaylistFormat},
+    },
+    error::warn,
+    log::{self, LogLevel},
+    notify::{self, Level},
+    play::{
+        dsp::{EffectKind, EffectSlot},
+        equalizer::{EQ_BANDS, EQ_GAIN_RANGE, EqPreset, EqSettings},
+        output,
+        player::Player,
+        shuffle::ShuffleMode,
+    },
+    theme::*,
+};
 
-pub struct Setting;
+/// 设置页事件
+#[derive(Clone, Copy)]
+pub enum SettingEvent {
+    /// 歌单列表发生变化（导入后）
+    PlaylistsChanged,
+}
+
+/// 可导出的歌单来源
+#[derive(Clone)]
+enum ExportSource {
+    Favorite,
+    History,
+    Custom(i64, SharedString),
+}
+
+pub struct Setting {
+    /// LibraryState 引用（导出收藏/历史时读取）
+    library_state: Entity<LibraryState>,
+    /// 自定义歌单（ID, 名称）
+    playlists: Vec<(i64, SharedString)>,
+    /// 导出时是否写入相对路径
+    export_relative: bool,
+    /// 最近一次导入结果
+    import_report: Option<ImportReport>,
+    /// 可用的音频输出设备
+    output_devices: Vec<String>,
+    /// 正在输入的均衡器预设名称（None 表示未在保存）
+    preset_name: Option<String>,
+    /// 打开名称输入框后下一次渲染时获取焦点
+    pending_focus: bool,
+    /// 是否明文显示 API 令牌
+    show_api_token: bool,
+    focus_handle: FocusHandle,
+}
+
+impl EventEmitter<SettingEvent> for Setting {}
 
 impl Setting {
+    pub fn new(library_state: Entity<LibraryState>, cx: &mut Context<Self>) -> Self {
+        let mut setting = Self {
+            library_state,
+            playlists: Vec::new(),
+            export_relative: false,
+            import_report: None,
+            output_devices: Vec::new(),
+            preset_name: None,
+            pending_focus: false,
+            show_api_token: false,
+            focus_handle: cx.focus_handle(),
+        };
+        setting.reload_playlists(cx);
+        setting.refresh_output_devices(cx);
+        setting
+    }
+
+    /// 在后台重新获取输出设备列表（枚举设备可能较慢）
+    fn refresh_output_devices(&mut self, cx: &mut Context<Self>) {
+        let devices = cx
+            .background_executor()
+            .spawn(async { output::output_devices() });
+        cx.spawn(async move |this: WeakEntity<Setting>, cx: &mut AsyncApp| {
+            let devices = devices.await;
+            this.update(cx, |this, cx| {
+                this.output_devices = devices;
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// 切换输出设备并写入配置
+    fn set_output_device(&mut self, device: Option<String>, cx: &mut Context<Self>) {
+        cx.global_mut::<Player>().set_output_device(device.clone());
+        cx.global_mut::<Config>().play_info.output_device = device;
+        cx.notify();
+    }
+
+    /// 从数据库重新加载自定义歌单列表
+    fn reload_playlists(&mut self, cx: &mut Context<Self>) {
+        match cx.global::<DB>().load_playlists() {
+            Ok(playlists) => self.playlists = playlists,
+            Err(e) => warn(&e, "加载歌单失败"),
+        }
+    }
+
+    /// 选择歌单文件并导入为自定义歌单
+    fn import_playlist(&self, cx: &mut Context<Self>) {
+        cx.spawn(async move |this: WeakEntity<Setting>, cx: &mut AsyncApp| {
+            let file = AsyncFileDialog::new()
+                .set_title("导入歌单")
+                .add_filter("歌单", PlaylistFormat::extensions())
+                .pick_file()
+                .await;
+
+            if let Some(file) = file {
+                let path = file.path().to_path_buf();
+                this.update(cx, |this, cx| {
+                    match cx.global::<DB>().import_playlist(&path) {
+                        Ok(report) => {
+                            this.import_report = Some(report);
+                            this.reload_playlists(cx);
+                            cx.emit(SettingEvent::PlaylistsChanged);
+                        }
+                        Err(e) => warn(&e, "导入歌单失败"),
+                    }
+                    cx.notify();
+                })
+                .ok();
+            }
+        })
+        .detach();
+    }
+
+    /// 选择保存位置并导出歌单（格式由扩展名决定）
+    fn export_playlist(&self, source: ExportSource, cx: &mut Context<Self>) {
+        let (name, items): (SharedString, Arc<Vec<AlbumInfo>>) = match &source {
+            ExportSource::Favorite => ("收藏".into(), self.library_state.read(cx).favorites()),
+            ExportSource::History => ("历史".into(), self.library_state.read(cx).history()),
+            ExportSource::Custom(id, name) => match cx.global::<DB>().load_playlist_items(*id) {
+                Ok(items) => (name.clone(), Arc::new(items)),
+                Err(e) => {
+                    warn(&e, "加载歌单失败");
+                    return;
+                }
+            },
+        };
+        let style = if self.export_relative {
+            PathStyle::Relative
+        } else {
+            PathStyle::Absolute
+        };
+
+        cx.spawn(
+            async move |_this: WeakEntity<Setting>, _cx: &mut AsyncApp| {
+                let file = AsyncFileDialog::new()
+                    .set_title("导出歌单")
+                    .set_file_name(format!("{}.m3u8", name))
+                    .add_filter("M3U8", &["m3u8"])
+                    .add_filter("M3U", &["m3u"])
+                    .add_filter("PLS", &["pls"])
+                    .add_filter("XSPF", &["xspf"])
+                    .save_file()
+                    .await;
+
+                if let Some(file) = file
+                    && let Err(e) = playlist::write_playlist(&items, &name, file.path(), style)
+                {
+                    warn(&e, "导出歌单失败");
+                }
+            },
+        )
+        .detach();
+    }
+
     /// 打开文件夹选择对话框并更新音乐目录
     fn pick_music_folder(&self, cx: &mut Context<Self>) {
-        cx.spawn(async move |_this: WeakEntity<Setting>, cx: &mut AsyncApp| {
+        cx.spawn(async move |this: WeakEntity<Setting>, cx: &mut AsyncApp| {
             let folder = AsyncFileDialog::new()
                 .set_title("选择音乐文件夹")
                 .pick_folder()
@@ -16,29 +189,890 @@ impl Setting {
 
             if let Some(folder) = folder {
                 let path = folder.path().to_string_lossy().to_string();
-                cx.update(|cx: &mut App| {
+                let Ok(scan) = cx.update(|cx: &mut App| {
                     // 更新配置
                     cx.update_global::<Config, _>(|config, _cx| {
                         config.media_file.music_directory = SharedString::new(path.clone());
                     });
 
-                    // 扫描并添加到数据库
-                    cx.update_global::<DB, _>(|db, _cx| {
-                        if let Err(e) = db.add_metadata_to_library(&path) {
-                            eprintln!("[WARN] 扫描音乐文件夹失败: {}", e);
-                        }
-                    });
-                })
-                .ok();
+                    // 在后台扫描并添加到数据库
+                    cx.global::<DB>().scan_in_background(path, cx)
+                }) else {
+                    return;
+                };
+
+                // 扫描完成后更新曲库
+                if scan.await.is_ok() {
+                    this.update(cx, |this, cx| {
+                        let items = cx.global::<DB>().load_all_albums();
+                        this.library_state
+                            .update(cx, |state, cx| state.update_library(items, cx));
+                    })
+                    .ok();
+                }
             }
         })
         .detach();
     }
 }
 
+impl Setting {
+    /// 渲染歌单导入导出区域
+    fn render_playlist_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let mut sources = vec![ExportSource::Favorite, ExportSource::History];
+        sources.extend(
+            self.playlists
+                .iter()
+                .map(|(id, name)| ExportSource::Custom(*id, name.clone())),
+        );
+
+        div()
+            .mb_4()
+            .child(
+                div()
+                    .text_sm()
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(text_secondary())
+                    .mb_2()
+                    .child("歌单"),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .flex_wrap()
+                    .items_center()
+                    .gap_3()
+                    .child(render_button("import-playlist", "导入歌单").on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, _evt, _window, cx| {
+                            this.import_playlist(cx);
+                        }),
+                    ))
+                    .children(sources.into_iter().enumerate().map(|(idx, source)| {
+                        let label = match &source {
+                            ExportSource::Favorite => "导出收藏".to_string(),
+                            ExportSource::History => "导出历史".to_string(),
+                            ExportSource::Custom(_, name) => format!("导出「{}」", name),
+                        };
+                        render_button(ElementId::Name(format!("export-{}", idx).into()), label)
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _evt, _window, cx| {
+                                    this.export_playlist(source.clone(), cx);
+                                }),
+                            )
+                    })),
+            )
+            // 相对/绝对路径切换
+            .child(
+                div()
+                    .id("export-relative")
+                    .mt_3()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_2()
+                    .cursor_pointer()
+                    .text_sm()
+                    .text_color(text_secondary())
+                    .child(
+                        div()
+                            .size(px(16.0))
+                            .rounded_sm()
+                            .border_1()
+                            .border_color(border_default())
+                            .when(self.export_relative, |this| this.bg(accent_blue())),
+                    )
+                    .child("导出时使用相对路径")
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, _evt, _window, cx| {
+                            this.export_relative = !this.export_relative;
+                            cx.notify();
+                        }),
+                    ),
+            )
+            // 最近一次导入结果
+            .when_some(self.import_report.as_ref(), |this, report| {
+                this.child(
+                    div()
+                        .mt_3()
+                        .text_xs()
+                        .text_color(text_tertiary())
+                        .child(format!(
+                            "已导入「{}」：匹配 {} 首，未匹配 {} 首",
+                            report.name,
+                            report.matched,
+                            report.unmatched.len()
+                        ))
+                        .children(report.unmatched.iter().map(|location| {
+                            div()
+                                .text_color(text_placeholder())
+                                .truncate()
+                                .child(location.clone())
+                        })),
+                )
+            })
+    }
+}
+
+impl Setting {
+    /// 渲染日志区域：日志级别和复制诊断信息
+    fn render_log_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let current = cx.global::<Config>().log.level;
+
+        div()
+            .mb_4()
+            .child(
+                div()
+                    .text_sm()
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(text_secondary())
+                    .mb_2()
+                    .child("日志"),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .flex_wrap()
+                    .items_center()
+                    .gap_2()
+                    .children(LogLevel::all().into_iter().map(|level| {
+                        div()
+                            .id(SharedString::from(format!("log-level-{}", level.label())))
+                            .px_3()
+                            .py_1()
+                            .rounded_lg()
+                            .cursor_pointer()
+                            .text_sm()
+                            .text_color(text_secondary())
+                            .hover(|s| s.bg(bg_hover()))
+                            .when(level == current, |this| this.bg(bg_active()))
+                            .child(level.label())
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |_this, _evt, _window, cx| {
+                                    log::set_level(level);
+                                    cx.global_mut::<Config>().log.level = level;
+                                    cx.notify();
+                                }),
+                            )
+                    }))
+                    .child(
+                        div()
+                            .id("copy-diagnostics")
+                            .px_3()
+                            .py_1()
+                            .rounded_lg()
+                            .cursor_pointer()
+                            .text_sm()
+                            .text_color(text_secondary())
+                            .bg(bg_input())
+                            .hover(|s| s.bg(bg_hover()))
+                            .child("复制诊断信息")
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|_this, _evt, _window, cx| {
+                                    let diagnostics = log::diagnostics(cx);
+                                    cx.write_to_clipboard(ClipboardItem::new_string(diagnostics));
+                                    notify::post(Level::Success, "诊断信息已复制到剪贴板", None);
+                                }),
+                            ),
+                    ),
+            )
+            .child(
+                div()
+                    .mt_2()
+                    .text_xs()
+                    .text_color(text_tertiary())
+                    .child(format!("日志文件: {}", log::log_dir().display())),
+            )
+    }
+
+    /// 渲染远程控制 API 区域：运行状态和访问令牌（显示、复制、重新生成）
+    fn render_api_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let config = &cx.global::<Config>().http_api;
+        let status = if config.enabled {
+            format!("已开启，监听 {}（修改后重启生效）", config.address)
+        } else {
+            "未开启（在配置文件中设置 http_api.enabled 后重启生效）".to_string()
+        };
+        let token = config.token.clone();
+        let token_text = if token.is_empty() {
+            "尚未生成".to_string()
+        } else if self.show_api_token {
+            token.clone()
+        } else {
+            "•".repeat(token.chars().count())
+        };
+        let chip = |id: &'static str, label: &'static str| {
+            div()
+                .id(id)
+                .px_3()
+                .py_1()
+                .rounded_lg()
+                .cursor_pointer()
+                .text_sm()
+                .text_color(text_secondary())
+                .bg(bg_input())
+                .hover(|s| s.bg(bg_hover()))
+                .child(label)
+        };
+
+        div()
+            .mb_4()
+            .child(
+                div()
+                    .text_sm()
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(text_secondary())
+                    .mb_2()
+                    .child("远程控制 API"),
+            )
+            .child(
+                div()
+                    .mb_2()
+                    .text_xs()
+                    .text_color(text_tertiary())
+                    .child(status),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_2()
+                    .child(
+                        div()
+                            .flex_1()
+                            .h(px(SETTING_ITEM_HEIGHT))
+                            .bg(bg_input())
+                            .rounded_lg()
+                            .flex()
+                            .items_center()
+                            .px_4()
+                            .text_sm()
+                            .text_color(text_secondary())
+                            .truncate()
+                            .child(token_text),
+                    )
+                    .when(!token.is_empty(), |this| {
+                        this.child(
+                            chip(
+                                "api-token-show",
+                                if self.show_api_token {
+                                    "隐藏"
+                                } else {
+                                    "显示"
+                                },
+                            )
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|this, _evt, _window, cx| {
+                                    this.show_api_token = !this.show_api_token;
+                                    cx.notify();
+                                }),
+                            ),
+                        )
+                        .child(
+                            chip("api-token-copy", "复制").on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |_this, _evt, _window, cx| {
+                                    cx.write_to_clipboard(ClipboardItem::new_string(token.clone()));
+                                    notify::post(Level::Success, "令牌已复制到剪贴板", None);
+                                }),
+                            ),
+                        )
+                    })
+                    .child(chip("api-token-regenerate", "重新生成").on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|_this, _evt, _window, cx| {
+                            api::regenerate_token(cx);
+                            notify::post(Level::Success, "已生成新令牌，旧令牌立即失效", None);
+                            cx.notify();
+                        }),
+                    )),
+            )
+    }
+
+    /// 渲染输出设备选择区域
+    fn render_output_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let selected = cx.global::<Config>().play_info.output_device.clone();
+        let fallback = cx.global::<Player>().output_fallback();
+        let devices = std::iter::once(None).chain(self.output_devices.iter().cloned().map(Some));
+
+        div()
+            .mb_4()
+            .child(
+                div()
+                    .text_sm()
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(text_secondary())
+                    .mb_2()
+                    .child("输出设备"),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .flex_wrap()
+                    .items_center()
+                    .gap_2()
+                    .children(devices.enumerate().map(|(idx, device)| {
+                        let label = device.clone().unwrap_or_else(|| "系统默认".to_string());
+                        div()
+                            .id(ElementId::Name(format!("output-device-{}", idx).into()))
+                            .px_3()
+                            .py_1()
+                            .rounded_lg()
+                            .cursor_pointer()
+                            .text_sm()
+                            .text_color(text_secondary())
+                            .hover(|s| s.bg(bg_hover()))
+                            .when(device == selected, |this| this.bg(bg_active()))
+                            .child(label)
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _evt, _window, cx| {
+                                    this.set_output_device(device.clone(), cx);
+                                }),
+                            )
+                    }))
+                    .child(
+                        div()
+                            .id("refresh-output-devices")
+                            .px_3()
+                            .py_1()
+                            .rounded_lg()
+                            .cursor_pointer()
+                            .text_sm()
+                            .text_color(text_tertiary())
+                            .hover(|s| s.bg(bg_hover()))
+                            .child("刷新")
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|this, _evt, _window, cx| {
+                                    this.refresh_output_devices(cx);
+                                }),
+                            ),
+                    ),
+            )
+            // 选择的设备不可用时提示已回退
+            .when(fallback, |this| {
+                this.child(
+                    div()
+                        .mt_2()
+                        .text_xs()
+                        .text_color(text_tertiary())
+                        .child("所选设备不可用，正在使用系统默认设备"),
+                )
+            })
+    }
+
+    /// 修改均衡器设置，立即生效并写入配置
+    fn set_equalizer(&mut self, settings: EqSettings, cx: &mut Context<Self>) {
+        cx.global_mut::<Player>().set_equalizer(settings.clone());
+        cx.global_mut::<Config>().equalizer.settings = settings;
+        cx.notify();
+    }
+
+    /// 把当前均衡器设置保存为用户预设（同名时覆盖）
+    fn save_preset(&mut self, cx: &mut Context<Self>) {
+        let Some(name) = self.preset_name.take() else {
+            return;
+        };
+        let name = name.trim();
+        if !name.is_empty() {
+            let settings = cx.global::<Player>().equalizer().clone();
+            let presets = &mut cx.global_mut::<Config>().equalizer.presets;
+            presets.retain(|preset| preset.name != name);
+            presets.push(EqPreset {
+                name: name.to_string(),
+                preamp: settings.preamp,
+                gains: settings.gains,
+            });
+        }
+        cx.notify();
+    }
+
+    fn handle_preset_name_key(&mut self, evt: &KeyDownEvent, cx: &mut Context<Self>) {
+        let Some(name) = &mut self.preset_name else {
+            return;
+        };
+        match evt.keystroke.key.as_str() {
+            "escape" => self.preset_name = None,
+            "enter" => self.save_preset(cx),
+            "backspace" => {
+                name.pop();
+            }
+            _ => {
+                if let Some(key_char) = &evt.keystroke.key_char {
+                    name.push_str(key_char);
+                }
+            }
+        }
+        cx.notify();
+    }
+
+    /// 渲染均衡器区域
+    fn render_equalizer_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let settings = cx.global::<Player>().equalizer().clone();
+        let user_presets = cx.global::<Config>().equalizer.presets.clone();
+        let presets = EqPreset::builtin()
+            .into_iter()
+            .map(|preset| (preset, false))
+            .chain(user_presets.into_iter().map(|preset| (preset, true)));
+
+        div()
+            .mb_4()
+            .child(
+                div()
+                    .text_sm()
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(text_secondary())
+                    .mb_2()
+                    .child("均衡器"),
+            )
+            // 开关
+            .child(
+                div()
+                    .id("eq-enabled")
+                    .mb_3()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_2()
+                    .cursor_pointer()
+                    .text_sm()
+                    .text_color(text_secondary())
+                    .child(
+                        div()
+                            .size(px(16.0))
+                            .rounded_sm()
+                            .border_1()
+                            .border_color(border_default())
+                            .when(settings.enabled, |this| this.bg(accent_blue())),
+                    )
+                    .child("启用均衡器")
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, _evt, _window, cx| {
+                            let mut settings = cx.global::<Player>().equalizer().clone();
+                            settings.enabled = !settings.enabled;
+                            this.set_equalizer(settings, cx);
+                        }),
+                    ),
+            )
+            // 预设
+            .child(
+                div()
+                    .mb_3()
+                    .flex()
+                    .flex_row()
+                    .flex_wrap()
+                    .items_center()
+                    .gap_2()
+                    .children(presets.enumerate().map(|(idx, (preset, custom))| {
+                        let active = settings.matches(&preset);
+                        let name = preset.name.clone();
+                        div()
+                            .id(ElementId::Name(format!("eq-preset-{}", idx).into()))
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap_1()
+                            .px_3()
+                            .py_1()
+                            .rounded_lg()
+                            .cursor_pointer()
+                            .text_sm()
+                            .text_color(text_secondary())
+                            .hover(|s| s.bg(bg_hover()))
+                            .when(active, |this| this.bg(bg_active()))
+                            .child(preset.name.clone())
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _evt, _window, cx| {
+                                    let mut settings = cx.global::<Player>().equalizer().clone();
+                                    settings.apply_preset(&preset);
+                                    settings.enabled = true;
+                                    this.set_equalizer(settings, cx);
+                                }),
+                            )
+                            .when(custom, |this| {
+                                this.child(
+                                    svg()
+                                        .path("svg/close.svg")
+                                        .size_3()
+                                        .text_color(text_tertiary())
+                                        .on_mouse_down(
+                                            MouseButton::Left,
+                                            cx.listener(move |_this, _evt, _window, cx| {
+                                                cx.stop_propagation();
+                                                cx.global_mut::<Config>()
+                                                    .equalizer
+                                                    .presets
+                                                    .retain(|preset| preset.name != name);
+                                                cx.notify();
+                                            }),
+                                        ),
+                                )
+                            })
+                    }))
+                    .child(match self.preset_name.clone() {
+                        None => div()
+                            .id("eq-save-preset")
+                            .px_3()
+                            .py_1()
+                            .rounded_lg()
+                            .cursor_pointer()
+                            .text_sm()
+                            .text_color(text_tertiary())
+                            .hover(|s| s.bg(bg_hover()))
+                            .child("保存为预设")
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|this, _evt, _window, cx| {
+                                    let count = cx.global::<Config>().equalizer.presets.len();
+                                    this.preset_name = Some(format!("自定义 {}", count + 1));
+                                    this.pending_focus = true;
+                                    cx.notify();
+                                }),
+                            ),
+                        // 预设名称输入框（回车保存，Esc 取消）
+                        Some(name) => div()
+                            .id("eq-preset-name")
+                            .key_context("EqPresetInput")
+                            .track_focus(&self.focus_handle)
+                            .w(px(160.0))
+                            .px_2()
+                            .py_1()
+                            .rounded_md()
+                            .bg(bg_input())
+                            .border_1()
+                            .border_color(input_focus_ring(true))
+                            .text_sm()
+                            .text_color(text_primary())
+                            .child(name)
+                            .on_key_down(cx.listener(|this, evt: &KeyDownEvent, _window, cx| {
+                                this.handle_preset_name_key(evt, cx);
+                            })),
+                    }),
+            )
+            // 前级增益和各频段
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_end()
+                    .gap_2()
+                    .when(!settings.enabled, |this| this.opacity(0.5))
+                    .child(render_eq_band("前级".into(), EqGain::Preamp, &settings, cx))
+                    .child(div().w(px(1.0)).h(px(120.0)).bg(border_default()))
+                    .children(EQ_BANDS.iter().enumerate().map(|(band, &frequency)| {
+                        render_eq_band(
+                            format_frequency(frequency),
+                            EqGain::Band(band),
+                            &settings,
+                            cx,
+                        )
+                    })),
+            )
+    }
+}
+
+impl Setting {
+    /// 修改音效链，立即生效并写入配置
+    fn set_effects(&mut self, effects: Vec<EffectSlot>, cx: &mut Context<Self>) {
+        cx.global_mut::<Player>().set_effects(effects.clone());
+        cx.global_mut::<Config>().effects = effects;
+        cx.notify();
+    }
+
+    /// 渲染音效链区域
+    fn render_effects_section(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let effects = cx.global::<Player>().effects().to_vec();
+        let count = effects.len();
+        // 对当前音效链做一次修改
+        let edit = |update: EditEffects| {
+            cx.listener(move |this, _evt: &MouseDownEvent, _window, cx| {
+                let mut effects = cx.global::<Player>().effects().to_vec();
+                update(&mut effects);
+                this.set_effects(effects, cx);
+            })
+        };
+
+        div()
+            .mb_4()
+            .child(
+                div()
+                    .text_sm()
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(text_secondary())
+                    .mb_2()
+                    .child("音效"),
+            )
+            .children(effects.into_iter().enumerate().map(|(idx, slot)| {
+                let params = slot.effect.params();
+                div()
+                    .mb_2()
+                    .flex()
+                    .flex_row()
+                    .flex_wrap()
+                    .items_center()
+                    .gap_3()
+                    .text_sm()
+                    .text_color(text_secondary())
+                    .child(
+                        div()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap_2()
+                            .w(px(140.0))
+                            .cursor_pointer()
+                            .child(
+                                div()
+                                    .size(px(16.0))
+                                    .rounded_sm()
+                                    .border_1()
+                                    .border_color(border_default())
+                                    .when(slot.enabled, |this| this.bg(accent_blue())),
+                            )
+                            .child(slot.effect.label())
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                edit(Box::new(move |effects| {
+                                    effects[idx].enabled = !effects[idx].enabled;
+                                })),
+                            ),
+                    )
+                    .children(params.into_iter().enumerate().map(|(param_idx, param)| {
+                        let step_button = |label: &'static str, value: f32| {
+                            div()
+                                .px_1()
+                                .rounded_sm()
+                                .cursor_pointer()
+                                .text_color(text_tertiary())
+                                .hover(|s| s.bg(bg_hover()).text_color(text_primary()))
+                                .child(label)
+                                .on_mouse_down(
+                                    MouseButton::Left,
+                                    edit(Box::new(move |effects| {
+                                        effects[idx].effect.set_param(param_idx, value);
+                                    })),
+                                )
+                        };
+                        div()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap_1()
+                            .text_xs()
+                            .child(div().text_color(text_tertiary()).child(param.label))
+                            .child(step_button("−", param.value - param.step))
+                            .child(format!("{}", param.value))
+                            .child(step_button("+", param.value + param.step))
+                    }))
+                    // 排序和删除
+                    .child(
+                        div()
+                            .ml_auto()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap_1()
+                            .when(idx > 0, |this| {
+                                this.child(render_icon_button("↑").on_mouse_down(
+                                    MouseButton::Left,
+                                    edit(Box::new(move |effects| effects.swap(idx, idx - 1))),
+                                ))
+                            })
+                            .when(idx + 1 < count, |this| {
+                                this.child(render_icon_button("↓").on_mouse_down(
+                                    MouseButton::Left,
+                                    edit(Box::new(move |effects| effects.swap(idx, idx + 1))),
+                                ))
+                            })
+                            .child(
+                                render_icon_button(
+                                    svg()
+                                        .path("svg/close.svg")
+                                        .size_3()
+                                        .text_color(text_tertiary()),
+                                )
+                                .on_mouse_down(
+                                    MouseButton::Left,
+                                    edit(Box::new(move |effects| {
+                                        effects.remove(idx);
+                                    })),
+                                ),
+                            ),
+                    )
+            }))
+            // 添加音效
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .flex_wrap()
+                    .items_center()
+                    .gap_2()
+                    .text_sm()
+                    .child(div().text_color(text_tertiary()).child("添加："))
+                    .children(EffectKind::all().into_iter().map(|effect| {
+                        div()
+                            .px_3()
+                            .py_1()
+                            .rounded_lg()
+                            .cursor_pointer()
+                            .text_color(text_secondary())
+                            .bg(bg_card())
+                            .hover(|s| s.bg(bg_hover()))
+                            .child(effect.label())
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                edit(Box::new(move |effects| {
+                                    effects.push(EffectSlot {
+                                        effect: effect.clone(),
+                                        enabled: true,
+                                    });
+                                })),
+                            )
+                    })),
+            )
+    }
+}
+
+/// 对音效链的一次修改
+type EditEffects = Box<dyn Fn(&mut Vec<EffectSlot>)>;
+
+/// 音效链中的小按钮
+fn render_icon_button(content: impl IntoElement) -> Div {
+    div()
+        .px_1()
+        .rounded_sm()
+        .cursor_pointer()
+        .text_color(text_tertiary())
+        .hover(|s| s.bg(bg_hover()).text_color(text_primary()))
+        .child(content)
+}
+
+/// 可调节的均衡器增益
+#[derive(Clone, Copy)]
+enum EqGain {
+    Preamp,
+    Band(usize),
+}
+
+impl EqGain {
+    fn get(self, settings: &EqSettings) -> f32 {
+        match self {
+            EqGain::Preamp => settings.preamp,
+            EqGain::Band(band) => settings.gains[band],
+        }
+    }
+
+    fn set(self, settings: &mut EqSettings, gain: f32) {
+        match self {
+            EqGain::Preamp => settings.preamp = gain,
+            EqGain::Band(band) => settings.gains[band] = gain,
+        }
+    }
+}
+
+/// 均衡器频段标签（如 "125"、"1k"）
+fn format_frequency(frequency: f32) -> SharedString {
+    if frequency >= 1000.0 {
+        format!("{}k", frequency / 1000.0).into()
+    } else {
+        format!("{}", frequency).into()
+    }
+}
+
+/// 渲染一个频段的增益调节：每 1 dB 一格，点击设置增益，点击数值归零
+fn render_eq_band(
+    label: SharedString,
+    target: EqGain,
+    settings: &EqSettings,
+    cx: &Context<Setting>,
+) -> impl IntoElement {
+    let gain = target.get(settings);
+    let set_gain = |gain: f32| {
+        cx.listener(move |this, _evt: &MouseDownEvent, _window, cx| {
+            let mut settings = cx.global::<Player>().equalizer().clone();
+            target.set(&mut settings, gain);
+            this.set_equalizer(settings, cx);
+        })
+    };
+    let (min, max) = (EQ_GAIN_RANGE.0 as i32, EQ_GAIN_RANGE.1 as i32);
+
+    div()
+        .flex()
+        .flex_col()
+        .items_center()
+        .gap_1()
+        .text_xs()
+        .child(
+            div()
+                .cursor_pointer()
+                .text_color(text_tertiary())
+                .hover(|s| s.text_color(text_primary()))
+                .child(format!("{:+}", gain))
+                .on_mouse_down(MouseButton::Left, set_gain(0.0)),
+        )
+        .child(
+            div()
+                .w(px(24.0))
+                .flex()
+                .flex_col()
+                .children((min..=max).rev().map(|step| {
+                    let step = step as f32;
+                    let filled = (gain > 0.0 && step > 0.0 && step <= gain)
+                        || (gain < 0.0 && step < 0.0 && step >= gain);
+                    div()
+                        .h(px(5.0))
+                        .w_full()
+                        .cursor_pointer()
+                        .when(filled, |this| this.bg(accent_blue()))
+                        .when(step == 0.0, |this| this.bg(border_default()))
+                        .hover(|s| s.bg(bg_hover()))
+                        .on_mouse_down(MouseButton::Left, set_gain(step))
+                })),
+        )
+        .child(div().text_color(text_secondary()).child(label))
+}
+
+/// 设置页通用按钮
+fn render_button(id: impl Into<ElementId>, label: impl Into<SharedString>) -> Stateful<Div> {
+    div()
+        .id(id)
+        .h(px(SETTING_ITEM_HEIGHT))
+        .px_4()
+        .flex()
+        .items_center()
+        .justify_center()
+        .bg(accent_blue())
+        .rounded_lg()
+        .cursor_pointer()
+        .text_sm()
+        .font_weight(FontWeight::MEDIUM)
+        .text_color(text_primary())
+        .hover(|s| s.bg(bg_active()))
+        .child(label.into())
+}
+
 impl Render for Setting {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if self.preset_name.is_some() && self.pending_focus {
+            window.focus(&self.focus_handle);
+            self.pending_focus = false;
+        }
         let music_dir = cx.global::<Config>().media_file.music_directory.to_string();
+        let write_rating_tags = cx.global::<Config>().media_file.write_rating_tags;
+        let shuffle_mode = cx.global::<Player>().shuffle_mode();
 
         div()
             .flex()
@@ -109,6 +1143,88 @@ impl Render for Setting {
                             ),
                     ),
             )
+            // 评分写入文件标签
+            .child(
+                div()
+                    .id("write-rating-tags")
+                    .mb_4()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_2()
+                    .cursor_pointer()
+                    .text_sm()
+                    .text_color(text_secondary())
+                    .child(
+                        div()
+                            .size(px(16.0))
+                            .rounded_sm()
+                            .border_1()
+                            .border_color(border_default())
+                            .when(write_rating_tags, |this| this.bg(accent_blue())),
+                    )
+                    .child("评分同时写入音频文件标签（POPM / RATING）")
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|_this, _evt, _window, cx| {
+                            cx.update_global::<Config, _>(|config, _cx| {
+                                config.media_file.write_rating_tags =
+                                    !config.media_file.write_rating_tags;
+                            });
+                            cx.notify();
+                        }),
+                    ),
+            )
+            // 随机播放方式
+            .child(
+                div()
+                    .mb_4()
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_weight(FontWeight::MEDIUM)
+                            .text_color(text_secondary())
+                            .mb_2()
+                            .child("随机播放方式"),
+                    )
+                    .child(div().flex().flex_row().gap_2().children(
+                        ShuffleMode::all().into_iter().map(|mode| {
+                            div()
+                                .id(mode.label())
+                                .px_3()
+                                .py_1()
+                                .rounded_lg()
+                                .cursor_pointer()
+                                .text_sm()
+                                .text_color(text_secondary())
+                                .hover(|s| s.bg(bg_hover()))
+                                .when(mode == shuffle_mode, |this| this.bg(bg_active()))
+                                .child(mode.label())
+                                .on_mouse_down(
+                                    MouseButton::Left,
+                                    cx.listener(move |_this, _evt, _window, cx| {
+                                        cx.update_global::<Player, _>(|player, _cx| {
+                                            player.set_shuffle_mode(mode);
+                                        });
+                                        cx.global_mut::<Config>().play_info.shuffle_mode = mode;
+                                        cx.notify();
+                                    }),
+                                )
+                        }),
+                    )),
+            )
+            // 输出设备
+            .child(self.render_output_section(cx))
+            // 均衡器
+            .child(self.render_equalizer_section(cx))
+            // 音效链
+            .child(self.render_effects_section(cx))
+            // 歌单导入导出
+            .child(self.render_playlist_section(cx))
+            // 远程控制 API
+            .child(self.render_api_section(cx))
+            // 日志
+            .child(self.render_log_section(cx))
             // 关于信息
             .child(
                 div()
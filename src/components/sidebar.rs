@@ -7,15 +7,27 @@ pub enum SidebarItem {
     Library,
     Favorite,
     History,
+    Stats,
     Settings,
-    #[allow(dead_code)]
-    Custom(usize),
+    /// 自定义歌单（数据库 ID）
+    Custom(i64),
+    /// 智能歌单（数据库 ID）
+    Smart(i64),
+}
+
+/// 侧边栏上的歌单管理操作
+#[derive(Clone, Copy, PartialEq)]
+pub enum SidebarAction {
+    /// 新建智能歌单
+    NewSmartPlaylist,
+    /// 编辑智能歌单（数据库 ID）
+    EditSmartPlaylist(i64),
 }
 
 /// 侧边栏菜单项配置
 struct Menu {
     icon: Option<&'static str>,
-    label: &'static str,
+    label: SharedString,
     item: SidebarItem,
     selected: bool,
 }
@@ -24,10 +36,12 @@ struct Menu {
 pub struct SideBar {
     origin_menu: Vec<Menu>,
 
-    /// 未来可扩展的自定义菜单
-    #[allow(dead_code)]
+    /// 自定义歌单菜单
     custom_menu: Vec<Menu>,
 
+    /// 智能歌单菜单
+    smart_menu: Vec<Menu>,
+
     select_setting: bool,
 }
 
@@ -37,30 +51,80 @@ impl SideBar {
             origin_menu: vec![
                 Menu {
                     icon: Some("svg/library.svg"),
-                    label: "曲库",
+                    label: "曲库".into(),
                     item: SidebarItem::Library,
                     selected: true,
                 },
                 Menu {
                     icon: Some("svg/heart.svg"),
-                    label: "收藏",
+                    label: "收藏".into(),
                     item: SidebarItem::Favorite,
                     selected: false,
                 },
                 Menu {
                     icon: Some("svg/history.svg"),
-                    label: "历史",
+                    label: "历史".into(),
                     item: SidebarItem::History,
                     selected: false,
                 },
+                Menu {
+                    icon: Some("svg/chart.svg"),
+                    label: "统计".into(),
+                    item: SidebarItem::Stats,
+                    selected: false,
+                },
             ],
             custom_menu: Vec::new(),
+            smart_menu: Vec::new(),
             select_setting: false,
         }
     }
 
+    /// 设置智能歌单菜单项（ID, 名称），保留当前选中状态
+    pub fn set_smart_playlists(&mut self, playlists: Vec<(i64, SharedString)>) {
+        let selected: Option<SidebarItem> = self
+            .smart_menu
+            .iter()
+            .find(|menu| menu.selected)
+            .map(|menu| menu.item);
+        self.smart_menu = playlists
+            .into_iter()
+            .map(|(id, name)| Menu {
+                icon: Some("svg/list.svg"),
+                label: name,
+                item: SidebarItem::Smart(id),
+                selected: selected == Some(SidebarItem::Smart(id)),
+            })
+            .collect();
+    }
+
+    /// 设置自定义歌单菜单项（ID, 名称），保留当前选中状态
+    pub fn set_custom_playlists(&mut self, playlists: Vec<(i64, SharedString)>) {
+        let selected: Option<SidebarItem> = self
+            .custom_menu
+            .iter()
+            .find(|menu| menu.selected)
+            .map(|menu| menu.item);
+        self.custom_menu = playlists
+            .into_iter()
+            .map(|(id, name)| Menu {
+                icon: Some("svg/folder.svg"),
+                label: name,
+                item: SidebarItem::Custom(id),
+                selected: selected == Some(SidebarItem::Custom(id)),
+            })
+            .collect();
+    }
+
+    fn all_menus_mut(&mut self) -> impl Iterator<Item = &mut Menu> {
+        self.origin_menu
+            .iter_mut()
+            .chain(self.smart_menu.iter_mut())
+            .chain(self.custom_menu.iter_mut())
+    }
+
     fn select_menus(&mut self, item: &SidebarItem) {
-        for menu in self.origin_menu.iter_mut() {
+        for menu in self.all_menus_mut() {
             menu.selected = menu.item == *item;
         }
         self.select_setting = false;
@@ -68,13 +132,23 @@ impl SideBar {
 
     fn select_setting(&mut self) {
         self.select_setting = true;
-        for menu in self.origin_menu.iter_mut() {
+        for menu in self.all_menus_mut() {
             menu.selected = false;
         }
     }
+
+    /// 从外部（快捷键、命令面板）切换视图时同步选中状态
+    pub fn select(&mut self, item: SidebarItem, cx: &mut Context<Self>) {
+        match item {
+            SidebarItem::Settings => self.select_setting(),
+            item => self.select_menus(&item),
+        }
+        cx.notify();
+    }
 }
 
 impl EventEmitter<SidebarItem> for SideBar {}
+impl EventEmitter<SidebarAction> for SideBar {}
 
 impl Render for SideBar {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
@@ -96,7 +170,7 @@ impl Render for SideBar {
             )
             .children(self.origin_menu.iter().map(|menu| {
                 let item = menu.item;
-                render_menu_item(menu.label, menu.icon, menu.label)
+                render_menu_item(menu.label.clone(), menu.icon, menu.label.clone())
                     .when(menu.selected, |this| this.bg(bg_active()))
                     .on_mouse_down(
                         MouseButton::Left,
@@ -107,6 +181,13 @@ impl Render for SideBar {
                         }),
                     )
             }))
+            .child(render_menu_section(
+                "智能歌单",
+                &self.smart_menu,
+                Some(SidebarAction::NewSmartPlaylist),
+                cx,
+            ))
+            .child(render_menu_section("歌单", &self.custom_menu, None, cx))
             .child(
                 div()
                     .id("setting")
@@ -138,6 +219,83 @@ impl Render for SideBar {
     }
 }
 
+/// 渲染歌单分组（标题 + 菜单项），分组为空且不能新建时不显示标题
+fn render_menu_section(
+    title: &'static str,
+    menus: &[Menu],
+    add: Option<SidebarAction>,
+    cx: &mut Context<SideBar>,
+) -> impl IntoElement {
+    div()
+        .flex()
+        .flex_col()
+        .when(!menus.is_empty() || add.is_some(), |this| {
+            this.child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .mx_3()
+                    .mt_4()
+                    .px_1()
+                    .text_xs()
+                    .text_color(text_placeholder())
+                    .child(title)
+                    .when_some(add, |this, action| {
+                        this.child(
+                            div()
+                                .id(title)
+                                .px_2()
+                                .rounded_md()
+                                .cursor_pointer()
+                                .text_sm()
+                                .hover(|s| s.bg(bg_active()).text_color(text_secondary()))
+                                .child("+")
+                                .on_mouse_down(
+                                    MouseButton::Left,
+                                    cx.listener(move |_this, _evt, _window, cx| {
+                                        cx.emit(action);
+                                    }),
+                                ),
+                        )
+                    }),
+            )
+        })
+        .children(menus.iter().map(|menu| {
+            let item = menu.item;
+            let id = match item {
+                SidebarItem::Smart(id) => SharedString::from(format!("smart-{}", id)),
+                SidebarItem::Custom(id) => SharedString::from(format!("custom-{}", id)),
+                _ => menu.label.clone(),
+            };
+            render_menu_item(id, menu.icon, menu.label.clone())
+                .when(menu.selected, |this| this.bg(bg_active()))
+                .on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(move |this, _evt, _window, cx| {
+                        this.select_menus(&item);
+                        cx.emit(item);
+                        cx.notify();
+                    }),
+                )
+                // 右键编辑智能歌单
+                .when_some(
+                    match item {
+                        SidebarItem::Smart(id) => Some(id),
+                        _ => None,
+                    },
+                    |this, id| {
+                        this.on_mouse_down(
+                            MouseButton::Right,
+                            cx.listener(move |_this, _evt, _window, cx| {
+                                cx.emit(SidebarAction::EditSmartPlaylist(id));
+                            }),
+                        )
+                    },
+                )
+        }))
+}
+
 /// 渲染单个菜单项
 fn render_menu_item(
     id: impl Into<ElementId>,
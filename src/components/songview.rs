@@ -1,12 +1,26 @@
 use gpui::{prelude::FluentBuilder, *};
 use rfd::AsyncFileDialog;
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
+use uuid::Uuid;
 
 use crate::{
-    db::{database::DB, dbstate::LibraryState, metadata::AlbumInfo, table::Table},
+    db::{
+        cover::{self, CoverSize},
+        database::DB,
+        dbstate::{LibraryEvent, LibraryState},
+        metadata::{AlbumInfo, write_tags},
+        table::Table,
+    },
+    error::warn,
+    keymap::{ClearSelection, PlaySelected, SelectAll, SelectNext, SelectPrevious},
     play::player::Player,
     theme::*,
-    ui::menu::{MenuAction, MenuContext},
+    ui::{
+        menu::{MenuAction, MenuContext},
+        rating::render_rating,
+        search,
+        tag_editor::{TagEditEvent, TagEditor},
+    },
     util::format_duration,
 };
 
@@ -18,6 +32,10 @@ pub enum ViewType {
     Favorite,
     History,
     Search,
+    /// 智能歌单（数据库 ID）
+    Smart(i64),
+    /// 自定义歌单（数据库 ID）
+    Custom(i64),
 }
 
 #[derive(Clone)]
@@ -30,59 +48,314 @@ pub struct AlbumList {
     search_query: String,
     /// 搜索结果
     search_results: Arc<Vec<AlbumInfo>>,
+    /// 当前歌单（智能歌单求值结果或自定义歌单内容）
+    playlist_results: Arc<Vec<AlbumInfo>>,
     /// 右键菜单实体
     context_menu: Entity<MenuContext>,
+    /// 标签编辑弹窗
+    tag_editor: Entity<TagEditor>,
+    /// 选中的歌曲
+    selected: HashSet<Uuid>,
+    /// 范围选择的锚点（当前列表中的下标）
+    selection_anchor: Option<usize>,
+    /// 列表焦点（用于键盘导航）
+    focus_handle: FocusHandle,
+    /// 列表滚动句柄（键盘导航时滚动到选中行）
+    scroll_handle: ScrollHandle,
 }
 
 impl AlbumList {
-    pub fn new(library_state: Entity<LibraryState>, cx: &mut Context<Self>) -> Self {
+    pub fn new(
+        library_state: Entity<LibraryState>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
         let context_menu = cx.new(|_| MenuContext::new());
 
-        cx.subscribe(&context_menu, |this, _that, evt: &MenuAction, cx| {
-            match evt {
-                MenuAction::AddToFavorite(album_id) => {
-                    // 添加到收藏（内存 + 数据库）
-                    this.library_state.update(cx, |state, cx| {
-                        state.add_to_favorites(album_id, cx);
-                    });
-                    if let Err(e) = cx.global::<DB>().add_to_table(Table::Favorite, album_id) {
-                        eprintln!("[WARN] 写入收藏到数据库失败: {}", e);
+        let tag_editor = cx.new(TagEditor::new);
+
+        cx.subscribe_in(
+            &context_menu,
+            window,
+            |this, _that, evt: &MenuAction, window, cx| {
+                match evt {
+                    MenuAction::AddToFavorite(ids) => {
+                        // 添加到收藏（先写数据库，智能歌单在事件中按数据库求值）
+                        for id in ids {
+                            if let Err(e) = cx.global::<DB>().add_to_table(Table::Favorite, id) {
+                                warn(&e, "写入收藏到数据库失败");
+                            }
+                        }
+                        this.library_state.update(cx, |state, cx| {
+                            for id in ids {
+                                state.add_to_favorites(id, cx);
+                            }
+                        });
                     }
-                }
-                MenuAction::RemoveFromFavorite(album_id) => {
-                    // 从收藏移除（内存 + 数据库）
-                    this.library_state.update(cx, |state, cx| {
-                        state.remove_from_favorites(album_id, cx);
-                    });
-                    if let Err(e) = cx
-                        .global::<DB>()
-                        .remove_from_table(Table::Favorite, album_id)
-                    {
-                        eprintln!("[WARN] 从数据库移除收藏失败: {}", e);
+                    MenuAction::RemoveFromFavorite(ids) => {
+                        // 从收藏移除（数据库 + 内存）
+                        for id in ids {
+                            if let Err(e) = cx.global::<DB>().remove_from_table(Table::Favorite, id)
+                            {
+                                warn(&e, "从数据库移除收藏失败");
+                            }
+                        }
+                        this.library_state.update(cx, |state, cx| {
+                            for id in ids {
+                                state.remove_from_favorites(id, cx);
+                            }
+                        });
                     }
-                }
-                MenuAction::PlayNext(album_id) => {
-                    // 下一首播放：查找歌曲并播放
-                    let item_clone = this.library_state.read(cx).get_by_id(album_id).cloned();
-                    if let Some(item) = item_clone {
+                    MenuAction::SetRating(ids, rating) => {
+                        this.rate(ids, *rating, cx);
+                    }
+                    MenuAction::PlayNext(ids) => {
+                        // 插入到队列最前面，当前没有播放时立即开始
+                        let items = this.items_by_ids(ids, cx);
+                        cx.update_global::<Player, _>(|player, _cx| {
+                            player.enqueue_next(items);
+                            if player.current_track().is_none() {
+                                player.next();
+                            }
+                        });
+                    }
+                    MenuAction::AddToQueue(ids) => {
+                        let items = this.items_by_ids(ids, cx);
+                        cx.update_global::<Player, _>(|player, _cx| {
+                            player.enqueue(items);
+                            if player.current_track().is_none() {
+                                player.next();
+                            }
+                        });
+                    }
+                    MenuAction::PlaySelection(ids) => {
+                        let items = Arc::new(this.items_by_ids(ids, cx));
                         cx.update_global::<Player, _>(|player, _cx| {
-                            player.play_track(&item);
+                            player.play_list(items);
                         });
                     }
+                    MenuAction::EditTags(ids) => {
+                        let items = this.items_by_ids(ids, cx);
+                        this.tag_editor.update(cx, |editor, cx| {
+                            editor.open(&items, cx);
+                        });
+                    }
+                    MenuAction::RemoveFromLibrary(ids) => {
+                        // 批量移除前确认
+                        let answer = window.prompt(
+                            PromptLevel::Warning,
+                            &format!("从曲库移除 {} 首歌曲？", ids.len()),
+                            Some("同时从收藏、历史和歌单中移除，文件不会被删除。"),
+                            &["移除", "取消"],
+                            cx,
+                        );
+                        let ids = ids.clone();
+                        cx.spawn(async move |this, cx| {
+                            if answer.await != Ok(0) {
+                                return;
+                            }
+                            this.update(cx, |this, cx| this.remove_from_library(&ids, cx))
+                                .ok();
+                        })
+                        .detach();
+                    }
+                    MenuAction::SelectAll => {
+                        this.select_all(cx);
+                    }
+                }
+                cx.notify();
+            },
+        )
+        .detach();
+
+        // 保存标签：写入文件和数据库，再更新内存中的曲库
+        cx.subscribe(&tag_editor, |this, _that, evt: &TagEditEvent, cx| {
+            let mut updated = Vec::new();
+            for mut item in this.items_by_ids(&evt.ids, cx) {
+                if let Err(e) = write_tags(&item.path(), &evt.edit) {
+                    warn(&e, &format!("写入标签失败: {}", item.path().display()));
+                    continue;
+                }
+                if let Err(e) = cx.global::<DB>().update_tags(&item.id(), &evt.edit) {
+                    warn(&e, "更新数据库标签失败");
                 }
+                item.apply_tag_edit(&evt.edit);
+                updated.push(item);
             }
+            this.library_state.update(cx, |state, cx| {
+                state.update_items(updated, cx);
+            });
         })
         .detach();
 
+        // 曲库变化时重新加载当前歌单（智能歌单需重新求值）和搜索结果
+        cx.subscribe(
+            &library_state,
+            |this, _that, evt: &LibraryEvent, cx| match this.view_type {
+                // 可播放状态只影响列表中的标记
+                _ if matches!(evt, LibraryEvent::PlayabilityChanged) => cx.notify(),
+                // 播放记录只影响按播放次数、最后播放时间筛选的智能歌单
+                ViewType::Custom(_) | ViewType::Search
+                    if matches!(evt, LibraryEvent::PlaysRecorded) => {}
+                ViewType::Smart(_) | ViewType::Custom(_) => {
+                    this.load_playlist(cx);
+                    cx.notify();
+                }
+                ViewType::Search => {
+                    let query = this.search_query.clone();
+                    this.search(&query, cx);
+                }
+                _ => {}
+            },
+        )
+        .detach();
+
         Self {
             view_type: ViewType::Library,
             library_state,
             search_query: String::new(),
             search_results: Arc::new(Vec::new()),
+            playlist_results: Arc::new(Vec::new()),
             context_menu,
+            tag_editor,
+            selected: HashSet::new(),
+            selection_anchor: None,
+            focus_handle: cx.focus_handle(),
+            scroll_handle: ScrollHandle::new(),
         }
     }
 
+    /// 从曲库移除歌曲，同时从播放列表和队列中移除，避免之后播放到失效的歌曲
+    fn remove_from_library(&mut self, ids: &[Uuid], cx: &mut Context<Self>) {
+        let db = cx.global::<DB>();
+        if let Err(e) = db.remove_from_library(ids) {
+            warn(&e, "从曲库移除失败");
+            return;
+        }
+        db.collect_covers_in_background(cx).detach();
+        let removed: HashSet<Uuid> = ids.iter().copied().collect();
+        cx.global_mut::<Player>().remove_tracks(&removed);
+        self.library_state.update(cx, |state, cx| {
+            state.remove_items(ids, cx);
+        });
+        self.clear_selection();
+        cx.notify();
+    }
+
+    /// 按给定顺序查找歌曲
+    fn items_by_ids(&self, ids: &[Uuid], cx: &App) -> Vec<AlbumInfo> {
+        let state = self.library_state.read(cx);
+        ids.iter()
+            .filter_map(|id| state.get_by_id(id).cloned())
+            .collect()
+    }
+
+    // ========== 多选 ==========
+
+    /// 当前列表中选中的歌曲 ID（按列表顺序）
+    fn selected_ids(&self, cx: &App) -> Vec<Uuid> {
+        self.get_current_items(cx)
+            .iter()
+            .map(|item| item.id())
+            .filter(|id| self.selected.contains(id))
+            .collect()
+    }
+
+    /// 单选（普通点击）
+    fn select_single(&mut self, idx: usize, id: Uuid) {
+        self.selected.clear();
+        self.selected.insert(id);
+        self.selection_anchor = Some(idx);
+    }
+
+    /// 切换单首歌曲的选中状态（Ctrl/Cmd + 点击）
+    fn toggle_selected(&mut self, idx: usize, id: Uuid) {
+        if !self.selected.remove(&id) {
+            self.selected.insert(id);
+        }
+        self.selection_anchor = Some(idx);
+    }
+
+    /// 从锚点到 idx 的范围选择（Shift + 点击）
+    fn select_range(&mut self, idx: usize, cx: &App) {
+        let anchor = self.selection_anchor.unwrap_or(idx);
+        let (start, end) = (anchor.min(idx), anchor.max(idx));
+        let items = self.get_current_items(cx);
+        self.selected = items
+            .iter()
+            .skip(start)
+            .take(end - start + 1)
+            .map(|item| item.id())
+            .collect();
+    }
+
+    /// 全选当前列表
+    pub fn select_all(&mut self, cx: &mut Context<Self>) {
+        self.selected = self
+            .get_current_items(cx)
+            .iter()
+            .map(|item| item.id())
+            .collect();
+        cx.notify();
+    }
+
+    fn clear_selection(&mut self) {
+        self.selected.clear();
+        self.selection_anchor = None;
+    }
+
+    // ========== 键盘导航 ==========
+
+    /// 将单选移动 delta 行并滚动到可见位置
+    fn move_selection(&mut self, delta: isize, cx: &mut Context<Self>) {
+        let items = self.get_current_items(cx);
+        if items.is_empty() {
+            return;
+        }
+        let idx = match self.selection_anchor {
+            Some(anchor) => anchor.saturating_add_signed(delta).min(items.len() - 1),
+            None => 0,
+        };
+        self.select_single(idx, items[idx].id());
+        self.scroll_handle.scroll_to_item(idx);
+        cx.notify();
+    }
+
+    fn select_next(&mut self, _: &SelectNext, _window: &mut Window, cx: &mut Context<Self>) {
+        self.move_selection(1, cx);
+    }
+
+    fn select_previous(
+        &mut self,
+        _: &SelectPrevious,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.move_selection(-1, cx);
+    }
+
+    fn play_selected(&mut self, _: &PlaySelected, _window: &mut Window, cx: &mut Context<Self>) {
+        let items = self.get_current_items(cx);
+        if let Some(item) = self.selection_anchor.and_then(|idx| items.get(idx)) {
+            self.play_item(item, cx);
+        }
+    }
+
+    /// 设置评分（0 表示清除）
+    fn rate(&mut self, ids: &[Uuid], rating: u8, cx: &mut Context<Self>) {
+        self.library_state.update(cx, |state, cx| {
+            state.set_rating(ids, rating, cx);
+        });
+    }
+
+    /// 播放歌曲（历史和播放次数由播放器的播放事件统一记录）
+    pub fn play_item(&mut self, item: &AlbumInfo, cx: &mut Context<Self>) {
+        cx.update_global::<Player, _>(|player, _cx| {
+            // 播放选中的歌曲
+            player.play_track(item);
+        });
+    }
+
     /// 切换视图类型，并返回当前列表用于更新播放列表
     pub fn set_view_type(
         &mut self,
@@ -90,14 +363,37 @@ impl AlbumList {
         cx: &Context<Self>,
     ) -> Arc<Vec<AlbumInfo>> {
         self.view_type = view_type;
+        self.clear_selection();
         // 切换视图时清除搜索
         if view_type != ViewType::Search {
             self.search_query.clear();
             self.search_results = Arc::new(Vec::new());
         }
+        self.load_playlist(cx);
         self.get_current_items(cx)
     }
 
+    /// 从数据库加载当前歌单视图的歌曲（智能歌单在 SQL 中求值）
+    fn load_playlist(&mut self, cx: &Context<Self>) {
+        let db = cx.global::<DB>();
+        let items = match self.view_type {
+            ViewType::Smart(id) => match db.load_smart_playlist(id) {
+                Ok(Some(playlist)) => db.load_smart_playlist_items(&playlist),
+                Ok(None) => Ok(Vec::new()),
+                Err(e) => Err(e),
+            },
+            ViewType::Custom(id) => db.load_playlist_items(id),
+            _ => return,
+        };
+        match items {
+            Ok(items) => self.playlist_results = Arc::new(items),
+            Err(e) => {
+                warn(&e, "加载歌单失败");
+                self.playlist_results = Arc::new(Vec::new());
+            }
+        }
+    }
+
     /// 获取当前视图类型
     pub fn view_type(&self) -> ViewType {
         self.view_type
@@ -110,6 +406,9 @@ impl AlbumList {
 
     /// 执行搜索
     pub fn search(&mut self, query: &str, cx: &mut Context<Self>) -> Arc<Vec<AlbumInfo>> {
+        if self.view_type != ViewType::Search {
+            self.clear_selection();
+        }
         self.search_query = query.to_lowercase();
         self.view_type = ViewType::Search;
 
@@ -120,9 +419,9 @@ impl AlbumList {
             .filter(|item| {
                 let query = &self.search_query;
                 // 搜索歌曲名、歌手、专辑
-                item.title().to_lowercase().contains(query)
-                    || item.artist().to_lowercase().contains(query)
-                    || item.album().to_lowercase().contains(query)
+                search::matches(&item.title(), query)
+                    || search::matches(&item.artist(), query)
+                    || search::matches(&item.album(), query)
             })
             .cloned()
             .collect();
@@ -134,6 +433,7 @@ impl AlbumList {
 
     /// 清除搜索
     pub fn clear_search(&mut self, cx: &mut Context<Self>) {
+        self.clear_selection();
         self.search_query.clear();
         self.search_results = Arc::new(Vec::new());
         self.view_type = ViewType::Library;
@@ -141,13 +441,14 @@ impl AlbumList {
     }
 
     /// 获取当前显示的列表（根据视图类型从 LibraryState 读取）
-    fn get_current_items(&self, cx: &Context<Self>) -> Arc<Vec<AlbumInfo>> {
+    fn get_current_items(&self, cx: &App) -> Arc<Vec<AlbumInfo>> {
         let state = self.library_state.read(cx);
         match self.view_type {
             ViewType::Library => state.library(),
             ViewType::Favorite => state.favorites(),
             ViewType::History => state.history(),
             ViewType::Search => Arc::clone(&self.search_results),
+            ViewType::Smart(_) | ViewType::Custom(_) => Arc::clone(&self.playlist_results),
         }
     }
 
@@ -161,18 +462,47 @@ impl AlbumList {
     }
 }
 
+impl Focusable for AlbumList {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
 impl Render for AlbumList {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let items = self.get_current_items(cx);
+        let cover_size = CoverSize::fit(COVER_THUMB_SIZE * window.scale_factor());
         let is_search = self.view_type == ViewType::Search;
         let search_query = self.search_query.clone();
+        let selected = self.selected.clone();
+        let scroll_handle = self.scroll_handle.clone();
+        let (ratings, unplayable): (Vec<u8>, Vec<bool>) = {
+            let state = self.library_state.read(cx);
+            items
+                .iter()
+                .map(|item| {
+                    let id = item.id();
+                    (state.rating(&id), state.unplayable_reason(&id).is_some())
+                })
+                .unzip()
+        };
 
         div()
             .id("album-list-container")
+            .key_context("AlbumList")
+            .track_focus(&self.focus_handle)
             .w_full()
             .flex_1()
             .min_h_0()
             .relative()
+            .on_action(cx.listener(Self::select_next))
+            .on_action(cx.listener(Self::select_previous))
+            .on_action(cx.listener(Self::play_selected))
+            .on_action(cx.listener(|this, _: &SelectAll, _window, cx| this.select_all(cx)))
+            .on_action(cx.listener(|this, _: &ClearSelection, _window, cx| {
+                this.clear_selection();
+                cx.notify();
+            }))
             .child(
                 div()
                     .id("album-list")
@@ -236,16 +566,18 @@ impl Render for AlbumList {
 
                                                         if let Some(folder) = folder {
                                                             let path = folder.path().to_string_lossy().to_string();
-                                                            cx.update(|cx: &mut App| {
-                                                                cx.update_global::<DB, _>(|db, _cx| {
-                                                                    db.add_metadata_to_library(&path).unwrap();
-                                                                });
-                                                            }).unwrap();
+                                                            let Ok(scan) = cx.update(|cx: &mut App| {
+                                                                cx.global::<DB>().scan_in_background(path, cx)
+                                                            }) else {
+                                                                return;
+                                                            };
 
                                                             // 添加完成后，更新曲库
-                                                            this.update(cx, |this, cx| {
-                                                                this.refresh_library(cx);
-                                                            }).unwrap();
+                                                            if scan.await.is_ok() {
+                                                                this.update(cx, |this, cx| {
+                                                                    this.refresh_library(cx);
+                                                                }).ok();
+                                                            }
                                                         }
                                                     },
                                                 )
@@ -260,10 +592,12 @@ impl Render for AlbumList {
                             .flex()
                             .flex_col()
                             .overflow_y_scroll()
+                            .track_scroll(&scroll_handle)
                             .children(items.iter().enumerate().map(|(idx, item)| {
                                 // 克隆 item 以获得所有权（Arc 克隆成本低）
                                 let item = item.clone();
                                 let item_id = item.id();
+                                let is_selected = selected.contains(&item_id);
                                 div()
                                     .id(ElementId::Name(format!("song-{}", idx).into()))
                                     .w_full()
@@ -274,7 +608,7 @@ impl Render for AlbumList {
                                     .py_2()
                                     .border_b_1()
                                     .border_color(border_default())
-                                    .bg(bg_card())
+                                    .bg(if is_selected { bg_selected() } else { bg_card() })
                                     .hover(move |style| style.bg(bg_hover()))
                                     .cursor_pointer()
                                     // 专辑封面
@@ -285,16 +619,13 @@ impl Render for AlbumList {
                                             .flex()
                                             .content_center()
                                             .justify_center()
-                                            .when_some(item.cover_64(), |this, cover| {
+                                            .when_some(item.cover(), |this, cover| {
                                                 this.child(
-                                                    img(Arc::new(Image::from_bytes(
-                                                        ImageFormat::Jpeg,
-                                                        cover.to_vec(),
-                                                    )))
-                                                    .size_full(),
+                                                    img(cover::image(cover, cover_size))
+                                                        .size_full(),
                                                 )
                                             })
-                                            .when_none(&item.cover_64(), |this| {
+                                            .when_none(&item.cover(), |this| {
                                                 this.child(
                                                     svg()
                                                         .path("svg/album.svg")
@@ -315,10 +646,30 @@ impl Render for AlbumList {
                                             .text_ellipsis()
                                             .child(
                                                 div()
-                                                    .text_base()
-                                                    .font_weight(FontWeight::MEDIUM)
-                                                    .truncate()
-                                                    .child(item.title()),
+                                                    .flex()
+                                                    .flex_row()
+                                                    .items_center()
+                                                    .gap_2()
+                                                    .child(
+                                                        div()
+                                                            .text_base()
+                                                            .font_weight(FontWeight::MEDIUM)
+                                                            .truncate()
+                                                            .when(unplayable[idx], |this| {
+                                                                this.text_color(text_muted()).line_through()
+                                                            })
+                                                            .child(item.title()),
+                                                    )
+                                                    // 解码失败的歌曲
+                                                    .when(unplayable[idx], |this| {
+                                                        this.child(
+                                                            div()
+                                                                .flex_shrink_0()
+                                                                .text_xs()
+                                                                .text_color(text_tertiary())
+                                                                .child("无法播放"),
+                                                        )
+                                                    }),
                                             )
                                             .child(
                                                 div()
@@ -341,6 +692,18 @@ impl Render for AlbumList {
                                             .font_weight(FontWeight::LIGHT)
                                             .child(item.album()),
                                     )
+                                    // 评分
+                                    .child(
+                                        div()
+                                            .flex_shrink_0()
+                                            .flex()
+                                            .items_center()
+                                            .child(render_rating(ratings[idx], 14.0, |stars| {
+                                                cx.listener(move |this, _evt: &MouseDownEvent, _window, cx| {
+                                                    this.rate(&[item_id], stars, cx);
+                                                })
+                                            })),
+                                    )
                                     // 时长
                                     .child(
                                         div()
@@ -355,41 +718,57 @@ impl Render for AlbumList {
                                     .on_mouse_down(
                                         MouseButton::Left,
                                         {
-                                            let item_for_play = item.clone();
-                                            cx.listener(move |this, _event, _window, cx| {
-                                                // 通过 LibraryState 添加到历史
-                                                this.library_state.update(cx, |state, cx| {
-                                                    state.add_to_history(&item.id(), cx);
-                                                });
-                                                // 同时写入数据库（忽略写入错误）
-                                                let _ = cx
-                                                    .global::<DB>()
-                                                    .add_to_table(Table::History, &item.id());
-
-                                                cx.update_global::<Player, _>(|player, _cx| {
-                                                    // 播放点击的歌曲
-                                                    player.play_track(&item_for_play);
-                                                });
+                                            cx.listener(move |this, evt: &MouseDownEvent, window, cx| {
+                                                window.focus(&this.focus_handle);
+                                                // Shift 范围选择，Ctrl/Cmd 切换选择，不触发播放
+                                                if evt.modifiers.shift {
+                                                    this.select_range(idx, cx);
+                                                    cx.notify();
+                                                    return;
+                                                }
+                                                if evt.modifiers.secondary() {
+                                                    this.toggle_selected(idx, item_id);
+                                                    cx.notify();
+                                                    return;
+                                                }
+                                                this.select_single(idx, item_id);
+
+                                                this.play_item(&item, cx);
                                             })
                                         },
                                     )
                                     .on_mouse_down(MouseButton::Right, {
                                         cx.listener(
                                             move |this, evt: &MouseDownEvent, _window, cx| {
-                                                // 检查当前是否已收藏
-                                                let is_fav = this
-                                                    .library_state
-                                                    .read(cx)
-                                                    .is_favorite(&item_id);
+                                                // 右键已选中的歌曲时作用于整个选择，否则只选中该歌曲
+                                                let ids = if this.selected.contains(&item_id) {
+                                                    this.selected_ids(cx)
+                                                } else {
+                                                    this.select_single(idx, item_id);
+                                                    vec![item_id]
+                                                };
+                                                // 全部已收藏时显示“取消收藏”
+                                                let state = this.library_state.read(cx);
+                                                let is_fav = ids.iter().all(|id| state.is_favorite(id));
+                                                // 评分一致时在菜单中显示该评分
+                                                let rating = state.rating(&ids[0]);
+                                                let rating = if ids.iter().all(|id| state.rating(id) == rating) {
+                                                    rating
+                                                } else {
+                                                    0
+                                                };
                                                 // 显示右键菜单
                                                 this.context_menu.update(cx, move |menu, cx| {
-                                                    menu.show(&item_id, cx, evt.position, is_fav);
+                                                    menu.show(ids, cx, evt.position, is_fav, rating);
                                                 });
+                                                cx.notify();
                                             },
                                         )
                                     })
                             }))
                     }),
             )
+            .child(self.context_menu.clone())
+            .child(self.tag_editor.clone())
     }
 }
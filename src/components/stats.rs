@@ -0,0 +1,334 @@
+use gpui::{prelude::FluentBuilder, *};
+use rfd::AsyncFileDialog;
+use tracing::warn;
+
+use crate::{
+    db::{
+        database::DB,
+        stats::{ListeningStats, StatsPeriod, TopEntry},
+    },
+    theme::*,
+};
+
+/// 排行榜显示条数
+const TOP_LIMIT: usize = 10;
+/// 热力图单元格尺寸
+const HEATMAP_CELL_SIZE: f32 = 18.0;
+
+const WEEKDAYS: [&str; 7] = ["周日", "周一", "周二", "周三", "周四", "周五", "周六"];
+
+/// 收听统计页
+pub struct StatsView {
+    period: StatsPeriod,
+    stats: Option<ListeningStats>,
+}
+
+impl StatsView {
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        let mut view = Self {
+            period: StatsPeriod::Month,
+            stats: None,
+        };
+        view.reload(cx);
+        view
+    }
+
+    /// 从数据库重新统计（每次打开统计页时调用）
+    pub fn reload(&mut self, cx: &mut Context<Self>) {
+        match cx.global::<DB>().load_stats(self.period, TOP_LIMIT) {
+            Ok(stats) => self.stats = Some(stats),
+            Err(e) => warn!("加载收听统计失败: {}", e),
+        }
+        cx.notify();
+    }
+
+    fn set_period(&mut self, period: StatsPeriod, cx: &mut Context<Self>) {
+        self.period = period;
+        self.reload(cx);
+    }
+
+    /// 选择保存位置并导出（格式由扩展名决定）
+    fn export(&self, cx: &mut Context<Self>) {
+        let Some(stats) = self.stats.clone() else {
+            return;
+        };
+        cx.spawn(
+            async move |_this: WeakEntity<StatsView>, _cx: &mut AsyncApp| {
+                let file = AsyncFileDialog::new()
+                    .set_title("导出收听统计")
+                    .set_file_name("zotu-stats.csv")
+                    .add_filter("CSV", &["csv"])
+                    .add_filter("JSON", &["json"])
+                    .save_file()
+                    .await;
+
+                if let Some(file) = file {
+                    let path = file.path();
+                    let is_json = path
+                        .extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+                    let result = if is_json {
+                        stats.write_json(path)
+                    } else {
+                        stats.write_csv(path)
+                    };
+                    if let Err(e) = result {
+                        warn!("导出收听统计失败: {}", e);
+                    }
+                }
+            },
+        )
+        .detach();
+    }
+}
+
+/// 收听时长：x 小时 y 分钟
+fn format_listening_time(seconds: i64) -> String {
+    let hours = seconds / 3600;
+    let minutes = seconds % 3600 / 60;
+    if hours > 0 {
+        format!("{} 小时 {} 分钟", hours, minutes)
+    } else {
+        format!("{} 分钟", minutes)
+    }
+}
+
+fn render_section_title(title: &'static str) -> Div {
+    div()
+        .text_sm()
+        .font_weight(FontWeight::MEDIUM)
+        .text_color(text_secondary())
+        .mb_2()
+        .child(title)
+}
+
+/// 概览卡片
+fn render_card(label: &'static str, value: String) -> Div {
+    div()
+        .flex_1()
+        .flex()
+        .flex_col()
+        .gap_1()
+        .p_4()
+        .bg(bg_card())
+        .border_1()
+        .border_color(border_default())
+        .rounded_lg()
+        .child(div().text_xs().text_color(text_tertiary()).child(label))
+        .child(
+            div()
+                .text_xl()
+                .font_weight(FontWeight::SEMIBOLD)
+                .text_color(text_primary())
+                .child(value),
+        )
+}
+
+/// 排行榜
+fn render_top_list(title: &'static str, entries: &[TopEntry]) -> Div {
+    div()
+        .flex_1()
+        .min_w_0()
+        .child(render_section_title(title))
+        .when(entries.is_empty(), |this| {
+            this.child(
+                div()
+                    .text_xs()
+                    .text_color(text_placeholder())
+                    .child("暂无数据"),
+            )
+        })
+        .children(entries.iter().enumerate().map(|(idx, entry)| {
+            div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap_2()
+                .py_1()
+                .text_sm()
+                .child(
+                    div()
+                        .w(px(20.0))
+                        .text_xs()
+                        .text_color(text_placeholder())
+                        .child(format!("{}", idx + 1)),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .min_w_0()
+                        .flex()
+                        .flex_col()
+                        .child(
+                            div()
+                                .truncate()
+                                .text_color(text_primary())
+                                .child(entry.name.clone()),
+                        )
+                        .when_some(entry.detail.clone(), |this, detail| {
+                            this.child(
+                                div()
+                                    .truncate()
+                                    .text_xs()
+                                    .text_color(text_tertiary())
+                                    .child(detail),
+                            )
+                        }),
+                )
+                .child(
+                    div()
+                        .text_xs()
+                        .text_color(text_tertiary())
+                        .child(format!("{} 次", entry.plays)),
+                )
+        }))
+}
+
+/// 收听热力图：行为星期，列为小时，颜色深浅表示播放次数
+fn render_heatmap(heatmap: &[[i64; 24]; 7]) -> Div {
+    let max = heatmap.iter().flatten().copied().max().unwrap_or(0).max(1);
+
+    div()
+        .flex()
+        .flex_col()
+        .gap(px(2.0))
+        .children(heatmap.iter().enumerate().map(|(weekday, hours)| {
+            div()
+                .flex()
+                .flex_row()
+                .items_center()
+                .gap(px(2.0))
+                .child(
+                    div()
+                        .w(px(36.0))
+                        .text_xs()
+                        .text_color(text_tertiary())
+                        .child(WEEKDAYS[weekday]),
+                )
+                .children(hours.iter().map(|plays| {
+                    let mut color = border_focus();
+                    color.a = if *plays == 0 {
+                        0.06
+                    } else {
+                        0.2 + 0.8 * (*plays as f32 / max as f32)
+                    };
+                    div().size(px(HEATMAP_CELL_SIZE)).rounded_sm().bg(color)
+                }))
+        }))
+        .child(
+            div()
+                .flex()
+                .flex_row()
+                .gap(px(2.0))
+                .child(div().w(px(36.0)))
+                .children((0..24).map(|hour| {
+                    div()
+                        .w(px(HEATMAP_CELL_SIZE))
+                        .text_xs()
+                        .text_color(text_placeholder())
+                        .when(hour % 6 == 0, |this| this.child(format!("{}", hour)))
+                })),
+        )
+}
+
+impl Render for StatsView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .id("stats")
+            .flex()
+            .flex_col()
+            .flex_1()
+            .p_6()
+            .bg(bg_content())
+            .overflow_y_scroll()
+            .child(
+                div()
+                    .mb_6()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_2()
+                    .child(
+                        div()
+                            .mr_auto()
+                            .text_2xl()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(text_primary())
+                            .child("收听统计"),
+                    )
+                    // 时间范围切换
+                    .children(StatsPeriod::all().into_iter().map(|period| {
+                        div()
+                            .id(period.label())
+                            .px_3()
+                            .py_1()
+                            .rounded_lg()
+                            .cursor_pointer()
+                            .text_sm()
+                            .text_color(text_secondary())
+                            .hover(|s| s.bg(bg_hover()))
+                            .when(period == self.period, |this| this.bg(bg_active()))
+                            .child(period.label())
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _evt, _window, cx| {
+                                    this.set_period(period, cx);
+                                }),
+                            )
+                    }))
+                    .child(
+                        div()
+                            .id("export-stats")
+                            .ml_2()
+                            .px_3()
+                            .py_1()
+                            .rounded_lg()
+                            .cursor_pointer()
+                            .text_sm()
+                            .bg(accent_blue())
+                            .hover(|s| s.bg(bg_active()))
+                            .child("导出")
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|this, _evt, _window, cx| {
+                                    this.export(cx);
+                                }),
+                            ),
+                    ),
+            )
+            .when_some(self.stats.as_ref(), |this, stats| {
+                this.child(
+                    div()
+                        .mb_6()
+                        .flex()
+                        .flex_row()
+                        .gap_3()
+                        .child(render_card("播放次数", format!("{}", stats.total_plays)))
+                        .child(render_card(
+                            "收听时长",
+                            format_listening_time(stats.total_seconds),
+                        ))
+                        .child(render_card(
+                            "当前连续",
+                            format!("{} 天", stats.streaks.current),
+                        ))
+                        .child(render_card(
+                            "最长连续",
+                            format!("{} 天", stats.streaks.longest),
+                        )),
+                )
+                .child(
+                    div()
+                        .mb_6()
+                        .flex()
+                        .flex_row()
+                        .gap_6()
+                        .child(render_top_list("最常听的歌曲", &stats.top_tracks))
+                        .child(render_top_list("最常听的艺术家", &stats.top_artists))
+                        .child(render_top_list("最常听的专辑", &stats.top_albums)),
+                )
+                .child(render_section_title("收听时段"))
+                .child(render_heatmap(&stats.heatmap))
+            })
+    }
+}
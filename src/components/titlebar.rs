@@ -1,6 +1,7 @@
 use gpui::*;
 
 use crate::{
+    keymap::ToggleNotifications,
     theme::*,
     ui::search::{ClearSearchEvent, SearchBox, SearchEvent},
 };
@@ -34,6 +35,13 @@ impl TitleBar {
 
         Self { search_box }
     }
+
+    /// 让搜索框获得焦点
+    pub fn focus_search(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.search_box.update(cx, |search_box, cx| {
+            search_box.focus(window, cx);
+        });
+    }
 }
 
 impl EventEmitter<SearchEvent> for TitleBar {}
@@ -71,6 +79,28 @@ impl Render for TitleBar {
                     .w_auto()
                     .flex()
                     .flex_row()
+                    // 通知历史
+                    .child(
+                        div()
+                            .w(px(30.0))
+                            .h(px(30.0))
+                            .flex()
+                            .items_center()
+                            .justify_center()
+                            .cursor_pointer()
+                            .child(
+                                svg()
+                                    .path("svg/bell.svg")
+                                    .size_4()
+                                    .text_color(text_secondary()),
+                            )
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|_this, _event, window, cx| {
+                                    window.dispatch_action(Box::new(ToggleNotifications), cx);
+                                }),
+                            ),
+                    )
                     // 最小化
                     .child(
                         svg()
@@ -1,13 +1,43 @@
 use gpui::{Global,SharedString};
 use serde::{Deserialize, Serialize};
-use std::{fs, io, path::Path};
-
-use crate::{db::metadata::AlbumInfo,play::player::LoopMode};
+use std::{collections::HashMap, fs, io, path::{Path, PathBuf}};
+
+use crate::{
+    db::metadata::AlbumInfo,
+    log::LogLevel,
+    play::{
+        dsp::EffectSlot,
+        equalizer::{EqPreset, EqSettings},
+        player::LoopMode,
+        shuffle::ShuffleMode,
+    },
+};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Config {
     pub media_file: MediaFile,
     pub play_info: PlayInfo,
+    /// 快捷键覆盖：动作名 -> 按键（如 "play_pause": "ctrl-space"），空字符串表示取消绑定
+    #[serde(default)]
+    pub keymap: HashMap<String, String>,
+    /// 局域网 HTTP/WebSocket 远程控制
+    #[serde(default)]
+    pub http_api: HttpApi,
+    /// 听歌记录上报
+    #[serde(default)]
+    pub scrobble: Scrobble,
+    /// 均衡器
+    #[serde(default)]
+    pub equalizer: Equalizer,
+    /// 音效链（按处理顺序）
+    #[serde(default)]
+    pub effects: Vec<EffectSlot>,
+    /// 日志
+    #[serde(default)]
+    pub log: Log,
+    /// 配置文件路径（不写入文件），用于设置变更后立即保存
+    #[serde(skip)]
+    pub path: PathBuf,
 }
 
 impl Global for Config{}
@@ -17,6 +47,13 @@ impl Default for Config {
         Config {
             media_file: MediaFile::default(),
             play_info: PlayInfo::default(),
+            keymap: HashMap::new(),
+            http_api: HttpApi::default(),
+            scrobble: Scrobble::default(),
+            equalizer: Equalizer::default(),
+            effects: Vec::new(),
+            log: Log::default(),
+            path: PathBuf::new(),
         }
     }
 }
@@ -26,6 +63,9 @@ impl Default for Config {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct MediaFile {
     pub music_directory: SharedString,
+    /// 评分同时写入音频文件标签（POPM / RATING）
+    #[serde(default)]
+    pub write_rating_tags: bool,
 }
 
 
@@ -34,6 +74,71 @@ pub struct PlayInfo{
     pub loop_mode: LoopMode,
     pub volume: f32,
     pub album: Option<AlbumInfo>,
+    /// 随机播放方式
+    #[serde(default)]
+    pub shuffle_mode: ShuffleMode,
+    /// 固定随机种子，设置后随机播放顺序可复现
+    #[serde(default)]
+    pub shuffle_seed: Option<u64>,
+    /// 睡眠定时结束前淡出
+    #[serde(default)]
+    pub sleep_fade: bool,
+    /// 播放速度（不改变音调）
+    #[serde(default = "default_playback_rate")]
+    pub playback_rate: f32,
+    /// 变调（半音）
+    #[serde(default)]
+    pub pitch_semitones: f32,
+    /// 音频输出设备（None 为系统默认）
+    #[serde(default)]
+    pub output_device: Option<String>,
+}
+
+fn default_playback_rate() -> f32 {
+    1.0
+}
+
+
+/// 远程控制 API 配置（默认关闭）
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct HttpApi {
+    pub enabled: bool,
+    /// 监听地址，默认只接受本机连接；局域网访问需要改为 0.0.0.0:8765
+    pub address: String,
+    /// 访问令牌，为空时启动时自动生成
+    pub token: String,
+}
+
+
+/// 听歌记录上报配置（ListenBrainz 兼容接口，默认关闭）
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct Scrobble {
+    pub enabled: bool,
+    /// 接口地址，可换成自建的兼容服务
+    pub endpoint: String,
+    /// 用户令牌
+    pub token: String,
+}
+
+
+/// 均衡器配置
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Equalizer {
+    pub settings: EqSettings,
+    /// 用户保存的预设
+    pub presets: Vec<EqPreset>,
+}
+
+
+/// 日志配置
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Log {
+    /// 记录的最低级别
+    pub level: LogLevel,
 }
 
 
@@ -41,6 +146,7 @@ impl Default for MediaFile {
     fn default() -> Self {
         MediaFile {
             music_directory: SharedString::from("C:/Users/ceinw/OneDrive/Desktop/Music"),
+            write_rating_tags: false,
         }
     }
     
@@ -52,31 +158,76 @@ impl Default for PlayInfo {
             loop_mode: LoopMode::List,
             volume: 0.5,
             album: None,
+            shuffle_mode: ShuffleMode::Track,
+            shuffle_seed: None,
+            sleep_fade: false,
+            playback_rate: 1.0,
+            pitch_semitones: 0.0,
+            output_device: None,
         }
     }
     
 }
 
+impl Default for HttpApi {
+    fn default() -> Self {
+        HttpApi {
+            enabled: false,
+            address: String::from("127.0.0.1:8765"),
+            token: String::new(),
+        }
+    }
+}
+
+impl Default for Scrobble {
+    fn default() -> Self {
+        Scrobble {
+            enabled: false,
+            endpoint: String::from("https://api.listenbrainz.org"),
+            token: String::new(),
+        }
+    }
+}
+
 impl Config {
     /// 读取配置：文件存在就读；不存在就用默认并写入一份
     pub fn load_or_create(path: impl AsRef<Path>) -> io::Result<Self> {
         let path = path.as_ref();
 
+        match Self::load(path)? {
+            Some(cfg) => Ok(cfg),
+            None => {
+                let cfg = Self {
+                    path: path.to_path_buf(),
+                    ..Self::default()
+                };
+                cfg.save(path)?;
+                Ok(cfg)
+            }
+        }
+    }
+
+    /// 只读取配置，文件不存在时返回 None（不会创建文件）
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Option<Self>> {
+        let path = path.as_ref();
+
         match fs::read_to_string(path) {
             Ok(text) => {
-                let cfg: Self =
+                let mut cfg: Self =
                     serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-                Ok(cfg)
-            }
-            Err(e) if e.kind() == io::ErrorKind::NotFound => {
-                let cfg = Self::default();
-                cfg.save(path)?;
-                Ok(cfg)
+                cfg.path = path.to_path_buf();
+                Ok(Some(cfg))
             }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
             Err(e) => Err(e),
         }
     }
 
+    /// 写回读取时的配置文件
+    pub fn persist(&self) -> io::Result<()> {
+        self.save(&self.path)
+    }
+
     /// 写入配置（覆盖写）
     pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
         let path = path.as_ref();
@@ -1,4 +1,8 @@
 pub mod metadata;
+pub mod cover;
 pub mod database;
 pub mod table;
-pub mod dbstate;
\ No newline at end of file
+pub mod dbstate;
+pub mod smart;
+pub mod playlist;
+pub mod stats;
\ No newline at end of file
@@ -0,0 +1,183 @@
+use gpui::{App, Asset, ImageCacheError, ImageSource, ImgResourceLoader, Resource, SharedString};
+use image::{ExtendedColorType, codecs::jpeg::JpegEncoder, imageops::FilterType, load_from_memory};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use uuid::Uuid;
+
+use crate::paths;
+
+/// 原图文件的扩展名（内容可能是任意图片格式）
+const ORIGINAL_EXT: &str = "orig";
+/// 缩略图的 JPEG 质量
+const JPEG_QUALITY: u8 = 85;
+/// 最近修改过的文件不回收，避免删掉扫描中刚写入、还没入库的封面
+const GC_GRACE: Duration = Duration::from_secs(3600);
+
+/// 封面缩略图尺寸
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CoverSize {
+    Small,
+    Medium,
+    Large,
+}
+
+impl CoverSize {
+    /// 边长（像素）
+    pub fn pixels(self) -> u32 {
+        match self {
+            CoverSize::Small => 64,
+            CoverSize::Medium => 256,
+            CoverSize::Large => 512,
+        }
+    }
+
+    /// 能清晰显示给定边长（物理像素）的最小尺寸
+    pub fn fit(pixels: f32) -> Self {
+        [CoverSize::Small, CoverSize::Medium]
+            .into_iter()
+            .find(|size| size.pixels() as f32 >= pixels)
+            .unwrap_or(CoverSize::Large)
+    }
+}
+
+/// 封面内容的哈希，作为封面在缓存中的键
+pub fn hash(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// 把封面原图存入缓存（内容相同的封面只存一份），返回哈希
+pub fn store(data: &[u8]) -> io::Result<String> {
+    image::guess_format(data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let hash = hash(data);
+    let path = original_path(&hash);
+    if !path.exists() {
+        write_atomic(&path, data)?;
+    }
+    Ok(hash)
+}
+
+/// 封面原图的路径
+pub fn original_path(hash: &str) -> PathBuf {
+    paths::cover_dir().join(format!("{}.{}", hash, ORIGINAL_EXT))
+}
+
+fn variant_path(hash: &str, size: CoverSize) -> PathBuf {
+    paths::cover_dir().join(format!("{}-{}.jpg", hash, size.pixels()))
+}
+
+/// 指定尺寸的封面缩略图，不存在时由原图生成（比目标小的原图不放大）
+pub fn variant(hash: &str, size: CoverSize) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = variant_path(hash, size);
+    if path.exists() {
+        return Ok(path);
+    }
+
+    let img = load_from_memory(&fs::read(original_path(hash))?)?;
+    let pixels = size.pixels();
+    let img = if img.width() > pixels || img.height() > pixels {
+        img.resize_to_fill(pixels, pixels, FilterType::Lanczos3)
+    } else {
+        img
+    };
+    let rgb = img.to_rgb8();
+    let mut data = Vec::new();
+    JpegEncoder::new_with_quality(&mut data, JPEG_QUALITY).encode(
+        &rgb,
+        rgb.width(),
+        rgb.height(),
+        ExtendedColorType::Rgb8,
+    )?;
+    write_atomic(&path, &data)?;
+    Ok(path)
+}
+
+/// 先写临时文件再重命名，避免并发读取到写了一半的图片
+///
+/// 临时文件名各不相同，多个线程同时生成同一张图时不会互相覆盖；中途退出留下的临时文件由回收清理
+fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{}.tmp", Uuid::new_v4().simple()));
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, data)?;
+    fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+/// 删除不再被任何歌曲引用的封面（包括已迁移完的旧版本按歌曲保存的封面），返回删除的文件数；
+/// `legacy` 是还没迁移的旧封面文件名，不会被删除
+pub fn collect_garbage(
+    referenced: &HashSet<String>,
+    legacy: &HashSet<OsString>,
+) -> io::Result<usize> {
+    let dir = paths::cover_dir();
+    if !dir.is_dir() {
+        return Ok(0);
+    }
+
+    let mut removed = 0;
+    for entry in fs::read_dir(&dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let recent = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.elapsed().ok())
+            .is_none_or(|age| age < GC_GRACE);
+        if !metadata.is_file() || recent {
+            continue;
+        }
+
+        let name = entry.file_name();
+        if legacy.contains(&name) {
+            continue;
+        }
+        let name = name.to_string_lossy();
+        let key = name.split(['.', '-']).next().unwrap_or_default();
+        // 写入中途退出留下的临时文件
+        if !referenced.contains(key) || name.ends_with(".tmp") {
+            fs::remove_file(entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// 在后台生成缩略图并返回其路径
+enum CoverLoader {}
+
+impl Asset for CoverLoader {
+    type Source = (SharedString, CoverSize);
+    type Output = Result<Arc<Path>, SharedString>;
+
+    fn load(
+        (hash, size): Self::Source,
+        cx: &mut App,
+    ) -> impl Future<Output = Self::Output> + Send + 'static {
+        cx.background_executor().spawn(async move {
+            variant(&hash, size)
+                .map(Arc::from)
+                .map_err(|e| SharedString::from(format!("生成封面缩略图失败: {}", e)))
+        })
+    }
+}
+
+/// 供 img() 使用的封面图片，缩略图按需在后台生成
+pub fn image(hash: SharedString, size: CoverSize) -> ImageSource {
+    ImageSource::Custom(Arc::new(move |window, cx| {
+        match window.use_asset::<CoverLoader>(&(hash.clone(), size), cx)? {
+            Ok(path) => window.use_asset::<ImgResourceLoader>(&Resource::Path(path), cx),
+            Err(e) => Some(Err(ImageCacheError::Asset(e))),
+        }
+    }))
+}
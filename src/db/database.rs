@@ -1,21 +1,53 @@
-use gpui::{Global, SharedString};
-use rusqlite::{Connection, params};
-use std::io;
-use std::{path::PathBuf, sync::Arc};
+use gpui::{App, Global, SharedString, Task};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::{HashMap, HashSet};
+use std::{ffi::OsString, fs, io};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tracing::{debug_span, info, info_span, warn};
 use uuid::Uuid;
 use walkdir::WalkDir;
 
-use super::metadata::AlbumInfo;
-use crate::db::table;
+use super::cover;
+use super::metadata::{AlbumInfo, TagEdit};
+use super::playlist::{self, ImportReport};
+use super::smart::SmartPlaylist;
+use super::stats::{ListeningStats, StatsPeriod, Streaks, TopEntry};
+use crate::{
+    db::table,
+    error::AppError,
+    notify::{self, Level},
+    paths,
+    play::{ab_loop::LoopRegion, shuffle::TrackStats, waveform::FileStamp},
+    scrobble::Listen,
+};
+
+/// library 表中映射到 AlbumInfo 的列（顺序与 map_row_to_album 对应）
+const ALBUM_COLUMNS: &str = "uuid, title, artist, album, duration, path, cover, genre";
+
+/// 当前数据库结构版本（PRAGMA user_version）
+const SCHEMA_VERSION: i32 = 10;
+
+/// 支持的音频文件扩展名
+const AUDIO_EXTENSIONS: [&str; 7] = ["mp3", "flac", "wav", "m4a", "ogg", "aac", "vorbis"];
+
+/// 后台扫描时每处理多少个文件更新一次进度通知
+const SCAN_PROGRESS_STEP: usize = 20;
 
 pub struct DB {
     conn: Connection,
+    /// 数据库文件路径（后台任务用它打开独立连接）
+    path: String,
 }
 
 impl Global for DB {}
 
 impl DB {
     pub fn new(db_path: &str) -> rusqlite::Result<DB> {
+        let _span = debug_span!("db_open", path = db_path).entered();
         let conn = Connection::open(db_path)?;
         // 启用性能优化
         conn.execute_batch(
@@ -24,6 +56,8 @@ impl DB {
              PRAGMA cache_size = 10000;
              PRAGMA temp_store = MEMORY;",
         )?;
+        // 后台扫描、上报等使用独立连接，写入冲突时等待而不是立即失败
+        conn.busy_timeout(Duration::from_secs(5))?;
 
         // 创建必要的表（如果不存在）
         conn.execute_batch(
@@ -33,9 +67,7 @@ impl DB {
                 artist TEXT,
                 album TEXT,
                 duration INTEGER NOT NULL,
-                path TEXT NOT NULL,
-                cover_path TEXT,
-                cover_64 BLOB
+                path TEXT NOT NULL
             );
             CREATE TABLE IF NOT EXISTS favorite (
                 uuid BLOB PRIMARY KEY
@@ -45,7 +77,173 @@ impl DB {
             );",
         )?;
 
-        Ok(DB { conn })
+        Self::migrate(&conn)?;
+
+        Ok(DB {
+            conn,
+            path: db_path.to_string(),
+        })
+    }
+
+    /// 按 user_version 逐步升级数据库结构
+    fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+        let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+        if version < 1 {
+            // v1：智能歌单所需的统计列与歌单表
+            Self::upgrade(conn, 1, |conn| {
+                conn.execute_batch(
+                    "ALTER TABLE library ADD COLUMN genre TEXT;
+                     ALTER TABLE library ADD COLUMN added_at INTEGER;
+                     ALTER TABLE library ADD COLUMN play_count INTEGER NOT NULL DEFAULT 0;
+                     ALTER TABLE library ADD COLUMN last_played INTEGER;
+                     UPDATE library SET added_at = CAST(strftime('%s', 'now') AS INTEGER);
+                     CREATE TABLE smart_playlist (
+                         id INTEGER PRIMARY KEY AUTOINCREMENT,
+                         name TEXT NOT NULL,
+                         definition TEXT NOT NULL
+                     );",
+                )?;
+                for playlist in SmartPlaylist::defaults() {
+                    Self::insert_smart_playlist(conn, &playlist)?;
+                }
+                Ok(())
+            })?;
+        }
+
+        if version < 2 {
+            // v2：自定义歌单
+            Self::upgrade(conn, 2, |conn| {
+                conn.execute_batch(
+                    "CREATE TABLE playlist (
+                         id INTEGER PRIMARY KEY AUTOINCREMENT,
+                         name TEXT NOT NULL
+                     );
+                     CREATE TABLE playlist_item (
+                         playlist_id INTEGER NOT NULL REFERENCES playlist(id) ON DELETE CASCADE,
+                         position INTEGER NOT NULL,
+                         uuid BLOB NOT NULL,
+                         PRIMARY KEY (playlist_id, position)
+                     );",
+                )
+            })?;
+        }
+
+        if version < 3 {
+            // v3：离线听歌记录上报队列
+            Self::upgrade(conn, 3, |conn| {
+                conn.execute_batch(
+                    "CREATE TABLE scrobble_queue (
+                         id INTEGER PRIMARY KEY AUTOINCREMENT,
+                         listen TEXT NOT NULL,
+                         attempts INTEGER NOT NULL DEFAULT 0,
+                         next_attempt INTEGER NOT NULL DEFAULT 0
+                     );",
+                )
+            })?;
+        }
+
+        if version < 4 {
+            // v4：播放日志（统计用），已有的最后播放时间作为一条记录保留
+            Self::upgrade(conn, 4, |conn| {
+                conn.execute_batch(
+                    "CREATE TABLE play_log (
+                         uuid BLOB NOT NULL,
+                         played_at INTEGER NOT NULL,
+                         duration INTEGER NOT NULL
+                     );
+                     CREATE INDEX play_log_played_at ON play_log (played_at);
+                     INSERT INTO play_log (uuid, played_at, duration)
+                         SELECT uuid, last_played, duration FROM library WHERE last_played IS NOT NULL;",
+                )
+            })?;
+        }
+
+        if version < 5 {
+            // v5：星级评分（0 表示未评分）
+            Self::upgrade(conn, 5, |conn| {
+                conn.execute_batch(
+                    "ALTER TABLE library ADD COLUMN rating INTEGER NOT NULL DEFAULT 0;",
+                )?;
+                Self::insert_smart_playlist(conn, &SmartPlaylist::top_rated())?;
+                Ok(())
+            })?;
+        }
+
+        if version < 6 {
+            // v6：每首歌保存的 A–B 循环片段
+            Self::upgrade(conn, 6, |conn| {
+                conn.execute_batch(
+                    "CREATE TABLE loop_region (
+                         id INTEGER PRIMARY KEY AUTOINCREMENT,
+                         uuid BLOB NOT NULL,
+                         name TEXT NOT NULL,
+                         start_ms INTEGER NOT NULL,
+                         end_ms INTEGER NOT NULL
+                     );
+                     CREATE INDEX loop_region_uuid ON loop_region (uuid);",
+                )
+            })?;
+        }
+
+        if version < 7 {
+            // v7：波形进度条的峰值缓存
+            Self::upgrade(conn, 7, |conn| {
+                conn.execute_batch(
+                    "CREATE TABLE waveform (
+                         uuid BLOB PRIMARY KEY,
+                         peaks BLOB NOT NULL
+                     );",
+                )
+            })?;
+        }
+
+        if version < 8 {
+            // v8：无法解码的歌曲记录失败原因（NULL 表示可以播放）
+            Self::upgrade(conn, 8, |conn| {
+                conn.execute_batch("ALTER TABLE library ADD COLUMN unplayable TEXT;")
+            })?;
+        }
+
+        if version < 9 {
+            // v9：封面按内容哈希共享存储，不再每首歌保存一份原图和 64px 缩略图
+            // （旧封面文件较多时读写很慢，启动后在后台迁移，见 migrate_covers）
+            Self::upgrade(conn, 9, |conn| {
+                conn.execute_batch("ALTER TABLE library ADD COLUMN cover TEXT;")
+            })?;
+        }
+
+        if version < 10 {
+            // v10：波形缓存记录文件修改时间和大小，文件变化后重新计算（旧缓存直接丢弃）
+            Self::upgrade(conn, 10, |conn| {
+                conn.execute_batch(
+                    "DROP TABLE waveform;
+                     CREATE TABLE waveform (
+                         uuid BLOB PRIMARY KEY,
+                         mtime INTEGER NOT NULL,
+                         size INTEGER NOT NULL,
+                         peaks BLOB NOT NULL
+                     );",
+                )
+            })?;
+        }
+
+        if version < SCHEMA_VERSION {
+            info!("数据库结构已从 v{} 升级到 v{}", version, SCHEMA_VERSION);
+        }
+        Ok(())
+    }
+
+    /// 在一个事务中执行一步升级并写入对应的 user_version，中途失败或退出时整步回滚
+    fn upgrade(
+        conn: &Connection,
+        version: i32,
+        step: impl FnOnce(&Connection) -> rusqlite::Result<()>,
+    ) -> rusqlite::Result<()> {
+        let tx = conn.unchecked_transaction()?;
+        step(&tx)?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()
     }
 
     /// 从数据库行映射到 AlbumInfo 的通用方法
@@ -59,28 +257,30 @@ impl DB {
         // 处理可能为 NULL 的字段
         let artist: Option<String> = row.get(2)?;
         let album: Option<String> = row.get(3)?;
-        let cover_path: Option<String> = row.get(6)?;
-        let cover_64: Option<Vec<u8>> = row.get(7)?;
+        let cover: Option<String> = row.get(6)?;
+        let genre: Option<String> = row.get(7)?;
 
         let title = SharedString::new(row.get::<_, String>(1)?);
         let artist = SharedString::new(artist.unwrap_or_else(|| "未知艺术家".to_string()));
         let album = SharedString::new(album.unwrap_or_else(|| "未知专辑".to_string()));
         let duration = row.get::<_, i64>(4)? as u64;
         let path = Arc::new(PathBuf::from(row.get::<_, String>(5)?));
-        let cover_path = cover_path.map(SharedString::new);
-        let cover_64 = cover_64.map(Arc::new);
+        let cover = cover.map(SharedString::new);
+        let genre = genre.map(SharedString::new);
 
         Ok(AlbumInfo::new(
-            id, title, artist, album, duration, path, cover_path, cover_64,
+            id, title, artist, album, duration, path, cover, genre,
         ))
     }
 
     /// 高性能加载所有专辑信息
     /// 使用预编译语句和批量处理优化性能
     pub fn load_all_albums(&self) -> Vec<AlbumInfo> {
-        let Ok(mut stmt) = self.conn.prepare_cached(
-            "SELECT uuid, title, artist, album, duration, path, cover_path, cover_64 FROM library",
-        ) else {
+        let _span = debug_span!("db_load_library").entered();
+        let Ok(mut stmt) = self
+            .conn
+            .prepare_cached(&format!("SELECT {} FROM library", ALBUM_COLUMNS))
+        else {
             return Vec::new();
         };
 
@@ -124,9 +324,10 @@ impl DB {
 
     /// 通过 UUID 查询单个专辑
     pub fn load_album_by_uuid(&self, uuid: &Uuid) -> rusqlite::Result<Option<AlbumInfo>> {
-        let mut stmt = self.conn.prepare_cached(
-            "SELECT uuid, title, artist, album, duration, path, cover_path, cover_64 FROM library WHERE uuid = ?",
-        )?;
+        let mut stmt = self.conn.prepare_cached(&format!(
+            "SELECT {} FROM library WHERE uuid = ?",
+            ALBUM_COLUMNS
+        ))?;
 
         let mut rows = stmt.query(params![uuid.as_bytes().as_slice()])?;
 
@@ -156,15 +357,23 @@ impl DB {
         Ok(())
     }
 
+    /// 清空指定表（收藏、历史）
+    pub fn clear_table(&self, table: table::Table) -> rusqlite::Result<()> {
+        self.conn
+            .execute(&format!("DELETE FROM {}", table.as_str()), [])?;
+        Ok(())
+    }
+
     /// 分页加载专辑（适用于大数据量场景）
     pub fn load_albums_paginated(
         &self,
         offset: i64,
         limit: i64,
     ) -> rusqlite::Result<Vec<AlbumInfo>> {
-        let mut stmt = self.conn.prepare_cached(
-            "SELECT uuid, title, artist, album, duration, path, cover_path, cover_64 FROM library LIMIT ? OFFSET ?"
-        )?;
+        let mut stmt = self.conn.prepare_cached(&format!(
+            "SELECT {} FROM library LIMIT ? OFFSET ?",
+            ALBUM_COLUMNS
+        ))?;
 
         let album_iter = stmt.query_map(params![limit, offset], Self::map_row_to_album)?;
 
@@ -175,32 +384,836 @@ impl DB {
         Ok(albums)
     }
 
+    /// 更新歌曲标签信息
+    pub fn update_tags(&self, id: &Uuid, edit: &TagEdit) -> rusqlite::Result<()> {
+        let mut stmt = self.conn.prepare_cached(
+            "UPDATE library SET title = COALESCE(?1, title), artist = COALESCE(?2, artist),
+                album = COALESCE(?3, album), genre = COALESCE(?4, genre)
+             WHERE uuid = ?5",
+        )?;
+        stmt.execute(params![
+            edit.title,
+            edit.artist,
+            edit.album,
+            edit.genre,
+            id.as_bytes().as_slice()
+        ])?;
+        Ok(())
+    }
+
+    /// 从曲库中移除歌曲（同时清理收藏、历史和歌单中的引用，不删除文件）
+    pub fn remove_from_library(&self, ids: &[Uuid]) -> rusqlite::Result<()> {
+        let _span = debug_span!("db_remove", count = ids.len()).entered();
+        let tx = self.conn.unchecked_transaction()?;
+        for table in [
+            "library",
+            "favorite",
+            "history",
+            "playlist_item",
+            "loop_region",
+            "waveform",
+        ] {
+            let mut stmt = tx.prepare_cached(&format!("DELETE FROM {} WHERE uuid = ?", table))?;
+            for id in ids {
+                stmt.execute(params![id.as_bytes().as_slice()])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// 记录一次播放：增加播放次数、更新最后播放时间，并把开始时间（Unix 秒）和实际收听的
+    /// 秒数写入播放日志
+    pub fn record_play(
+        &self,
+        id: &Uuid,
+        started_at: i64,
+        played_secs: u64,
+    ) -> rusqlite::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.prepare_cached(
+            "UPDATE library SET play_count = play_count + 1, last_played = ? WHERE uuid = ?",
+        )?
+        .execute(params![started_at, id.as_bytes().as_slice()])?;
+        tx.prepare_cached(
+            "INSERT INTO play_log (uuid, played_at, duration)
+             SELECT uuid, ?, ? FROM library WHERE uuid = ?",
+        )?
+        .execute(params![
+            started_at,
+            played_secs as i64,
+            id.as_bytes().as_slice()
+        ])?;
+        tx.commit()
+    }
+
+    /// 设置歌曲评分（0–5，0 表示清除评分）
+    pub fn set_rating(&self, ids: &[Uuid], rating: u8) -> rusqlite::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare_cached("UPDATE library SET rating = ? WHERE uuid = ?")?;
+            for id in ids {
+                stmt.execute(params![rating, id.as_bytes().as_slice()])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// 标记歌曲无法播放（reason 为失败原因），None 表示恢复为可播放
+    pub fn set_unplayable(&self, id: &Uuid, reason: Option<&str>) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE library SET unplayable = ? WHERE uuid = ?",
+            params![reason, id.as_bytes().as_slice()],
+        )?;
+        Ok(())
+    }
+
+    /// 所有歌曲引用的封面哈希
+    pub fn referenced_covers(&self) -> rusqlite::Result<HashSet<String>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT DISTINCT cover FROM library WHERE cover IS NOT NULL")?;
+        stmt.query_map([], |row| row.get(0))?.collect()
+    }
+
+    /// 旧版本的 library 表带有 cover_path 列，新建的数据库没有这一列
+    fn has_legacy_covers(&self) -> rusqlite::Result<bool> {
+        self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM pragma_table_info('library') WHERE name = 'cover_path')",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    /// 还没迁移的旧封面的文件名（可能已随旧文件迁移复制到封面缓存目录，回收时要保留）
+    pub fn legacy_cover_files(&self) -> rusqlite::Result<HashSet<OsString>> {
+        if !self.has_legacy_covers()? {
+            return Ok(HashSet::new());
+        }
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT cover_path FROM library WHERE cover_path IS NOT NULL")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut names = HashSet::new();
+        for cover_path in rows {
+            if let Some(name) = Path::new(&cover_path?).file_name() {
+                names.insert(name.to_owned());
+            }
+        }
+        Ok(names)
+    }
+
+    /// 把旧版本按歌曲保存的封面文件存入封面缓存，返回迁移的封面数。
+    /// 读写文件在事务之外进行，最后在一个事务中更新所有迁移成功的歌曲；
+    /// 读取失败的保留 cover_path，下次启动时重试（旧文件留给封面回收删除）
+    pub fn migrate_covers(&self) -> rusqlite::Result<usize> {
+        if !self.has_legacy_covers()? {
+            return Ok(0);
+        }
+        let rows: Vec<(Vec<u8>, String)> = self
+            .conn
+            .prepare("SELECT uuid, cover_path FROM library WHERE cover_path IS NOT NULL")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        if rows.is_empty() {
+            return Ok(0);
+        }
+
+        let mut migrated = Vec::new();
+        for (uuid, cover_path) in rows {
+            // 旧封面可能已随旧文件迁移复制到封面缓存目录
+            let path = PathBuf::from(&cover_path);
+            let copied = path.file_name().map(|name| paths::cover_dir().join(name));
+            let data = fs::read(&path).or_else(|e| match &copied {
+                Some(copied) if e.kind() == io::ErrorKind::NotFound => fs::read(copied),
+                _ => Err(e),
+            });
+            match data.and_then(|data| cover::store(&data)) {
+                Ok(hash) => migrated.push((uuid, cover_path, Some(hash))),
+                // 文件已经不存在，没有可迁移的内容
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    warn!("旧封面已不存在: {}", cover_path);
+                    migrated.push((uuid, cover_path, None));
+                }
+                Err(e) => warn!("迁移封面失败: {}: {}", cover_path, e),
+            }
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut count = 0;
+        {
+            let mut stmt = tx.prepare(
+                "UPDATE library SET cover = ?1, cover_path = NULL, cover_64 = NULL
+                 WHERE uuid = ?2 AND cover_path = ?3",
+            )?;
+            for (uuid, cover_path, hash) in &migrated {
+                stmt.execute(params![hash, uuid, cover_path])?;
+                count += hash.is_some() as usize;
+            }
+        }
+        tx.commit()?;
+        Ok(count)
+    }
+
+    /// 在后台迁移旧版本的封面，返回迁移的封面数
+    pub fn migrate_covers_in_background(&self, cx: &App) -> Task<usize> {
+        let db_path = self.path.clone();
+        cx.background_executor().spawn(async move {
+            let _span = info_span!("cover_migrate").entered();
+            match DB::new(&db_path).and_then(|db| db.migrate_covers()) {
+                Ok(0) => 0,
+                Ok(migrated) => {
+                    info!("已迁移 {} 个旧版本的封面", migrated);
+                    migrated
+                }
+                Err(e) => {
+                    warn!("迁移封面失败: {}", e);
+                    0
+                }
+            }
+        })
+    }
+
+    /// 在后台删除不再被引用的封面
+    pub fn collect_covers_in_background(&self, cx: &App) -> Task<()> {
+        let db_path = self.path.clone();
+        cx.background_executor().spawn(async move {
+            let _span = info_span!("cover_gc").entered();
+            let references = DB::new(&db_path)
+                .and_then(|db| Ok((db.referenced_covers()?, db.legacy_cover_files()?)));
+            let (referenced, legacy) = match references {
+                Ok(references) => references,
+                Err(e) => {
+                    warn!("读取封面引用失败: {}", e);
+                    return;
+                }
+            };
+            match cover::collect_garbage(&referenced, &legacy) {
+                Ok(0) => {}
+                Ok(removed) => info!("已清理 {} 个无用的封面文件", removed),
+                Err(e) => warn!("清理封面缓存失败: {}", e),
+            }
+        })
+    }
+
+    /// 加载所有无法播放的歌曲及失败原因
+    pub fn load_unplayable(&self) -> rusqlite::Result<HashMap<Uuid, String>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT uuid, unplayable FROM library WHERE unplayable IS NOT NULL")?;
+        let rows = stmt.query_map([], |row| {
+            let uuid_bytes: Vec<u8> = row.get(0)?;
+            let id = Uuid::from_slice(&uuid_bytes).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    0,
+                    rusqlite::types::Type::Blob,
+                    Box::new(e),
+                )
+            })?;
+            Ok((id, row.get(1)?))
+        })?;
+        rows.collect()
+    }
+
+    /// 加载所有已评分歌曲的评分
+    pub fn load_ratings(&self) -> rusqlite::Result<HashMap<Uuid, u8>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT uuid, rating FROM library WHERE rating > 0")?;
+        let rows = stmt.query_map([], |row| {
+            let uuid_bytes: Vec<u8> = row.get(0)?;
+            let id = Uuid::from_slice(&uuid_bytes).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    0,
+                    rusqlite::types::Type::Blob,
+                    Box::new(e),
+                )
+            })?;
+            Ok((id, row.get(1)?))
+        })?;
+        rows.collect()
+    }
+
+    /// 加载加权随机使用的评分和播放次数
+    pub fn load_track_stats(&self) -> rusqlite::Result<HashMap<Uuid, TrackStats>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT uuid, rating, play_count FROM library")?;
+        let rows = stmt.query_map([], |row| {
+            let uuid_bytes: Vec<u8> = row.get(0)?;
+            let id = Uuid::from_slice(&uuid_bytes).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    0,
+                    rusqlite::types::Type::Blob,
+                    Box::new(e),
+                )
+            })?;
+            let stats = TrackStats {
+                rating: row.get(1)?,
+                play_count: row.get(2)?,
+            };
+            Ok((id, stats))
+        })?;
+        rows.collect()
+    }
+
+    // ========== 收听统计 ==========
+
+    /// 统计指定时间范围内的收听数据，排行榜各取前 limit 名
+    pub fn load_stats(
+        &self,
+        period: StatsPeriod,
+        limit: usize,
+    ) -> rusqlite::Result<ListeningStats> {
+        let now: i64 =
+            self.conn
+                .query_row("SELECT CAST(strftime('%s', 'now') AS INTEGER)", [], |row| {
+                    row.get(0)
+                })?;
+        let since = period.since(now);
+
+        let (total_plays, total_seconds) = self.conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(duration), 0) FROM play_log WHERE played_at >= ?",
+            params![since],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        let mut heatmap = [[0; 24]; 7];
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT CAST(strftime('%w', played_at, 'unixepoch', 'localtime') AS INTEGER),
+                    CAST(strftime('%H', played_at, 'unixepoch', 'localtime') AS INTEGER),
+                    COUNT(*)
+             FROM play_log WHERE played_at >= ? GROUP BY 1, 2",
+        )?;
+        let cells = stmt.query_map(params![since], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get(2)?))
+        })?;
+        for cell in cells {
+            let (weekday, hour, plays) = cell?;
+            if (0..7).contains(&weekday) && (0..24).contains(&hour) {
+                heatmap[weekday as usize][hour as usize] = plays;
+            }
+        }
+
+        Ok(ListeningStats {
+            period,
+            total_plays,
+            total_seconds,
+            top_tracks: self.load_top("l.title, l.artist", "p.uuid", since, limit)?,
+            top_artists: self.load_top("l.artist, NULL", "l.artist", since, limit)?,
+            top_albums: self.load_top("l.album, l.artist", "l.album, l.artist", since, limit)?,
+            heatmap,
+            streaks: self.load_streaks()?,
+        })
+    }
+
+    /// 按 group_by 聚合播放次数和时长，columns 为（名称, 补充信息）两列
+    fn load_top(
+        &self,
+        columns: &str,
+        group_by: &str,
+        since: i64,
+        limit: usize,
+    ) -> rusqlite::Result<Vec<TopEntry>> {
+        let mut stmt = self.conn.prepare_cached(&format!(
+            "SELECT {}, COUNT(*) AS plays, SUM(p.duration) AS seconds
+             FROM play_log p JOIN library l ON l.uuid = p.uuid
+             WHERE p.played_at >= ?
+             GROUP BY {}
+             ORDER BY plays DESC, seconds DESC
+             LIMIT ?",
+            columns, group_by
+        ))?;
+        let rows = stmt.query_map(params![since, limit as i64], |row| {
+            Ok(TopEntry {
+                name: row
+                    .get::<_, Option<String>>(0)?
+                    .unwrap_or_else(|| "未知".to_string()),
+                detail: row.get(1)?,
+                plays: row.get(2)?,
+                seconds: row.get(3)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// 按本地日期计算连续收听天数（相邻日期归为一段）
+    fn load_streaks(&self) -> rusqlite::Result<Streaks> {
+        let mut stmt = self.conn.prepare_cached(
+            "WITH days AS (
+                 SELECT DISTINCT date(played_at, 'unixepoch', 'localtime') AS day FROM play_log
+             ),
+             runs AS (
+                 SELECT day, julianday(day) - ROW_NUMBER() OVER (ORDER BY day) AS run FROM days
+             )
+             SELECT MAX(day) >= date('now', 'localtime', '-1 day'), COUNT(*)
+             FROM runs GROUP BY run ORDER BY MAX(day) DESC",
+        )?;
+        let runs = stmt.query_map([], |row| Ok((row.get::<_, bool>(0)?, row.get(1)?)))?;
+
+        let mut streaks = Streaks::default();
+        for (idx, run) in runs.enumerate() {
+            let (recent, days) = run?;
+            // 最近一段截至今天或昨天才算“当前”
+            if idx == 0 && recent {
+                streaks.current = days;
+            }
+            streaks.longest = streaks.longest.max(days);
+        }
+        Ok(streaks)
+    }
+
+    // ========== 智能歌单 ==========
+
+    fn insert_smart_playlist(conn: &Connection, playlist: &SmartPlaylist) -> rusqlite::Result<i64> {
+        let definition = serde_json::to_string(playlist)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        conn.execute(
+            "INSERT INTO smart_playlist (name, definition) VALUES (?1, ?2)",
+            params![playlist.name.to_string(), definition],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 保存智能歌单，返回新 ID
+    pub fn add_smart_playlist(&self, playlist: &SmartPlaylist) -> rusqlite::Result<i64> {
+        Self::insert_smart_playlist(&self.conn, playlist)
+    }
+
+    /// 更新已保存的智能歌单（名称和规则）
+    pub fn update_smart_playlist(&self, playlist: &SmartPlaylist) -> rusqlite::Result<()> {
+        let definition = serde_json::to_string(playlist)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        self.conn.execute(
+            "UPDATE smart_playlist SET name = ?1, definition = ?2 WHERE id = ?3",
+            params![playlist.name.to_string(), definition, playlist.id],
+        )?;
+        Ok(())
+    }
+
+    /// 删除智能歌单
+    pub fn remove_smart_playlist(&self, id: i64) -> rusqlite::Result<()> {
+        self.conn
+            .execute("DELETE FROM smart_playlist WHERE id = ?", params![id])?;
+        Ok(())
+    }
+
+    /// 加载所有智能歌单定义（按创建顺序）
+    pub fn load_smart_playlists(&self) -> rusqlite::Result<Vec<SmartPlaylist>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT id, definition FROM smart_playlist ORDER BY id")?;
+        let rows = stmt.query_map([], |row| {
+            let id: i64 = row.get(0)?;
+            let definition: String = row.get(1)?;
+            Ok((id, definition))
+        })?;
+
+        let mut playlists = Vec::new();
+        for row in rows {
+            let (id, definition) = row?;
+            match serde_json::from_str::<SmartPlaylist>(&definition) {
+                Ok(mut playlist) => {
+                    playlist.id = id;
+                    playlists.push(playlist);
+                }
+                Err(e) => warn!("解析智能歌单 {} 失败: {}", id, e),
+            }
+        }
+        Ok(playlists)
+    }
+
+    /// 通过 ID 加载单个智能歌单定义
+    pub fn load_smart_playlist(&self, id: i64) -> rusqlite::Result<Option<SmartPlaylist>> {
+        let definition: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT definition FROM smart_playlist WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(definition) = definition else {
+            return Ok(None);
+        };
+        match serde_json::from_str::<SmartPlaylist>(&definition) {
+            Ok(mut playlist) => {
+                playlist.id = id;
+                Ok(Some(playlist))
+            }
+            Err(e) => {
+                warn!("解析智能歌单 {} 失败: {}", id, e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// 在 SQL 中求值智能歌单规则，返回匹配的歌曲
+    pub fn load_smart_playlist_items(
+        &self,
+        playlist: &SmartPlaylist,
+    ) -> rusqlite::Result<Vec<AlbumInfo>> {
+        let mut values = Vec::new();
+        let clause = playlist.to_sql(&mut values);
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT {} FROM library{}", ALBUM_COLUMNS, clause))?;
+
+        let album_iter = stmt.query_map(
+            rusqlite::params_from_iter(values.iter()),
+            Self::map_row_to_album,
+        )?;
+        album_iter.collect()
+    }
+
+    // ========== 自定义歌单 ==========
+
+    /// 新建歌单并写入歌曲（按给定顺序），返回歌单 ID
+    pub fn create_playlist(&self, name: &str, items: &[Uuid]) -> rusqlite::Result<i64> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("INSERT INTO playlist (name) VALUES (?)", params![name])?;
+        let id = tx.last_insert_rowid();
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO playlist_item (playlist_id, position, uuid) VALUES (?1, ?2, ?3)",
+            )?;
+            for (position, uuid) in items.iter().enumerate() {
+                stmt.execute(params![id, position as i64, uuid.as_bytes().as_slice()])?;
+            }
+        }
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// 删除歌单及其歌曲
+    pub fn remove_playlist(&self, id: i64) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "DELETE FROM playlist_item WHERE playlist_id = ?",
+            params![id],
+        )?;
+        self.conn
+            .execute("DELETE FROM playlist WHERE id = ?", params![id])?;
+        Ok(())
+    }
+
+    /// 加载所有歌单（ID, 名称）
+    pub fn load_playlists(&self) -> rusqlite::Result<Vec<(i64, SharedString)>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT id, name FROM playlist ORDER BY id")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                SharedString::new(row.get::<_, String>(1)?),
+            ))
+        })?;
+        rows.collect()
+    }
+
+    /// 按顺序加载歌单中的歌曲（已不在曲库中的条目会被忽略）
+    pub fn load_playlist_items(&self, id: i64) -> rusqlite::Result<Vec<AlbumInfo>> {
+        let columns = ALBUM_COLUMNS
+            .split(", ")
+            .map(|c| format!("l.{}", c))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut stmt = self.conn.prepare_cached(&format!(
+            "SELECT {} FROM playlist_item p JOIN library l ON l.uuid = p.uuid
+             WHERE p.playlist_id = ? ORDER BY p.position",
+            columns
+        ))?;
+        let album_iter = stmt.query_map(params![id], Self::map_row_to_album)?;
+        album_iter.collect()
+    }
+
+    /// 导入歌单文件（M3U/M3U8、PLS、XSPF），条目按路径匹配到曲库
+    pub fn import_playlist(&self, path: &std::path::Path) -> Result<ImportReport, AppError> {
+        let _span = info_span!("import_playlist", path = %path.display()).entered();
+        let (name, entries) = playlist::read_playlist(path)?;
+        let base_dir = path.parent().unwrap_or(std::path::Path::new(""));
+        let library = self.load_all_albums();
+        let (matched, unmatched) = playlist::match_entries(&entries, base_dir, &library);
+
+        let playlist_id = self.create_playlist(&name, &matched)?;
+        Ok(ImportReport {
+            playlist_id,
+            name,
+            matched: matched.len(),
+            unmatched,
+        })
+    }
+
+    // ========== A–B 循环片段 ==========
+
+    /// 保存循环片段，返回片段 ID
+    pub fn add_loop_region(
+        &self,
+        track: &Uuid,
+        name: &str,
+        start: Duration,
+        end: Duration,
+    ) -> rusqlite::Result<i64> {
+        self.conn.execute(
+            "INSERT INTO loop_region (uuid, name, start_ms, end_ms) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                track.as_bytes().as_slice(),
+                name,
+                start.as_millis() as i64,
+                end.as_millis() as i64
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn remove_loop_region(&self, id: i64) -> rusqlite::Result<()> {
+        self.conn
+            .execute("DELETE FROM loop_region WHERE id = ?", params![id])?;
+        Ok(())
+    }
+
+    /// 加载歌曲的循环片段（按起点排序）
+    pub fn load_loop_regions(&self, track: &Uuid) -> rusqlite::Result<Vec<LoopRegion>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, name, start_ms, end_ms FROM loop_region
+             WHERE uuid = ? ORDER BY start_ms, id",
+        )?;
+        let rows = stmt.query_map(params![track.as_bytes().as_slice()], |row| {
+            Ok(LoopRegion {
+                id: row.get(0)?,
+                track: *track,
+                name: row.get(1)?,
+                start: Duration::from_millis(row.get::<_, i64>(2)?.max(0) as u64),
+                end: Duration::from_millis(row.get::<_, i64>(3)?.max(0) as u64),
+            })
+        })?;
+        rows.collect()
+    }
+
+    // ========== 波形缓存 ==========
+
+    /// 读取缓存的波形峰值（文件修改时间或大小与缓存时不同则视为没有缓存）
+    pub fn load_waveform(
+        &self,
+        track: &Uuid,
+        stamp: FileStamp,
+    ) -> rusqlite::Result<Option<Vec<u8>>> {
+        self.conn
+            .query_row(
+                "SELECT peaks FROM waveform WHERE uuid = ?1 AND mtime = ?2 AND size = ?3",
+                params![track.as_bytes().as_slice(), stamp.mtime, stamp.size],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    pub fn save_waveform(
+        &self,
+        track: &Uuid,
+        stamp: FileStamp,
+        peaks: &[u8],
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO waveform (uuid, mtime, size, peaks) VALUES (?1, ?2, ?3, ?4)",
+            params![track.as_bytes().as_slice(), stamp.mtime, stamp.size, peaks],
+        )?;
+        Ok(())
+    }
+
+    // ========== 听歌记录上报队列 ==========
+
+    /// 加入上报队列（立即可发送）
+    pub fn enqueue_scrobble(&self, listen: &Listen) -> rusqlite::Result<()> {
+        let listen = serde_json::to_string(listen)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        self.conn.execute(
+            "INSERT INTO scrobble_queue (listen) VALUES (?)",
+            params![listen],
+        )?;
+        Ok(())
+    }
+
+    /// 取出到期的记录（ID, 记录, 已重试次数），按加入顺序
+    pub fn load_due_scrobbles(
+        &self,
+        now: i64,
+        limit: usize,
+    ) -> rusqlite::Result<Vec<(i64, Listen, u32)>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, listen, attempts FROM scrobble_queue
+             WHERE next_attempt <= ? ORDER BY id LIMIT ?",
+        )?;
+        let rows = stmt.query_map(params![now, limit as i64], |row| {
+            let listen: String = row.get(1)?;
+            let listen = serde_json::from_str(&listen).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    1,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })?;
+            Ok((row.get(0)?, listen, row.get(2)?))
+        })?;
+        rows.collect()
+    }
+
+    /// 最早的下次发送时间，队列为空时返回 None
+    pub fn next_scrobble_attempt(&self) -> rusqlite::Result<Option<i64>> {
+        self.conn
+            .query_row("SELECT MIN(next_attempt) FROM scrobble_queue", [], |row| {
+                row.get(0)
+            })
+    }
+
+    /// 发送成功（或被服务端拒绝）后从队列删除
+    pub fn remove_scrobbles(&self, ids: &[i64]) -> rusqlite::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare_cached("DELETE FROM scrobble_queue WHERE id = ?")?;
+            for id in ids {
+                stmt.execute(params![id])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// 发送失败后记录重试次数和下次发送时间
+    pub fn defer_scrobble(
+        &self,
+        id: i64,
+        attempts: u32,
+        next_attempt: i64,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE scrobble_queue SET attempts = ?, next_attempt = ? WHERE id = ?",
+            params![attempts, next_attempt, id],
+        )?;
+        Ok(())
+    }
+
     /// 获取专辑总数
     pub fn get_album_count(&self) -> rusqlite::Result<i64> {
         self.conn
             .query_row("SELECT COUNT(*) FROM library", [], |row| row.get(0))
     }
 
-    /// 批量添加音频文件的元数据到库中
+    /// 批量添加音频文件的元数据到库中（也可以是单个文件），已在曲库中的路径会跳过，返回新增数量
     pub fn add_metadata_to_library(
         &self,
         folder_path: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        // 定义支持的音频文件扩展名
-        let audio_extensions = ["mp3", "flac", "wav", "m4a", "ogg", "aac", "vorbis"];
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        self.scan_folder(folder_path, |_, _| {})
+    }
 
+    /// 扫描文件夹，把不在曲库中的音频文件加入曲库，返回新增数量；
+    /// 处理第 n 个文件前调用 on_progress(n, 总数)
+    pub fn scan_folder(
+        &self,
+        folder_path: &str,
+        mut on_progress: impl FnMut(usize, usize),
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let _span = info_span!("scan", folder = folder_path).entered();
         // 遍历文件夹获取所有音频文件
-        let audio_files = self.get_audio_files(folder_path, &audio_extensions)?;
+        let audio_files = self.get_audio_files(folder_path, &AUDIO_EXTENSIONS)?;
+        let total = audio_files.len();
 
         // 批量处理音频文件
-        for file_path in audio_files {
-            if let Err(e) = self.process_single_audio_file(&file_path) {
-                eprintln!("处理文件 {:?} 时出错: {}", file_path, e);
+        let mut added = 0;
+        for (done, file_path) in audio_files.into_iter().enumerate() {
+            on_progress(done + 1, total);
+            match self.load_album_by_path(&file_path) {
+                Ok(Some(_)) => continue,
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("查询文件 {:?} 时出错: {}", file_path, e);
+                    continue;
+                }
+            }
+            match self.process_single_audio_file(&file_path) {
+                Ok(()) => added += 1,
                 // 继续处理其他文件，不中断整个流程
+                Err(e) => warn!("处理文件 {:?} 时出错: {}", file_path, e),
             }
         }
 
-        Ok(())
+        info!("扫描完成: {} 个文件，新增 {} 首", total, added);
+        Ok(added)
+    }
+
+    /// 在后台用独立连接扫描文件夹，进度和结果显示为通知；返回新增数量或错误说明
+    pub fn scan_in_background(&self, folder_path: String, cx: &App) -> Task<Result<usize, String>> {
+        let db_path = self.path.clone();
+        cx.background_executor().spawn(async move {
+            let id = notify::next_id();
+            notify::progress(id, format!("正在扫描 {}", folder_path), 0.0);
+            let result = DB::new(&db_path).map_err(|e| e.to_string()).and_then(|db| {
+                db.scan_folder(&folder_path, |done, total| {
+                    if done.is_multiple_of(SCAN_PROGRESS_STEP) || done == total {
+                        notify::progress(
+                            id,
+                            format!("正在扫描 {} / {}", done, total),
+                            done as f32 / total as f32,
+                        );
+                    }
+                })
+                .map_err(|e| e.to_string())
+            });
+
+            match result {
+                Ok(added) => {
+                    let detail = format!("新增 {} 首歌曲", added);
+                    notify::finish(id, Level::Success, "扫描完成", Some(detail));
+                    Ok(added)
+                }
+                Err(e) => {
+                    warn!("扫描音乐文件夹失败: {}", e);
+                    notify::finish(id, Level::Error, "扫描音乐文件夹失败", Some(e.clone()));
+                    Err(e)
+                }
+            }
+        })
+    }
+
+    /// 按路径查询曲库中的歌曲
+    pub fn load_album_by_path(&self, path: &Path) -> rusqlite::Result<Option<AlbumInfo>> {
+        let mut stmt = self.conn.prepare_cached(&format!(
+            "SELECT {} FROM library WHERE path = ?",
+            ALBUM_COLUMNS
+        ))?;
+
+        let mut rows = stmt.query(params![path.to_string_lossy().to_string()])?;
+
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::map_row_to_album(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// 按文件路径顺序加载文件夹（或单个文件）中已在曲库里的歌曲
+    pub fn load_tracks_from_path(&self, path: &Path) -> Result<Vec<AlbumInfo>, AppError> {
+        let mut audio_files = self.get_audio_files(&path.to_string_lossy(), &AUDIO_EXTENSIONS)?;
+        audio_files.sort();
+
+        let mut tracks = Vec::with_capacity(audio_files.len());
+        for file_path in audio_files {
+            if let Some(album) = self.load_album_by_path(&file_path)? {
+                tracks.push(album);
+            }
+        }
+        Ok(tracks)
+    }
+
+    /// 在后台用独立连接读取文件或文件夹中已在曲库的歌曲（按路径排序）
+    pub fn load_tracks_in_background(
+        &self,
+        path: PathBuf,
+        cx: &App,
+    ) -> Task<Result<Vec<AlbumInfo>, String>> {
+        let db_path = self.path.clone();
+        cx.background_executor().spawn(async move {
+            DB::new(&db_path)
+                .map_err(|e| e.to_string())
+                .and_then(|db| db.load_tracks_from_path(&path).map_err(|e| e.to_string()))
+        })
     }
 
     /// 获取指定文件夹下的所有音频文件
@@ -233,26 +1246,19 @@ impl DB {
         &self,
         file_path: &PathBuf,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let cover_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-            .join("assets")
-            .join("covers");
-
         // 从文件创建 AlbumInfo 结构体
-        let album_info = AlbumInfo::new_from_file(file_path, &cover_dir).map_err(|e| {
-            io::Error::other(format!(
-                "读取元数据失败: file={:?}, cover_dir={:?}, error={}",
-                file_path, cover_dir, e
-            ))
+        let album_info = AlbumInfo::new_from_file(file_path).map_err(|e| {
+            io::Error::other(format!("读取元数据失败: file={:?}, error={}", file_path, e))
         })?;
 
         // 从 AlbumInfo 中提取数据
-        let cover_path = album_info.cover_path().map(|s| s.to_string());
-        let cover_64 = album_info.cover_64().map(|arc| arc.as_ref().clone());
+        let cover = album_info.cover().map(|s| s.to_string());
+        let genre = album_info.genre().map(|s| s.to_string());
 
         // 插入数据库
         self.conn.execute(
-            "INSERT INTO library (uuid, title, artist, album, duration, path, cover_path, cover_64)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            "INSERT INTO library (uuid, title, artist, album, duration, path, cover, genre, added_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, CAST(strftime('%s', 'now') AS INTEGER))",
             params![
                 album_info.id().as_bytes().as_slice(),
                 album_info.title().to_string(),
@@ -260,11 +1266,337 @@ impl DB {
                 album_info.album().to_string(),
                 album_info.duration() as i64,
                 album_info.path().to_string_lossy().to_string(),
-                cover_path,
-                cover_64
+                cover,
+                genre
             ],
         )?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{DB, params};
+    use crate::db::{
+        smart::{Condition, Rule, SmartPlaylist, SortKey},
+        stats::{StatsPeriod, TopEntry},
+        table::Table,
+    };
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use uuid::Uuid;
+
+    const DAY: i64 = 86400;
+
+    /// 内存数据库，结构与正式数据库相同
+    fn open() -> DB {
+        DB::new(":memory:").unwrap()
+    }
+
+    /// 插入一首歌曲，added_days_ago 天前加入曲库
+    fn insert(
+        db: &DB,
+        title: &str,
+        artist: &str,
+        genre: Option<&str>,
+        duration: i64,
+        added_days_ago: i64,
+    ) -> Uuid {
+        let id = Uuid::new_v4();
+        db.conn
+            .execute(
+                "INSERT INTO library (uuid, title, artist, album, duration, path, genre, added_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, CAST(strftime('%s', 'now') AS INTEGER) - ?8)",
+                params![
+                    id.as_bytes().as_slice(),
+                    title,
+                    artist,
+                    format!("{} 专辑", artist),
+                    duration,
+                    format!("/music/{}.flac", title),
+                    genre,
+                    added_days_ago * DAY
+                ],
+            )
+            .unwrap();
+        id
+    }
+
+    fn set_stats(db: &DB, id: &Uuid, play_count: i64, rating: u8) {
+        db.conn
+            .execute(
+                "UPDATE library SET play_count = ?1, rating = ?2 WHERE uuid = ?3",
+                params![play_count, rating, id.as_bytes().as_slice()],
+            )
+            .unwrap();
+    }
+
+    /// A：Alpha / Rock / 180 秒 / 播放 12 次 / 5 星 / 今天加入
+    /// B：Beta / Jazz / 240 秒 / 播放 3 次 / 3 星 / 60 天前加入 / 已收藏
+    /// C：Gamma / Pop / 90 秒 / 未播放 / 未评分 / 10 天前加入
+    /// D：alpha / 无流派 / 300 秒 / 未播放 / 4 星 / 400 天前加入
+    fn library() -> DB {
+        let db = open();
+        let a = insert(&db, "A", "Alpha", Some("Rock"), 180, 0);
+        let b = insert(&db, "B", "Beta", Some("Jazz"), 240, 60);
+        insert(&db, "C", "Gamma", Some("Pop"), 90, 10);
+        let d = insert(&db, "D", "alpha", None, 300, 400);
+        set_stats(&db, &a, 12, 5);
+        set_stats(&db, &b, 3, 3);
+        set_stats(&db, &d, 0, 4);
+        db.add_to_table(Table::Favorite, &b).unwrap();
+        db
+    }
+
+    /// 按 SQL 返回的顺序列出标题
+    fn titles(db: &DB, playlist: &SmartPlaylist) -> Vec<String> {
+        db.load_smart_playlist_items(playlist)
+            .unwrap()
+            .iter()
+            .map(|track| track.title().to_string())
+            .collect()
+    }
+
+    /// 不关心顺序时按标题排序后比较
+    fn matching(db: &DB, condition: Condition) -> Vec<String> {
+        let mut titles = titles(db, &SmartPlaylist::new("测试", condition));
+        titles.sort();
+        titles
+    }
+
+    #[test]
+    fn each_rule_selects_matching_tracks() {
+        let db = library();
+        let cases = [
+            (Rule::ArtistIs("ALPHA".into()), vec!["A", "D"]),
+            (
+                Rule::GenreIn(vec!["rock".into(), "JAZZ".into()]),
+                vec!["A", "B"],
+            ),
+            (Rule::GenreIn(Vec::new()), vec![]),
+            (Rule::AddedWithinDays(30), vec!["A", "C"]),
+            (Rule::PlayCountGreaterThan(3), vec!["A"]),
+            (Rule::NeverPlayed, vec!["C", "D"]),
+            (Rule::DurationLessThan(200), vec!["A", "C"]),
+            (Rule::IsFavorite, vec!["B"]),
+            (Rule::RatingAtLeast(4), vec!["A", "D"]),
+        ];
+        for (rule, expected) in cases {
+            assert_eq!(
+                matching(&db, Condition::Rule(rule.clone())),
+                expected,
+                "{:?}",
+                rule
+            );
+        }
+    }
+
+    #[test]
+    fn nested_conditions_combine_with_and_or() {
+        let db = library();
+        let alpha_or_favorite = Condition::Any(vec![
+            Condition::Rule(Rule::ArtistIs("Alpha".into())),
+            Condition::Rule(Rule::IsFavorite),
+        ]);
+        assert_eq!(
+            matching(
+                &db,
+                Condition::All(vec![
+                    alpha_or_favorite,
+                    Condition::Rule(Rule::RatingAtLeast(4)),
+                ])
+            ),
+            vec!["A", "D"]
+        );
+        assert_eq!(
+            matching(
+                &db,
+                Condition::Any(vec![
+                    Condition::All(vec![
+                        Condition::Rule(Rule::NeverPlayed),
+                        Condition::Rule(Rule::DurationLessThan(100)),
+                    ]),
+                    Condition::Rule(Rule::IsFavorite),
+                ])
+            ),
+            vec!["B", "C"]
+        );
+        // 空的 AND 匹配全部，空的 OR 不匹配任何歌曲
+        assert_eq!(
+            matching(&db, Condition::All(Vec::new())),
+            vec!["A", "B", "C", "D"]
+        );
+        assert!(matching(&db, Condition::Any(Vec::new())).is_empty());
+    }
+
+    #[test]
+    fn sort_key_and_order_are_applied() {
+        let db = library();
+        let all = || SmartPlaylist::new("测试", Condition::All(Vec::new()));
+        assert_eq!(
+            titles(&db, &all().sort_by(SortKey::Duration, false)),
+            vec!["C", "A", "B", "D"]
+        );
+        assert_eq!(
+            titles(&db, &all().sort_by(SortKey::Duration, true)),
+            vec!["D", "B", "A", "C"]
+        );
+        assert_eq!(
+            titles(&db, &all().sort_by(SortKey::AddedAt, true)),
+            vec!["A", "C", "B", "D"]
+        );
+        assert_eq!(
+            titles(&db, &all().sort_by(SortKey::Rating, true)),
+            vec!["A", "D", "B", "C"]
+        );
+    }
+
+    #[test]
+    fn limit_is_applied_after_sorting() {
+        let db = library();
+        let shortest = SmartPlaylist::new("测试", Condition::All(Vec::new()))
+            .sort_by(SortKey::Duration, false)
+            .limit(2);
+        assert_eq!(titles(&db, &shortest), vec!["C", "A"]);
+
+        // LIMIT 的参数排在条件参数之后
+        let alpha = SmartPlaylist::new("测试", Condition::Rule(Rule::ArtistIs("alpha".into())))
+            .sort_by(SortKey::Duration, true)
+            .limit(1);
+        assert_eq!(titles(&db, &alpha), vec!["D"]);
+    }
+
+    #[test]
+    fn smart_playlist_is_loaded_by_id() {
+        let db = open();
+        let playlist = SmartPlaylist::new("高评分", Condition::Rule(Rule::RatingAtLeast(4)))
+            .sort_by(SortKey::Rating, true)
+            .limit(10);
+        let id = db.add_smart_playlist(&playlist).unwrap();
+
+        let loaded = db.load_smart_playlist(id).unwrap().unwrap();
+        assert_eq!(loaded.id, id);
+        assert_eq!(loaded.name, playlist.name);
+        assert_eq!(loaded.condition, playlist.condition);
+        assert_eq!(loaded.sort, playlist.sort);
+        assert_eq!(loaded.limit, Some(10));
+        assert!(db.load_smart_playlist(id + 100).unwrap().is_none());
+    }
+
+    /// 记录一次 days_ago 天前开始、收听 secs 秒的播放
+    fn play(db: &DB, id: &Uuid, days_ago: i64, secs: u64) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        db.record_play(id, now - days_ago * DAY, secs).unwrap();
+    }
+
+    #[test]
+    fn totals_follow_the_period() {
+        let db = open();
+        let a = insert(&db, "A", "Alpha", None, 200, 0);
+        let b = insert(&db, "B", "Beta", None, 100, 0);
+        play(&db, &a, 1, 200);
+        play(&db, &b, 1, 100);
+        play(&db, &a, 10, 150);
+        play(&db, &b, 100, 60);
+        play(&db, &a, 500, 30);
+
+        let totals = |period| {
+            let stats = db.load_stats(period, 10).unwrap();
+            (stats.total_plays, stats.total_seconds)
+        };
+        assert_eq!(totals(StatsPeriod::Week), (2, 300));
+        assert_eq!(totals(StatsPeriod::Month), (3, 450));
+        assert_eq!(totals(StatsPeriod::Year), (4, 510));
+        assert_eq!(totals(StatsPeriod::AllTime), (5, 540));
+
+        let heatmap = db.load_stats(StatsPeriod::AllTime, 10).unwrap().heatmap;
+        assert_eq!(heatmap.iter().flatten().sum::<i64>(), 5);
+    }
+
+    #[test]
+    fn top_lists_are_ordered_by_plays_then_seconds() {
+        let db = open();
+        let a = insert(&db, "A", "Alpha", None, 200, 0);
+        let b = insert(&db, "B", "Beta", None, 200, 0);
+        let c = insert(&db, "C", "Alpha", None, 200, 0);
+        let d = insert(&db, "D", "Delta", None, 200, 0);
+        for (id, plays, secs) in [(&a, 3, 100), (&b, 2, 200), (&c, 2, 100), (&d, 1, 50)] {
+            for _ in 0..plays {
+                play(&db, id, 0, secs);
+            }
+        }
+        // 范围之外的播放不计入排行
+        for _ in 0..5 {
+            play(&db, &d, 40, 100);
+        }
+
+        let stats = db.load_stats(StatsPeriod::Month, 3).unwrap();
+        let names = |entries: &[TopEntry]| {
+            entries
+                .iter()
+                .map(|entry| (entry.name.clone(), entry.plays, entry.seconds))
+                .collect::<Vec<_>>()
+        };
+        // 次数相同时时长多的在前，只取前 3 名
+        assert_eq!(
+            names(&stats.top_tracks),
+            vec![
+                ("A".to_string(), 3, 300),
+                ("B".to_string(), 2, 400),
+                ("C".to_string(), 2, 200),
+            ]
+        );
+        assert_eq!(stats.top_tracks[0].detail.as_deref(), Some("Alpha"));
+        assert_eq!(
+            names(&stats.top_artists),
+            vec![
+                ("Alpha".to_string(), 5, 500),
+                ("Beta".to_string(), 2, 400),
+                ("Delta".to_string(), 1, 50),
+            ]
+        );
+        assert_eq!(stats.top_albums[0].name, "Alpha 专辑");
+        assert_eq!(stats.top_albums[0].plays, 5);
+    }
+
+    #[test]
+    fn streaks_ignore_the_period() {
+        let db = open();
+        let a = insert(&db, "A", "Alpha", None, 200, 0);
+        // 今天和昨天是当前连续；6–9 天前的四天跨过了 7 天范围的边界
+        for days_ago in [0, 1, 6, 7, 8, 9, 20] {
+            play(&db, &a, days_ago, 60);
+        }
+        // 同一天多次播放只算一天
+        play(&db, &a, 7, 60);
+
+        for period in StatsPeriod::all() {
+            let streaks = db.load_stats(period, 10).unwrap().streaks;
+            assert_eq!((streaks.current, streaks.longest), (2, 4), "{:?}", period);
+        }
+
+        // 最近一次播放在前天时没有当前连续
+        let db = open();
+        let a = insert(&db, "A", "Alpha", None, 200, 0);
+        for days_ago in [2, 3] {
+            play(&db, &a, days_ago, 60);
+        }
+        let streaks = db.load_stats(StatsPeriod::Week, 10).unwrap().streaks;
+        assert_eq!((streaks.current, streaks.longest), (0, 2));
+    }
+
+    #[test]
+    fn empty_play_log_gives_empty_stats() {
+        let db = library();
+        let stats = db.load_stats(StatsPeriod::AllTime, 10).unwrap();
+        assert_eq!((stats.total_plays, stats.total_seconds), (0, 0));
+        assert!(stats.top_tracks.is_empty());
+        assert!(stats.top_artists.is_empty());
+        assert!(stats.top_albums.is_empty());
+        assert!(stats.heatmap.iter().flatten().all(|&plays| plays == 0));
+        assert_eq!((stats.streaks.current, stats.streaks.longest), (0, 0));
+    }
+}
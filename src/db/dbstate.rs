@@ -3,9 +3,16 @@ use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
 };
+use tracing::warn;
 use uuid::Uuid;
 
-use crate::db::metadata::AlbumInfo;
+use crate::{
+    config::Config,
+    db::{
+        database::DB,
+        metadata::{AlbumInfo, write_rating},
+    },
+};
 
 /// 曲库状态事件
 #[derive(Clone, Copy)]
@@ -16,6 +23,12 @@ pub enum LibraryEvent {
     FavoriteRemoved(Uuid),
     /// 歌曲被添加到历史
     HistoryAdded(Uuid),
+    /// 歌曲评分变化
+    RatingsChanged,
+    /// 歌曲被标记为无法播放或恢复为可播放
+    PlayabilityChanged,
+    /// 播放次数、最后播放时间和播放日志变化
+    PlaysRecorded,
     /// 曲库更新
     LibraryUpdated,
 }
@@ -35,6 +48,10 @@ pub struct LibraryState {
     history: Arc<Vec<AlbumInfo>>,
     /// 历史 UUID 集合，用于去重
     history_ids: HashSet<Uuid>,
+    /// 评分：UUID -> 1–5 星（未评分的歌曲不在表中）
+    ratings: HashMap<Uuid, u8>,
+    /// 无法播放的歌曲：UUID -> 失败原因
+    unplayable: HashMap<Uuid, String>,
 }
 
 impl EventEmitter<LibraryEvent> for LibraryState {}
@@ -44,6 +61,8 @@ impl LibraryState {
         library: Vec<AlbumInfo>,
         favorite_uuids: Vec<Uuid>,
         history_uuids: Vec<Uuid>,
+        ratings: HashMap<Uuid, u8>,
+        unplayable: HashMap<Uuid, String>,
     ) -> Self {
         // 构建曲库索引
         let library_index: HashMap<Uuid, usize> = library
@@ -79,6 +98,8 @@ impl LibraryState {
             favorite_ids,
             history: Arc::new(history),
             history_ids,
+            ratings,
+            unplayable,
         }
     }
 
@@ -161,6 +182,64 @@ impl LibraryState {
         }
     }
 
+    // ========== 评分操作 ==========
+
+    /// 获取歌曲评分（0 表示未评分）
+    pub fn rating(&self, id: &Uuid) -> u8 {
+        self.ratings.get(id).copied().unwrap_or(0)
+    }
+
+    /// 设置评分（0–5，0 表示清除）：写入数据库，开启选项时同时写入文件标签
+    pub fn set_rating(&mut self, ids: &[Uuid], rating: u8, cx: &mut Context<Self>) {
+        let rating = rating.min(5);
+        if let Err(e) = cx.global::<DB>().set_rating(ids, rating) {
+            warn!("写入评分到数据库失败: {}", e);
+            return;
+        }
+        if cx.global::<Config>().media_file.write_rating_tags {
+            for item in ids.iter().filter_map(|id| self.get_by_id(id)) {
+                if let Err(e) = write_rating(&item.path(), rating) {
+                    warn!("写入评分标签失败: {:?} - {}", item.path(), e);
+                }
+            }
+        }
+
+        for id in ids {
+            if rating == 0 {
+                self.ratings.remove(id);
+            } else {
+                self.ratings.insert(*id, rating);
+            }
+        }
+        cx.emit(LibraryEvent::RatingsChanged);
+        cx.notify();
+    }
+
+    // ========== 可播放状态 ==========
+
+    /// 获取歌曲无法播放的原因（None 表示可以播放）
+    pub fn unplayable_reason(&self, id: &Uuid) -> Option<&str> {
+        self.unplayable.get(id).map(String::as_str)
+    }
+
+    /// 所有无法播放的歌曲
+    pub fn unplayable_ids(&self) -> HashSet<Uuid> {
+        self.unplayable.keys().copied().collect()
+    }
+
+    /// 标记歌曲无法播放（reason 为 None 表示恢复为可播放），同时写入数据库
+    pub fn set_unplayable(&mut self, id: &Uuid, reason: Option<String>, cx: &mut Context<Self>) {
+        if let Err(e) = cx.global::<DB>().set_unplayable(id, reason.as_deref()) {
+            warn!("写入歌曲可播放状态失败: {}", e);
+        }
+        match reason {
+            Some(reason) => self.unplayable.insert(*id, reason),
+            None => self.unplayable.remove(id),
+        };
+        cx.emit(LibraryEvent::PlayabilityChanged);
+        cx.notify();
+    }
+
     // ========== 历史记录操作 ==========
 
     /// 添加歌曲到历史记录
@@ -199,6 +278,68 @@ impl LibraryState {
         }
     }
 
+    /// 记录一次播放：写入播放次数、最后播放时间和播放日志（started_at 为开始播放的 Unix 秒）
+    pub fn record_play(
+        &mut self,
+        id: &Uuid,
+        started_at: i64,
+        played_secs: u64,
+        cx: &mut Context<Self>,
+    ) {
+        if let Err(e) = cx.global::<DB>().record_play(id, started_at, played_secs) {
+            warn!("记录播放次数失败: {}", e);
+            return;
+        }
+        cx.emit(LibraryEvent::PlaysRecorded);
+        cx.notify();
+    }
+
+    /// 替换已修改的歌曲（标签编辑后调用），同步更新收藏与历史中的副本
+    pub fn update_items(&mut self, items: Vec<AlbumInfo>, cx: &mut Context<Self>) {
+        if items.is_empty() {
+            return;
+        }
+        let updated: HashMap<Uuid, AlbumInfo> =
+            items.into_iter().map(|item| (item.id(), item)).collect();
+        let replace = |list: &Arc<Vec<AlbumInfo>>| -> Arc<Vec<AlbumInfo>> {
+            Arc::new(
+                list.iter()
+                    .map(|item| updated.get(&item.id()).unwrap_or(item).clone())
+                    .collect(),
+            )
+        };
+
+        self.library = replace(&self.library);
+        self.favorites = replace(&self.favorites);
+        self.history = replace(&self.history);
+
+        cx.emit(LibraryEvent::LibraryUpdated);
+        cx.notify();
+    }
+
+    /// 从曲库中移除歌曲（同时从收藏和历史中移除）
+    pub fn remove_items(&mut self, ids: &[Uuid], cx: &mut Context<Self>) {
+        let ids: HashSet<Uuid> = ids.iter().copied().collect();
+        let retain = |list: &Arc<Vec<AlbumInfo>>| -> Arc<Vec<AlbumInfo>> {
+            Arc::new(
+                list.iter()
+                    .filter(|item| !ids.contains(&item.id()))
+                    .cloned()
+                    .collect(),
+            )
+        };
+
+        let library = retain(&self.library);
+        self.favorites = retain(&self.favorites);
+        self.history = retain(&self.history);
+        self.favorite_ids.retain(|id| !ids.contains(id));
+        self.history_ids.retain(|id| !ids.contains(id));
+        self.ratings.retain(|id, _| !ids.contains(id));
+        self.unplayable.retain(|id, _| !ids.contains(id));
+
+        self.update_library(Arc::unwrap_or_clone(library), cx);
+    }
+
     /// 清空历史记录
     pub fn clear_history(&mut self, cx: &mut Context<Self>) {
         self.history = Arc::new(Vec::new());
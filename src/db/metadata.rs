@@ -1,20 +1,113 @@
 use gpui::SharedString;
-use image::{ExtendedColorType, codecs::jpeg::JpegEncoder, imageops::FilterType, load_from_memory};
 use lofty::{
+    config::WriteOptions,
     file::AudioFile,
-    picture::MimeType,
+    id3::v2::PopularimeterFrame,
     prelude::{Accessor, TaggedFileExt},
     read_from_path,
+    tag::{ItemKey, ItemValue, Tag, TagExt, TagItem, TagType},
 };
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use std::{
     borrow::Cow,
-    fs,
     path::{Path, PathBuf},
     sync::Arc,
 };
+
+use super::cover;
+/// 标签编辑内容：None 表示保持不变
+#[derive(Clone, Debug, Default)]
+pub struct TagEdit {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+}
+
+impl TagEdit {
+    /// 是否没有任何修改
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.artist.is_none()
+            && self.album.is_none()
+            && self.genre.is_none()
+    }
+}
+
+/// 将标签修改写入音频文件（文件没有标签时按格式创建主标签）
+pub fn write_tags(path: &Path, edit: &TagEdit) -> Result<(), Box<dyn std::error::Error>> {
+    let mut tagged_file = read_from_path(path)?;
+    if tagged_file.primary_tag().is_none() {
+        let tag_type = tagged_file.primary_tag_type();
+        tagged_file.insert_tag(Tag::new(tag_type));
+    }
+    let tag = tagged_file.primary_tag_mut().ok_or("无法为文件创建标签")?;
+
+    if let Some(title) = &edit.title {
+        tag.set_title(title.clone());
+    }
+    if let Some(artist) = &edit.artist {
+        tag.set_artist(artist.clone());
+    }
+    if let Some(album) = &edit.album {
+        tag.set_album(album.clone());
+    }
+    if let Some(genre) = &edit.genre {
+        tag.set_genre(genre.clone());
+    }
+
+    tag.save_to_path(path, WriteOptions::default())?;
+    Ok(())
+}
+
+/// 写入 POPM 帧时使用的用户标识（与 Windows Media Player 一致，兼容性最好）
+const POPM_EMAIL: &str = "Windows Media Player 9 Series";
+
+/// 星级评分（1–5）对应的 POPM 值（0–255）
+fn popm_rating(stars: u8) -> u8 {
+    match stars {
+        0 => 0,
+        1 => 1,
+        2 => 64,
+        3 => 128,
+        4 => 196,
+        _ => 255,
+    }
+}
+
+/// 将星级评分（0 表示未评分）写入音频文件：
+/// ID3v2 写 POPM 帧，其他格式写 RATING 等文本字段（0–100）
+pub fn write_rating(path: &Path, stars: u8) -> Result<(), Box<dyn std::error::Error>> {
+    let mut tagged_file = read_from_path(path)?;
+    if tagged_file.primary_tag().is_none() {
+        let tag_type = tagged_file.primary_tag_type();
+        tagged_file.insert_tag(Tag::new(tag_type));
+    }
+    let tag = tagged_file.primary_tag_mut().ok_or("无法为文件创建标签")?;
+
+    tag.remove_key(&ItemKey::Popularimeter);
+    if stars > 0 {
+        let inserted = match tag.tag_type() {
+            TagType::Id3v2 => {
+                let frame = PopularimeterFrame::new(POPM_EMAIL.to_string(), popm_rating(stars), 0);
+                tag.insert(TagItem::new(
+                    ItemKey::Popularimeter,
+                    ItemValue::Binary(frame.as_bytes()?),
+                ))
+            }
+            _ => tag.insert_text(ItemKey::Popularimeter, (stars.min(5) * 20).to_string()),
+        };
+        if !inserted {
+            return Err("该格式不支持评分标签".into());
+        }
+    }
+
+    tag.save_to_path(path, WriteOptions::default())?;
+    Ok(())
+}
+
 /// 音乐专辑元信息,包含标题、艺术家、专辑名、时长、文件路径及封面等
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct AlbumInfo {
@@ -24,8 +117,11 @@ pub struct AlbumInfo {
     album: SharedString,
     duration: u64,
     path: Arc<PathBuf>,
-    cover_path: Option<SharedString>,
-    cover_64: Option<Arc<Vec<u8>>>,
+    /// 封面在缓存中的哈希
+    #[serde(default)]
+    cover: Option<SharedString>,
+    #[serde(default)]
+    genre: Option<SharedString>,
 }
 
 impl AlbumInfo {
@@ -37,8 +133,8 @@ impl AlbumInfo {
         album: SharedString,
         duration: u64,
         path: Arc<PathBuf>,
-        cover_path: Option<SharedString>,
-        cover_64: Option<Arc<Vec<u8>>>,
+        cover: Option<SharedString>,
+        genre: Option<SharedString>,
     ) -> Self {
         AlbumInfo {
             id,
@@ -47,20 +143,18 @@ impl AlbumInfo {
             album,
             duration,
             path,
-            cover_path,
-            cover_64,
+            cover,
+            genre,
         }
     }
 
     /// 从音频文件中读取元信息并创建 AlbumInfo 实例
     pub fn new_from_file(
         source_path: impl AsRef<Path>,
-        cover_dir: impl AsRef<Path>,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let id = Uuid::new_v4();
 
         let path = source_path.as_ref();
-        let cover_dir = cover_dir.as_ref();
         let tagged_file = read_from_path(path)?;
         let props = tagged_file.properties();
 
@@ -87,45 +181,14 @@ impl AlbumInfo {
             ),
         };
 
-        // cover 处理：最终变成 Option<(cover_path, cover_64)>
-        let cover_pack: Option<(SharedString, Arc<Vec<u8>>)> = tag
-            .and_then(|t| t.pictures().first())
-            .map(|cover| -> Result<_, Box<dyn std::error::Error>> {
-                let ext = cover
-                    .mime_type()
-                    .cloned()
-                    .unwrap_or(MimeType::Png)
-                    .ext()
-                    .unwrap_or("png")
-                    .to_string();
-
-                // 生成 64x64 jpeg bytes
-                let img = load_from_memory(cover.data())?;
-                let resized = img.resize_exact(64, 64, FilterType::Lanczos3).to_rgb8();
-                let mut cover_64 = Vec::new();
-                JpegEncoder::new_with_quality(&mut cover_64, 85).encode(
-                    &resized,
-                    64,
-                    64,
-                    ExtendedColorType::Rgb8,
-                )?;
-
-                // 落盘原图
-                fs::create_dir_all(cover_dir)?;
-                let cover_path = cover_dir.join(format!("{id}.{ext}"));
-                fs::write(&cover_path, cover.data())?;
-
-                Ok((
-                    SharedString::new(cover_path.to_string_lossy()),
-                    Arc::new(cover_64),
-                ))
-            })
-            .transpose()?; // Option<Result<T>> -> Result<Option<T>>
+        let genre = tag.and_then(|t| t.genre()).map(SharedString::new);
 
-        let (cover_path, cover_64) = match cover_pack {
-            Some((p, b)) => (Some(p), Some(b)),
-            None => (None, None),
-        };
+        // 封面存入缓存，内容相同的封面共用一份
+        let cover = tag
+            .and_then(|t| t.pictures().first())
+            .map(|picture| cover::store(picture.data()))
+            .transpose()?
+            .map(SharedString::from);
 
         Ok(AlbumInfo {
             id,
@@ -134,8 +197,8 @@ impl AlbumInfo {
             album: SharedString::new(album),
             duration: props.duration().as_secs(),
             path: Arc::new(path.to_path_buf()),
-            cover_path,
-            cover_64,
+            cover,
+            genre,
         })
     }
 
@@ -160,12 +223,29 @@ impl AlbumInfo {
         Arc::clone(&self.path)
     }
 
-    pub fn cover_path(&self) -> Option<SharedString> {
-        self.cover_path.clone()
+    /// 封面哈希，用 cover::image 或 cover::original_path 取图
+    pub fn cover(&self) -> Option<SharedString> {
+        self.cover.clone()
+    }
+
+    pub fn genre(&self) -> Option<SharedString> {
+        self.genre.clone()
     }
 
-    pub fn cover_64(&self) -> Option<Arc<Vec<u8>>> {
-        self.cover_64.as_ref().map(Arc::clone)
+    /// 应用标签修改（仅更新内存中的信息）
+    pub fn apply_tag_edit(&mut self, edit: &TagEdit) {
+        if let Some(title) = &edit.title {
+            self.title = SharedString::new(title.as_str());
+        }
+        if let Some(artist) = &edit.artist {
+            self.artist = SharedString::new(artist.as_str());
+        }
+        if let Some(album) = &edit.album {
+            self.album = SharedString::new(album.as_str());
+        }
+        if let Some(genre) = &edit.genre {
+            self.genre = Some(SharedString::new(genre.as_str()));
+        }
     }
 
     pub fn id(&self) -> Uuid {
@@ -0,0 +1,522 @@
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+use quick_xml::{Reader, events::Event};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+use url::Url;
+use uuid::Uuid;
+
+use super::metadata::AlbumInfo;
+use crate::{error::AppError, util::relative_path};
+
+/// XSPF 相对路径中需要编码的字符（与 URI 路径段一致）
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// 歌单文件格式
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PlaylistFormat {
+    /// M3U / M3U8（支持 #EXTINF）
+    M3u,
+    Pls,
+    Xspf,
+}
+
+impl PlaylistFormat {
+    /// 根据文件扩展名判断格式
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "m3u" | "m3u8" => Some(PlaylistFormat::M3u),
+            "pls" => Some(PlaylistFormat::Pls),
+            "xspf" => Some(PlaylistFormat::Xspf),
+            _ => None,
+        }
+    }
+
+    /// 文件对话框使用的扩展名列表
+    pub fn extensions() -> &'static [&'static str] {
+        &["m3u", "m3u8", "pls", "xspf"]
+    }
+}
+
+/// 导出时写入的路径形式
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PathStyle {
+    Absolute,
+    /// 相对于歌单文件所在目录（无法相对化时回退为绝对路径）
+    Relative,
+}
+
+/// 歌单文件中的一条记录
+#[derive(Clone, Debug, Default)]
+pub struct PlaylistEntry {
+    /// 原始位置（路径或 file:// URI）
+    pub location: String,
+    pub title: Option<String>,
+    /// 时长（秒）
+    pub duration: Option<i64>,
+}
+
+/// 导入结果报告
+#[derive(Clone, Debug)]
+pub struct ImportReport {
+    /// 新建歌单的 ID
+    pub playlist_id: i64,
+    pub name: String,
+    /// 成功匹配到曲库的条目数
+    pub matched: usize,
+    /// 未能匹配的条目（原始位置）
+    pub unmatched: Vec<String>,
+}
+
+/// 读取并解析歌单文件，返回（歌单名, 条目）
+pub fn read_playlist(path: &Path) -> Result<(String, Vec<PlaylistEntry>), AppError> {
+    let format = PlaylistFormat::from_path(path)
+        .ok_or_else(|| AppError::Playlist(format!("不支持的歌单格式: {:?}", path)))?;
+    let bytes = fs::read(path)?;
+    let text = String::from_utf8_lossy(&bytes);
+    let text = text.trim_start_matches('\u{feff}');
+
+    let default_name = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("导入的歌单")
+        .to_string();
+
+    match format {
+        PlaylistFormat::M3u => Ok((default_name, parse_m3u(text))),
+        PlaylistFormat::Pls => Ok((default_name, parse_pls(text))),
+        PlaylistFormat::Xspf => {
+            let (title, entries) = parse_xspf(text)?;
+            Ok((title.unwrap_or(default_name), entries))
+        }
+    }
+}
+
+fn parse_m3u(text: &str) -> Vec<PlaylistEntry> {
+    let mut entries = Vec::new();
+    let mut pending = PlaylistEntry::default();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            // #EXTINF:<秒>,<标题>
+            let (secs, title) = info.split_once(',').unwrap_or((info, ""));
+            pending.duration = secs.trim().parse::<i64>().ok().filter(|d| *d >= 0);
+            pending.title = Some(title.trim().to_string()).filter(|t| !t.is_empty());
+        } else if !line.starts_with('#') {
+            pending.location = line.to_string();
+            entries.push(std::mem::take(&mut pending));
+        }
+    }
+    entries
+}
+
+fn parse_pls(text: &str) -> Vec<PlaylistEntry> {
+    let mut entries: HashMap<u32, PlaylistEntry> = HashMap::new();
+
+    for line in text.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim();
+
+        for (prefix, field) in [("file", 0), ("title", 1), ("length", 2)] {
+            let Some(Ok(n)) = key.strip_prefix(prefix).map(str::parse::<u32>) else {
+                continue;
+            };
+            let entry = entries.entry(n).or_default();
+            match field {
+                0 => entry.location = value.to_string(),
+                1 => entry.title = Some(value.to_string()),
+                _ => entry.duration = value.parse::<i64>().ok().filter(|d| *d >= 0),
+            }
+        }
+    }
+
+    let mut numbered: Vec<(u32, PlaylistEntry)> = entries
+        .into_iter()
+        .filter(|(_, e)| !e.location.is_empty())
+        .collect();
+    numbered.sort_by_key(|(n, _)| *n);
+    numbered.into_iter().map(|(_, e)| e).collect()
+}
+
+fn parse_xspf(text: &str) -> Result<(Option<String>, Vec<PlaylistEntry>), AppError> {
+    let mut reader = Reader::from_str(text);
+    reader.config_mut().trim_text(true);
+
+    let mut title = None;
+    let mut entries = Vec::new();
+    let mut current: Option<PlaylistEntry> = None;
+    let mut element = Vec::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = e.local_name().as_ref().to_vec();
+                if name == b"track" {
+                    current = Some(PlaylistEntry::default());
+                }
+                element = name;
+            }
+            Ok(Event::Text(t)) => {
+                let value = t
+                    .unescape()
+                    .map_err(|e| AppError::Playlist(e.to_string()))?
+                    .to_string();
+                match (current.as_mut(), element.as_slice()) {
+                    (Some(entry), b"location") => entry.location = decode_location(&value),
+                    (Some(entry), b"title") => entry.title = Some(value),
+                    (Some(entry), b"duration") => {
+                        // XSPF 时长单位为毫秒
+                        entry.duration = value.parse::<i64>().ok().map(|ms| ms / 1000);
+                    }
+                    (None, b"title") => title = Some(value),
+                    _ => {}
+                }
+            }
+            Ok(Event::End(e)) => {
+                if e.local_name().as_ref() == b"track"
+                    && let Some(entry) = current.take().filter(|e| !e.location.is_empty())
+                {
+                    entries.push(entry);
+                }
+                element.clear();
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(e) => return Err(AppError::Playlist(format!("XSPF 解析失败: {}", e))),
+        }
+    }
+
+    Ok((title, entries))
+}
+
+/// XSPF 的相对位置是 URI 引用，解码为普通路径；file:// URI 留给 Url 解析
+fn decode_location(location: &str) -> String {
+    if location.contains("://") {
+        return location.to_string();
+    }
+    percent_decode_str(location).decode_utf8_lossy().to_string()
+}
+
+/// 将相对路径编码为 URI 引用（逐段编码，保留分隔符）
+fn encode_location(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('\\', "/")
+        .split('/')
+        .map(|segment| utf8_percent_encode(segment, PATH_SEGMENT).to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 将歌单中的位置解析为本地路径（支持 file:// URI 与相对路径）
+pub fn resolve_location(location: &str, base_dir: &Path) -> Option<PathBuf> {
+    if location.contains("://") {
+        return Url::parse(location).ok()?.to_file_path().ok();
+    }
+
+    // 其他平台导出的歌单可能使用反斜杠分隔
+    let location = if cfg!(windows) {
+        location.to_string()
+    } else {
+        location.replace('\\', "/")
+    };
+    let path = PathBuf::from(location);
+    let path = if path.is_absolute() {
+        path
+    } else {
+        base_dir.join(path)
+    };
+    Some(normalize(&path))
+}
+
+/// 词法规范化路径（去掉 `.` 与 `..`，不访问文件系统）
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// 路径比较键：优先使用规范路径，Windows 下忽略大小写
+fn path_key(path: &Path) -> String {
+    let path = path.canonicalize().unwrap_or_else(|_| normalize(path));
+    let key = path.to_string_lossy().to_string();
+    if cfg!(windows) {
+        key.to_lowercase()
+    } else {
+        key
+    }
+}
+
+/// 将歌单条目匹配到曲库，返回（匹配到的 UUID 列表, 未匹配的条目）
+pub fn match_entries(
+    entries: &[PlaylistEntry],
+    base_dir: &Path,
+    library: &[AlbumInfo],
+) -> (Vec<Uuid>, Vec<String>) {
+    let by_path: HashMap<String, Uuid> = library
+        .iter()
+        .map(|item| (path_key(&item.path()), item.id()))
+        .collect();
+
+    // 文件名唯一时作为回退匹配（歌单来自其他设备时路径前缀不同）
+    let mut by_name: HashMap<String, Option<Uuid>> = HashMap::new();
+    for item in library {
+        if let Some(name) = item.path().file_name().and_then(|n| n.to_str()) {
+            by_name
+                .entry(name.to_lowercase())
+                .and_modify(|id| *id = None)
+                .or_insert(Some(item.id()));
+        }
+    }
+
+    let mut matched = Vec::new();
+    let mut unmatched = Vec::new();
+    for entry in entries {
+        let found = resolve_location(&entry.location, base_dir).and_then(|path| {
+            by_path.get(&path_key(&path)).copied().or_else(|| {
+                let name = path.file_name()?.to_str()?.to_lowercase();
+                by_name.get(&name).copied().flatten()
+            })
+        });
+        match found {
+            Some(id) => matched.push(id),
+            None => unmatched.push(entry.location.clone()),
+        }
+    }
+    (matched, unmatched)
+}
+
+/// 导出歌单，格式由输出文件扩展名决定
+pub fn write_playlist(
+    items: &[AlbumInfo],
+    name: &str,
+    out_path: &Path,
+    style: PathStyle,
+) -> Result<(), AppError> {
+    let format = PlaylistFormat::from_path(out_path)
+        .ok_or_else(|| AppError::Playlist(format!("不支持的歌单格式: {:?}", out_path)))?;
+    let base_dir = out_path.parent().unwrap_or(Path::new(""));
+
+    let location = |item: &AlbumInfo| -> PathBuf {
+        let path = item.path();
+        match style {
+            PathStyle::Absolute => path.to_path_buf(),
+            PathStyle::Relative => {
+                relative_path(&path, base_dir).unwrap_or_else(|| path.to_path_buf())
+            }
+        }
+    };
+
+    let text = match format {
+        PlaylistFormat::M3u => {
+            let mut out = String::from("#EXTM3U\n");
+            for item in items {
+                out.push_str(&format!(
+                    "#EXTINF:{},{} - {}\n{}\n",
+                    item.duration(),
+                    item.artist(),
+                    item.title(),
+                    location(item).to_string_lossy()
+                ));
+            }
+            out
+        }
+        PlaylistFormat::Pls => {
+            let mut out = String::from("[playlist]\n");
+            for (i, item) in items.iter().enumerate() {
+                let n = i + 1;
+                out.push_str(&format!(
+                    "File{n}={}\nTitle{n}={} - {}\nLength{n}={}\n",
+                    location(item).to_string_lossy(),
+                    item.artist(),
+                    item.title(),
+                    item.duration()
+                ));
+            }
+            out.push_str(&format!("NumberOfEntries={}\nVersion=2\n", items.len()));
+            out
+        }
+        PlaylistFormat::Xspf => {
+            let mut out = String::from(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n",
+            );
+            out.push_str(&format!(
+                "  <title>{}</title>\n  <trackList>\n",
+                escape(name)
+            ));
+            for item in items {
+                let path = location(item);
+                // 绝对路径写为 file:// URI，相对路径按 URI 引用写入
+                let uri = if path.is_absolute() {
+                    Url::from_file_path(&path)
+                        .map(|u| u.to_string())
+                        .unwrap_or_else(|_| path.to_string_lossy().to_string())
+                } else {
+                    encode_location(&path)
+                };
+                out.push_str(&format!(
+                    "    <track>\n      <location>{}</location>\n      <title>{}</title>\n      <creator>{}</creator>\n      <album>{}</album>\n      <duration>{}</duration>\n    </track>\n",
+                    escape(&uri),
+                    escape(&item.title()),
+                    escape(&item.artist()),
+                    escape(&item.album()),
+                    item.duration() * 1000
+                ));
+            }
+            out.push_str("  </trackList>\n</playlist>\n");
+            out
+        }
+    };
+
+    if let Some(parent) = out_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(out_path, text)?;
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    quick_xml::escape::escape(text).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn m3u_reads_extinf_and_skips_comments() {
+        let text = "#EXTM3U\n\
+                    #EXTINF:215,甲 - 第一首\n\
+                    music/01.flac\n\
+                    # 注释\n\
+                    \n\
+                    /abs/02.mp3\n\
+                    #EXTINF:-1,\n\
+                    http://example.com/stream\n";
+        let entries = parse_m3u(text);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].location, "music/01.flac");
+        assert_eq!(entries[0].title.as_deref(), Some("甲 - 第一首"));
+        assert_eq!(entries[0].duration, Some(215));
+        // 没有 #EXTINF 的条目不继承上一条的信息
+        assert_eq!(entries[1].location, "/abs/02.mp3");
+        assert_eq!(entries[1].title, None);
+        assert_eq!(entries[1].duration, None);
+        // 负时长（未知）和空标题都忽略
+        assert_eq!(entries[2].duration, None);
+        assert_eq!(entries[2].title, None);
+    }
+
+    #[test]
+    fn pls_orders_entries_by_number() {
+        let text = "[playlist]\n\
+                    File2=b.flac\n\
+                    Title2=第二首\n\
+                    file1 = a.flac\n\
+                    Length1=120\n\
+                    Title3=没有文件\n\
+                    NumberOfEntries=3\n\
+                    Version=2\n";
+        let entries = parse_pls(text);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].location, "a.flac");
+        assert_eq!(entries[0].duration, Some(120));
+        assert_eq!(entries[0].title, None);
+        assert_eq!(entries[1].location, "b.flac");
+        assert_eq!(entries[1].title.as_deref(), Some("第二首"));
+    }
+
+    #[test]
+    fn xspf_reads_title_tracks_and_decodes_locations() {
+        let text = r#"<?xml version="1.0" encoding="UTF-8"?>
+<playlist version="1" xmlns="http://xspf.org/ns/0/">
+  <title>夜晚 &amp; 雨</title>
+  <trackList>
+    <track>
+      <location>My%20Song.flac</location>
+      <title>My Song</title>
+      <duration>215500</duration>
+    </track>
+    <track>
+      <location>file:///music/%E6%AD%8C.flac</location>
+    </track>
+    <track>
+      <title>没有位置</title>
+    </track>
+  </trackList>
+</playlist>"#;
+        let (title, entries) = parse_xspf(text).unwrap();
+        assert_eq!(title.as_deref(), Some("夜晚 & 雨"));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].location, "My Song.flac");
+        assert_eq!(entries[0].title.as_deref(), Some("My Song"));
+        assert_eq!(entries[0].duration, Some(215));
+        // file:// URI 保持原样，由 resolve_location 解码
+        assert_eq!(entries[1].location, "file:///music/%E6%AD%8C.flac");
+        assert_eq!(
+            resolve_location(&entries[1].location, Path::new("/")),
+            Some(PathBuf::from("/music/歌.flac"))
+        );
+    }
+
+    #[test]
+    fn xspf_rejects_malformed_xml() {
+        assert!(parse_xspf("<playlist><trackList></playlist>").is_err());
+    }
+
+    #[test]
+    fn relative_locations_round_trip_through_encoding() {
+        let path = Path::new("专辑 #1/100% [live]?.flac");
+        let encoded = encode_location(path);
+        assert_eq!(
+            encoded,
+            "%E4%B8%93%E8%BE%91%20%231/100%25%20%5Blive%5D%3F.flac"
+        );
+        assert_eq!(decode_location(&encoded), path.to_string_lossy());
+    }
+
+    #[test]
+    fn resolve_location_normalizes_relative_paths() {
+        let base = Path::new("/music/lists");
+        assert_eq!(
+            resolve_location("../一/./01.flac", base),
+            Some(PathBuf::from("/music/一/01.flac"))
+        );
+        assert_eq!(
+            resolve_location("/abs/02.flac", base),
+            Some(PathBuf::from("/abs/02.flac"))
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use gpui::SharedString;
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+
+/// 智能歌单的单条规则
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Rule {
+    /// 艺术家等于指定值
+    ArtistIs(String),
+    /// 流派属于给定列表之一
+    GenreIn(Vec<String>),
+    /// 最近 N 天内添加
+    AddedWithinDays(u32),
+    /// 播放次数大于 N
+    PlayCountGreaterThan(u32),
+    /// 从未播放过
+    NeverPlayed,
+    /// 时长小于 N 秒
+    DurationLessThan(u64),
+    /// 已收藏
+    IsFavorite,
+    /// 评分不低于 N 星
+    RatingAtLeast(u8),
+}
+
+/// 规则组合：叶子规则或 AND/OR 组合
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Condition {
+    Rule(Rule),
+    /// 所有子条件都满足（AND）
+    All(Vec<Condition>),
+    /// 任一子条件满足（OR）
+    Any(Vec<Condition>),
+}
+
+/// 排序字段
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum SortKey {
+    Title,
+    Artist,
+    Album,
+    Duration,
+    AddedAt,
+    PlayCount,
+    LastPlayed,
+    Rating,
+    Random,
+}
+
+/// 排序方式
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub struct SortOrder {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+/// 由规则定义的智能歌单
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct SmartPlaylist {
+    /// 数据库中的 ID（未保存时为 0）
+    #[serde(skip)]
+    pub id: i64,
+    pub name: SharedString,
+    pub condition: Condition,
+    pub sort: Option<SortOrder>,
+    pub limit: Option<u32>,
+}
+
+impl Rule {
+    /// 生成 SQL 条件片段，参数按顺序追加到 params
+    fn to_sql(&self, params: &mut Vec<Value>) -> String {
+        match self {
+            Rule::ArtistIs(artist) => {
+                params.push(Value::Text(artist.clone()));
+                "artist = ? COLLATE NOCASE".to_string()
+            }
+            Rule::GenreIn(genres) => {
+                if genres.is_empty() {
+                    return "0".to_string();
+                }
+                let placeholders = vec!["?"; genres.len()].join(", ");
+                params.extend(genres.iter().map(|g| Value::Text(g.to_lowercase())));
+                format!("lower(genre) IN ({})", placeholders)
+            }
+            Rule::AddedWithinDays(days) => {
+                params.push(Value::Integer(*days as i64 * 86400));
+                "added_at >= CAST(strftime('%s', 'now') AS INTEGER) - ?".to_string()
+            }
+            Rule::PlayCountGreaterThan(count) => {
+                params.push(Value::Integer(*count as i64));
+                "play_count > ?".to_string()
+            }
+            Rule::NeverPlayed => "play_count = 0".to_string(),
+            Rule::DurationLessThan(secs) => {
+                params.push(Value::Integer(*secs as i64));
+                "duration < ?".to_string()
+            }
+            Rule::IsFavorite => "uuid IN (SELECT uuid FROM favorite)".to_string(),
+            Rule::RatingAtLeast(stars) => {
+                params.push(Value::Integer(*stars as i64));
+                "rating >= ?".to_string()
+            }
+        }
+    }
+}
+
+impl Condition {
+    /// 生成 SQL WHERE 子句（不含 WHERE 关键字）
+    pub fn to_sql(&self, params: &mut Vec<Value>) -> String {
+        match self {
+            Condition::Rule(rule) => rule.to_sql(params),
+            Condition::All(children) => Self::join(children, " AND ", "1", params),
+            Condition::Any(children) => Self::join(children, " OR ", "0", params),
+        }
+    }
+
+    fn join(children: &[Condition], sep: &str, empty: &str, params: &mut Vec<Value>) -> String {
+        if children.is_empty() {
+            return empty.to_string();
+        }
+        let parts: Vec<String> = children
+            .iter()
+            .map(|c| format!("({})", c.to_sql(params)))
+            .collect();
+        parts.join(sep)
+    }
+}
+
+impl SortKey {
+    /// 所有排序字段（按编辑器中的显示顺序）
+    pub fn all() -> [SortKey; 9] {
+        [
+            SortKey::Title,
+            SortKey::Artist,
+            SortKey::Album,
+            SortKey::Duration,
+            SortKey::AddedAt,
+            SortKey::PlayCount,
+            SortKey::LastPlayed,
+            SortKey::Rating,
+            SortKey::Random,
+        ]
+    }
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortKey::Title => "标题",
+            SortKey::Artist => "艺术家",
+            SortKey::Album => "专辑",
+            SortKey::Duration => "时长",
+            SortKey::AddedAt => "添加时间",
+            SortKey::PlayCount => "播放次数",
+            SortKey::LastPlayed => "最后播放",
+            SortKey::Rating => "评分",
+            SortKey::Random => "随机",
+        }
+    }
+}
+
+impl SortOrder {
+    /// 生成 ORDER BY 子句（不含 ORDER BY 关键字）
+    pub fn to_sql(&self) -> String {
+        let column = match self.key {
+            SortKey::Title => "title COLLATE NOCASE",
+            SortKey::Artist => "artist COLLATE NOCASE",
+            SortKey::Album => "album COLLATE NOCASE",
+            SortKey::Duration => "duration",
+            SortKey::AddedAt => "added_at",
+            SortKey::PlayCount => "play_count",
+            SortKey::LastPlayed => "last_played",
+            SortKey::Rating => "rating",
+            SortKey::Random => return "RANDOM()".to_string(),
+        };
+        let dir = if self.descending { "DESC" } else { "ASC" };
+        format!("{} {}", column, dir)
+    }
+}
+
+impl SmartPlaylist {
+    pub fn new(name: impl Into<SharedString>, condition: Condition) -> Self {
+        Self {
+            id: 0,
+            name: name.into(),
+            condition,
+            sort: None,
+            limit: None,
+        }
+    }
+
+    pub fn sort_by(mut self, key: SortKey, descending: bool) -> Self {
+        self.sort = Some(SortOrder { key, descending });
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// 生成 WHERE / ORDER BY / LIMIT 部分，拼接在 SELECT ... FROM library 之后
+    pub fn to_sql(&self, params: &mut Vec<Value>) -> String {
+        let mut sql = format!(" WHERE {}", self.condition.to_sql(params));
+        if let Some(sort) = &self.sort {
+            sql.push_str(" ORDER BY ");
+            sql.push_str(&sort.to_sql());
+        }
+        if let Some(limit) = self.limit {
+            sql.push_str(" LIMIT ?");
+            params.push(Value::Integer(limit as i64));
+        }
+        sql
+    }
+
+    /// 首次运行时预置的智能歌单
+    pub fn defaults() -> Vec<SmartPlaylist> {
+        vec![
+            SmartPlaylist::new("最近添加", Condition::Rule(Rule::AddedWithinDays(30)))
+                .sort_by(SortKey::AddedAt, true),
+            SmartPlaylist::new("常听", Condition::Rule(Rule::PlayCountGreaterThan(10)))
+                .sort_by(SortKey::PlayCount, true)
+                .limit(100),
+            SmartPlaylist::new("从未播放", Condition::Rule(Rule::NeverPlayed)),
+        ]
+    }
+
+    /// 四星及以上的歌曲（数据库升级到 v5 时预置）
+    pub fn top_rated() -> SmartPlaylist {
+        SmartPlaylist::new("高评分", Condition::Rule(Rule::RatingAtLeast(4)))
+            .sort_by(SortKey::Rating, true)
+    }
+}
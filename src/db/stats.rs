@@ -0,0 +1,148 @@
+use serde::Serialize;
+use std::{fs, path::Path};
+
+use crate::error::AppError;
+
+/// 统计的时间范围：从当前时刻往前推 7 / 30 / 365 天的滚动窗口，不按自然周、月、年划分
+#[derive(Clone, Copy, Serialize, Debug, PartialEq)]
+pub enum StatsPeriod {
+    Week,
+    Month,
+    Year,
+    AllTime,
+}
+
+impl StatsPeriod {
+    pub fn all() -> [StatsPeriod; 4] {
+        [
+            StatsPeriod::Week,
+            StatsPeriod::Month,
+            StatsPeriod::Year,
+            StatsPeriod::AllTime,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatsPeriod::Week => "近 7 天",
+            StatsPeriod::Month => "近 30 天",
+            StatsPeriod::Year => "近 365 天",
+            StatsPeriod::AllTime => "全部",
+        }
+    }
+
+    /// 起始时间（Unix 时间戳），全部时间返回 0
+    pub fn since(&self, now: i64) -> i64 {
+        let days = match self {
+            StatsPeriod::Week => 7,
+            StatsPeriod::Month => 30,
+            StatsPeriod::Year => 365,
+            StatsPeriod::AllTime => return 0,
+        };
+        now - days * 86400
+    }
+}
+
+/// 排行榜条目
+#[derive(Clone, Serialize, Debug)]
+pub struct TopEntry {
+    /// 歌名 / 艺术家 / 专辑名
+    pub name: String,
+    /// 补充信息（歌曲和专辑为艺术家）
+    pub detail: Option<String>,
+    pub plays: i64,
+    /// 收听时长（秒）
+    pub seconds: i64,
+}
+
+/// 连续收听天数
+#[derive(Clone, Copy, Serialize, Debug, Default)]
+pub struct Streaks {
+    /// 截至今天（或昨天）的连续天数
+    pub current: i64,
+    pub longest: i64,
+}
+
+/// 收听统计（由 `DB::load_stats` 生成）
+#[derive(Clone, Serialize, Debug)]
+pub struct ListeningStats {
+    pub period: StatsPeriod,
+    pub total_plays: i64,
+    /// 总收听时长（秒）
+    pub total_seconds: i64,
+    pub top_tracks: Vec<TopEntry>,
+    pub top_artists: Vec<TopEntry>,
+    pub top_albums: Vec<TopEntry>,
+    /// 收听热力图：[星期（0 = 周日）][小时] -> 播放次数
+    pub heatmap: [[i64; 24]; 7],
+    /// 连续收听天数（不受时间范围影响）
+    pub streaks: Streaks,
+}
+
+impl ListeningStats {
+    /// 导出为 JSON
+    pub fn write_json(&self, path: &Path) -> Result<(), AppError> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 导出为 CSV：每行一条数据，第一列区分类别
+    pub fn write_csv(&self, path: &Path) -> Result<(), AppError> {
+        let mut out = String::from("section,name,detail,plays,seconds\n");
+        let mut push = |section: &str, name: &str, detail: &str, plays: i64, seconds: i64| {
+            out.push_str(&format!(
+                "{},{},{},{},{}\n",
+                section,
+                csv_field(name),
+                csv_field(detail),
+                plays,
+                seconds
+            ));
+        };
+
+        push(
+            "total",
+            self.period.label(),
+            "",
+            self.total_plays,
+            self.total_seconds,
+        );
+        for (section, entries) in [
+            ("track", &self.top_tracks),
+            ("artist", &self.top_artists),
+            ("album", &self.top_albums),
+        ] {
+            for entry in entries {
+                let detail = entry.detail.as_deref().unwrap_or("");
+                push(section, &entry.name, detail, entry.plays, entry.seconds);
+            }
+        }
+        for (weekday, hours) in self.heatmap.iter().enumerate() {
+            for (hour, plays) in hours.iter().enumerate() {
+                if *plays > 0 {
+                    push(
+                        "heatmap",
+                        &weekday.to_string(),
+                        &hour.to_string(),
+                        *plays,
+                        0,
+                    );
+                }
+            }
+        }
+        push("streak", "current", "", self.streaks.current, 0);
+        push("streak", "longest", "", self.streaks.longest, 0);
+
+        fs::write(path, out)?;
+        Ok(())
+    }
+}
+
+/// 含逗号、引号或换行的字段加引号转义
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
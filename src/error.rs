@@ -1,4 +1,7 @@
 use std::fmt;
+use tracing::{error, warn};
+
+use crate::notify::{self, Level};
 
 /// 应用级错误类型
 #[derive(Debug)]
@@ -13,6 +16,8 @@ pub enum AppError {
     Audio(String),
     /// 元数据解析错误
     Metadata(String),
+    /// 歌单文件读写错误
+    Playlist(String),
     /// 通用错误
     Other(String),
 }
@@ -25,6 +30,7 @@ impl fmt::Display for AppError {
             AppError::Config(e) => write!(f, "配置错误: {}", e),
             AppError::Audio(e) => write!(f, "音频错误: {}", e),
             AppError::Metadata(e) => write!(f, "元数据错误: {}", e),
+            AppError::Playlist(e) => write!(f, "歌单错误: {}", e),
             AppError::Other(e) => write!(f, "{}", e),
         }
     }
@@ -50,14 +56,21 @@ impl From<serde_json::Error> for AppError {
     }
 }
 
-/// 非致命错误的处理：打印警告并继续
+/// 非致命错误的处理：记录警告并继续
 pub fn warn_if_err<T, E: std::fmt::Display>(result: Result<T, E>, context: &str) {
     if let Err(e) = result {
-        eprintln!("[WARN] {}: {}", context, e);
+        warn(&e, context);
     }
 }
 
-/// 致命错误的处理：打印错误
+/// 记录警告日志，并在界面中弹出自动消失的提示
+pub fn warn<E: std::fmt::Display>(error: &E, context: &str) {
+    warn!("{}: {}", context, error);
+    notify::post(Level::Warning, context, Some(error.to_string()));
+}
+
+/// 致命错误的处理：记录错误日志，并在界面中保留到用户关闭
 pub fn log_error<E: std::fmt::Display>(error: &E, context: &str) {
-    eprintln!("[ERROR] {}: {}", context, error);
+    error!("{}: {}", context, error);
+    notify::post(Level::Error, context, Some(error.to_string()));
 }
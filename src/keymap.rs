@@ -0,0 +1,143 @@
+use gpui::*;
+use std::collections::HashMap;
+use tracing::warn;
+
+actions!(
+    zotu,
+    [
+        PlayPause,
+        NextTrack,
+        PreviousTrack,
+        SeekForward,
+        SeekBackward,
+        VolumeUp,
+        VolumeDown,
+        PlaybackFaster,
+        PlaybackSlower,
+        ResetPlaybackRate,
+        ToggleShuffle,
+        ToggleLoopMode,
+        CycleShuffleMode,
+        ToggleStopAfterCurrent,
+        CycleAbLoop,
+        FocusSearch,
+        OpenNowPlaying,
+        ShowLibrary,
+        ShowFavorites,
+        ShowHistory,
+        ShowStats,
+        OpenSettings,
+        RescanLibrary,
+        ClearHistory,
+        ToggleCommandPalette,
+        ToggleNotifications,
+    ]
+);
+
+actions!(
+    album_list,
+    [
+        SelectNext,
+        SelectPrevious,
+        PlaySelected,
+        SelectAll,
+        ClearSelection
+    ]
+);
+
+/// 全局快捷键的生效范围（输入框获得焦点时不触发）
+const GLOBAL_CONTEXT: &str = "Zotu && !SearchInput && !TagEditor && !SmartPlaylistEditor \
+     && !CommandPalette && !LoopNameInput && !EqPresetInput";
+/// 命令面板开关在搜索框和面板内也生效
+const PALETTE_CONTEXT: &str = "Zotu && !TagEditor && !SmartPlaylistEditor";
+/// 歌曲列表快捷键的生效范围
+const LIST_CONTEXT: &str = "AlbumList && !TagEditor";
+
+/// 一条可配置的按键绑定
+struct Binding {
+    /// config.json 中 keymap 使用的名字
+    name: &'static str,
+    default_keys: &'static str,
+    context: &'static str,
+    build: fn(&str, Option<&str>) -> KeyBinding,
+}
+
+fn binding<A: Action + Default>(
+    name: &'static str,
+    default_keys: &'static str,
+    context: &'static str,
+) -> Binding {
+    Binding {
+        name,
+        default_keys,
+        context,
+        build: |keys, context| KeyBinding::new(keys, A::default(), context),
+    }
+}
+
+fn bindings() -> Vec<Binding> {
+    vec![
+        binding::<PlayPause>("play_pause", "space", GLOBAL_CONTEXT),
+        binding::<NextTrack>("next_track", "secondary-right", GLOBAL_CONTEXT),
+        binding::<PreviousTrack>("previous_track", "secondary-left", GLOBAL_CONTEXT),
+        binding::<SeekForward>("seek_forward", "right", GLOBAL_CONTEXT),
+        binding::<SeekBackward>("seek_backward", "left", GLOBAL_CONTEXT),
+        binding::<VolumeUp>("volume_up", "secondary-up", GLOBAL_CONTEXT),
+        binding::<VolumeDown>("volume_down", "secondary-down", GLOBAL_CONTEXT),
+        binding::<PlaybackFaster>("playback_faster", "]", GLOBAL_CONTEXT),
+        binding::<PlaybackSlower>("playback_slower", "[", GLOBAL_CONTEXT),
+        binding::<ResetPlaybackRate>("reset_playback_rate", "", GLOBAL_CONTEXT),
+        binding::<ToggleShuffle>("toggle_shuffle", "s", GLOBAL_CONTEXT),
+        binding::<ToggleLoopMode>("toggle_loop_mode", "r", GLOBAL_CONTEXT),
+        binding::<CycleShuffleMode>("cycle_shuffle_mode", "", GLOBAL_CONTEXT),
+        binding::<ToggleStopAfterCurrent>("stop_after_current", "", GLOBAL_CONTEXT),
+        binding::<CycleAbLoop>("cycle_ab_loop", "l", GLOBAL_CONTEXT),
+        binding::<FocusSearch>("focus_search", "secondary-f", GLOBAL_CONTEXT),
+        binding::<OpenNowPlaying>("open_now_playing", "secondary-p", GLOBAL_CONTEXT),
+        binding::<ShowLibrary>("show_library", "", GLOBAL_CONTEXT),
+        binding::<ShowFavorites>("show_favorites", "", GLOBAL_CONTEXT),
+        binding::<ShowHistory>("show_history", "", GLOBAL_CONTEXT),
+        binding::<ShowStats>("show_stats", "", GLOBAL_CONTEXT),
+        binding::<OpenSettings>("open_settings", "secondary-,", GLOBAL_CONTEXT),
+        binding::<RescanLibrary>("rescan_library", "", GLOBAL_CONTEXT),
+        binding::<ClearHistory>("clear_history", "", GLOBAL_CONTEXT),
+        binding::<ToggleCommandPalette>(
+            "toggle_command_palette",
+            "secondary-shift-p",
+            PALETTE_CONTEXT,
+        ),
+        binding::<ToggleNotifications>("toggle_notifications", "", GLOBAL_CONTEXT),
+        binding::<SelectNext>("select_next", "down", LIST_CONTEXT),
+        binding::<SelectPrevious>("select_previous", "up", LIST_CONTEXT),
+        binding::<PlaySelected>("play_selected", "enter", LIST_CONTEXT),
+        binding::<SelectAll>("select_all", "secondary-a", LIST_CONTEXT),
+        binding::<ClearSelection>("clear_selection", "escape", LIST_CONTEXT),
+    ]
+}
+
+/// 注册快捷键：默认绑定可被 config.json 中的 keymap 覆盖（值为空字符串表示取消绑定）
+pub fn bind_keys(overrides: &HashMap<String, String>, cx: &mut App) {
+    let bindings = bindings();
+
+    for name in overrides.keys() {
+        if !bindings.iter().any(|b| b.name == name) {
+            warn!("未知的快捷键动作: {}", name);
+        }
+    }
+
+    // KeyBinding::new 解析失败会 panic，先校验配置中的按键
+    let is_valid = |keys: &str| keys.split_whitespace().all(|k| Keystroke::parse(k).is_ok());
+
+    let key_bindings = bindings.iter().filter_map(|b| {
+        let keys = match overrides.get(b.name).map(|keys| keys.trim()) {
+            Some(keys) if keys.is_empty() || is_valid(keys) => keys,
+            Some(keys) => {
+                warn!("快捷键 {} 的按键 \"{}\" 无效，使用默认按键", b.name, keys);
+                b.default_keys
+            }
+            None => b.default_keys,
+        };
+        (!keys.is_empty()).then(|| (b.build)(keys, Some(b.context)))
+    });
+    cx.bind_keys(key_bindings);
+}
@@ -1,10 +1,18 @@
+pub mod api;
 pub mod app;
 pub mod assets;
+pub mod cli;
 pub mod components;
 pub mod config;
 pub mod db;
 pub mod error;
+pub mod keymap;
+pub mod log;
+pub mod notify;
+pub mod paths;
 pub mod play;
+pub mod remote;
+pub mod scrobble;
 pub mod theme;
 pub mod ui;
 pub mod util;
@@ -0,0 +1,227 @@
+use gpui::App;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+use tracing_subscriber::{
+    Registry, filter::LevelFilter, fmt, layer::SubscriberExt, reload, util::SubscriberInitExt,
+};
+
+use crate::{config::Config, db::database::DB, paths, play::player::Player};
+
+/// 当前日志文件名，轮转后依次为 zotu.1.log、zotu.2.log
+const LOG_FILE: &str = "zotu.log";
+/// 单个日志文件的大小上限
+const MAX_LOG_SIZE: u64 = 2 * 1024 * 1024;
+/// 保留的日志文件数（含当前文件）
+const MAX_LOG_FILES: usize = 3;
+/// 诊断信息中附带的日志行数
+const DIAGNOSTIC_LOG_LINES: usize = 200;
+
+/// 运行中修改日志级别用的句柄
+static LEVEL_HANDLE: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+
+/// 日志级别
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn all() -> [LogLevel; 5] {
+        [
+            LogLevel::Error,
+            LogLevel::Warn,
+            LogLevel::Info,
+            LogLevel::Debug,
+            LogLevel::Trace,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LogLevel::Error => "错误",
+            LogLevel::Warn => "警告",
+            LogLevel::Info => "信息",
+            LogLevel::Debug => "调试",
+            LogLevel::Trace => "跟踪",
+        }
+    }
+
+    fn filter(&self) -> LevelFilter {
+        match self {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// 日志目录（用户数据目录下的 logs）
+pub fn log_dir() -> PathBuf {
+    paths::data_dir().join("logs")
+}
+
+fn log_path(dir: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        dir.join(LOG_FILE)
+    } else {
+        dir.join(format!("zotu.{}.log", index))
+    }
+}
+
+/// 超过大小上限后轮转的日志文件
+struct RotatingFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path(dir, 0))?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            file,
+            size,
+        })
+    }
+
+    /// zotu.log → zotu.1.log → zotu.2.log，最旧的文件被覆盖
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (1..MAX_LOG_FILES).rev() {
+            let from = log_path(&self.dir, index - 1);
+            if from.exists() {
+                fs::rename(from, log_path(&self.dir, index))?;
+            }
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path(&self.dir, 0))?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > MAX_LOG_SIZE {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// 初始化日志：输出到 stderr 和日志目录下的轮转文件（文件打不开时只输出到 stderr）
+pub fn init(level: LogLevel) {
+    let (filter, handle) = reload::Layer::new(level.filter());
+    let file = match RotatingFile::open(&log_dir()) {
+        Ok(file) => Some(file),
+        Err(e) => {
+            eprintln!("[WARN] 无法打开日志文件 {:?}: {}", log_dir(), e);
+            None
+        }
+    };
+    let file_layer = file.map(|file| fmt::layer().with_ansi(false).with_writer(Mutex::new(file)));
+
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(io::stderr))
+        .with(file_layer)
+        .try_init();
+    match result {
+        Ok(()) => {
+            let _ = LEVEL_HANDLE.set(handle);
+        }
+        Err(e) => eprintln!("[WARN] 初始化日志失败: {}", e),
+    }
+}
+
+/// 运行中修改日志级别
+pub fn set_level(level: LogLevel) {
+    if let Some(handle) = LEVEL_HANDLE.get()
+        && let Err(e) = handle.reload(level.filter())
+    {
+        tracing::warn!("修改日志级别失败: {}", e);
+    }
+}
+
+/// 最近的日志（最多 lines 行，包含上一个轮转文件的末尾）
+pub fn recent_logs(lines: usize) -> String {
+    let dir = log_dir();
+    let text: String = [1, 0]
+        .iter()
+        .filter_map(|&index| fs::read_to_string(log_path(&dir, index)).ok())
+        .collect();
+    let all: Vec<&str> = text.lines().collect();
+    all[all.len().saturating_sub(lines)..].join("\n")
+}
+
+/// 配置转为 JSON，隐去令牌和上次播放的歌曲
+fn redacted_config(config: &Config) -> String {
+    let mut value = match serde_json::to_value(config) {
+        Ok(value) => value,
+        Err(e) => return format!("序列化配置失败: {}", e),
+    };
+    for section in ["http_api", "scrobble"] {
+        if let Some(token) = value.pointer_mut(&format!("/{}/token", section))
+            && token.as_str().is_some_and(|token| !token.is_empty())
+        {
+            *token = Value::from("<已隐藏>");
+        }
+    }
+    if let Some(album) = value.pointer_mut("/play_info/album") {
+        *album = Value::Null;
+    }
+    serde_json::to_string_pretty(&value).unwrap_or_default()
+}
+
+/// 诊断信息：版本、系统、播放状态、配置和最近的日志，供用户反馈问题时附上
+pub fn diagnostics(cx: &App) -> String {
+    let player = cx.global::<Player>();
+    let library_size = cx.global::<DB>().get_album_count().unwrap_or(0);
+    format!(
+        "Zotu {}\n系统: {} {}\n输出设备: {}{}\n曲库: {} 首\n数据目录: {}\n缓存目录: {}\n日志目录: {}\n\n\
+         ---------- 配置 ----------\n{}\n\n\
+         ---------- 最近日志 ----------\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        player.output_device().unwrap_or("系统默认"),
+        if player.output_fallback() {
+            "（不可用，已回退到系统默认）"
+        } else {
+            ""
+        },
+        library_size,
+        paths::data_dir().display(),
+        paths::cache_dir().display(),
+        log_dir().display(),
+        redacted_config(cx.global::<Config>()),
+        recent_logs(DIAGNOSTIC_LOG_LINES),
+    )
+}
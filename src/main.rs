@@ -1,10 +1,74 @@
 use gpui::*;
+use std::process::ExitCode;
+use tracing::{error, info, warn};
 use zotu::{
-    app::Zotu, assets::Assets, config::Config, db::database::DB, error::log_error,
+    api,
+    app::Zotu,
+    assets::Assets,
+    cli::{self, Cli},
+    config::Config,
+    db::database::DB,
+    error::log_error,
+    keymap,
+    log::{self, LogLevel},
+    paths,
     play::player::Player,
+    remote::{self, RemoteServer},
+    scrobble,
 };
 
-fn main() {
+fn main() -> ExitCode {
+    let cli = match Cli::parse(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    if let Some(dir) = cli.data_dir.clone() {
+        paths::set_data_dir(dir);
+    }
+    log::init(LogLevel::default());
+
+    // 已有实例时转发命令，scan / status 可无头执行（无头执行前同样迁移旧文件）
+    if let Some(code) = cli::run(&cli) {
+        return code;
+    }
+
+    // 抢先占用 socket，之后启动的进程会把命令转发过来
+    let server = match RemoteServer::bind(&remote::socket_path(&cli.db_path)) {
+        Ok(server) => Some(server),
+        Err(e) => {
+            warn!("远程控制不可用: {}", e);
+            None
+        }
+    };
+
+    // 旧版本把配置和数据库写在当前目录，首次以默认路径启动界面时复制到用户目录
+    // （占用 socket 成功说明没有其他实例正在使用这些文件）
+    if server.is_some() && cli.uses_default_paths() {
+        paths::migrate_legacy_files();
+    }
+
+    // 读取或创建配置文件（失败时使用默认配置），按配置调整日志级别
+    let config_path = cli.config_path;
+    let config = match Config::load_or_create(&config_path) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            log_error(&e, "加载配置文件失败，使用默认配置");
+            Config {
+                path: config_path.clone(),
+                ..Config::default()
+            }
+        }
+    };
+    log::set_level(config.log.level);
+    info!("Zotu {} 启动", env!("CARGO_PKG_VERSION"));
+
+    let db_path = cli.db_path.to_string_lossy().to_string();
+    let initial_command = cli.command;
+
     let window_options = WindowOptions {
         titlebar: Some(TitlebarOptions {
             title: Some(SharedString::new("Zotu")),
@@ -17,46 +81,99 @@ fn main() {
     };
 
     Application::new()
-        .with_assets(Assets::new("./assets"))
+        .with_assets(Assets)
         .run(move |cx: &mut App| {
-            // 读取或创建配置文件（失败时使用默认配置）
-            let config = match Config::load_or_create("config.json") {
-                Ok(cfg) => cfg,
-                Err(e) => {
-                    log_error(&e, "加载配置文件失败，使用默认配置");
-                    Config::default()
-                }
-            };
-            cx.set_global(config);
+            keymap::bind_keys(&config.keymap, cx);
 
-            cx.set_global(Player::new());
+            let mut player = Player::new();
+            player.set_volume(config.play_info.volume);
+            player.set_shuffle_mode(config.play_info.shuffle_mode);
+            player.set_shuffle_seed(config.play_info.shuffle_seed);
+            player.set_sleep_fade(config.play_info.sleep_fade);
+            player.set_playback_rate(config.play_info.playback_rate);
+            player.set_pitch_semitones(config.play_info.pitch_semitones);
+            player.set_equalizer(config.equalizer.settings.clone());
+            player.set_effects(config.effects.clone());
+            if config.play_info.output_device.is_some() {
+                player.set_output_device(config.play_info.output_device.clone());
+            }
+            cx.set_global(config);
+            cx.set_global(player);
 
             // 初始化全局数据库连接（失败时退出）
-            match DB::new("metadata.db") {
-                Ok(db) => cx.set_global(db),
+            let cover_migration = match DB::new(&db_path) {
+                Ok(db) => {
+                    // 旧版本的封面在后台迁移，完成后再清理已移除歌曲留下的封面
+                    let migration = db.migrate_covers_in_background(cx);
+                    cx.set_global(db);
+                    migration
+                }
                 Err(e) => {
                     log_error(&e, "数据库初始化失败，应用无法启动");
-                    eprintln!("[FATAL] 数据库连接失败: {}", e);
+                    error!("数据库连接失败: {}", e);
                     return;
                 }
             };
 
-            match cx.open_window(window_options, |window, cx| {
+            // 听歌记录上报（默认关闭）
+            scrobble::init(&db_path, cx);
+
+            // Linux 下注册 MPRIS 服务（媒体键、桌面媒体组件）
+            #[cfg(target_os = "linux")]
+            zotu::play::mpris::init(cx);
+
+            let window = match cx.open_window(window_options, |window, cx| {
                 cx.new(|cx| Zotu::new(window, cx))
             }) {
-                Ok(_) => {}
+                Ok(window) => window,
                 Err(e) => {
                     log_error(&e, "打开窗口失败");
                     return;
                 }
+            };
+
+            cx.spawn(async move |cx: &mut AsyncApp| {
+                let migrated = cover_migration.await;
+                cx.update(|cx: &mut App| {
+                    cx.global::<DB>().collect_covers_in_background(cx).detach();
+                })
+                .ok();
+                if migrated > 0 {
+                    window
+                        .update(cx, |zotu, _window, cx| zotu.refresh_library(cx))
+                        .ok();
+                }
+            })
+            .detach();
+
+            // 命令行启动时附带的 play / enqueue
+            if let Some(command) = initial_command {
+                let result = window.update(cx, |zotu, window, cx| {
+                    zotu.handle_remote(command, window, cx)
+                });
+                if let Ok(response) = result {
+                    cli::print_response(&response, false);
+                }
+            }
+            if let Some(server) = server {
+                remote::init(server, window, cx);
+            }
+
+            // 局域网远程控制 API（默认关闭）
+            if let Ok(library_state) =
+                window.update(cx, |zotu, _window, _cx| zotu.library_state().clone())
+            {
+                api::init(&library_state, cx);
             }
 
             // 在应用关闭时保存配置（忽略保存错误）
             cx.on_window_closed(move |app| {
-                if let Err(e) = app.global::<Config>().save("config.json") {
-                    eprintln!("[WARN] 保存配置失败: {}", e);
+                if let Err(e) = app.global::<Config>().save(&config_path) {
+                    warn!("保存配置失败: {}", e);
                 }
             })
             .detach();
         });
+
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,91 @@
+use crossbeam::channel::{self, Receiver, Sender};
+
+use std::{
+    sync::{
+        OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::SystemTime,
+};
+
+/// 通知级别
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Level {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Level {
+    /// 错误需要手动关闭，其余提示自动消失
+    pub fn is_persistent(&self) -> bool {
+        *self == Level::Error
+    }
+}
+
+/// 一条通知
+#[derive(Clone, Debug)]
+pub struct Notification {
+    /// ID 相同的通知会替换界面中已有的那条（用于更新进度）
+    pub id: u64,
+    pub level: Level,
+    pub title: String,
+    pub detail: Option<String>,
+    /// 后台任务进度（0.0 ~ 1.0），None 表示不是进度通知
+    pub progress: Option<f32>,
+    pub time: SystemTime,
+}
+
+/// 所有通知汇集到这里，任意线程都可以发送，界面定时取出显示
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(channel::unbounded)
+}
+
+/// 分配一个通知 ID，之后可用它更新同一条通知
+pub fn next_id() -> u64 {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+fn send(id: u64, level: Level, title: String, detail: Option<String>, progress: Option<f32>) {
+    // 接收端与发送端同在静态变量中，发送不会失败
+    let _ = channel().0.send(Notification {
+        id,
+        level,
+        title,
+        detail,
+        progress,
+        time: SystemTime::now(),
+    });
+}
+
+/// 发送一条通知，返回它的 ID
+pub fn post(level: Level, title: impl Into<String>, detail: Option<String>) -> u64 {
+    let id = next_id();
+    send(id, level, title.into(), detail, None);
+    id
+}
+
+/// 发送或更新进度通知（fraction 为 0.0 ~ 1.0）
+pub fn progress(id: u64, title: impl Into<String>, fraction: f32) {
+    send(
+        id,
+        Level::Info,
+        title.into(),
+        None,
+        Some(fraction.clamp(0.0, 1.0)),
+    );
+}
+
+/// 结束进度通知，替换为最终结果
+pub fn finish(id: u64, level: Level, title: impl Into<String>, detail: Option<String>) {
+    send(id, level, title.into(), detail, None);
+}
+
+/// 取出所有尚未显示的通知
+pub fn drain() -> Vec<Notification> {
+    channel().1.try_iter().collect()
+}
@@ -0,0 +1,210 @@
+use rusqlite::{Connection, OpenFlags};
+use std::{
+    env,
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+use tracing::{info, warn};
+
+use crate::config::Config;
+
+/// 应用目录名
+const APP_DIR: &str = "zotu";
+/// 配置文件名
+pub const CONFIG_FILE: &str = "config.json";
+/// 数据库文件名
+pub const DB_FILE: &str = "metadata.db";
+/// 数据库的 WAL 附属文件，随数据库一起迁移
+const DB_SIDE_FILES: [&str; 2] = ["-wal", "-shm"];
+
+/// --data-dir 指定的数据目录，设置后缓存也放在它下面
+static DATA_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// 使用命令行指定的数据目录（只能在启动时设置一次）
+pub fn set_data_dir(dir: PathBuf) {
+    let _ = DATA_DIR_OVERRIDE.set(dir);
+}
+
+/// 用户配置目录：$XDG_CONFIG_HOME/zotu，未设置时为 ~/.config/zotu（Windows 下为 %APPDATA%\zotu）
+pub fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config", "APPDATA")
+}
+
+/// 用户数据目录：$XDG_DATA_HOME/zotu，未设置时为 ~/.local/share/zotu（Windows 下为 %APPDATA%\zotu）
+pub fn data_dir() -> PathBuf {
+    match DATA_DIR_OVERRIDE.get() {
+        Some(dir) => dir.clone(),
+        None => xdg_dir("XDG_DATA_HOME", ".local/share", "APPDATA"),
+    }
+}
+
+/// 缓存目录：$XDG_CACHE_HOME/zotu，未设置时为 ~/.cache/zotu（Windows 下为 %LOCALAPPDATA%\zotu）
+pub fn cache_dir() -> PathBuf {
+    match DATA_DIR_OVERRIDE.get() {
+        Some(dir) => dir.join("cache"),
+        None => xdg_dir("XDG_CACHE_HOME", ".cache", "LOCALAPPDATA"),
+    }
+}
+
+/// 封面缓存目录
+pub fn cover_dir() -> PathBuf {
+    cache_dir().join("covers")
+}
+
+/// 默认配置文件路径
+pub fn default_config_path() -> PathBuf {
+    config_dir().join(CONFIG_FILE)
+}
+
+/// 默认数据库路径
+pub fn default_db_path() -> PathBuf {
+    data_dir().join(DB_FILE)
+}
+
+/// 旧版本存放封面的目录（编译时的源码目录）
+pub fn legacy_cover_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("covers")
+}
+
+fn xdg_dir(var: &str, fallback: &str, windows_var: &str) -> PathBuf {
+    if cfg!(windows)
+        && let Some(dir) = env::var_os(windows_var)
+    {
+        return PathBuf::from(dir).join(APP_DIR);
+    }
+    match env::var_os(var).filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir).join(APP_DIR),
+        None => home_dir().join(fallback).join(APP_DIR),
+    }
+}
+
+/// 用户主目录，取不到时使用当前目录
+fn home_dir() -> PathBuf {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// 首次运行时把旧版本写在当前目录的配置和数据库、源码目录下的封面复制到默认位置
+/// （目标已存在或旧文件无法识别时跳过，旧文件原样保留）
+pub fn migrate_legacy_files() {
+    let config_path = default_config_path();
+    let legacy_config = Path::new(CONFIG_FILE);
+    if should_migrate(legacy_config, &config_path) {
+        match validate_config(legacy_config) {
+            Ok(()) => {
+                copy_legacy_file(legacy_config, &config_path);
+            }
+            Err(e) => warn!("跳过迁移无法识别的配置文件 {:?}: {}", legacy_config, e),
+        }
+    }
+
+    let db_path = default_db_path();
+    let legacy_db = Path::new(DB_FILE);
+    if should_migrate(legacy_db, &db_path) {
+        match validate_db(legacy_db) {
+            Ok(()) => {
+                if copy_legacy_file(legacy_db, &db_path) {
+                    for suffix in DB_SIDE_FILES {
+                        let mut to = db_path.as_os_str().to_owned();
+                        to.push(suffix);
+                        let from = format!("{}{}", DB_FILE, suffix);
+                        if should_migrate(Path::new(&from), Path::new(&to)) {
+                            copy_legacy_file(Path::new(&from), Path::new(&to));
+                        }
+                    }
+                }
+            }
+            Err(e) => warn!("跳过迁移无法识别的数据库 {:?}: {}", legacy_db, e),
+        }
+    }
+
+    let legacy = legacy_cover_dir();
+    let covers = cover_dir();
+    if legacy.is_dir() && legacy != covers {
+        match copy_dir_contents(&legacy, &covers) {
+            Ok(0) => {}
+            Ok(copied) => info!("已迁移 {} 个封面到 {:?}", copied, covers),
+            Err(e) => warn!("迁移封面失败: {:?} -> {:?}: {}", legacy, covers, e),
+        }
+    }
+}
+
+/// 旧文件存在、目标还不存在且两者不是同一个文件时才需要迁移
+fn should_migrate(from: &Path, to: &Path) -> bool {
+    from.is_file() && !to.exists() && !same_file(from, to)
+}
+
+/// 旧配置必须能解析为当前的配置结构
+fn validate_config(path: &Path) -> Result<(), Box<dyn Error>> {
+    serde_json::from_str::<Config>(&fs::read_to_string(path)?)?;
+    Ok(())
+}
+
+/// 旧数据库必须是包含曲库表的 SQLite 数据库
+fn validate_db(path: &Path) -> Result<(), Box<dyn Error>> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let has_library: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'library')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_library {
+        return Err("缺少 library 表".into());
+    }
+    Ok(())
+}
+
+/// 复制单个文件，返回是否迁移成功
+fn copy_legacy_file(from: &Path, to: &Path) -> bool {
+    match copy_file(from, to) {
+        Ok(()) => {
+            info!("已迁移 {:?} -> {:?}", from, to);
+            true
+        }
+        Err(e) => {
+            warn!("迁移文件失败: {:?} -> {:?}: {}", from, to, e);
+            false
+        }
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// 先复制到临时文件再重命名，中途失败不会留下不完整的目标文件
+fn copy_file(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut tmp = to.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    if let Err(e) = fs::copy(from, &tmp).and_then(|_| fs::rename(&tmp, to)) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    Ok(())
+}
+
+fn copy_dir_contents(from: &Path, to: &Path) -> io::Result<usize> {
+    let mut copied = 0;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_file() && !target.exists() {
+            copy_file(&entry.path(), &target)?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
@@ -1 +1,15 @@
-pub mod player;
\ No newline at end of file
+pub mod player;
+pub mod shuffle;
+pub mod stretch;
+pub mod ab_loop;
+pub mod equalizer;
+pub mod dsp;
+pub mod analyzer;
+pub mod waveform;
+pub mod output;
+
+#[cfg(test)]
+mod test_source;
+
+#[cfg(target_os = "linux")]
+pub mod mpris;
@@ -0,0 +1,209 @@
+use rodio::{ChannelCount, SampleRate, Source, source::SeekError};
+use uuid::Uuid;
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+/// 越过 B 点多少以内仍跳回 A 点（设置 B 点时解码位置通常已略超过 B 点），
+/// 超出则认为是用户跳转到了区间之后，不再循环
+const LOOP_GRACE: Duration = Duration::from_secs(1);
+/// B 点前淡出、跳回 A 点后淡入的时长，避免拼接处的爆音
+const LOOP_FADE: Duration = Duration::from_millis(5);
+/// 每隔多少帧更新一次共享的播放位置
+const POSITION_UPDATE_FRAMES: u64 = 256;
+
+/// A–B 循环：只设置了 A 点时 b 为 None，设置 B 点后开始循环
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AbLoop {
+    pub a: Duration,
+    pub b: Option<Duration>,
+}
+
+impl AbLoop {
+    /// 完整的循环区间
+    pub fn region(&self) -> Option<(Duration, Duration)> {
+        self.b.map(|b| (self.a, b))
+    }
+}
+
+/// 保存在数据库中的命名循环片段
+#[derive(Clone, Debug)]
+pub struct LoopRegion {
+    pub id: i64,
+    pub track: Uuid,
+    pub name: String,
+    pub start: Duration,
+    pub end: Duration,
+}
+
+/// 播放线程与 `Player` 共享的循环区间和解码位置（毫秒，u64::MAX 表示无循环）
+#[derive(Clone, Debug)]
+pub struct LoopControl(Arc<LoopShared>);
+
+#[derive(Debug)]
+struct LoopShared {
+    start_ms: AtomicU64,
+    end_ms: AtomicU64,
+    position_ms: AtomicU64,
+}
+
+impl Default for LoopControl {
+    fn default() -> Self {
+        Self(Arc::new(LoopShared {
+            start_ms: AtomicU64::new(0),
+            end_ms: AtomicU64::new(u64::MAX),
+            position_ms: AtomicU64::new(0),
+        }))
+    }
+}
+
+impl LoopControl {
+    /// 设置或取消循环区间
+    pub fn set_region(&self, region: Option<(Duration, Duration)>) {
+        let (start, end) = match region {
+            Some((start, end)) => (start.as_millis() as u64, end.as_millis() as u64),
+            None => (0, u64::MAX),
+        };
+        self.0.start_ms.store(start, Ordering::Relaxed);
+        self.0.end_ms.store(end, Ordering::Relaxed);
+    }
+
+    fn region(&self) -> Option<(Duration, Duration)> {
+        let end = self.0.end_ms.load(Ordering::Relaxed);
+        (end != u64::MAX).then(|| {
+            let start = self.0.start_ms.load(Ordering::Relaxed);
+            (Duration::from_millis(start), Duration::from_millis(end))
+        })
+    }
+
+    /// 当前解码到的位置（原始音频时间）
+    pub fn position(&self) -> Duration {
+        Duration::from_millis(self.0.position_ms.load(Ordering::Relaxed))
+    }
+
+    fn set_position(&self, position: Duration) {
+        self.0
+            .position_ms
+            .store(position.as_millis() as u64, Ordering::Relaxed);
+    }
+}
+
+/// 在解码器之后按采样计数，到达 B 点时直接在解码器上跳回 A 点，实现无缝循环
+pub struct SegmentLoop<I> {
+    input: I,
+    control: LoopControl,
+    channels: u64,
+    sample_rate: u64,
+    /// 当前的循环区间（每帧从 control 读取）
+    region: Option<(Duration, Duration)>,
+    /// 最近一次跳转的位置
+    base: Duration,
+    /// 跳转后已输出的采样数
+    samples: u64,
+    /// 剩余的淡入采样数
+    fade_remaining: u64,
+}
+
+impl<I: Source> SegmentLoop<I> {
+    pub fn new(input: I, control: LoopControl) -> Self {
+        let channels = input.channels().max(1) as u64;
+        let sample_rate = input.sample_rate().max(1) as u64;
+        control.set_position(Duration::ZERO);
+        Self {
+            input,
+            control,
+            channels,
+            sample_rate,
+            region: None,
+            base: Duration::ZERO,
+            samples: 0,
+            fade_remaining: 0,
+        }
+    }
+
+    fn decoded_position(&self) -> Duration {
+        let frames = self.samples / self.channels;
+        self.base + Duration::from_nanos(frames * 1_000_000_000 / self.sample_rate)
+    }
+
+    fn fade_len(&self) -> u64 {
+        LOOP_FADE.as_micros() as u64 * self.sample_rate / 1_000_000 * self.channels
+    }
+
+    /// 在帧边界检查是否到达 B 点
+    fn check_loop(&mut self) {
+        self.region = self.control.region();
+        let Some((start, end)) = self.region else {
+            return;
+        };
+        let position = self.decoded_position();
+        if position >= end && position < end + LOOP_GRACE && self.input.try_seek(start).is_ok() {
+            self.base = start;
+            self.samples = 0;
+            self.fade_remaining = self.fade_len();
+            self.control.set_position(start);
+        }
+    }
+}
+
+impl<I: Source> Iterator for SegmentLoop<I> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.samples.is_multiple_of(self.channels) {
+            self.check_loop();
+            if self
+                .samples
+                .is_multiple_of(POSITION_UPDATE_FRAMES * self.channels)
+            {
+                self.control.set_position(self.decoded_position());
+            }
+        }
+
+        let mut sample = self.input.next()?;
+        if let Some((_, end)) = self.region {
+            let left = end.saturating_sub(self.decoded_position());
+            if !left.is_zero() && left < LOOP_FADE {
+                sample *= left.as_secs_f32() / LOOP_FADE.as_secs_f32();
+            }
+        }
+        self.samples += 1;
+        if self.fade_remaining > 0 {
+            let fade_len = self.fade_len().max(1);
+            sample *= 1.0 - self.fade_remaining as f32 / fade_len as f32;
+            self.fade_remaining -= 1;
+        }
+        Some(sample)
+    }
+}
+
+impl<I: Source> Source for SegmentLoop<I> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.base = pos;
+        self.samples = 0;
+        self.control.set_position(pos);
+        Ok(())
+    }
+}
@@ -0,0 +1,278 @@
+use rodio::{ChannelCount, SampleRate, Source, source::SeekError};
+
+use std::{
+    f32::consts::PI,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+/// 环形缓冲区长度（单声道采样数，2 的幂）
+const RING_LEN: usize = 8192;
+/// 频谱分析的 FFT 长度
+pub const FFT_LEN: usize = 2048;
+/// 频谱显示的频率范围（Hz）
+const SPECTRUM_RANGE: (f32, f32) = (40.0, 16000.0);
+/// 频谱显示的动态范围（dB），低于此值显示为 0
+const SPECTRUM_FLOOR_DB: f32 = -70.0;
+
+/// 送往 sink 的采样（混为单声道），播放线程无锁写入，界面随时读取最近一段
+#[derive(Clone)]
+pub struct SampleTap(Arc<TapShared>);
+
+struct TapShared {
+    samples: Box<[AtomicU32]>,
+    /// 已写入的采样总数
+    written: AtomicUsize,
+    sample_rate: AtomicU32,
+}
+
+impl Default for SampleTap {
+    fn default() -> Self {
+        Self(Arc::new(TapShared {
+            samples: (0..RING_LEN).map(|_| AtomicU32::new(0)).collect(),
+            written: AtomicUsize::new(0),
+            sample_rate: AtomicU32::new(44100),
+        }))
+    }
+}
+
+impl SampleTap {
+    fn push(&self, sample: f32) {
+        let index = self.0.written.load(Ordering::Relaxed);
+        self.0.samples[index % RING_LEN].store(sample.to_bits(), Ordering::Relaxed);
+        self.0
+            .written
+            .store(index.wrapping_add(1), Ordering::Release);
+    }
+
+    /// 把最近写入的 out.len() 个采样复制到 out（按时间顺序）
+    pub fn snapshot(&self, out: &mut [f32]) {
+        let len = out.len().min(RING_LEN);
+        let end = self.0.written.load(Ordering::Acquire);
+        let start = end.wrapping_sub(len);
+        for (i, sample) in out.iter_mut().take(len).enumerate() {
+            let index = start.wrapping_add(i) % RING_LEN;
+            *sample = f32::from_bits(self.0.samples[index].load(Ordering::Relaxed));
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.0.sample_rate.load(Ordering::Relaxed)
+    }
+}
+
+/// 在 source 之后把每帧的平均值写入 SampleTap，采样原样输出
+pub struct TapSource<I> {
+    input: I,
+    tap: SampleTap,
+    channels: usize,
+    channel: usize,
+    sum: f32,
+}
+
+impl<I: Source> TapSource<I> {
+    pub fn new(input: I, tap: SampleTap) -> Self {
+        let channels = input.channels().max(1) as usize;
+        tap.0
+            .sample_rate
+            .store(input.sample_rate(), Ordering::Relaxed);
+        Self {
+            input,
+            tap,
+            channels,
+            channel: 0,
+            sum: 0.0,
+        }
+    }
+}
+
+impl<I: Source> Iterator for TapSource<I> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.input.next()?;
+        self.sum += sample;
+        self.channel += 1;
+        if self.channel == self.channels {
+            self.tap.push(self.sum / self.channels as f32);
+            self.channel = 0;
+            self.sum = 0.0;
+        }
+        Some(sample)
+    }
+}
+
+impl<I: Source> Source for TapSource<I> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.channel = 0;
+        self.sum = 0.0;
+        Ok(())
+    }
+}
+
+/// 原地基 2 FFT（re、im 长度相同且为 2 的幂）
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len *= 2;
+    }
+}
+
+/// 计算频谱：按对数频率分成 bands 段，每段取最大幅度，映射到 0.0 ~ 1.0
+pub fn spectrum(samples: &[f32], sample_rate: u32, bands: usize) -> Vec<f32> {
+    let n = samples.len();
+    if n < 2 || !n.is_power_of_two() || bands == 0 {
+        return vec![0.0; bands];
+    }
+
+    // 汉宁窗，幅度按窗的相干增益（0.5）和 n/2 归一化，满幅正弦约为 0 dB
+    let mut re: Vec<f32> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| sample * (0.5 - 0.5 * (2.0 * PI * i as f32 / n as f32).cos()))
+        .collect();
+    let mut im = vec![0.0; n];
+    fft(&mut re, &mut im);
+    let scale = 4.0 / n as f32;
+
+    let bin_hz = sample_rate as f32 / n as f32;
+    let (low, high) = (
+        SPECTRUM_RANGE.0,
+        SPECTRUM_RANGE.1.min(sample_rate as f32 / 2.0),
+    );
+    let ratio = (high / low).powf(1.0 / bands as f32);
+    (0..bands)
+        .map(|band| {
+            let from = low * ratio.powi(band as i32);
+            let to = from * ratio;
+            let first = ((from / bin_hz) as usize).max(1);
+            let last = ((to / bin_hz).ceil() as usize).max(first + 1).min(n / 2);
+            let peak = (first..last)
+                .map(|bin| (re[bin] * re[bin] + im[bin] * im[bin]).sqrt() * scale)
+                .fold(0f32, f32::max);
+            let db = 20.0 * peak.max(1e-9).log10();
+            ((db - SPECTRUM_FLOOR_DB) / -SPECTRUM_FLOOR_DB).clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 44100;
+    const BANDS: usize = 32;
+
+    fn sine(frequency: f32, amplitude: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| amplitude * (2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn fft_of_cosine_peaks_at_its_bin() {
+        let n = 64;
+        let mut re: Vec<f32> = (0..n)
+            .map(|i| (2.0 * PI * 5.0 * i as f32 / n as f32).cos())
+            .collect();
+        let mut im = vec![0.0; n];
+        fft(&mut re, &mut im);
+        for bin in 0..n {
+            let magnitude = (re[bin] * re[bin] + im[bin] * im[bin]).sqrt();
+            let expected = if bin == 5 || bin == n - 5 {
+                n as f32 / 2.0
+            } else {
+                0.0
+            };
+            assert!(
+                (magnitude - expected).abs() < 1e-3,
+                "bin {}: {}",
+                bin,
+                magnitude
+            );
+        }
+    }
+
+    #[test]
+    fn sine_peaks_in_its_band() {
+        // 取第 12 段的对数中心频率，避免主瓣跨到相邻段
+        let ratio = (SPECTRUM_RANGE.1 / SPECTRUM_RANGE.0).powf(1.0 / BANDS as f32);
+        let band = 12;
+        let frequency = SPECTRUM_RANGE.0 * ratio.powf(band as f32 + 0.5);
+
+        let levels = spectrum(&sine(frequency, 0.5, FFT_LEN), SAMPLE_RATE, BANDS);
+        assert_eq!(levels.len(), BANDS);
+        let peak = (0..BANDS)
+            .max_by(|&a, &b| levels[a].total_cmp(&levels[b]))
+            .unwrap();
+        assert_eq!(peak, band);
+        // 半幅正弦约为 -6 dB
+        let expected = (-6.02 - SPECTRUM_FLOOR_DB) / -SPECTRUM_FLOOR_DB;
+        assert!((levels[band] - expected).abs() < 0.03, "{}", levels[band]);
+        for (i, level) in levels.iter().enumerate() {
+            if i.abs_diff(band) > 2 {
+                assert!(*level < 0.3, "段 {}: {}", i, level);
+            }
+        }
+    }
+
+    #[test]
+    fn silence_gives_zero_levels() {
+        let levels = spectrum(&vec![0.0; FFT_LEN], SAMPLE_RATE, BANDS);
+        assert_eq!(levels, vec![0.0; BANDS]);
+    }
+
+    #[test]
+    fn unsupported_input_gives_zero_levels() {
+        let tone = sine(1000.0, 0.5, 1000);
+        for samples in [&tone[..], &tone[..1], &[]] {
+            assert_eq!(spectrum(samples, SAMPLE_RATE, BANDS), vec![0.0; BANDS]);
+        }
+        assert!(spectrum(&tone[..512], SAMPLE_RATE, 0).is_empty());
+        // 很短的 2 的幂长度也能计算
+        assert_eq!(spectrum(&tone[..4], SAMPLE_RATE, BANDS).len(), BANDS);
+    }
+}
@@ -0,0 +1,496 @@
+pub mod effects;
+
+use rodio::{ChannelCount, SampleRate, Source, source::SeekError};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    f32::consts::PI,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use effects::{Balance, ChannelSwap, Compressor, Limiter, MonoDownmix, StereoWidth, ToneShelf};
+
+/// 每次加锁处理的帧数
+const BLOCK_FRAMES: usize = 256;
+
+/// 音效：逐帧处理采样（每帧每个声道一个采样），不依赖音频设备，
+/// 可以直接喂入合成的采样测试
+pub trait Effect: Send {
+    /// 加入效果链或声道数、采样率变化时调用
+    fn configure(&mut self, channels: usize, sample_rate: u32);
+    /// 原地处理一帧
+    fn process(&mut self, frame: &mut [f32]);
+    /// 跳转后清空内部状态
+    fn reset(&mut self) {}
+    /// 播放中修改参数（kind 与创建时同类），保留包络、滤波器等内部状态
+    fn update(&mut self, _kind: &EffectKind) {}
+}
+
+/// 按顺序处理一段交错采样（末尾不足一帧的采样原样保留）
+pub fn process_interleaved(effect: &mut dyn Effect, samples: &mut [f32], channels: usize) {
+    for frame in samples.chunks_exact_mut(channels.max(1)) {
+        effect.process(frame);
+    }
+}
+
+/// 播放线程与 `Player` 共享的效果链，修改后实时生效
+#[derive(Clone, Default)]
+pub struct DspChain(Arc<Mutex<ChainState>>);
+
+#[derive(Default)]
+struct ChainState {
+    effects: Vec<Box<dyn Effect>>,
+    /// 效果器已配置的声道数和采样率（None 表示有新加入的效果器需要配置）
+    format: Option<(usize, u32)>,
+}
+
+impl DspChain {
+    /// 替换整条效果链（按处理顺序）
+    pub fn set(&self, effects: Vec<Box<dyn Effect>>) {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        state.effects = effects;
+        state.format = None;
+    }
+
+    /// 按顺序把新参数交给现有的效果器（效果器的类型和顺序不变时使用）
+    pub fn update<'a>(&self, kinds: impl IntoIterator<Item = &'a EffectKind>) {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        for (effect, kind) in state.effects.iter_mut().zip(kinds) {
+            effect.update(kind);
+        }
+    }
+
+    /// 在末尾注册一个效果器
+    pub fn push(&self, effect: Box<dyn Effect>) {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        state.effects.push(effect);
+        state.format = None;
+    }
+
+    /// 调整效果器的处理顺序
+    pub fn move_effect(&self, from: usize, to: usize) {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if from < state.effects.len() && to < state.effects.len() {
+            let effect = state.effects.remove(from);
+            state.effects.insert(to, effect);
+        }
+    }
+
+    pub fn remove(&self, index: usize) -> Option<Box<dyn Effect>> {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        (index < state.effects.len()).then(|| state.effects.remove(index))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .effects
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 依次经过所有效果器处理一段交错采样
+    pub fn process(&self, samples: &mut [f32], channels: usize, sample_rate: u32) {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if state.effects.is_empty() {
+            return;
+        }
+        if state.format != Some((channels, sample_rate)) {
+            for effect in &mut state.effects {
+                effect.configure(channels, sample_rate);
+            }
+            state.format = Some((channels, sample_rate));
+        }
+        for frame in samples.chunks_exact_mut(channels.max(1)) {
+            for effect in &mut state.effects {
+                effect.process(frame);
+            }
+        }
+    }
+
+    fn reset(&self) {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        for effect in &mut state.effects {
+            effect.reset();
+        }
+    }
+}
+
+/// 把效果链接在 source 之后
+pub struct DspSource<I> {
+    input: I,
+    chain: DspChain,
+    buffer: Vec<f32>,
+    position: usize,
+    /// 缓冲块的声道数和采样率（输入已经读到块之后，不能再用输入的格式）
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    /// 缓冲块是否读到了输入当前 span 的末尾
+    span_end: bool,
+}
+
+impl<I: Source> DspSource<I> {
+    pub fn new(input: I, chain: DspChain) -> Self {
+        let channels = input.channels();
+        let sample_rate = input.sample_rate();
+        Self {
+            input,
+            chain,
+            buffer: Vec::new(),
+            position: 0,
+            channels,
+            sample_rate,
+            span_end: false,
+        }
+    }
+
+    /// 读取并处理下一块采样，输入结束时返回 false
+    ///
+    /// 块在输入的 span 边界处截断，保证一块内的声道数和采样率不变
+    fn fill(&mut self) -> bool {
+        self.channels = self.input.channels();
+        self.sample_rate = self.input.sample_rate();
+        let channels = self.channels.max(1) as usize;
+        let block = BLOCK_FRAMES * channels;
+        let len = match self.input.current_span_len() {
+            Some(span) if span > 0 => span.min(block),
+            _ => block,
+        };
+        self.span_end = self.input.current_span_len() == Some(len);
+
+        self.buffer.clear();
+        self.position = 0;
+        self.buffer.extend(self.input.by_ref().take(len));
+        self.chain
+            .process(&mut self.buffer, channels, self.sample_rate);
+        !self.buffer.is_empty()
+    }
+}
+
+impl<I: Source> Iterator for DspSource<I> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position >= self.buffer.len() && !self.fill() {
+            return None;
+        }
+        let sample = self.buffer[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl<I: Source> Source for DspSource<I> {
+    fn current_span_len(&self) -> Option<usize> {
+        let buffered = self.buffer.len() - self.position;
+        if buffered == 0 {
+            return self.input.current_span_len();
+        }
+        if self.span_end {
+            return Some(buffered);
+        }
+        self.input.current_span_len().map(|span| span + buffered)
+    }
+
+    fn channels(&self) -> ChannelCount {
+        if self.position < self.buffer.len() {
+            self.channels
+        } else {
+            self.input.channels()
+        }
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        if self.position < self.buffer.len() {
+            self.sample_rate
+        } else {
+            self.input.sample_rate()
+        }
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.buffer.clear();
+        self.position = 0;
+        self.chain.reset();
+        Ok(())
+    }
+}
+
+/// 二阶 IIR 滤波器系数（RBJ Audio EQ Cookbook，已按 a0 归一化）
+#[derive(Clone, Copy, Debug)]
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+}
+
+impl Biquad {
+    fn normalized(b: [f32; 3], a: [f32; 3]) -> Self {
+        Biquad {
+            b0: b[0] / a[0],
+            b1: b[1] / a[0],
+            b2: b[2] / a[0],
+            a1: a[1] / a[0],
+            a2: a[2] / a[0],
+        }
+    }
+
+    /// 峰值滤波器
+    pub fn peaking(frequency: f32, gain_db: f32, q: f32, sample_rate: f32) -> Self {
+        let a = 10f32.powf(gain_db / 40.0);
+        let w0 = 2.0 * PI * frequency / sample_rate;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos = w0.cos();
+        Self::normalized(
+            [1.0 + alpha * a, -2.0 * cos, 1.0 - alpha * a],
+            [1.0 + alpha / a, -2.0 * cos, 1.0 - alpha / a],
+        )
+    }
+
+    /// 低架滤波器（斜率 S = 1）
+    pub fn low_shelf(frequency: f32, gain_db: f32, sample_rate: f32) -> Self {
+        let (a, cos, beta) = Self::shelf_terms(frequency, gain_db, sample_rate);
+        Self::normalized(
+            [
+                a * ((a + 1.0) - (a - 1.0) * cos + beta),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                a * ((a + 1.0) - (a - 1.0) * cos - beta),
+            ],
+            [
+                (a + 1.0) + (a - 1.0) * cos + beta,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                (a + 1.0) + (a - 1.0) * cos - beta,
+            ],
+        )
+    }
+
+    /// 高架滤波器（斜率 S = 1）
+    pub fn high_shelf(frequency: f32, gain_db: f32, sample_rate: f32) -> Self {
+        let (a, cos, beta) = Self::shelf_terms(frequency, gain_db, sample_rate);
+        Self::normalized(
+            [
+                a * ((a + 1.0) + (a - 1.0) * cos + beta),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                a * ((a + 1.0) + (a - 1.0) * cos - beta),
+            ],
+            [
+                (a + 1.0) - (a - 1.0) * cos + beta,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                (a + 1.0) - (a - 1.0) * cos - beta,
+            ],
+        )
+    }
+
+    fn shelf_terms(frequency: f32, gain_db: f32, sample_rate: f32) -> (f32, f32, f32) {
+        let a = 10f32.powf(gain_db / 40.0);
+        let w0 = 2.0 * PI * frequency / sample_rate;
+        // S = 1 时 alpha = sin(w0) / √2，2·√A·alpha = √(2A)·sin(w0)
+        (a, w0.cos(), (2.0 * a).sqrt() * w0.sin())
+    }
+}
+
+/// 单个声道的滤波器状态（直接 II 型转置）
+#[derive(Clone, Copy, Default, Debug)]
+pub struct BiquadState {
+    z1: f32,
+    z2: f32,
+}
+
+impl BiquadState {
+    pub fn process(&mut self, filter: &Biquad, input: f32) -> f32 {
+        let output = filter.b0 * input + self.z1;
+        self.z1 = filter.b1 * input - filter.a1 * output + self.z2;
+        self.z2 = filter.b2 * input - filter.a2 * output;
+        output
+    }
+}
+
+/// 内置音效及其参数（保存在配置中）
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EffectKind {
+    /// 压缩器
+    Compressor {
+        threshold_db: f32,
+        ratio: f32,
+        attack_ms: f32,
+        release_ms: f32,
+        makeup_db: f32,
+    },
+    /// 限幅器
+    Limiter { ceiling_db: f32, release_ms: f32 },
+    /// 立体声宽度（0 为单声道，1 为原样，2 为最宽）
+    StereoWidth { width: f32 },
+    /// 混为单声道
+    MonoDownmix,
+    /// 交换左右声道
+    ChannelSwap,
+    /// 左右平衡（-1 为全左，1 为全右）
+    Balance { balance: f32 },
+    /// 低音 / 高音架式滤波
+    ToneShelf { bass_db: f32, treble_db: f32 },
+}
+
+/// 音效的一个可调参数
+#[derive(Clone, Copy, Debug)]
+pub struct EffectParam {
+    pub label: &'static str,
+    pub value: f32,
+    pub step: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl EffectKind {
+    /// 所有内置音效（默认参数）
+    pub fn all() -> Vec<EffectKind> {
+        vec![
+            EffectKind::Compressor {
+                threshold_db: -18.0,
+                ratio: 3.0,
+                attack_ms: 10.0,
+                release_ms: 150.0,
+                makeup_db: 3.0,
+            },
+            EffectKind::Limiter {
+                ceiling_db: -1.0,
+                release_ms: 100.0,
+            },
+            EffectKind::StereoWidth { width: 1.5 },
+            EffectKind::MonoDownmix,
+            EffectKind::ChannelSwap,
+            EffectKind::Balance { balance: 0.0 },
+            EffectKind::ToneShelf {
+                bass_db: 0.0,
+                treble_db: 0.0,
+            },
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EffectKind::Compressor { .. } => "压缩器",
+            EffectKind::Limiter { .. } => "限幅器",
+            EffectKind::StereoWidth { .. } => "立体声扩展",
+            EffectKind::MonoDownmix => "单声道",
+            EffectKind::ChannelSwap => "交换左右声道",
+            EffectKind::Balance { .. } => "声道平衡",
+            EffectKind::ToneShelf { .. } => "低音 / 高音",
+        }
+    }
+
+    /// 可调参数（顺序与 set_param 的下标对应）
+    pub fn params(&self) -> Vec<EffectParam> {
+        let param = |label, value, step, min, max| EffectParam {
+            label,
+            value,
+            step,
+            min,
+            max,
+        };
+        match *self {
+            EffectKind::Compressor {
+                threshold_db,
+                ratio,
+                attack_ms,
+                release_ms,
+                makeup_db,
+            } => vec![
+                param("阈值 dB", threshold_db, 1.0, -60.0, 0.0),
+                param("比率", ratio, 0.5, 1.0, 20.0),
+                param("启动 ms", attack_ms, 1.0, 0.1, 200.0),
+                param("释放 ms", release_ms, 10.0, 10.0, 2000.0),
+                param("补偿 dB", makeup_db, 1.0, 0.0, 24.0),
+            ],
+            EffectKind::Limiter {
+                ceiling_db,
+                release_ms,
+            } => vec![
+                param("上限 dB", ceiling_db, 0.5, -24.0, 0.0),
+                param("释放 ms", release_ms, 10.0, 10.0, 2000.0),
+            ],
+            EffectKind::StereoWidth { width } => vec![param("宽度", width, 0.1, 0.0, 2.0)],
+            EffectKind::MonoDownmix | EffectKind::ChannelSwap => Vec::new(),
+            EffectKind::Balance { balance } => vec![param("平衡", balance, 0.1, -1.0, 1.0)],
+            EffectKind::ToneShelf { bass_db, treble_db } => vec![
+                param("低音 dB", bass_db, 1.0, -12.0, 12.0),
+                param("高音 dB", treble_db, 1.0, -12.0, 12.0),
+            ],
+        }
+    }
+
+    /// 修改第 index 个参数（超出范围时截断）
+    pub fn set_param(&mut self, index: usize, value: f32) {
+        let Some(param) = self.params().get(index).copied() else {
+            return;
+        };
+        // 消除按步长累加产生的浮点误差
+        let value = ((value.clamp(param.min, param.max) * 100.0).round()) / 100.0;
+        match self {
+            EffectKind::Compressor {
+                threshold_db,
+                ratio,
+                attack_ms,
+                release_ms,
+                makeup_db,
+            } => *[threshold_db, ratio, attack_ms, release_ms, makeup_db][index] = value,
+            EffectKind::Limiter {
+                ceiling_db,
+                release_ms,
+            } => *[ceiling_db, release_ms][index] = value,
+            EffectKind::StereoWidth { width } => *width = value,
+            EffectKind::MonoDownmix | EffectKind::ChannelSwap => {}
+            EffectKind::Balance { balance } => *balance = value,
+            EffectKind::ToneShelf { bass_db, treble_db } => *[bass_db, treble_db][index] = value,
+        }
+    }
+
+    /// 创建对应的效果器
+    pub fn build(&self) -> Box<dyn Effect> {
+        match *self {
+            EffectKind::Compressor {
+                threshold_db,
+                ratio,
+                attack_ms,
+                release_ms,
+                makeup_db,
+            } => Box::new(Compressor::new(
+                threshold_db,
+                ratio,
+                attack_ms,
+                release_ms,
+                makeup_db,
+            )),
+            EffectKind::Limiter {
+                ceiling_db,
+                release_ms,
+            } => Box::new(Limiter::new(ceiling_db, release_ms)),
+            EffectKind::StereoWidth { width } => Box::new(StereoWidth::new(width)),
+            EffectKind::MonoDownmix => Box::new(MonoDownmix),
+            EffectKind::ChannelSwap => Box::new(ChannelSwap),
+            EffectKind::Balance { balance } => Box::new(Balance::new(balance)),
+            EffectKind::ToneShelf { bass_db, treble_db } => {
+                Box::new(ToneShelf::new(bass_db, treble_db))
+            }
+        }
+    }
+}
+
+/// 效果链中的一项
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct EffectSlot {
+    pub effect: EffectKind,
+    pub enabled: bool,
+}
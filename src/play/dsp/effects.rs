@@ -0,0 +1,613 @@
+use super::{Biquad, BiquadState, Effect, EffectKind};
+
+/// 低音架式滤波的转折频率
+const BASS_FREQUENCY: f32 = 200.0;
+/// 高音架式滤波的转折频率
+const TREBLE_FREQUENCY: f32 = 4000.0;
+
+fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// 时间常数对应的单极点平滑系数
+fn smoothing(ms: f32, sample_rate: u32) -> f32 {
+    if ms <= 0.0 {
+        return 0.0;
+    }
+    (-1.0 / (ms / 1000.0 * sample_rate as f32)).exp()
+}
+
+/// 各声道取峰值的包络跟随器（声道联动，避免声像漂移）
+#[derive(Default)]
+struct Envelope {
+    attack: f32,
+    release: f32,
+    level: f32,
+    sample_rate: u32,
+}
+
+impl Envelope {
+    fn configure(&mut self, attack_ms: f32, release_ms: f32, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.attack = smoothing(attack_ms, sample_rate);
+        self.release = smoothing(release_ms, sample_rate);
+    }
+
+    /// 按已配置的采样率修改时间常数，保留当前电平
+    fn set_times(&mut self, attack_ms: f32, release_ms: f32) {
+        self.configure(attack_ms, release_ms, self.sample_rate);
+    }
+
+    fn follow(&mut self, frame: &[f32]) -> f32 {
+        let peak = frame
+            .iter()
+            .fold(0f32, |peak, sample| peak.max(sample.abs()));
+        let coefficient = if peak > self.level {
+            self.attack
+        } else {
+            self.release
+        };
+        self.level = peak + coefficient * (self.level - peak);
+        self.level
+    }
+}
+
+/// 压缩器：超过阈值的部分按比率压缩，再加补偿增益
+pub struct Compressor {
+    threshold_db: f32,
+    ratio: f32,
+    attack_ms: f32,
+    release_ms: f32,
+    makeup: f32,
+    envelope: Envelope,
+}
+
+impl Compressor {
+    pub fn new(
+        threshold_db: f32,
+        ratio: f32,
+        attack_ms: f32,
+        release_ms: f32,
+        makeup_db: f32,
+    ) -> Self {
+        Self {
+            threshold_db,
+            ratio: ratio.max(1.0),
+            attack_ms,
+            release_ms,
+            makeup: db_to_gain(makeup_db),
+            envelope: Envelope::default(),
+        }
+    }
+}
+
+impl Effect for Compressor {
+    fn configure(&mut self, _channels: usize, sample_rate: u32) {
+        self.envelope
+            .configure(self.attack_ms, self.release_ms, sample_rate);
+    }
+
+    fn process(&mut self, frame: &mut [f32]) {
+        let level_db = 20.0 * self.envelope.follow(frame).max(1e-6).log10();
+        let over = level_db - self.threshold_db;
+        let reduction_db = if over > 0.0 {
+            over * (1.0 - 1.0 / self.ratio)
+        } else {
+            0.0
+        };
+        let gain = db_to_gain(-reduction_db) * self.makeup;
+        for sample in frame {
+            *sample *= gain;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.envelope.level = 0.0;
+    }
+
+    fn update(&mut self, kind: &EffectKind) {
+        if let EffectKind::Compressor {
+            threshold_db,
+            ratio,
+            attack_ms,
+            release_ms,
+            makeup_db,
+        } = *kind
+        {
+            self.threshold_db = threshold_db;
+            self.ratio = ratio.max(1.0);
+            self.attack_ms = attack_ms;
+            self.release_ms = release_ms;
+            self.makeup = db_to_gain(makeup_db);
+            self.envelope.set_times(attack_ms, release_ms);
+        }
+    }
+}
+
+/// 限幅器：瞬时压下超过上限的峰值，缓慢释放，最后硬削波兜底
+pub struct Limiter {
+    ceiling: f32,
+    release_ms: f32,
+    envelope: Envelope,
+}
+
+impl Limiter {
+    pub fn new(ceiling_db: f32, release_ms: f32) -> Self {
+        Self {
+            ceiling: db_to_gain(ceiling_db),
+            release_ms,
+            envelope: Envelope::default(),
+        }
+    }
+}
+
+impl Effect for Limiter {
+    fn configure(&mut self, _channels: usize, sample_rate: u32) {
+        self.envelope.configure(0.0, self.release_ms, sample_rate);
+    }
+
+    fn process(&mut self, frame: &mut [f32]) {
+        let level = self.envelope.follow(frame);
+        let gain = if level > self.ceiling {
+            self.ceiling / level
+        } else {
+            1.0
+        };
+        for sample in frame {
+            *sample = (*sample * gain).clamp(-self.ceiling, self.ceiling);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.envelope.level = 0.0;
+    }
+
+    fn update(&mut self, kind: &EffectKind) {
+        if let EffectKind::Limiter {
+            ceiling_db,
+            release_ms,
+        } = *kind
+        {
+            self.ceiling = db_to_gain(ceiling_db);
+            self.release_ms = release_ms;
+            self.envelope.set_times(0.0, release_ms);
+        }
+    }
+}
+
+/// 立体声扩展：按宽度缩放侧声道（M/S 处理），仅对双声道生效
+pub struct StereoWidth {
+    width: f32,
+}
+
+impl StereoWidth {
+    pub fn new(width: f32) -> Self {
+        Self { width }
+    }
+}
+
+impl Effect for StereoWidth {
+    fn configure(&mut self, _channels: usize, _sample_rate: u32) {}
+
+    fn process(&mut self, frame: &mut [f32]) {
+        if let [left, right] = frame {
+            let mid = (*left + *right) / 2.0;
+            let side = (*left - *right) / 2.0 * self.width;
+            *left = mid + side;
+            *right = mid - side;
+        }
+    }
+
+    fn update(&mut self, kind: &EffectKind) {
+        if let EffectKind::StereoWidth { width } = *kind {
+            self.width = width;
+        }
+    }
+}
+
+/// 混为单声道：所有声道取平均
+pub struct MonoDownmix;
+
+impl Effect for MonoDownmix {
+    fn configure(&mut self, _channels: usize, _sample_rate: u32) {}
+
+    fn process(&mut self, frame: &mut [f32]) {
+        let mean = frame.iter().sum::<f32>() / frame.len() as f32;
+        frame.fill(mean);
+    }
+}
+
+/// 交换左右声道
+pub struct ChannelSwap;
+
+impl Effect for ChannelSwap {
+    fn configure(&mut self, _channels: usize, _sample_rate: u32) {}
+
+    fn process(&mut self, frame: &mut [f32]) {
+        if frame.len() >= 2 {
+            frame.swap(0, 1);
+        }
+    }
+}
+
+/// 左右平衡：衰减另一侧声道，居中时不改变音量
+pub struct Balance {
+    left: f32,
+    right: f32,
+}
+
+impl Balance {
+    pub fn new(balance: f32) -> Self {
+        let balance = balance.clamp(-1.0, 1.0);
+        Self {
+            left: (1.0 - balance).min(1.0),
+            right: (1.0 + balance).min(1.0),
+        }
+    }
+}
+
+impl Effect for Balance {
+    fn configure(&mut self, _channels: usize, _sample_rate: u32) {}
+
+    fn process(&mut self, frame: &mut [f32]) {
+        if let [left, right] = frame {
+            *left *= self.left;
+            *right *= self.right;
+        }
+    }
+
+    fn update(&mut self, kind: &EffectKind) {
+        if let EffectKind::Balance { balance } = *kind {
+            *self = Balance::new(balance);
+        }
+    }
+}
+
+/// 低音 / 高音：低架和高架滤波各一个
+pub struct ToneShelf {
+    bass_db: f32,
+    treble_db: f32,
+    sample_rate: f32,
+    /// 低架、高架滤波器，增益为 0（或高音超出可用频率）时跳过
+    filters: [Option<Biquad>; 2],
+    /// 按 channel * 2 + filter 存放
+    states: Vec<BiquadState>,
+}
+
+impl ToneShelf {
+    pub fn new(bass_db: f32, treble_db: f32) -> Self {
+        Self {
+            bass_db,
+            treble_db,
+            sample_rate: 0.0,
+            filters: [None; 2],
+            states: Vec::new(),
+        }
+    }
+
+    /// 按当前增益和采样率计算滤波器系数，不清空正在使用的滤波器状态
+    fn update_filters(&mut self) {
+        let bass = (self.bass_db != 0.0)
+            .then(|| Biquad::low_shelf(BASS_FREQUENCY, self.bass_db, self.sample_rate));
+        let treble = (self.treble_db != 0.0 && TREBLE_FREQUENCY < self.sample_rate * 0.45)
+            .then(|| Biquad::high_shelf(TREBLE_FREQUENCY, self.treble_db, self.sample_rate));
+        for (index, filter) in [bass, treble].into_iter().enumerate() {
+            // 重新启用的滤波器从静止状态开始
+            if self.filters[index].is_none() {
+                for state in self.states.iter_mut().skip(index).step_by(2) {
+                    *state = BiquadState::default();
+                }
+            }
+            self.filters[index] = filter;
+        }
+    }
+}
+
+impl Effect for ToneShelf {
+    fn configure(&mut self, channels: usize, sample_rate: u32) {
+        self.sample_rate = sample_rate as f32;
+        self.states = vec![BiquadState::default(); channels * 2];
+        self.update_filters();
+    }
+
+    fn process(&mut self, frame: &mut [f32]) {
+        for (channel, sample) in frame.iter_mut().enumerate() {
+            for (index, filter) in self.filters.iter().enumerate() {
+                if let (Some(filter), Some(state)) =
+                    (filter, self.states.get_mut(channel * 2 + index))
+                {
+                    *sample = state.process(filter, *sample);
+                }
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.states.fill(BiquadState::default());
+    }
+
+    fn update(&mut self, kind: &EffectKind) {
+        if let EffectKind::ToneShelf { bass_db, treble_db } = *kind {
+            self.bass_db = bass_db;
+            self.treble_db = treble_db;
+            if self.sample_rate > 0.0 {
+                self.update_filters();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{DspChain, DspSource, process_interleaved};
+    use super::*;
+    use crate::play::test_source::Spans;
+    use rodio::Source;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// 正弦波，交错写入所有声道
+    fn sine(frequency: f32, amplitude: f32, frames: usize, channels: usize) -> Vec<f32> {
+        (0..frames)
+            .flat_map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                let sample = amplitude * (2.0 * std::f32::consts::PI * frequency * t).sin();
+                std::iter::repeat_n(sample, channels)
+            })
+            .collect()
+    }
+
+    fn run(effect: &mut dyn Effect, samples: &mut [f32], channels: usize) {
+        effect.configure(channels, SAMPLE_RATE);
+        process_interleaved(effect, samples, channels);
+    }
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples
+            .iter()
+            .fold(0f32, |peak, sample| peak.max(sample.abs()))
+    }
+
+    #[test]
+    fn channel_swap_swaps_left_and_right() {
+        let mut samples = vec![0.1, 0.2, 0.3, 0.4, 0.5];
+        run(&mut ChannelSwap, &mut samples, 2);
+        assert_eq!(samples, vec![0.2, 0.1, 0.4, 0.3, 0.5]);
+    }
+
+    #[test]
+    fn mono_downmix_averages_channels() {
+        let mut samples = vec![1.0, 0.0, 0.2, 0.6];
+        run(&mut MonoDownmix, &mut samples, 2);
+        assert_eq!(samples, vec![0.5, 0.5, 0.4, 0.4]);
+
+        let mut samples = vec![0.3, 0.6, 0.9];
+        run(&mut MonoDownmix, &mut samples, 3);
+        for sample in samples {
+            assert!((sample - 0.6).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn balance_at_extremes_silences_other_side() {
+        let mut samples = vec![0.5, 0.5, -0.5, -0.5];
+        run(&mut Balance::new(-1.0), &mut samples, 2);
+        assert_eq!(samples, vec![0.5, 0.0, -0.5, 0.0]);
+
+        let mut samples = vec![0.5, 0.5, -0.5, -0.5];
+        run(&mut Balance::new(1.0), &mut samples, 2);
+        assert_eq!(samples, vec![0.0, 0.5, 0.0, -0.5]);
+
+        let mut samples = vec![0.5, 0.5];
+        run(&mut Balance::new(0.0), &mut samples, 2);
+        assert_eq!(samples, vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn limiter_never_exceeds_ceiling() {
+        let ceiling = db_to_gain(-3.0);
+        let mut samples = sine(440.0, 2.0, 4096, 2);
+        samples.extend(sine(60.0, 1.5, 4096, 2));
+        run(&mut Limiter::new(-3.0, 50.0), &mut samples, 2);
+        assert!(peak(&samples) <= ceiling + 1e-6);
+        assert!(peak(&samples) > ceiling * 0.9);
+    }
+
+    #[test]
+    fn compressor_reduces_gain_above_threshold() {
+        let mut quiet = sine(440.0, 0.05, 8192, 2);
+        let mut compressor = Compressor::new(-12.0, 4.0, 1.0, 50.0, 0.0);
+        run(&mut compressor, &mut quiet, 2);
+        assert!((peak(&quiet) - 0.05).abs() < 1e-3);
+
+        // 0 dBFS 的输入超出阈值 12 dB，4:1 压缩后约为 -9 dBFS
+        let mut loud = sine(440.0, 1.0, 8192, 2);
+        let mut compressor = Compressor::new(-12.0, 4.0, 1.0, 50.0, 0.0);
+        run(&mut compressor, &mut loud, 2);
+        let tail = peak(&loud[loud.len() / 2..]);
+        assert!(tail < db_to_gain(-8.0));
+        assert!(tail > db_to_gain(-10.0));
+    }
+
+    #[test]
+    fn tone_shelf_at_zero_db_passes_through() {
+        let input = sine(1000.0, 0.8, 1024, 2);
+        let mut samples = input.clone();
+        run(&mut ToneShelf::new(0.0, 0.0), &mut samples, 2);
+        assert_eq!(samples, input);
+    }
+
+    #[test]
+    fn stereo_width_collapses_and_widens() {
+        let mut samples = vec![0.75, 0.25, -0.5, 0.5];
+        run(&mut StereoWidth::new(0.0), &mut samples, 2);
+        assert_eq!(samples, vec![0.5, 0.5, 0.0, 0.0]);
+
+        // 宽度 2 时侧声道加倍，中间声道不变
+        let mut samples = vec![0.75, 0.25, -0.5, 0.5];
+        run(&mut StereoWidth::new(2.0), &mut samples, 2);
+        assert_eq!(samples, vec![1.0, 0.0, -1.0, 1.0]);
+
+        let mut samples = vec![0.75, 0.25];
+        run(&mut StereoWidth::new(1.0), &mut samples, 2);
+        assert_eq!(samples, vec![0.75, 0.25]);
+    }
+
+    /// 经过音调滤波后的稳态峰值（跳过前半段的瞬态）
+    fn shelf_peak(bass_db: f32, treble_db: f32, frequency: f32) -> f32 {
+        let mut samples = sine(frequency, 0.25, 16384, 2);
+        run(&mut ToneShelf::new(bass_db, treble_db), &mut samples, 2);
+        peak(&samples[samples.len() / 2..])
+    }
+
+    #[test]
+    fn tone_shelf_boosts_and_cuts_its_band() {
+        let (low, high) = (50.0, 15000.0);
+        for gain_db in [6.0, -6.0] {
+            let expected = 0.25 * db_to_gain(gain_db);
+            let bass = shelf_peak(gain_db, 0.0, low);
+            assert!(
+                (bass - expected).abs() < expected * 0.05,
+                "低音 {}: {}",
+                gain_db,
+                bass
+            );
+            let treble = shelf_peak(0.0, gain_db, high);
+            assert!(
+                (treble - expected).abs() < expected * 0.05,
+                "高音 {}: {}",
+                gain_db,
+                treble
+            );
+
+            // 另一端基本不受影响
+            assert!((shelf_peak(gain_db, 0.0, high) - 0.25).abs() < 0.25 * 0.05);
+            assert!((shelf_peak(0.0, gain_db, low) - 0.25).abs() < 0.25 * 0.05);
+        }
+    }
+
+    #[test]
+    fn chain_update_keeps_effect_state() {
+        let compressor = EffectKind::Compressor {
+            threshold_db: -12.0,
+            ratio: 4.0,
+            attack_ms: 1.0,
+            release_ms: 500.0,
+            makeup_db: 0.0,
+        };
+        let tone = EffectKind::ToneShelf {
+            bass_db: 6.0,
+            treble_db: 0.0,
+        };
+        let build = || {
+            let chain = DspChain::default();
+            chain.set(vec![compressor.build(), tone.build()]);
+            chain
+        };
+        let input = sine(80.0, 1.0, 4096, 2);
+        let (updated, rebuilt, untouched) = (build(), build(), build());
+        let mut outputs = [input.clone(), input.clone(), input.clone()];
+        for (chain, output) in [&updated, &rebuilt, &untouched].iter().zip(&mut outputs) {
+            let (first, _) = output.split_at_mut(input.len() / 2);
+            chain.process(first, 2, SAMPLE_RATE);
+        }
+
+        // 参数不变的更新与不更新完全一致，重建则丢失包络和滤波器状态
+        updated.update([&compressor, &tone]);
+        rebuilt.set(vec![compressor.build(), tone.build()]);
+        for (chain, output) in [&updated, &rebuilt, &untouched].iter().zip(&mut outputs) {
+            let (_, second) = output.split_at_mut(input.len() / 2);
+            chain.process(second, 2, SAMPLE_RATE);
+        }
+        assert_eq!(outputs[0], outputs[2]);
+        assert_ne!(outputs[1], outputs[2]);
+
+        // 修改参数后按新参数处理
+        let mut before = input.clone();
+        let mut after = input.clone();
+        untouched.process(&mut before, 2, SAMPLE_RATE);
+        untouched.update([
+            &compressor,
+            &EffectKind::ToneShelf {
+                bass_db: -6.0,
+                treble_db: 0.0,
+            },
+        ]);
+        untouched.process(&mut after, 2, SAMPLE_RATE);
+        assert!(peak(&after[after.len() / 2..]) < peak(&before[before.len() / 2..]) * 0.7);
+    }
+
+    #[test]
+    fn chain_applies_effects_in_order() {
+        let chain = DspChain::default();
+        assert!(chain.is_empty());
+        chain.push(Box::new(ChannelSwap));
+        chain.push(Box::new(Balance::new(1.0)));
+        assert_eq!(chain.len(), 2);
+
+        // 先交换再静音左声道：原来的左声道被静音
+        let mut samples = vec![0.25, 0.75];
+        chain.process(&mut samples, 2, SAMPLE_RATE);
+        assert_eq!(samples, vec![0.0, 0.25]);
+
+        // 先静音左声道再交换：原来的右声道被静音
+        chain.move_effect(1, 0);
+        let mut samples = vec![0.25, 0.75];
+        chain.process(&mut samples, 2, SAMPLE_RATE);
+        assert_eq!(samples, vec![0.75, 0.0]);
+
+        // 越界的移动和删除不改变效果链
+        chain.move_effect(0, 5);
+        assert!(chain.remove(5).is_none());
+        assert_eq!(chain.len(), 2);
+
+        assert!(chain.remove(0).is_some());
+        let mut samples = vec![0.25, 0.75];
+        chain.process(&mut samples, 2, SAMPLE_RATE);
+        assert_eq!(samples, vec![0.75, 0.25]);
+
+        chain.remove(0);
+        assert!(chain.is_empty());
+        let mut samples = vec![0.25, 0.75];
+        chain.process(&mut samples, 2, SAMPLE_RATE);
+        assert_eq!(samples, vec![0.25, 0.75]);
+    }
+
+    #[test]
+    fn source_keeps_format_changes_on_span_boundaries() {
+        // 单声道 300 个采样之后切换为 48 kHz 立体声，边界不在处理块的整数倍上
+        let mono: Vec<f32> = (0..300).map(|i| i as f32).collect();
+        let stereo: Vec<f32> = (0..400)
+            .map(|i| if i % 2 == 0 { 1.0 } else { -1.0 })
+            .collect();
+        let input = Spans::new(vec![(1, SAMPLE_RATE, mono.clone()), (2, 48000, stereo)]);
+        let chain = DspChain::default();
+        chain.push(Box::new(ChannelSwap));
+        let mut source = DspSource::new(input, chain);
+
+        let mut output = Vec::new();
+        let mut formats = Vec::new();
+        loop {
+            let format = (source.channels(), source.sample_rate());
+            let span = source.current_span_len();
+            let Some(sample) = source.next() else {
+                break;
+            };
+            if output.is_empty() || output.len() == 300 {
+                // span 开始处报告的长度正好到下一次格式变化
+                assert_eq!(span, Some(if output.is_empty() { 300 } else { 400 }));
+            }
+            formats.push(format);
+            output.push(sample);
+        }
+
+        assert_eq!(output.len(), 700);
+        assert!(formats[..300].iter().all(|&f| f == (1, SAMPLE_RATE)));
+        assert!(formats[300..].iter().all(|&f| f == (2, 48000)));
+        // 单声道部分原样输出，立体声部分左右交换
+        assert_eq!(output[..300], mono[..]);
+        for frame in output[300..].chunks(2) {
+            assert_eq!(frame, [-1.0, 1.0]);
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use rodio::{ChannelCount, SampleRate, Source, source::SeekError};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::play::dsp::{Biquad, BiquadState};
+
+/// 十段均衡器的中心频率（Hz），相邻频段相差一个倍频程
+pub const EQ_BANDS: [f32; 10] = [
+    31.0, 62.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0,
+];
+/// 每个频段和前级增益的调节范围（dB）
+pub const EQ_GAIN_RANGE: (f32, f32) = (-12.0, 12.0);
+/// 一个倍频程带宽对应的 Q 值
+const BAND_Q: f32 = 1.41;
+/// 每隔多少帧检查一次设置是否变化
+const UPDATE_FRAMES: usize = 512;
+
+/// 均衡器设置
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct EqSettings {
+    pub enabled: bool,
+    /// 前级增益（dB），提升频段时可降低以避免削波
+    pub preamp: f32,
+    /// 各频段增益（dB），与 EQ_BANDS 一一对应
+    pub gains: [f32; EQ_BANDS.len()],
+}
+
+impl Default for EqSettings {
+    fn default() -> Self {
+        EqSettings {
+            enabled: false,
+            preamp: 0.0,
+            gains: [0.0; EQ_BANDS.len()],
+        }
+    }
+}
+
+impl EqSettings {
+    /// 套用预设（保持开关状态）
+    pub fn apply_preset(&mut self, preset: &EqPreset) {
+        self.preamp = preset.preamp;
+        self.gains = preset.gains;
+    }
+
+    /// 是否与预设的增益一致
+    pub fn matches(&self, preset: &EqPreset) -> bool {
+        self.preamp == preset.preamp && self.gains == preset.gains
+    }
+}
+
+/// 均衡器预设
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct EqPreset {
+    pub name: String,
+    pub preamp: f32,
+    pub gains: [f32; EQ_BANDS.len()],
+}
+
+impl EqPreset {
+    /// 内置预设
+    pub fn builtin() -> Vec<EqPreset> {
+        let preset = |name: &str, preamp: f32, gains: [f32; EQ_BANDS.len()]| EqPreset {
+            name: name.to_string(),
+            preamp,
+            gains,
+        };
+        vec![
+            preset("平直", 0.0, [0.0; EQ_BANDS.len()]),
+            preset(
+                "摇滚",
+                -4.0,
+                [5.0, 4.0, 3.0, 1.0, -1.0, -1.0, 1.0, 3.0, 4.0, 5.0],
+            ),
+            preset(
+                "人声",
+                -3.0,
+                [-2.0, -2.0, -1.0, 1.0, 3.0, 4.0, 3.0, 1.0, 0.0, -1.0],
+            ),
+            preset(
+                "低音增强",
+                -6.0,
+                [6.0, 5.0, 4.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            ),
+        ]
+    }
+}
+
+/// 播放线程与 `Player` 共享的均衡器设置，修改后实时生效
+#[derive(Clone, Debug)]
+pub struct EqControl(Arc<EqShared>);
+
+#[derive(Debug)]
+struct EqShared {
+    enabled: AtomicBool,
+    preamp: AtomicU32,
+    gains: [AtomicU32; EQ_BANDS.len()],
+    /// 每次修改加一，播放线程据此判断是否需要重新计算系数
+    version: AtomicU64,
+}
+
+impl Default for EqControl {
+    fn default() -> Self {
+        Self(Arc::new(EqShared {
+            enabled: AtomicBool::new(false),
+            preamp: AtomicU32::new(0f32.to_bits()),
+            gains: std::array::from_fn(|_| AtomicU32::new(0f32.to_bits())),
+            version: AtomicU64::new(0),
+        }))
+    }
+}
+
+impl EqControl {
+    pub fn set(&self, settings: &EqSettings) {
+        let clamp = |gain: f32| gain.clamp(EQ_GAIN_RANGE.0, EQ_GAIN_RANGE.1);
+        self.0.enabled.store(settings.enabled, Ordering::Relaxed);
+        self.0
+            .preamp
+            .store(clamp(settings.preamp).to_bits(), Ordering::Relaxed);
+        for (shared, gain) in self.0.gains.iter().zip(settings.gains) {
+            shared.store(clamp(gain).to_bits(), Ordering::Relaxed);
+        }
+        self.0.version.fetch_add(1, Ordering::Release);
+    }
+
+    fn version(&self) -> u64 {
+        self.0.version.load(Ordering::Acquire)
+    }
+
+    fn enabled(&self) -> bool {
+        self.0.enabled.load(Ordering::Relaxed)
+    }
+
+    fn preamp(&self) -> f32 {
+        f32::from_bits(self.0.preamp.load(Ordering::Relaxed))
+    }
+
+    fn gain(&self, band: usize) -> f32 {
+        f32::from_bits(self.0.gains[band].load(Ordering::Relaxed))
+    }
+}
+
+/// 十段图示均衡器：每个频段一个峰值滤波器，串联后乘以前级增益
+pub struct Equalizer<I> {
+    input: I,
+    control: EqControl,
+    channels: usize,
+    sample_rate: SampleRate,
+    /// 已应用的设置版本
+    version: Option<u64>,
+    enabled: bool,
+    preamp: f32,
+    /// 参与计算的频段（增益为 0 或超过奈奎斯特频率的频段被跳过）
+    filters: Vec<(usize, Biquad)>,
+    /// 各声道各频段的状态，按 channel * EQ_BANDS.len() + band 存放
+    states: Vec<BiquadState>,
+    /// 当前声道
+    channel: usize,
+    /// 距离下次检查设置的采样数
+    until_update: usize,
+}
+
+impl<I: Source> Equalizer<I> {
+    pub fn new(input: I, control: EqControl) -> Self {
+        let channels = input.channels().max(1) as usize;
+        let sample_rate = input.sample_rate();
+        Self {
+            input,
+            control,
+            channels,
+            sample_rate,
+            version: None,
+            enabled: false,
+            preamp: 1.0,
+            filters: Vec::new(),
+            states: vec![BiquadState::default(); channels * EQ_BANDS.len()],
+            channel: 0,
+            until_update: 0,
+        }
+    }
+
+    /// 设置或采样率变化时重新计算滤波器系数
+    fn update(&mut self) {
+        let version = self.control.version();
+        let sample_rate = self.input.sample_rate();
+        if self.version == Some(version) && sample_rate == self.sample_rate {
+            return;
+        }
+        self.version = Some(version);
+        self.sample_rate = sample_rate;
+
+        self.enabled = self.control.enabled();
+        self.preamp = 10f32.powf(self.control.preamp() / 20.0);
+        let nyquist = sample_rate as f32 / 2.0;
+        self.filters = EQ_BANDS
+            .iter()
+            .enumerate()
+            .filter_map(|(band, &frequency)| {
+                let gain = self.control.gain(band);
+                (gain != 0.0 && frequency < nyquist * 0.9).then(|| {
+                    (
+                        band,
+                        Biquad::peaking(frequency, gain, BAND_Q, sample_rate as f32),
+                    )
+                })
+            })
+            .collect();
+        // 未参与计算的频段清空状态，重新启用时从静止开始
+        for band in 0..EQ_BANDS.len() {
+            if !self.enabled || !self.filters.iter().any(|(active, _)| *active == band) {
+                for channel in 0..self.channels {
+                    self.states[channel * EQ_BANDS.len() + band] = BiquadState::default();
+                }
+            }
+        }
+    }
+}
+
+impl<I: Source> Iterator for Equalizer<I> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.channel == 0 {
+            if self.until_update == 0 {
+                self.update();
+                self.until_update = UPDATE_FRAMES;
+            }
+            self.until_update -= 1;
+        }
+
+        let sample = self.input.next()?;
+        let channel = self.channel;
+        self.channel = (self.channel + 1) % self.channels;
+        if !self.enabled {
+            return Some(sample);
+        }
+
+        let mut value = sample * self.preamp;
+        for (band, filter) in &self.filters {
+            value = self.states[channel * EQ_BANDS.len() + band].process(filter, value);
+        }
+        Some(value)
+    }
+}
+
+impl<I: Source> Source for Equalizer<I> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.states.fill(BiquadState::default());
+        self.channel = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+    use std::f32::consts::PI;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// 交错写入两个声道的正弦波
+    fn sine(frequency: f32, amplitude: f32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .flat_map(|i| {
+                let sample =
+                    amplitude * (2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32).sin();
+                [sample, sample]
+            })
+            .collect()
+    }
+
+    fn equalize(samples: Vec<f32>, settings: &EqSettings) -> Vec<f32> {
+        let control = EqControl::default();
+        control.set(settings);
+        Equalizer::new(SamplesBuffer::new(2, SAMPLE_RATE, samples), control).collect()
+    }
+
+    /// 稳态峰值（跳过前半段的瞬态）
+    fn steady_peak(samples: &[f32]) -> f32 {
+        samples[samples.len() / 2..]
+            .iter()
+            .fold(0f32, |peak, sample| peak.max(sample.abs()))
+    }
+
+    #[test]
+    fn flat_preset_passes_samples_through() {
+        let input = sine(440.0, 0.5, 4096);
+        let mut settings = EqSettings {
+            enabled: true,
+            ..EqSettings::default()
+        };
+        let flat = EqPreset::builtin()
+            .into_iter()
+            .find(|preset| preset.name == "平直")
+            .unwrap();
+        settings.apply_preset(&flat);
+        assert_eq!(equalize(input.clone(), &settings), input);
+
+        // 关闭时即使有增益也原样输出
+        let mut settings = EqSettings::default();
+        settings.gains[5] = 6.0;
+        assert_eq!(equalize(input.clone(), &settings), input);
+    }
+
+    #[test]
+    fn band_gain_changes_sine_at_its_frequency() {
+        let band = 5;
+        let frequency = EQ_BANDS[band];
+        for gain_db in [6.0, -6.0] {
+            let mut settings = EqSettings {
+                enabled: true,
+                ..EqSettings::default()
+            };
+            settings.gains[band] = gain_db;
+
+            let expected = 0.25 * 10f32.powf(gain_db / 20.0);
+            let at_band = steady_peak(&equalize(sine(frequency, 0.25, 16384), &settings));
+            assert!(
+                (at_band - expected).abs() < expected * 0.03,
+                "{} dB: {}",
+                gain_db,
+                at_band
+            );
+            // 相隔四个倍频程的频率基本不受影响
+            let far = steady_peak(&equalize(sine(EQ_BANDS[1], 0.25, 16384), &settings));
+            assert!((far - 0.25).abs() < 0.25 * 0.03, "{} dB: {}", gain_db, far);
+        }
+    }
+}
@@ -0,0 +1,850 @@
+// MPRIS2 D-Bus 服务（仅 Linux），让媒体键、桌面媒体组件和 playerctl 可以控制 Zotu
+//
+// D-Bus 方法在 zbus 的后台线程中执行，只把命令放入通道；UI 线程定时取出命令作用于
+// `Player`，再把播放状态快照推送回来并发出 PropertiesChanged 信号。
+
+use gpui::*;
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex, MutexGuard,
+        mpsc::{self, Receiver, Sender},
+    },
+    time::Duration,
+};
+use tracing::warn;
+use url::Url;
+use uuid::Uuid;
+use zbus::{
+    blocking::{Connection, connection::Builder},
+    fdo,
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value},
+};
+
+use crate::{
+    config::Config,
+    db::cover::{self, CoverSize},
+    play::player::{LoopMode, PLAYBACK_RATE_RANGE, PlayState, Player},
+};
+
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.zotu";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+/// UI 线程同步状态的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// 进度跳变超过该值（微秒）时认为发生了 seek
+const SEEK_TOLERANCE_US: i64 = 1_000_000;
+
+/// D-Bus 客户端发来的控制命令
+#[derive(Clone, Debug, PartialEq)]
+pub enum MprisCommand {
+    Raise,
+    Quit,
+    Play,
+    Pause,
+    PlayPause,
+    Stop,
+    Next,
+    Previous,
+    /// 相对偏移（微秒）
+    Seek(i64),
+    /// 绝对位置（曲目 ID, 微秒）
+    SetPosition(Uuid, i64),
+    SetVolume(f64),
+    SetLoopMode(LoopMode),
+    SetRate(f64),
+}
+
+/// 当前曲目的元数据
+#[derive(Clone, Debug, PartialEq)]
+struct TrackMeta {
+    id: Uuid,
+    title: String,
+    artist: String,
+    album: String,
+    genre: Option<String>,
+    /// 时长（微秒）
+    length: i64,
+    art_url: Option<String>,
+}
+
+impl TrackMeta {
+    fn track_id(&self) -> OwnedObjectPath {
+        track_path(&self.id)
+    }
+}
+
+/// 封面 hash 对应的 artUrl，生成失败记为 None，不再重试
+type ArtUrls = HashMap<SharedString, Option<String>>;
+
+/// 生成封面缩略图并转换为 file:// URL（读写磁盘，在后台线程调用）
+fn resolve_art_url(hash: &str) -> Option<String> {
+    // 原图可能是桌面组件不支持的格式，统一使用 JPEG 缩略图
+    let path = cover::variant(hash, CoverSize::Large)
+        .inspect_err(|e| warn!("生成 MPRIS 封面失败: {}", e))
+        .ok()?;
+    Url::from_file_path(path).ok().map(|url| url.to_string())
+}
+
+/// 播放状态快照（由 UI 线程从 `Player` 生成）
+#[derive(Clone, Debug, PartialEq)]
+pub struct MprisState {
+    status: PlayState,
+    loop_mode: LoopMode,
+    volume: f64,
+    /// 播放速度
+    rate: f64,
+    /// 播放位置（微秒）
+    position: i64,
+    can_go_next: bool,
+    can_go_previous: bool,
+    track: Option<TrackMeta>,
+}
+
+impl MprisState {
+    /// 封面只从 `art` 中读取，还没生成的先不带 artUrl
+    fn from_player(player: &Player, art: &ArtUrls) -> Self {
+        let track = player.current_track().map(|track| TrackMeta {
+            id: track.id(),
+            title: track.title().to_string(),
+            artist: track.artist().to_string(),
+            album: track.album().to_string(),
+            genre: track.genre().map(|g| g.to_string()),
+            length: track.duration() as i64 * 1_000_000,
+            art_url: track
+                .cover()
+                .and_then(|hash| art.get(&hash).cloned().flatten()),
+        });
+        Self {
+            status: player.play_state(),
+            loop_mode: player.loop_mode(),
+            volume: player.volume() as f64,
+            rate: player.playback_rate() as f64,
+            position: player
+                .current_track()
+                .map(|_| player.elapsed().as_micros() as i64)
+                .unwrap_or(0),
+            can_go_next: player.has_playlist() || !player.queue().is_empty(),
+            can_go_previous: player.can_go_back(),
+            track,
+        }
+    }
+
+    fn playback_status(&self) -> &'static str {
+        match self.status {
+            PlayState::Play => "Playing",
+            PlayState::Paused => "Paused",
+            PlayState::Stopped => "Stopped",
+        }
+    }
+
+    /// LoopMode -> LoopStatus：单曲循环为 Track，其余为 Playlist
+    fn loop_status(&self) -> &'static str {
+        match self.loop_mode {
+            LoopMode::Single => "Track",
+            LoopMode::List | LoopMode::Random => "Playlist",
+        }
+    }
+
+    /// LoopMode -> Shuffle：只有随机播放为 true
+    fn shuffle(&self) -> bool {
+        self.loop_mode == LoopMode::Random
+    }
+
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        let mut values: Vec<(&str, Value)> = Vec::new();
+        match &self.track {
+            Some(track) => {
+                values.push(("mpris:trackid", Value::from(track.track_id())));
+                values.push(("mpris:length", Value::from(track.length)));
+                values.push(("xesam:title", Value::from(track.title.clone())));
+                values.push(("xesam:artist", Value::from(vec![track.artist.clone()])));
+                values.push(("xesam:album", Value::from(track.album.clone())));
+                if let Some(genre) = &track.genre {
+                    values.push(("xesam:genre", Value::from(vec![genre.clone()])));
+                }
+                if let Some(art_url) = &track.art_url {
+                    values.push(("mpris:artUrl", Value::from(art_url.clone())));
+                }
+            }
+            None => {
+                let no_track = ObjectPath::from_static_str_unchecked(NO_TRACK);
+                values.push(("mpris:trackid", Value::from(no_track)));
+            }
+        }
+        values
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.to_string(), OwnedValue::try_from(value).ok()?)))
+            .collect()
+    }
+}
+
+fn track_path(id: &Uuid) -> OwnedObjectPath {
+    // UUID 的十六进制形式只含合法的对象路径字符
+    OwnedObjectPath::from(ObjectPath::from_string_unchecked(format!(
+        "/org/zotu/track/{}",
+        id.simple()
+    )))
+}
+
+/// org.mpris.MediaPlayer2
+struct RootInterface {
+    commands: Sender<MprisCommand>,
+}
+
+#[zbus::interface(name = "org.mpris.MediaPlayer2")]
+impl RootInterface {
+    fn raise(&self) {
+        self.commands.send(MprisCommand::Raise).ok();
+    }
+
+    fn quit(&self) {
+        self.commands.send(MprisCommand::Quit).ok();
+    }
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> String {
+        "Zotu".to_string()
+    }
+
+    #[zbus(property)]
+    fn desktop_entry(&self) -> String {
+        "zotu".to_string()
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// org.mpris.MediaPlayer2.Player
+struct PlayerInterface {
+    commands: Sender<MprisCommand>,
+    state: Arc<Mutex<MprisState>>,
+}
+
+impl PlayerInterface {
+    fn send(&self, command: MprisCommand) {
+        self.commands.send(command).ok();
+    }
+
+    /// 先更新本地状态，使 zbus 在 setter 后自动发出的 PropertiesChanged 带上新值
+    fn set_loop_mode(&self, mode: LoopMode) {
+        self.state().loop_mode = mode;
+        self.send(MprisCommand::SetLoopMode(mode));
+    }
+
+    fn state(&self) -> MutexGuard<'_, MprisState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[zbus::interface(name = "org.mpris.MediaPlayer2.Player")]
+impl PlayerInterface {
+    fn next(&self) {
+        self.send(MprisCommand::Next);
+    }
+
+    fn previous(&self) {
+        self.send(MprisCommand::Previous);
+    }
+
+    fn pause(&self) {
+        self.send(MprisCommand::Pause);
+    }
+
+    fn play_pause(&self) {
+        self.send(MprisCommand::PlayPause);
+    }
+
+    fn stop(&self) {
+        self.send(MprisCommand::Stop);
+    }
+
+    fn play(&self) {
+        self.send(MprisCommand::Play);
+    }
+
+    fn seek(&self, offset: i64) {
+        self.send(MprisCommand::Seek(offset));
+    }
+
+    fn set_position(&self, track_id: ObjectPath<'_>, position: i64) {
+        // 曲目 ID 与当前曲目不一致时按规范忽略
+        if let Some(track) = &self.state().track
+            && track.track_id().as_ref() == track_id
+        {
+            self.send(MprisCommand::SetPosition(track.id, position));
+        }
+    }
+
+    fn open_uri(&self, _uri: String) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported("不支持打开 URI".to_string()))
+    }
+
+    #[zbus(property)]
+    fn playback_status(&self) -> String {
+        self.state().playback_status().to_string()
+    }
+
+    #[zbus(property)]
+    fn loop_status(&self) -> String {
+        self.state().loop_status().to_string()
+    }
+
+    /// 没有“不循环”模式，None 按列表循环处理；随机播放中设置 Playlist 时保持随机
+    #[zbus(property)]
+    fn set_loop_status(&mut self, value: String) {
+        let current = self.state().loop_mode;
+        let mode = match value.as_str() {
+            "Track" => LoopMode::Single,
+            _ if current == LoopMode::Random => LoopMode::Random,
+            _ => LoopMode::List,
+        };
+        self.set_loop_mode(mode);
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        self.state().rate
+    }
+
+    /// 按规范速度为 0 时暂停
+    #[zbus(property)]
+    fn set_rate(&mut self, value: f64) {
+        if value <= 0.0 {
+            self.send(MprisCommand::Pause);
+            return;
+        }
+        let value = value.clamp(self.minimum_rate(), self.maximum_rate());
+        self.state().rate = value;
+        self.send(MprisCommand::SetRate(value));
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn minimum_rate(&self) -> f64 {
+        PLAYBACK_RATE_RANGE.0 as f64
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn maximum_rate(&self) -> f64 {
+        PLAYBACK_RATE_RANGE.1 as f64
+    }
+
+    #[zbus(property)]
+    fn shuffle(&self) -> bool {
+        self.state().shuffle()
+    }
+
+    #[zbus(property)]
+    fn set_shuffle(&mut self, value: bool) {
+        let mode = if value {
+            LoopMode::Random
+        } else {
+            LoopMode::List
+        };
+        self.set_loop_mode(mode);
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        self.state().metadata()
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.state().volume
+    }
+
+    #[zbus(property)]
+    fn set_volume(&mut self, value: f64) {
+        let value = value.clamp(0.0, 1.0);
+        self.state().volume = value;
+        self.send(MprisCommand::SetVolume(value));
+    }
+
+    /// 位置按规范不发 PropertiesChanged，客户端自行轮询
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        self.state().position
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        self.state().can_go_next
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        self.state().can_go_previous
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        self.state().track.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        self.state().track.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        self.state().track.is_some()
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+/// MPRIS 服务端：持有 D-Bus 连接、共享状态和命令通道
+pub struct MprisServer {
+    connection: Connection,
+    state: Arc<Mutex<MprisState>>,
+    commands: Receiver<MprisCommand>,
+}
+
+impl MprisServer {
+    /// 在会话总线上注册服务
+    pub fn connect_session(initial: MprisState) -> zbus::Result<Self> {
+        Self::connect(Builder::session()?, initial)
+    }
+
+    /// 在给定连接上注册服务（可传入 `Builder::address` 连接私有总线）
+    pub fn connect(builder: Builder<'_>, initial: MprisState) -> zbus::Result<Self> {
+        let (sender, commands) = mpsc::channel();
+        let state = Arc::new(Mutex::new(initial));
+
+        let connection = builder
+            .name(BUS_NAME)?
+            .serve_at(
+                OBJECT_PATH,
+                RootInterface {
+                    commands: sender.clone(),
+                },
+            )?
+            .serve_at(
+                OBJECT_PATH,
+                PlayerInterface {
+                    commands: sender,
+                    state: state.clone(),
+                },
+            )?
+            .build()?;
+
+        Ok(Self {
+            connection,
+            state,
+            commands,
+        })
+    }
+
+    /// 取出所有待处理的命令
+    pub fn pending_commands(&self) -> impl Iterator<Item = MprisCommand> + '_ {
+        self.commands.try_iter()
+    }
+
+    /// 更新状态快照，并为变化的属性发出 PropertiesChanged / Seeked
+    pub fn update(&self, new: MprisState) -> zbus::Result<()> {
+        let old = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            std::mem::replace(&mut *state, new.clone())
+        };
+        if old == new {
+            return Ok(());
+        }
+
+        let mut changed: HashMap<&str, Value> = HashMap::new();
+        if old.status != new.status {
+            changed.insert("PlaybackStatus", Value::from(new.playback_status()));
+        }
+        if old.loop_status() != new.loop_status() {
+            changed.insert("LoopStatus", Value::from(new.loop_status()));
+        }
+        if old.shuffle() != new.shuffle() {
+            changed.insert("Shuffle", Value::from(new.shuffle()));
+        }
+        if old.volume != new.volume {
+            changed.insert("Volume", Value::from(new.volume));
+        }
+        if old.rate != new.rate {
+            changed.insert("Rate", Value::from(new.rate));
+        }
+        if old.can_go_next != new.can_go_next {
+            changed.insert("CanGoNext", Value::from(new.can_go_next));
+        }
+        if old.can_go_previous != new.can_go_previous {
+            changed.insert("CanGoPrevious", Value::from(new.can_go_previous));
+        }
+        if old.track != new.track {
+            let has_track = new.track.is_some();
+            changed.insert("Metadata", Value::from(new.metadata()));
+            changed.insert("CanPlay", Value::from(has_track));
+            changed.insert("CanPause", Value::from(has_track));
+            changed.insert("CanSeek", Value::from(has_track));
+        }
+
+        if !changed.is_empty() {
+            self.connection.emit_signal(
+                None::<()>,
+                OBJECT_PATH,
+                "org.freedesktop.DBus.Properties",
+                "PropertiesChanged",
+                &(PLAYER_INTERFACE, changed, Vec::<&str>::new()),
+            )?;
+        }
+
+        // 同一曲目内进度跳变（前进超过轮询间隔或后退）视为 seek
+        let expected = old.position
+            + if old.status == PlayState::Play {
+                (POLL_INTERVAL.as_micros() as f64 * old.rate) as i64
+            } else {
+                0
+            };
+        if old.track == new.track
+            && new.track.is_some()
+            && (new.position - expected).abs() > SEEK_TOLERANCE_US
+        {
+            self.connection.emit_signal(
+                None::<()>,
+                OBJECT_PATH,
+                PLAYER_INTERFACE,
+                "Seeked",
+                &(new.position,),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// 在 UI 线程执行命令
+fn apply_command(command: MprisCommand, cx: &mut App) {
+    match command {
+        MprisCommand::Raise => cx.activate(true),
+        MprisCommand::Quit => cx.quit(),
+        MprisCommand::SetVolume(volume) => {
+            let volume = cx.update_global::<Player, _>(|player, _cx| {
+                player.set_volume(volume as f32);
+                player.volume()
+            });
+            cx.global_mut::<Config>().play_info.volume = volume;
+        }
+        MprisCommand::SetRate(rate) => {
+            let rate = cx.update_global::<Player, _>(|player, _cx| {
+                player.set_playback_rate(rate as f32);
+                player.playback_rate()
+            });
+            cx.global_mut::<Config>().play_info.playback_rate = rate;
+        }
+        command => cx.update_global::<Player, _>(|player, _cx| match command {
+            MprisCommand::Play if player.is_paused() => player.toggle_play(),
+            MprisCommand::Pause if player.is_playing() => player.toggle_play(),
+            MprisCommand::PlayPause => player.toggle_play(),
+            MprisCommand::Stop => player.clear(),
+            MprisCommand::Next => player.next(),
+            MprisCommand::Previous => player.previous(),
+            MprisCommand::Seek(offset) => seek_relative(player, offset),
+            MprisCommand::SetPosition(id, position)
+                if player.current_track().map(|t| t.id()) == Some(id)
+                    && (0..=track_length(player)).contains(&position) =>
+            {
+                player.seek_precise(Duration::from_micros(position as u64));
+            }
+            MprisCommand::SetLoopMode(mode) => player.set_loop_mode(mode),
+            _ => {}
+        }),
+    }
+}
+
+/// 当前曲目时长（微秒）
+fn track_length(player: &Player) -> i64 {
+    player
+        .current_track()
+        .map(|track| track.duration() as i64 * 1_000_000)
+        .unwrap_or(0)
+}
+
+/// 按微秒偏移跳转：早于开头时跳到开头，超过结尾时按规范切到下一首
+fn seek_relative(player: &mut Player, offset: i64) {
+    if player.current_track().is_none() {
+        return;
+    }
+    let target = (player.elapsed().as_micros() as i64).saturating_add(offset);
+    if target >= track_length(player) {
+        player.next();
+    } else {
+        player.seek_precise(Duration::from_micros(target.max(0) as u64));
+    }
+}
+
+/// 启动 MPRIS 服务（会话总线不可用时只打印警告）
+pub fn init(cx: &mut App) {
+    let mut art = ArtUrls::new();
+    let initial = MprisState::from_player(cx.global::<Player>(), &art);
+    let server = match MprisServer::connect_session(initial) {
+        Ok(server) => server,
+        Err(e) => {
+            warn!("MPRIS 服务启动失败: {}", e);
+            return;
+        }
+    };
+
+    cx.spawn(async move |cx: &mut AsyncApp| {
+        loop {
+            cx.background_executor().timer(POLL_INTERVAL).await;
+
+            let result = cx.update(|cx: &mut App| {
+                let mut handled = false;
+                for command in server.pending_commands() {
+                    apply_command(command, cx);
+                    handled = true;
+                }
+                // 外部控制后刷新界面（播放栏只在播放中自动刷新）
+                if handled {
+                    cx.refresh_windows();
+                }
+                let player = cx.global::<Player>();
+                let missing = player
+                    .current_track()
+                    .and_then(|track| track.cover())
+                    .filter(|hash| !art.contains_key(hash));
+                (MprisState::from_player(player, &art), missing)
+            });
+            let Ok((state, missing)) = result else {
+                break;
+            };
+            if let Err(e) = server.update(state) {
+                warn!("更新 MPRIS 状态失败: {}", e);
+            }
+
+            // 换到新封面时在后台生成缩略图，下一轮再带上 artUrl
+            if let Some(hash) = missing {
+                let key = hash.clone();
+                let url = cx
+                    .background_executor()
+                    .spawn(async move { resolve_art_url(&key) })
+                    .await;
+                art.insert(hash, url);
+            }
+        }
+    })
+    .detach();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BUS_NAME, LoopMode, MprisCommand, MprisServer, MprisState, OBJECT_PATH, PLAYER_INTERFACE,
+        PlayState, TrackMeta, track_path,
+    };
+    use std::{
+        collections::HashMap,
+        io::{BufRead, BufReader},
+        process::{Child, Command, Stdio},
+        sync::mpsc,
+        thread,
+        time::Duration,
+    };
+    use uuid::Uuid;
+    use zbus::{
+        MatchRule,
+        blocking::{Connection, MessageIterator, connection::Builder},
+        message::Type,
+        zvariant::OwnedValue,
+    };
+
+    /// 测试用的私有会话总线，drop 时结束进程
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        /// 启动 dbus-daemon，系统中没有时返回 None 跳过测试
+        fn start() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address=1"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?)
+                .read_line(&mut address)
+                .ok()?;
+            Some(Self {
+                daemon,
+                address: address.trim().to_string(),
+            })
+        }
+
+        fn connect(&self) -> Connection {
+            Builder::address(self.address.as_str())
+                .unwrap()
+                .build()
+                .unwrap()
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            self.daemon.kill().ok();
+            self.daemon.wait().ok();
+        }
+    }
+
+    fn paused_state(id: Uuid) -> MprisState {
+        MprisState {
+            status: PlayState::Paused,
+            loop_mode: LoopMode::List,
+            volume: 0.5,
+            rate: 1.0,
+            position: 0,
+            can_go_next: true,
+            can_go_previous: false,
+            track: Some(TrackMeta {
+                id,
+                title: "标题".to_string(),
+                artist: "艺术家".to_string(),
+                album: "专辑".to_string(),
+                genre: None,
+                length: 180_000_000,
+                art_url: None,
+            }),
+        }
+    }
+
+    fn call(
+        client: &Connection,
+        method: &str,
+        body: &(impl serde::Serialize + zbus::zvariant::DynamicType),
+    ) {
+        client
+            .call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some(PLAYER_INTERFACE),
+                method,
+                body,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn methods_send_commands_with_microsecond_positions() {
+        let Some(bus) = PrivateBus::start() else {
+            return;
+        };
+        let id = Uuid::new_v4();
+        let server = MprisServer::connect(
+            Builder::address(bus.address.as_str()).unwrap(),
+            paused_state(id),
+        )
+        .unwrap();
+        let client = bus.connect();
+
+        call(&client, "PlayPause", &());
+        call(&client, "Seek", &(1_500_001i64,));
+        call(&client, "SetPosition", &(track_path(&id), 42_000_123i64));
+        // 曲目 ID 不一致时忽略
+        call(&client, "SetPosition", &(track_path(&Uuid::new_v4()), 1i64));
+
+        let commands: Vec<MprisCommand> = server.pending_commands().collect();
+        assert_eq!(
+            commands,
+            vec![
+                MprisCommand::PlayPause,
+                MprisCommand::Seek(1_500_001),
+                MprisCommand::SetPosition(id, 42_000_123),
+            ]
+        );
+    }
+
+    #[test]
+    fn update_emits_properties_changed() {
+        let Some(bus) = PrivateBus::start() else {
+            return;
+        };
+        let id = Uuid::new_v4();
+        let initial = paused_state(id);
+        let server = MprisServer::connect(
+            Builder::address(bus.address.as_str()).unwrap(),
+            initial.clone(),
+        )
+        .unwrap();
+        let client = bus.connect();
+
+        let rule = MatchRule::builder()
+            .msg_type(Type::Signal)
+            .interface("org.freedesktop.DBus.Properties")
+            .unwrap()
+            .member("PropertiesChanged")
+            .unwrap()
+            .build();
+        let mut signals = MessageIterator::for_match_rule(rule, &client, None).unwrap();
+        let (sender, received) = mpsc::channel();
+        thread::spawn(move || {
+            if let Some(Ok(message)) = signals.next() {
+                let body: (String, HashMap<String, OwnedValue>, Vec<String>) =
+                    message.body().deserialize().unwrap();
+                sender.send(body).ok();
+            }
+        });
+
+        server
+            .update(MprisState {
+                status: PlayState::Play,
+                volume: 0.8,
+                ..initial
+            })
+            .unwrap();
+
+        let (interface, changed, _) = received.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(interface, PLAYER_INTERFACE);
+        let status: String = changed["PlaybackStatus"].clone().try_into().unwrap();
+        assert_eq!(status, "Playing");
+        let volume: f64 = changed["Volume"].clone().try_into().unwrap();
+        assert_eq!(volume, 0.8);
+        assert!(!changed.contains_key("Metadata"));
+
+        // 属性也能直接读到新值
+        let reply = client
+            .call_method(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                Some("org.freedesktop.DBus.Properties"),
+                "Get",
+                &(PLAYER_INTERFACE, "PlaybackStatus"),
+            )
+            .unwrap();
+        let value: OwnedValue = reply.body().deserialize().unwrap();
+        let status: String = value.try_into().unwrap();
+        assert_eq!(status, "Playing");
+    }
+}
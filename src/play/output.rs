@@ -0,0 +1,110 @@
+use rodio::{
+    DeviceTrait, OutputStream, OutputStreamBuilder, StreamError,
+    cpal::{self, traits::HostTrait},
+};
+use tracing::warn;
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+/// 等待所选设备重新出现时检查设备列表的间隔
+const DEVICE_WATCH_INTERVAL: Duration = Duration::from_secs(3);
+
+/// 列出可用的音频输出设备名称
+pub fn output_devices() -> Vec<String> {
+    match cpal::default_host().output_devices() {
+        Ok(devices) => devices.filter_map(|device| device.name().ok()).collect(),
+        Err(e) => {
+            warn!("获取音频输出设备失败: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// 打开输出流：指定设备不存在或打开失败时回退到默认设备。
+/// 返回输出流和实际使用的设备名（None 表示默认设备）；
+/// 设备在播放中断开时把 lost 置为 true。
+pub fn open_stream(
+    device: Option<&str>,
+    lost: Arc<AtomicBool>,
+) -> Result<(OutputStream, Option<String>), StreamError> {
+    let on_error = move |e: cpal::StreamError| {
+        warn!("音频输出出错: {}", e);
+        if matches!(e, cpal::StreamError::DeviceNotAvailable) {
+            lost.store(true, Ordering::Relaxed);
+        }
+    };
+
+    if let Some(name) = device {
+        let found = cpal::default_host()
+            .output_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|d| d.name().is_ok_and(|n| n == name)));
+        match found {
+            Some(found) => {
+                match OutputStreamBuilder::from_device(found)
+                    .map(|builder| builder.with_error_callback(on_error.clone()))
+                    .and_then(|builder| builder.open_stream_or_fallback())
+                {
+                    Ok(stream) => return Ok((stream, Some(name.to_string()))),
+                    Err(e) => warn!("打开音频输出设备 {} 失败: {}", name, e),
+                }
+            }
+            None => warn!("未找到音频输出设备 {}，使用默认设备", name),
+        }
+    }
+
+    let stream = OutputStreamBuilder::from_default_device()?
+        .with_error_callback(on_error)
+        .open_stream_or_fallback()?;
+    Ok((stream, None))
+}
+
+/// 回退到默认设备期间，在后台线程里等待所选设备重新出现；drop 时停止
+pub struct DeviceWatch {
+    found: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+}
+
+impl DeviceWatch {
+    pub fn start(name: String) -> Self {
+        let found = Arc::new(AtomicBool::new(false));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (found_flag, stop_flag) = (found.clone(), stop.clone());
+        let spawned = thread::Builder::new()
+            .name("zotu-device-watch".into())
+            .spawn(move || {
+                loop {
+                    thread::sleep(DEVICE_WATCH_INTERVAL);
+                    if stop_flag.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    if output_devices().contains(&name) {
+                        found_flag.store(true, Ordering::Relaxed);
+                        break;
+                    }
+                }
+            });
+        if let Err(e) = spawned {
+            warn!("启动输出设备检测失败: {}", e);
+        }
+        Self { found, stop }
+    }
+
+    /// 所选设备是否已重新出现
+    pub fn found(&self) -> bool {
+        self.found.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for DeviceWatch {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
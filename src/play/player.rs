@@ -1,12 +1,32 @@
 use gpui::Global;
-use rand::seq::SliceRandom;
 use rodio::{Decoder, OutputStream, Sink};
 use serde::{Deserialize, Serialize};
+use tracing::{debug_span, info, info_span, warn};
 use uuid::Uuid;
 
-use std::{collections::HashMap, error::Error, path::PathBuf, sync::Arc, time::Instant};
-
-use crate::db::metadata::AlbumInfo;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{
+    db::metadata::AlbumInfo,
+    error::AppError,
+    play::{
+        ab_loop::{AbLoop, LoopControl, SegmentLoop},
+        analyzer::{SampleTap, TapSource},
+        dsp::{DspChain, DspSource, EffectKind, EffectSlot},
+        equalizer::{EqControl, EqSettings, Equalizer},
+        output,
+        shuffle::{self, ShuffleMode, TrackStats},
+        stretch::{StretchSpeed, TimeStretch},
+    },
+};
 
 /// 循环播放模式
 #[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
@@ -35,6 +55,31 @@ pub enum PlayState {
     Stopped,
 }
 
+/// 播放速度范围
+pub const PLAYBACK_RATE_RANGE: (f32, f32) = (0.5, 2.0);
+/// 变调范围（半音）
+pub const PITCH_RANGE: (f32, f32) = (-12.0, 12.0);
+
+/// A–B 循环的最短长度
+const MIN_AB_LOOP: Duration = Duration::from_millis(500);
+
+/// 播放不足该时长就切走的不算一次播放
+const MIN_COUNTED_PLAY: Duration = Duration::from_secs(3);
+
+/// 睡眠定时结束前的淡出时长
+const SLEEP_FADE_DURATION: Duration = Duration::from_secs(10);
+
+/// 睡眠定时
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SleepTimer {
+    /// 到达指定时间后暂停
+    At(Instant),
+    /// 当前歌曲播放完后停止
+    EndOfTrack,
+    /// 再播放完 N 首后停止（当前歌曲计为第一首）
+    Tracks(u32),
+}
+
 /// 播放进度信息（供 UI 使用）
 #[derive(Clone, Debug)]
 pub struct PlayProgress {
@@ -46,6 +91,56 @@ pub struct PlayProgress {
     pub progress: f32,
 }
 
+/// 歌曲可播放状态的变化，由界面取出后写入数据库并提示用户
+#[derive(Debug)]
+pub enum PlayabilityChange {
+    /// 解码失败，已标记为无法播放并跳过
+    Failed(Box<AlbumInfo>, AppError),
+    /// 之前标记为无法播放的歌曲已能正常解码
+    Recovered(Uuid),
+}
+
+/// 播放事件，由界面取出后写入播放历史和播放统计
+#[derive(Clone, Debug)]
+pub enum PlayEvent {
+    /// 开始播放一首歌曲（点击、切歌、自动下一首、队列等所有来源）
+    Started(AlbumInfo),
+    /// 一首歌曲结束播放（播完、切走、停止或退出），started_at 为开始播放的时间，
+    /// played 为实际收听的时长（不含暂停）
+    Finished {
+        track: AlbumInfo,
+        started_at: SystemTime,
+        played: Duration,
+    },
+}
+
+/// 当前歌曲实际收听的时长（只在播放状态下计时）
+#[derive(Default)]
+struct ListenClock {
+    played: Duration,
+    since: Option<Instant>,
+    /// 这次收听开始的时间（发出 Started 时记录）
+    started_at: Option<SystemTime>,
+}
+
+impl ListenClock {
+    fn resume(&mut self) {
+        self.since.get_or_insert_with(Instant::now);
+    }
+
+    fn pause(&mut self) {
+        if let Some(since) = self.since.take() {
+            self.played += since.elapsed();
+        }
+    }
+
+    /// 停止计时并返回（开始时间, 累计时长），之后从零开始
+    fn take(&mut self) -> (Option<SystemTime>, Duration) {
+        self.pause();
+        (self.started_at.take(), std::mem::take(&mut self.played))
+    }
+}
+
 struct PlayList {
     items: Arc<Vec<AlbumInfo>>,
     index: HashMap<Uuid, usize>,
@@ -68,9 +163,8 @@ impl PlayList {
         }
     }
 
-    fn shuffle(&mut self) {
-        let mut rng = rand::rng();
-        self.shuffle_order.shuffle(&mut rng);
+    fn shuffle(&mut self, mode: ShuffleMode, stats: &HashMap<Uuid, TrackStats>, seed: u64) {
+        self.shuffle_order = shuffle::shuffle_order(&self.items, mode, stats, seed);
     }
 
     fn len(&self) -> usize {
@@ -85,12 +179,68 @@ impl PlayList {
 pub struct Player {
     stream: OutputStream,
     sink: Sink,
+    /// 选择的输出设备（None 表示默认设备），回退到默认设备期间仍保留
+    output_device: Option<String>,
+    /// 所选设备不可用、正在使用默认设备时等待它重新出现
+    device_watch: Option<output::DeviceWatch>,
+    /// 输出设备断开时由音频线程置位
+    device_lost: Arc<AtomicBool>,
     playlist: Option<PlayList>,
     current_index: Option<usize>,
     current_shuffle_index: Option<usize>,
     current_track: Option<AlbumInfo>,
     loop_mode: LoopMode,
+    /// 随机播放方式
+    shuffle_mode: ShuffleMode,
+    /// 固定的随机种子（None 时每次打乱都重新生成），相同种子得到相同的播放顺序
+    shuffle_seed: Option<u64>,
+    /// 加权随机使用的评分和播放次数
+    track_stats: HashMap<Uuid, TrackStats>,
     play_state: PlayState,
+    /// 音量 (0.0 ~ 1.0)，切歌重建 sink 时保持
+    volume: f32,
+    /// 播放速度（不改变音调）
+    playback_rate: f32,
+    /// 变调（半音）
+    pitch_semitones: f32,
+    /// 时间伸缩倍率，所有 source 共享
+    stretch_speed: StretchSpeed,
+    /// A–B 循环（切换歌曲时清除）
+    ab_loop: Option<AbLoop>,
+    /// 与播放线程共享的循环区间和解码位置
+    loop_control: LoopControl,
+    /// 均衡器设置
+    equalizer: EqSettings,
+    /// 与播放线程共享的均衡器参数
+    eq_control: EqControl,
+    /// 音效链设置（按处理顺序）
+    effects: Vec<EffectSlot>,
+    /// 与播放线程共享的音效链
+    dsp_chain: DspChain,
+    /// 送往 sink 的采样，供频谱和波形显示
+    sample_tap: SampleTap,
+    /// 无法解码的歌曲，自动切歌时跳过
+    unplayable: HashSet<Uuid>,
+    /// 尚未被界面取走的可播放状态变化
+    playability_changes: Vec<PlayabilityChange>,
+    /// 正在跳过无法播放的歌曲（避免递归）
+    skipping_unplayable: bool,
+    /// 当前歌曲的收听计时
+    listen_clock: ListenClock,
+    /// 尚未被界面取走的播放事件
+    play_events: Vec<PlayEvent>,
+    /// 已发出的 Started 事件数，用于区分同一首歌的多次播放（单曲循环、重新点击）
+    plays_started: u64,
+
+    /// 睡眠定时
+    sleep_timer: Option<SleepTimer>,
+    /// 睡眠定时结束前是否淡出
+    sleep_fade: bool,
+    /// 当前歌曲播放完后停止（触发一次后自动取消）
+    stop_after_current: bool,
+
+    /// 播放队列（优先于播放列表播放）
+    queue: VecDeque<AlbumInfo>,
 
     /// 播放历史记录（存储播放过的歌曲索引）
     play_history: Vec<usize>,
@@ -99,28 +249,56 @@ pub struct Player {
 
     /// 当前曲目开始播放的时间点（用于计算进度）
     track_start_time: Option<Instant>,
-    /// 暂停时已播放的时长（秒）
-    paused_elapsed: Option<u64>,
+    /// 暂停（或调整播放速度）时已播放的时长
+    paused_elapsed: Option<Duration>,
 }
 
 impl Global for Player {}
 
 impl Player {
     pub fn new() -> Self {
-        let stream =
-            rodio::OutputStreamBuilder::open_default_stream().expect("open default audio stream");
+        let device_lost = Arc::new(AtomicBool::new(false));
+        let (stream, _) =
+            output::open_stream(None, device_lost.clone()).expect("open default audio stream");
 
         let sink = Sink::connect_new(&stream.mixer());
 
         Self {
             stream,
             sink,
+            output_device: None,
+            device_watch: None,
+            device_lost,
             playlist: None,
             current_index: None,
             current_shuffle_index: None,
             current_track: None,
             loop_mode: LoopMode::List,
+            shuffle_mode: ShuffleMode::Track,
+            shuffle_seed: None,
+            track_stats: HashMap::new(),
             play_state: PlayState::Stopped,
+            volume: 1.0,
+            playback_rate: 1.0,
+            pitch_semitones: 0.0,
+            stretch_speed: StretchSpeed::new(1.0),
+            ab_loop: None,
+            loop_control: LoopControl::default(),
+            equalizer: EqSettings::default(),
+            eq_control: EqControl::default(),
+            effects: Vec::new(),
+            dsp_chain: DspChain::default(),
+            sample_tap: SampleTap::default(),
+            unplayable: HashSet::new(),
+            playability_changes: Vec::new(),
+            skipping_unplayable: false,
+            listen_clock: ListenClock::default(),
+            play_events: Vec::new(),
+            plays_started: 0,
+            sleep_timer: None,
+            sleep_fade: false,
+            stop_after_current: false,
+            queue: VecDeque::new(),
             play_history: Vec::new(),
             history_position: None,
             track_start_time: None,
@@ -132,21 +310,24 @@ impl Player {
 
     fn play(&mut self) {
         self.sink.play();
-        self.play_state = PlayState::Play;
-        // 记录开始播放的时间点
-        if self.track_start_time.is_none() {
+        self.listen_clock.resume();
+        // 记录开始播放的时间点（从暂停恢复时已播放的时长保存在 paused_elapsed 中）
+        if self.track_start_time.is_none() || self.play_state == PlayState::Paused {
             self.track_start_time = Some(Instant::now());
         }
+        self.play_state = PlayState::Play;
     }
 
     fn pause(&mut self) {
         self.sink.pause();
+        self.listen_clock.pause();
         self.play_state = PlayState::Paused;
         // 保存暂停时已播放的时长
-        self.paused_elapsed = Some(self.current_elapsed());
+        self.paused_elapsed = Some(self.elapsed());
     }
 
     fn stop(&mut self) {
+        self.finish_listen();
         self.sink.stop();
         self.play_state = PlayState::Stopped;
         self.track_start_time = None;
@@ -167,6 +348,11 @@ impl Player {
         if self.current_track.is_none() {
             return;
         }
+        // 播完停止（本首后停止或睡眠定时）后，从下一首继续
+        if self.play_state == PlayState::Stopped {
+            self.next();
+            return;
+        }
         if self.sink.is_paused() {
             self.play();
         } else {
@@ -197,18 +383,19 @@ impl Player {
 
     /// 计算当前已播放时长（秒）
     fn current_elapsed(&self) -> u64 {
+        self.elapsed().as_secs()
+    }
+
+    /// 当前已播放的音频时长（按播放速度换算，不是实际经过的时间）
+    pub fn elapsed(&self) -> Duration {
+        let base = self.paused_elapsed.unwrap_or_default();
         match self.play_state {
-            PlayState::Play => {
-                if let Some(start) = self.track_start_time {
-                    let base = self.paused_elapsed.unwrap_or(0);
-                    let extra = start.elapsed().as_secs();
-                    base + extra
-                } else {
-                    self.paused_elapsed.unwrap_or(0)
-                }
-            }
-            PlayState::Paused => self.paused_elapsed.unwrap_or(0),
-            PlayState::Stopped => 0,
+            PlayState::Play => match self.track_start_time {
+                Some(start) => base + start.elapsed().mul_f32(self.playback_rate),
+                None => base,
+            },
+            PlayState::Paused => base,
+            PlayState::Stopped => Duration::ZERO,
         }
     }
 
@@ -217,12 +404,466 @@ impl Player {
         if let Some(track) = self.current_track.clone() {
             let path = track.path();
             // 保存当前位置信息
-            self.paused_elapsed = Some(position_secs);
+            self.paused_elapsed = Some(Duration::from_secs(position_secs));
             // 重新开始播放
-            self.play_source_internal(path, track, Some(position_secs));
+            self.play_source_internal(path, track, Some(Duration::from_secs(position_secs)));
+        }
+    }
+
+    /// 相对当前位置前进/后退若干秒
+    pub fn seek_by(&mut self, delta_secs: i64) {
+        let Some(progress) = self.progress() else {
+            return;
+        };
+        let target = (progress.elapsed as i64 + delta_secs).clamp(0, progress.duration as i64);
+        self.seek(target as u64);
+    }
+
+    // ========== 音量 ==========
+
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+        self.sink.set_volume(self.volume);
+    }
+
+    // ========== 播放速度和变调 ==========
+
+    pub fn playback_rate(&self) -> f32 {
+        self.playback_rate
+    }
+
+    /// 设置播放速度（不改变音调）
+    pub fn set_playback_rate(&mut self, rate: f32) {
+        // 以当前位置为新的计时起点，之前的部分按旧速度计算
+        if self.play_state == PlayState::Play {
+            self.paused_elapsed = Some(self.elapsed());
+            self.track_start_time = Some(Instant::now());
+        }
+        self.playback_rate = rate.clamp(PLAYBACK_RATE_RANGE.0, PLAYBACK_RATE_RANGE.1);
+        self.apply_rate_and_pitch();
+    }
+
+    pub fn pitch_semitones(&self) -> f32 {
+        self.pitch_semitones
+    }
+
+    /// 设置变调（半音，不改变播放速度）
+    pub fn set_pitch_semitones(&mut self, semitones: f32) {
+        self.pitch_semitones = semitones.clamp(PITCH_RANGE.0, PITCH_RANGE.1);
+        self.apply_rate_and_pitch();
+    }
+
+    /// 变调对应的频率倍率
+    fn pitch_factor(&self) -> f32 {
+        2f32.powf(self.pitch_semitones / 12.0)
+    }
+
+    /// 变调通过 sink 重采样实现（同时改变速度），再用时间伸缩把速度补偿回目标值
+    fn apply_rate_and_pitch(&mut self) {
+        let pitch = self.pitch_factor();
+        self.stretch_speed.set(self.playback_rate / pitch);
+        self.sink.set_speed(pitch);
+    }
+
+    // ========== 输出设备 ==========
+
+    pub fn output_device(&self) -> Option<&str> {
+        self.output_device.as_deref()
+    }
+
+    /// 所选设备不可用，正在临时使用系统默认设备
+    pub fn output_fallback(&self) -> bool {
+        self.device_watch.is_some()
+    }
+
+    /// 切换输出设备（None 为默认设备），正在播放的歌曲从原位置继续。
+    /// 所选设备不可用时临时使用默认设备，设备重新出现后自动切换回去。
+    pub fn set_output_device(&mut self, device: Option<String>) {
+        let _span = info_span!("output_device", device = device.as_deref()).entered();
+        self.device_lost.store(false, Ordering::Relaxed);
+        let (stream, active) =
+            match output::open_stream(device.as_deref(), self.device_lost.clone()) {
+                Ok(opened) => opened,
+                Err(e) => {
+                    warn!("打开音频输出失败: {}", e);
+                    return;
+                }
+            };
+
+        let position = self.elapsed();
+        let state = self.play_state;
+        self.sink.stop();
+        let mut old = std::mem::replace(&mut self.stream, stream);
+        old.log_on_drop(false);
+        drop(old);
+        info!("音频输出: {}", active.as_deref().unwrap_or("系统默认"));
+        self.device_watch = match (&device, active) {
+            (Some(name), None) => Some(output::DeviceWatch::start(name.clone())),
+            _ => None,
+        };
+        self.output_device = device;
+        self.sink = Sink::connect_new(self.stream.mixer());
+        self.sink.set_volume(self.volume);
+        self.sink.set_speed(self.pitch_factor());
+
+        if state == PlayState::Stopped {
+            return;
+        }
+        if let Some(track) = self.current_track.clone() {
+            self.play_source_internal(track.path(), track, None);
+            self.seek_precise(position);
+            if state == PlayState::Paused {
+                self.pause();
+            }
+        }
+    }
+
+    /// 输出设备断开时重新打开输出（所选设备不在则回退到默认设备），
+    /// 回退期间所选设备重新出现时切换回去
+    fn check_device_lost(&mut self) {
+        if self.device_lost.swap(false, Ordering::Relaxed) {
+            warn!("音频输出设备已断开，重新打开输出");
+            self.set_output_device(self.output_device.clone());
+        } else if self
+            .device_watch
+            .as_ref()
+            .is_some_and(output::DeviceWatch::found)
+        {
+            info!("所选音频输出设备已恢复");
+            self.set_output_device(self.output_device.clone());
+        }
+    }
+
+    // ========== 无法播放的歌曲 ==========
+
+    /// 设置已知无法播放的歌曲（启动时从数据库加载）
+    pub fn set_unplayable(&mut self, ids: HashSet<Uuid>) {
+        self.unplayable = ids;
+    }
+
+    /// 取出尚未处理的播放事件
+    pub fn take_play_events(&mut self) -> Vec<PlayEvent> {
+        std::mem::take(&mut self.play_events)
+    }
+
+    /// 已开始播放的次数，每次发出 Started 事件时加一（跳转进度不算）
+    pub fn plays_started(&self) -> u64 {
+        self.plays_started
+    }
+
+    /// 结束当前歌曲的收听计时，听够时长的记为一次播放
+    fn finish_listen(&mut self) {
+        let (started_at, played) = self.listen_clock.take();
+        if let Some(track) = self.current_track.clone()
+            && played >= MIN_COUNTED_PLAY
+        {
+            let started_at = started_at.unwrap_or_else(|| SystemTime::now() - played);
+            self.play_events.push(PlayEvent::Finished {
+                track,
+                started_at,
+                played,
+            });
+        }
+    }
+
+    /// 退出前结束正在进行的收听，之后由 `take_play_events` 取出写入统计
+    pub fn flush_listen(&mut self) {
+        self.finish_listen();
+    }
+
+    /// 取出尚未处理的可播放状态变化
+    pub fn take_playability_changes(&mut self) -> Vec<PlayabilityChange> {
+        std::mem::take(&mut self.playability_changes)
+    }
+
+    /// 解码失败：记录下来并按当前循环模式跳到下一首可播放的歌曲
+    fn handle_decode_failure(&mut self, track: AlbumInfo, error: AppError) {
+        warn!("解码音频文件失败: {:?} - {}", track.path(), error);
+        self.unplayable.insert(track.id());
+        self.playability_changes
+            .push(PlayabilityChange::Failed(Box::new(track), error));
+        self.current_track = None;
+        self.stop();
+
+        if !self.skipping_unplayable {
+            self.skipping_unplayable = true;
+            self.skip_unplayable();
+            self.skipping_unplayable = false;
+        }
+    }
+
+    /// 依次尝试队列和播放列表中的后续歌曲，直到有一首能正常播放；
+    /// 单曲循环时按列表顺序跳过，最多尝试一轮
+    fn skip_unplayable(&mut self) {
+        let attempts = self.queue.len() + self.playlist_len();
+        for _ in 0..attempts {
+            if self.play_from_queue() {
+                if self.current_track.is_some() {
+                    return;
+                }
+                continue;
+            }
+
+            let Some(playlist) = &self.playlist else {
+                return;
+            };
+            if playlist.len() == 0 {
+                return;
+            }
+            let idx = if self.loop_mode == LoopMode::Random {
+                let next_shuffle_idx = self
+                    .current_shuffle_index
+                    .map(|i| (i + 1) % playlist.len())
+                    .unwrap_or(0);
+                self.current_shuffle_index = Some(next_shuffle_idx);
+                playlist.shuffle_order.get(next_shuffle_idx).copied()
+            } else {
+                Some(
+                    self.current_index
+                        .map(|i| (i + 1) % playlist.len())
+                        .unwrap_or(0),
+                )
+            };
+            let Some(idx) = idx else {
+                return;
+            };
+
+            self.current_index = Some(idx);
+            let known_bad = playlist
+                .get(idx)
+                .is_some_and(|item| self.unplayable.contains(&item.id()));
+            if known_bad {
+                continue;
+            }
+            self.add_to_history(idx);
+            self.play_by_index(idx);
+            if self.current_track.is_some() {
+                return;
+            }
+        }
+    }
+
+    // ========== 均衡器 ==========
+
+    pub fn equalizer(&self) -> &EqSettings {
+        &self.equalizer
+    }
+
+    /// 修改均衡器设置，正在播放的歌曲立即生效
+    pub fn set_equalizer(&mut self, settings: EqSettings) {
+        self.eq_control.set(&settings);
+        self.equalizer = settings;
+    }
+
+    // ========== 音效链 ==========
+
+    pub fn effects(&self) -> &[EffectSlot] {
+        &self.effects
+    }
+
+    /// 替换音效链（增删、排序、改参数），正在播放的歌曲立即生效
+    ///
+    /// 只改参数时更新现有的效果器，保留包络和滤波器状态，避免拖动滑块时爆音
+    pub fn set_effects(&mut self, effects: Vec<EffectSlot>) {
+        let enabled = |slots: &[EffectSlot]| {
+            slots
+                .iter()
+                .filter(|slot| slot.enabled)
+                .map(|slot| std::mem::discriminant(&slot.effect))
+                .collect::<Vec<_>>()
+        };
+        let active = effects
+            .iter()
+            .filter(|slot| slot.enabled)
+            .map(|slot| &slot.effect);
+        if enabled(&self.effects) == enabled(&effects) {
+            self.dsp_chain.update(active);
+        } else {
+            self.dsp_chain.set(active.map(EffectKind::build).collect());
+        }
+        self.effects = effects;
+    }
+
+    /// 调整音效的处理顺序
+    pub fn move_effect(&mut self, from: usize, to: usize) {
+        if from < self.effects.len() && to < self.effects.len() {
+            let mut effects = self.effects.clone();
+            let slot = effects.remove(from);
+            effects.insert(to, slot);
+            self.set_effects(effects);
+        }
+    }
+
+    /// 最近播放的采样（频谱和波形显示）
+    pub fn sample_tap(&self) -> &SampleTap {
+        &self.sample_tap
+    }
+
+    // ========== A–B 循环 ==========
+
+    pub fn ab_loop(&self) -> Option<AbLoop> {
+        self.ab_loop
+    }
+
+    /// 以当前位置为 A 点（清除已有的 B 点）
+    pub fn set_loop_a(&mut self) {
+        if self.current_track.is_none() {
+            return;
+        }
+        self.ab_loop = Some(AbLoop {
+            a: self.elapsed(),
+            b: None,
+        });
+        self.loop_control.set_region(None);
+    }
+
+    /// 以当前位置为 B 点并开始循环，未设置 A 点或区间过短时忽略
+    pub fn set_loop_b(&mut self) {
+        if let Some(AbLoop { a, .. }) = self.ab_loop {
+            self.set_ab_loop(a, self.elapsed());
+        }
+    }
+
+    /// 直接设置循环区间（如选择已保存的片段），当前位置不在区间内时跳到 A 点
+    pub fn set_ab_loop(&mut self, a: Duration, b: Duration) {
+        if self.current_track.is_none() || b < a + MIN_AB_LOOP {
+            return;
+        }
+        self.ab_loop = Some(AbLoop { a, b: Some(b) });
+        self.loop_control.set_region(Some((a, b)));
+        let elapsed = self.elapsed();
+        if elapsed < a || elapsed > b {
+            self.seek_precise(a);
+        }
+    }
+
+    pub fn clear_ab_loop(&mut self) {
+        self.ab_loop = None;
+        self.loop_control.set_region(None);
+    }
+
+    /// 依次：设置 A 点 -> 设置 B 点 -> 清除
+    pub fn cycle_ab_loop(&mut self) {
+        match self.ab_loop {
+            None => self.set_loop_a(),
+            Some(AbLoop { b: None, .. }) => self.set_loop_b(),
+            Some(_) => self.clear_ab_loop(),
+        }
+    }
+
+    /// 精确跳转到指定位置：在当前 sink 上跳转（不重建 sink），已播完或停止时从该位置重新播放
+    pub fn seek_precise(&mut self, position: Duration) {
+        if self.sink.empty() || self.play_state == PlayState::Stopped {
+            if let Some(track) = self.current_track.clone() {
+                self.play_source_internal(track.path(), track, Some(position));
+            }
+            return;
+        }
+        let target = position.div_f32(self.pitch_factor());
+        if let Err(e) = self.sink.try_seek(target) {
+            warn!("跳转播放位置失败: {}", e);
+            return;
+        }
+        self.paused_elapsed = Some(position);
+        if self.play_state == PlayState::Play {
+            self.track_start_time = Some(Instant::now());
+        }
+    }
+
+    /// 循环中以解码位置校准计时（跳回 A 点后墙钟计时不再准确）
+    fn sync_loop_position(&mut self) {
+        if self.ab_loop.and_then(|ab| ab.b).is_some() && self.play_state == PlayState::Play {
+            self.paused_elapsed = Some(self.loop_control.position());
+            self.track_start_time = Some(Instant::now());
+        }
+    }
+
+    // ========== 睡眠定时 ==========
+
+    pub fn sleep_timer(&self) -> Option<SleepTimer> {
+        self.sleep_timer
+    }
+
+    /// 设置或取消睡眠定时（取消时恢复被淡出的音量）
+    pub fn set_sleep_timer(&mut self, timer: Option<SleepTimer>) {
+        self.sleep_timer = timer;
+        self.sink.set_volume(self.volume);
+    }
+
+    /// N 分钟后暂停
+    pub fn sleep_after_minutes(&mut self, minutes: u64) {
+        let deadline = Instant::now() + Duration::from_secs(minutes * 60);
+        self.set_sleep_timer(Some(SleepTimer::At(deadline)));
+    }
+
+    pub fn sleep_fade(&self) -> bool {
+        self.sleep_fade
+    }
+
+    pub fn set_sleep_fade(&mut self, fade: bool) {
+        self.sleep_fade = fade;
+        if !fade {
+            self.sink.set_volume(self.volume);
         }
     }
 
+    /// 睡眠定时的剩余时间（按首数计且多于一首时无法预估，返回 None）
+    pub fn sleep_remaining(&self) -> Option<Duration> {
+        match self.sleep_timer? {
+            SleepTimer::At(deadline) => Some(deadline.saturating_duration_since(Instant::now())),
+            SleepTimer::EndOfTrack | SleepTimer::Tracks(1) => self.track_remaining(),
+            SleepTimer::Tracks(_) => None,
+        }
+    }
+
+    pub fn stop_after_current(&self) -> bool {
+        self.stop_after_current
+    }
+
+    pub fn toggle_stop_after_current(&mut self) {
+        self.stop_after_current = !self.stop_after_current;
+    }
+
+    /// 当前歌曲播放完还需的实际时间（按播放速度换算）
+    fn track_remaining(&self) -> Option<Duration> {
+        let track = self.current_track.as_ref()?;
+        let remaining = Duration::from_secs(track.duration()).saturating_sub(self.elapsed());
+        Some(remaining.div_f32(self.playback_rate))
+    }
+
+    /// 睡眠定时即将结束时按剩余时间逐渐降低音量
+    fn apply_sleep_fade(&mut self) {
+        if !self.sleep_fade || self.play_state != PlayState::Play {
+            return;
+        }
+        if let Some(remaining) = self.sleep_remaining()
+            && remaining < SLEEP_FADE_DURATION
+        {
+            let factor = remaining.as_secs_f32() / SLEEP_FADE_DURATION.as_secs_f32();
+            self.sink.set_volume(self.volume * factor);
+        }
+    }
+
+    /// 歌曲自然播放完时检查是否应停止，同时推进按首数计的睡眠定时
+    fn should_stop_at_track_end(&mut self) -> bool {
+        let mut stop = std::mem::take(&mut self.stop_after_current);
+        match self.sleep_timer {
+            Some(SleepTimer::EndOfTrack) | Some(SleepTimer::Tracks(0..=1)) => {
+                self.sleep_timer = None;
+                stop = true;
+            }
+            Some(SleepTimer::Tracks(n)) => {
+                self.sleep_timer = Some(SleepTimer::Tracks(n - 1));
+            }
+            _ => {}
+        }
+        stop
+    }
+
     // ========== 状态查询 ==========
 
     pub fn is_playing(&self) -> bool {
@@ -245,21 +886,39 @@ impl Player {
         self.loop_mode
     }
 
-    /// 检查 sink 是否播放完毕，用于轮询自动下一首
+    /// 轮询播放状态：处理睡眠定时和淡出，sink 播放完毕时自动下一首
     pub fn check_and_auto_next(&mut self) {
+        self.check_device_lost();
+        if let Some(SleepTimer::At(deadline)) = self.sleep_timer
+            && Instant::now() >= deadline
+        {
+            // 暂停而不是停止，保留播放位置
+            if self.play_state == PlayState::Play {
+                self.pause();
+            }
+            self.set_sleep_timer(None);
+            return;
+        }
+        self.apply_sleep_fade();
+        self.sync_loop_position();
+
         if self.sink.empty() && self.play_state == PlayState::Play {
-            self.auto_next();
+            if self.should_stop_at_track_end() {
+                self.stop();
+                self.sink.set_volume(self.volume);
+            } else {
+                self.auto_next();
+            }
         }
     }
 
     // ========== 播放列表管理 ==========
 
     pub fn set_playlist(&mut self, items: Arc<Vec<AlbumInfo>>) {
-        let mut playlist = PlayList::new(items);
+        self.playlist = Some(PlayList::new(items));
         if self.loop_mode == LoopMode::Random {
-            playlist.shuffle();
+            self.reshuffle();
         }
-        self.playlist = Some(playlist);
     }
 
     pub fn has_playlist(&self) -> bool {
@@ -270,20 +929,125 @@ impl Player {
         self.playlist.as_ref().map(|p| p.len()).unwrap_or(0)
     }
 
+    /// 以给定歌曲作为播放列表并从头播放（随机模式下从随机顺序的第一首开始）
+    pub fn play_list(&mut self, items: Arc<Vec<AlbumInfo>>) {
+        self.set_playlist(items);
+        let first = self.playlist.as_ref().and_then(|playlist| {
+            let idx = if self.loop_mode == LoopMode::Random {
+                *playlist.shuffle_order.first()?
+            } else {
+                0
+            };
+            playlist.get(idx).cloned()
+        });
+        if let Some(item) = first {
+            self.play_track(&item);
+        }
+    }
+
+    /// 从播放列表、队列和前进后退历史中移除歌曲（从曲库移除后调用）
+    ///
+    /// 正在播放的歌曲继续播放，下一首接着原位置之后的歌曲
+    pub fn remove_tracks(&mut self, ids: &HashSet<Uuid>) {
+        self.queue.retain(|item| !ids.contains(&item.id()));
+
+        let Some(playlist) = &self.playlist else {
+            return;
+        };
+        if !playlist.items.iter().any(|item| ids.contains(&item.id())) {
+            return;
+        }
+
+        // 旧下标 -> 新下标（被移除的歌曲为 None）
+        let mut remap = Vec::with_capacity(playlist.len());
+        let mut items = Vec::with_capacity(playlist.len());
+        for item in playlist.items.iter() {
+            if ids.contains(&item.id()) {
+                remap.push(None);
+            } else {
+                remap.push(Some(items.len()));
+                items.push(item.clone());
+            }
+        }
+        let shuffle_order: Vec<usize> = playlist
+            .shuffle_order
+            .iter()
+            .filter_map(|&i| remap.get(i).copied().flatten())
+            .collect();
+
+        // 当前歌曲被移除时停在它之前的一首，next() 播放原来的下一首
+        self.current_index = self.current_index.and_then(|idx| match remap.get(idx) {
+            Some(Some(new)) => Some(*new),
+            _ => remap[..idx.min(remap.len())].iter().rev().find_map(|i| *i),
+        });
+
+        let position = self.history_position.unwrap_or(0);
+        let mut history = Vec::with_capacity(self.play_history.len());
+        let mut history_position = None;
+        for (pos, idx) in self.play_history.iter().enumerate() {
+            if let Some(Some(new)) = remap.get(*idx) {
+                history.push(*new);
+            }
+            if pos == position && !history.is_empty() {
+                history_position = Some(history.len() - 1);
+            }
+        }
+        self.history_position = self.history_position.and(history_position);
+        self.play_history = history;
+
+        let mut playlist = PlayList::new(Arc::new(items));
+        playlist.shuffle_order = shuffle_order;
+        self.current_shuffle_index = self
+            .current_index
+            .and_then(|idx| playlist.shuffle_order.iter().position(|&i| i == idx));
+        self.playlist = Some(playlist);
+    }
+
+    // ========== 播放队列 ==========
+
+    /// 添加到队列末尾
+    pub fn enqueue(&mut self, items: impl IntoIterator<Item = AlbumInfo>) {
+        self.queue.extend(items);
+    }
+
+    /// 插入到队列最前面（保持给定顺序），作为下一首播放
+    pub fn enqueue_next(&mut self, items: Vec<AlbumInfo>) {
+        for item in items.into_iter().rev() {
+            self.queue.push_front(item);
+        }
+    }
+
+    pub fn queue(&self) -> &VecDeque<AlbumInfo> {
+        &self.queue
+    }
+
+    pub fn clear_queue(&mut self) {
+        self.queue.clear();
+    }
+
+    /// 从队列取出下一首播放，队列为空时返回 false
+    fn play_from_queue(&mut self) -> bool {
+        let Some(item) = self.queue.pop_front() else {
+            return false;
+        };
+        if let Some(&idx) = self
+            .playlist
+            .as_ref()
+            .and_then(|playlist| playlist.index.get(&item.id()))
+        {
+            self.current_index = Some(idx);
+            self.add_to_history(idx);
+        }
+        self.play_source(&item, None);
+        true
+    }
+
     // ========== 循环模式 ==========
 
     pub fn set_loop_mode(&mut self, mode: LoopMode) {
         self.loop_mode = mode;
         if mode == LoopMode::Random {
-            if let Some(playlist) = &mut self.playlist {
-                playlist.shuffle();
-                if let Some(current_idx) = self.current_index {
-                    self.current_shuffle_index = playlist
-                        .shuffle_order
-                        .iter()
-                        .position(|&i| i == current_idx);
-                }
-            }
+            self.reshuffle();
         }
     }
 
@@ -292,6 +1056,63 @@ impl Player {
         self.set_loop_mode(next_mode);
     }
 
+    /// 在随机播放和列表循环之间切换
+    pub fn toggle_shuffle(&mut self) {
+        let mode = if self.loop_mode == LoopMode::Random {
+            LoopMode::List
+        } else {
+            LoopMode::Random
+        };
+        self.set_loop_mode(mode);
+    }
+
+    // ========== 随机播放 ==========
+
+    pub fn shuffle_mode(&self) -> ShuffleMode {
+        self.shuffle_mode
+    }
+
+    /// 设置随机播放方式，随机模式下立即重新打乱
+    pub fn set_shuffle_mode(&mut self, mode: ShuffleMode) {
+        self.shuffle_mode = mode;
+        if self.loop_mode == LoopMode::Random {
+            self.reshuffle();
+        }
+    }
+
+    pub fn shuffle_seed(&self) -> Option<u64> {
+        self.shuffle_seed
+    }
+
+    /// 固定随机种子（None 恢复为每次随机），随机模式下立即重新打乱
+    pub fn set_shuffle_seed(&mut self, seed: Option<u64>) {
+        self.shuffle_seed = seed;
+        if self.loop_mode == LoopMode::Random {
+            self.reshuffle();
+        }
+    }
+
+    /// 更新加权随机使用的统计（下次打乱时生效）
+    pub fn set_track_stats(&mut self, stats: HashMap<Uuid, TrackStats>) {
+        self.track_stats = stats;
+    }
+
+    /// 记录一次播放，供加权随机降低该歌曲的权重
+    pub fn count_play(&mut self, id: &Uuid) {
+        self.track_stats.entry(*id).or_default().play_count += 1;
+    }
+
+    /// 按当前随机方式重新生成播放顺序，并定位当前歌曲
+    fn reshuffle(&mut self) {
+        let seed = self.shuffle_seed.unwrap_or_else(rand::random);
+        if let Some(playlist) = &mut self.playlist {
+            playlist.shuffle(self.shuffle_mode, &self.track_stats, seed);
+            self.current_shuffle_index = self
+                .current_index
+                .and_then(|idx| playlist.shuffle_order.iter().position(|&i| i == idx));
+        }
+    }
+
     // ========== 播放操作 ==========
 
     /// 点击歌曲列表中的歌曲播放
@@ -341,6 +1162,10 @@ impl Player {
             }
         }
 
+        if self.play_from_queue() {
+            return;
+        }
+
         if let Some(playlist) = &self.playlist {
             if playlist.len() == 0 {
                 return;
@@ -431,19 +1256,21 @@ impl Player {
                         playlist.shuffle_order.iter().position(|&i| i == idx);
                 }
 
-                let path = item.path();
-                self.play_source_internal(path, item.clone(), None);
+                let item = item.clone();
+                self.play_source(&item, None);
             }
         }
     }
 
     /// 自动播放下一首（由后台线程调用）
     fn auto_next(&mut self) {
+        if self.play_from_queue() {
+            return;
+        }
         match self.loop_mode {
             LoopMode::Single => {
-                if let Some(track) = &self.current_track {
-                    let path = track.path();
-                    self.play_source_internal(path, track.clone(), None);
+                if let Some(track) = self.current_track.clone() {
+                    self.play_source(&track, None);
                 }
             }
             _ => {
@@ -485,33 +1312,61 @@ impl Player {
         }
     }
 
-    fn play_source(&mut self, item: &AlbumInfo, seek_to: Option<u64>) {
+    /// 开始播放一首歌曲：结束上一首的计时，成功后发出 Started
+    fn play_source(&mut self, item: &AlbumInfo, seek_to: Option<Duration>) {
+        self.finish_listen();
         let path = item.path();
-        self.play_source_internal(path, item.clone(), seek_to);
+        if self.play_source_internal(path, item.clone(), seek_to) {
+            self.play_events.push(PlayEvent::Started(item.clone()));
+            self.plays_started += 1;
+            self.listen_clock.started_at = Some(SystemTime::now());
+        }
     }
 
+    /// 重建 sink 播放指定歌曲（跳转、切换输出设备时也用于继续当前歌曲），返回是否成功
     fn play_source_internal(
         &mut self,
         path: Arc<PathBuf>,
         track_info: AlbumInfo,
-        seek_to: Option<u64>,
-    ) {
+        seek_to: Option<Duration>,
+    ) -> bool {
+        let _span = info_span!("play", track = %track_info.title()).entered();
+        // 切换到其他歌曲时清除 A–B 循环
+        if self.current_track.as_ref().map(|t| t.id()) != Some(track_info.id()) {
+            self.clear_ab_loop();
+        }
+
         // 停止当前播放
         self.sink.stop();
 
         // 重新创建 sink
         self.sink = Sink::connect_new(&self.stream.mixer());
+        self.sink.set_volume(self.volume);
+        self.sink.set_speed(self.pitch_factor());
 
         match decode(path.clone()) {
             Ok(source) => {
-                self.sink.append(source);
+                let source = SegmentLoop::new(source, self.loop_control.clone());
+                let source = TimeStretch::new(source, self.stretch_speed.clone());
+                let source = Equalizer::new(source, self.eq_control.clone());
+                let source = DspSource::new(source, self.dsp_chain.clone());
+                self.sink
+                    .append(TapSource::new(source, self.sample_tap.clone()));
+                if self.unplayable.remove(&track_info.id()) {
+                    self.playability_changes
+                        .push(PlayabilityChange::Recovered(track_info.id()));
+                }
+                info!("开始播放: {}", path.display());
                 self.current_track = Some(track_info);
 
-                // 如果有 seek 位置，则跳转（注意：rodio 的 Sink 不支持 seek，这是一个近似实现）
-                if let Some(_pos) = seek_to {
-                    // rodio 0.21 的 Sink 不支持精确 seek
-                    // 作为近似，记录起始偏移
-                    self.paused_elapsed = seek_to;
+                // 如果有 seek 位置，则跳转并记录起始偏移
+                if let Some(pos) = seek_to {
+                    // sink 的变速层会把跳转位置乘以重采样倍率，这里先除回来
+                    let target = pos.div_f32(self.pitch_factor());
+                    if let Err(e) = self.sink.try_seek(target) {
+                        warn!("跳转播放位置失败: {}", e);
+                    }
+                    self.paused_elapsed = Some(pos);
                 } else {
                     self.paused_elapsed = None;
                 }
@@ -519,21 +1374,22 @@ impl Player {
                 self.track_start_time = Some(Instant::now());
                 self.play_state = PlayState::Play;
                 self.sink.play();
+                self.listen_clock.resume();
+                true
             }
             Err(e) => {
-                eprintln!("[ERROR] 解码音频文件失败: {:?} - {}", path, e);
-                self.current_track = None;
-                self.play_state = PlayState::Stopped;
+                self.handle_decode_failure(track_info, e);
+                false
             }
         }
     }
 }
 
-/// 解码音频文件
-fn decode(
-    path: Arc<PathBuf>,
-) -> Result<Decoder<std::io::BufReader<std::fs::File>>, Box<dyn Error + Send + Sync>> {
-    let file = std::fs::File::open(path.as_path())?;
-    let source = Decoder::new(std::io::BufReader::new(file))?;
-    Ok(source)
+/// 解码音频文件（打开文件失败也作为音频错误）
+fn decode(path: Arc<PathBuf>) -> Result<Decoder<std::io::BufReader<std::fs::File>>, AppError> {
+    let _span = debug_span!("decode", path = %path.display()).entered();
+    let file = std::fs::File::open(path.as_path())
+        .map_err(|e| AppError::Audio(format!("无法打开文件: {}", e)))?;
+    Decoder::new(std::io::BufReader::new(file))
+        .map_err(|e| AppError::Audio(format!("无法解码: {}", e)))
 }
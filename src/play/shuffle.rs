@@ -0,0 +1,363 @@
+use rand::{Rng, SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use std::{collections::HashMap, path::Path};
+
+use crate::db::metadata::AlbumInfo;
+
+/// 随机播放方式（循环模式为随机时生效）
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug, Default)]
+pub enum ShuffleMode {
+    /// 逐首随机
+    #[default]
+    Track,
+    /// 专辑随机：随机挑选专辑，专辑内按顺序播放
+    Album,
+    /// 艺术家分散：尽量避免同一艺术家的歌曲连续播放
+    ArtistSpread,
+    /// 加权随机：评分高、播放少的歌曲更靠前
+    Weighted,
+}
+
+impl ShuffleMode {
+    pub fn all() -> [ShuffleMode; 4] {
+        [
+            ShuffleMode::Track,
+            ShuffleMode::Album,
+            ShuffleMode::ArtistSpread,
+            ShuffleMode::Weighted,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ShuffleMode::Track => "逐首随机",
+            ShuffleMode::Album => "专辑随机",
+            ShuffleMode::ArtistSpread => "艺术家分散",
+            ShuffleMode::Weighted => "加权随机",
+        }
+    }
+
+    /// 切换到下一种随机方式
+    pub fn next(&self) -> Self {
+        match self {
+            ShuffleMode::Track => ShuffleMode::Album,
+            ShuffleMode::Album => ShuffleMode::ArtistSpread,
+            ShuffleMode::ArtistSpread => ShuffleMode::Weighted,
+            ShuffleMode::Weighted => ShuffleMode::Track,
+        }
+    }
+}
+
+/// 加权随机使用的歌曲统计
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TrackStats {
+    /// 0–5 星，0 表示未评分
+    pub rating: u8,
+    pub play_count: u32,
+}
+
+impl TrackStats {
+    /// 权重：评分越高越大（未评分按两星计），播放次数越多越小
+    fn weight(&self) -> f64 {
+        let rating = if self.rating == 0 { 2 } else { self.rating };
+        (1.0 + rating as f64) / (1.0 + self.play_count as f64).sqrt()
+    }
+}
+
+/// 生成随机播放顺序（playlist 下标的排列）。相同的 seed 和输入总是得到相同的顺序
+/// （ChaCha8 的输出跨平台、跨版本固定，StdRng 不保证这一点）。
+pub fn shuffle_order(
+    items: &[AlbumInfo],
+    mode: ShuffleMode,
+    stats: &HashMap<Uuid, TrackStats>,
+    seed: u64,
+) -> Vec<usize> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    match mode {
+        ShuffleMode::Track => {
+            let mut order: Vec<usize> = (0..items.len()).collect();
+            order.shuffle(&mut rng);
+            order
+        }
+        ShuffleMode::Album => album_order(items, &mut rng),
+        ShuffleMode::ArtistSpread => artist_spread_order(items, &mut rng),
+        ShuffleMode::Weighted => weighted_order(items, stats, &mut rng),
+    }
+}
+
+/// 按 key 分组，组内保持原有顺序，组的顺序为首次出现的顺序
+fn group_by<K: std::hash::Hash + Eq>(
+    items: &[AlbumInfo],
+    key: impl Fn(&AlbumInfo) -> K,
+) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut index: HashMap<K, usize> = HashMap::new();
+    for (i, item) in items.iter().enumerate() {
+        let group = *index.entry(key(item)).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push(i);
+    }
+    groups
+}
+
+/// 专辑随机：专辑按名称和所在文件夹区分（合辑不会因艺术家不同被拆开），
+/// 打乱专辑顺序，专辑内按文件路径排序（文件名通常带音轨号）
+fn album_order(items: &[AlbumInfo], rng: &mut ChaCha8Rng) -> Vec<usize> {
+    let mut albums = group_by(items, |item| {
+        (item.album(), item.path().parent().map(Path::to_path_buf))
+    });
+    albums.shuffle(rng);
+    albums
+        .into_iter()
+        .flat_map(|mut album| {
+            album.sort_by_key(|&i| items[i].path());
+            album
+        })
+        .collect()
+}
+
+/// 艺术家分散：每次从剩余歌曲中随机挑选（按剩余数量加权）一位与上一首不同的艺术家；
+/// 某位艺术家的剩余歌曲超过一半时优先安排，保证后面仍能错开
+fn artist_spread_order(items: &[AlbumInfo], rng: &mut ChaCha8Rng) -> Vec<usize> {
+    let mut artists = group_by(items, |item| item.artist());
+    for tracks in &mut artists {
+        tracks.shuffle(rng);
+    }
+
+    let mut order = Vec::with_capacity(items.len());
+    let mut remaining = items.len();
+    let mut last: Option<usize> = None;
+    while remaining > 0 {
+        let candidates: Vec<usize> = (0..artists.len())
+            .filter(|&a| !artists[a].is_empty() && Some(a) != last)
+            .collect();
+
+        let artist = if candidates.is_empty() {
+            // 只剩上一位艺术家的歌曲，无法避免连续
+            last.unwrap_or_default()
+        } else if let Some(&crowded) = candidates
+            .iter()
+            .find(|&&a| artists[a].len() * 2 > remaining)
+        {
+            crowded
+        } else {
+            let mut pick = rng.random_range(0..remaining - last.map_or(0, |a| artists[a].len()));
+            *candidates
+                .iter()
+                .find(|&&a| {
+                    if pick < artists[a].len() {
+                        true
+                    } else {
+                        pick -= artists[a].len();
+                        false
+                    }
+                })
+                .unwrap_or(&candidates[0])
+        };
+
+        if let Some(track) = artists[artist].pop() {
+            order.push(track);
+        }
+        remaining -= 1;
+        last = Some(artist);
+    }
+    order
+}
+
+/// 加权随机（Efraimidis–Spirakis）：每首歌取 u^(1/w) 作为排序键，权重越大越容易靠前
+fn weighted_order(
+    items: &[AlbumInfo],
+    stats: &HashMap<Uuid, TrackStats>,
+    rng: &mut ChaCha8Rng,
+) -> Vec<usize> {
+    let mut keyed: Vec<(f64, usize)> = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let weight = stats.get(&item.id()).copied().unwrap_or_default().weight();
+            let u: f64 = rng.random();
+            (u.powf(1.0 / weight), i)
+        })
+        .collect();
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+    keyed.into_iter().map(|(_, i)| i).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::SharedString;
+    use std::{path::PathBuf, sync::Arc};
+
+    const SEED: u64 = 42;
+
+    /// (艺术家, 专辑, 文件路径)
+    fn library() -> Vec<AlbumInfo> {
+        [
+            ("甲", "一", "/music/一/02.flac"),
+            ("甲", "一", "/music/一/01.flac"),
+            ("甲", "一", "/music/一/03.flac"),
+            ("乙", "二", "/music/二/01.flac"),
+            ("乙", "二", "/music/二/02.flac"),
+            ("丙", "三", "/music/三/01.flac"),
+            ("丁", "四", "/music/四/01.flac"),
+            ("丁", "四", "/music/四/02.flac"),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (artist, album, path))| {
+            AlbumInfo::new(
+                Uuid::from_u128(i as u128 + 1),
+                format!("歌曲 {}", i).into(),
+                artist.into(),
+                album.into(),
+                200,
+                Arc::new(PathBuf::from(path)),
+                None,
+                None,
+            )
+        })
+        .collect()
+    }
+
+    fn order(mode: ShuffleMode) -> Vec<usize> {
+        let items = library();
+        let stats: HashMap<Uuid, TrackStats> = items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let stats = TrackStats {
+                    rating: (i % 6) as u8,
+                    play_count: i as u32 * 3,
+                };
+                (item.id(), stats)
+            })
+            .collect();
+        shuffle_order(&items, mode, &stats, SEED)
+    }
+
+    #[test]
+    fn track_order_is_fixed_for_seed() {
+        assert_eq!(order(ShuffleMode::Track), vec![7, 1, 5, 4, 6, 0, 3, 2]);
+    }
+
+    #[test]
+    fn album_order_is_fixed_for_seed() {
+        assert_eq!(order(ShuffleMode::Album), vec![5, 3, 4, 1, 0, 2, 6, 7]);
+    }
+
+    #[test]
+    fn artist_spread_order_is_fixed_for_seed() {
+        assert_eq!(
+            order(ShuffleMode::ArtistSpread),
+            vec![6, 4, 0, 5, 3, 1, 7, 2]
+        );
+    }
+
+    #[test]
+    fn weighted_order_is_fixed_for_seed() {
+        assert_eq!(order(ShuffleMode::Weighted), vec![1, 0, 3, 7, 2, 4, 5, 6]);
+    }
+
+    #[test]
+    fn weighted_order_favours_rated_and_unplayed_tracks() {
+        // (评分, 播放次数)：高分、低分、中等未播放、中等常播放
+        let stats = [(5, 0), (1, 0), (3, 0), (3, 50)];
+        let items: Vec<AlbumInfo> = library().into_iter().take(stats.len()).collect();
+        let stats: HashMap<Uuid, TrackStats> = items
+            .iter()
+            .zip(stats)
+            .map(|(item, (rating, play_count))| (item.id(), TrackStats { rating, play_count }))
+            .collect();
+
+        const RUNS: u64 = 4000;
+        let mut first = [0u64; 4];
+        for seed in 0..RUNS {
+            let order = shuffle_order(&items, ShuffleMode::Weighted, &stats, seed);
+            first[order[0]] += 1;
+        }
+
+        // 评分高的更常排在第一，同样评分时播放少的更常排在第一
+        assert!(first[0] > first[2] && first[2] > first[1], "{:?}", first);
+        assert!(first[2] > first[3], "{:?}", first);
+
+        // 排在第一的概率与权重成正比
+        let weights: Vec<f64> = items
+            .iter()
+            .map(|item| stats[&item.id()].weight())
+            .collect();
+        let total: f64 = weights.iter().sum();
+        for (count, weight) in first.iter().zip(&weights) {
+            let observed = *count as f64 / RUNS as f64;
+            let expected = weight / total;
+            assert!(
+                (observed - expected).abs() < 0.03,
+                "排第一的比例 {:.3} 与期望 {:.3} 相差过大: {:?}",
+                observed,
+                expected,
+                first
+            );
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_order() {
+        for mode in ShuffleMode::all() {
+            assert_eq!(order(mode), order(mode));
+        }
+    }
+
+    #[test]
+    fn album_tracks_stay_together_in_path_order() {
+        let items = library();
+        let order = order(ShuffleMode::Album);
+        let albums: Vec<SharedString> = order.iter().map(|&i| items[i].album()).collect();
+        let mut seen = albums.clone();
+        seen.dedup();
+        assert_eq!(seen.len(), 4, "同一专辑的歌曲应连续播放: {:?}", albums);
+        for pair in order.windows(2) {
+            let (a, b) = (&items[pair[0]], &items[pair[1]]);
+            if a.album() == b.album() {
+                assert!(a.path() < b.path());
+            }
+        }
+    }
+
+    #[test]
+    fn artist_spread_never_repeats_artist_when_avoidable() {
+        // 一位艺术家占一半：仍可完全错开
+        let mut items = library();
+        items.extend(library().into_iter().take(2).map(|item| {
+            AlbumInfo::new(
+                Uuid::new_v4(),
+                item.title(),
+                item.artist(),
+                item.album(),
+                item.duration(),
+                item.path(),
+                None,
+                None,
+            )
+        }));
+        for seed in 0..200 {
+            let order = shuffle_order(&items, ShuffleMode::ArtistSpread, &HashMap::new(), seed);
+            let mut sorted = order.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, (0..items.len()).collect::<Vec<_>>());
+            for pair in order.windows(2) {
+                assert_ne!(
+                    items[pair[0]].artist(),
+                    items[pair[1]].artist(),
+                    "seed {} 的顺序中同一艺术家连续: {:?}",
+                    seed,
+                    order
+                );
+            }
+        }
+    }
+}
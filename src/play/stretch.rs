@@ -0,0 +1,379 @@
+use rodio::{ChannelCount, SampleRate, Source, source::SeekError};
+
+use std::{
+    f32::consts::PI,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+
+/// 分析帧长度（毫秒），相邻帧重叠一半
+const FRAME_MS: u32 = 40;
+/// 拼接位置的搜索范围（毫秒）
+const SEARCH_MS: u32 = 10;
+
+/// 时间伸缩的速度倍率，播放中可实时调整（多个 source 共享同一个句柄）
+#[derive(Clone, Debug)]
+pub struct StretchSpeed(Arc<AtomicU32>);
+
+impl StretchSpeed {
+    pub fn new(speed: f32) -> Self {
+        Self(Arc::new(AtomicU32::new(speed.to_bits())))
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn set(&self, speed: f32) {
+        self.0.store(speed.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// 变速不变调（WSOLA）：按速度倍率跳读输入帧，在搜索范围内挑选与上一帧
+/// 自然延续最相似的位置，加汉宁窗后重叠相加
+///
+/// 输入按 span 读取，span 结束后格式变化时把之前的输入处理完，再按新格式重新开始；
+/// 始终备好下一块输出，以便报告这一块的格式和长度
+pub struct TimeStretch<I> {
+    input: I,
+    speed: StretchSpeed,
+    channels: usize,
+    sample_rate: SampleRate,
+    /// 帧长度（帧数，每帧含所有声道）
+    frame_len: usize,
+    /// 输出步长（帧长的一半）
+    hop: usize,
+    /// 搜索范围（帧数）
+    search: usize,
+    window: Vec<f32>,
+    /// 输入缓冲（交错采样）
+    buffer: Vec<f32>,
+    /// 输入当前 span 还剩的采样数
+    span_left: Option<usize>,
+    /// 当前格式的输入已经读完（输入结束或格式即将变化）
+    segment_done: bool,
+    input_done: bool,
+    /// 下一帧按速度倍率推算的理想位置（相对缓冲区起点）
+    nominal: f64,
+    /// 上一帧的自然延续位置
+    natural: usize,
+    /// 重叠相加的累加器（交错采样，帧长）
+    overlap: Vec<f32>,
+    /// 待输出的采样
+    output: Vec<f32>,
+    output_pos: usize,
+}
+
+impl<I: Source> TimeStretch<I> {
+    pub fn new(input: I, speed: StretchSpeed) -> Self {
+        let mut stretch = Self {
+            input,
+            speed,
+            channels: 1,
+            sample_rate: 0,
+            frame_len: 0,
+            hop: 0,
+            search: 0,
+            window: Vec::new(),
+            buffer: Vec::new(),
+            span_left: None,
+            segment_done: false,
+            input_done: false,
+            nominal: 0.0,
+            natural: 0,
+            overlap: Vec::new(),
+            output: Vec::new(),
+            output_pos: 0,
+        };
+        stretch.start_segment();
+        stretch.advance();
+        stretch
+    }
+
+    /// 按输入当前的格式重新计算帧参数并清空状态（开始播放、格式变化和跳转后调用）
+    fn start_segment(&mut self) {
+        self.channels = (self.input.channels() as usize).max(1);
+        self.sample_rate = self.input.sample_rate();
+        self.hop = (self.sample_rate * FRAME_MS / 2000).max(1) as usize;
+        self.frame_len = self.hop * 2;
+        self.search = (self.sample_rate * SEARCH_MS / 1000) as usize;
+        // 周期汉宁窗，50% 重叠时各帧权重之和恒为 1
+        self.window = (0..self.frame_len)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / self.frame_len as f32).cos())
+            .collect();
+
+        self.buffer.clear();
+        self.span_left = self.input.current_span_len();
+        self.segment_done = false;
+        self.input_done = false;
+        self.nominal = 0.0;
+        self.natural = 0;
+        self.overlap.clear();
+        self.overlap.resize(self.frame_len * self.channels, 0.0);
+        self.output.clear();
+        self.output_pos = 0;
+
+        // 补上开头之前那一帧的后半部分，第一帧叠加后与原信号一致（原速时不会淡入）
+        self.fill(self.hop);
+        for i in 0..self.hop {
+            let weight = self.window[self.hop + i];
+            for c in 0..self.channels {
+                self.overlap[i * self.channels + c] = self.sample(i, c) * weight;
+            }
+        }
+    }
+
+    fn buffered_frames(&self) -> usize {
+        self.buffer.len() / self.channels
+    }
+
+    /// 从输入读取，直到缓冲区至少有 frames 帧或当前格式的输入读完
+    fn fill(&mut self, frames: usize) {
+        while !self.segment_done && self.buffered_frames() < frames {
+            if self.span_left == Some(0) {
+                // span 结束后格式可能变化，变化时当前这段输入到此为止
+                if self.input.channels() as usize != self.channels
+                    || self.input.sample_rate() != self.sample_rate
+                {
+                    self.segment_done = true;
+                    break;
+                }
+                self.span_left = self.input.current_span_len();
+            }
+            match self.input.next() {
+                Some(sample) => {
+                    self.buffer.push(sample);
+                    if let Some(left) = &mut self.span_left {
+                        *left = left.saturating_sub(1);
+                    }
+                }
+                None => {
+                    self.input_done = true;
+                    self.segment_done = true;
+                }
+            }
+        }
+    }
+
+    /// 缓冲区中的采样，超出范围（输入结束后）视为静音
+    fn sample(&self, frame: usize, channel: usize) -> f32 {
+        self.buffer
+            .get(frame * self.channels + channel)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// 在 [lo, hi] 中找与自然延续段最相似（归一化互相关最大）的位置
+    fn best_offset(&self, lo: usize, hi: usize) -> usize {
+        let mono =
+            |frame: usize| -> f32 { (0..self.channels).map(|c| self.sample(frame, c)).sum() };
+        let mut best = (f32::MIN, lo);
+        for candidate in (lo..=hi).step_by(2) {
+            let (mut corr, mut energy) = (0.0, 0.0);
+            for i in (0..self.hop).step_by(2) {
+                let value = mono(candidate + i);
+                corr += value * mono(self.natural + i);
+                energy += value * value;
+            }
+            let score = corr / (energy + 1e-9).sqrt();
+            if score > best.0 {
+                best = (score, candidate);
+            }
+        }
+        best.1
+    }
+
+    /// 处理一帧并输出最多 hop 帧，当前格式的输入耗尽时返回 false
+    fn process(&mut self) -> bool {
+        let speed = self.speed.get().clamp(0.1, 10.0) as f64;
+
+        let position = if (speed - 1.0).abs() < 1e-3 {
+            // 原速时直接顺接，汉宁窗叠加后与原信号一致
+            self.natural
+        } else {
+            let lo = (self.nominal as usize).saturating_sub(self.search);
+            let hi = self.nominal as usize + self.search;
+            self.fill(hi + self.hop);
+            self.fill(self.natural + self.hop);
+            self.best_offset(lo, hi)
+        };
+
+        self.fill(position + self.frame_len);
+        let available = self.buffered_frames().saturating_sub(position);
+        if self.segment_done && available == 0 {
+            return false;
+        }
+
+        for i in 0..self.frame_len {
+            let weight = self.window[i];
+            for c in 0..self.channels {
+                self.overlap[i * self.channels + c] += self.sample(position + i, c) * weight;
+            }
+        }
+        let hop_samples = self.hop * self.channels;
+        self.output.clear();
+        self.output.extend(self.overlap.drain(..hop_samples));
+        self.overlap.resize(self.frame_len * self.channels, 0.0);
+        if self.segment_done {
+            // 不输出输入末尾之后补的静音
+            self.output
+                .truncate(available.min(self.hop) * self.channels);
+        }
+        self.output_pos = 0;
+
+        self.natural = position + self.hop;
+        self.nominal = if (speed - 1.0).abs() < 1e-3 {
+            self.natural as f64
+        } else {
+            self.nominal + self.hop as f64 * speed
+        };
+
+        // 丢弃之后不会再用到的输入
+        let consumed = (self.nominal as usize)
+            .saturating_sub(self.search)
+            .min(self.natural)
+            .min(self.buffered_frames());
+        self.buffer.drain(..consumed * self.channels);
+        self.nominal -= consumed as f64;
+        self.natural -= consumed;
+        true
+    }
+
+    /// 准备下一块输出，当前格式的输入耗尽时按新格式重新开始；输入结束时返回 false
+    fn advance(&mut self) -> bool {
+        loop {
+            if self.process() {
+                return true;
+            }
+            if self.input_done {
+                self.output.clear();
+                self.output_pos = 0;
+                return false;
+            }
+            self.start_segment();
+        }
+    }
+}
+
+impl<I: Source> Iterator for TimeStretch<I> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = *self.output.get(self.output_pos)?;
+        self.output_pos += 1;
+        if self.output_pos == self.output.len() {
+            self.advance();
+        }
+        Some(sample)
+    }
+}
+
+impl<I: Source> Source for TimeStretch<I> {
+    /// 当前这一块剩余的采样数，下一块可能换成新的格式
+    fn current_span_len(&self) -> Option<usize> {
+        Some(self.output.len() - self.output_pos)
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.channels as ChannelCount
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        let speed = self.speed.get();
+        self.input
+            .total_duration()
+            .map(|duration| duration.div_f32(speed))
+    }
+
+    /// 跳转位置为原始音频的时间
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.start_segment();
+        self.advance();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::play::test_source::{Spans, drain};
+    use rodio::buffer::SamplesBuffer;
+
+    /// 交错写入所有声道的正弦波
+    fn sine(frequency: f32, sample_rate: u32, frames: usize, channels: usize) -> Vec<f32> {
+        (0..frames)
+            .flat_map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                std::iter::repeat_n(0.5 * (2.0 * PI * frequency * t).sin(), channels)
+            })
+            .collect()
+    }
+
+    fn stretch(samples: Vec<f32>, channels: ChannelCount, speed: f32) -> Vec<f32> {
+        let input = SamplesBuffer::new(channels, 44100, samples);
+        TimeStretch::new(input, StretchSpeed::new(speed)).collect()
+    }
+
+    #[test]
+    fn normal_speed_passes_samples_through() {
+        let input = sine(440.0, 44100, 44100, 2);
+        let output = stretch(input.clone(), 2, 1.0);
+        assert_eq!(output.len(), input.len());
+        for (a, b) in output.iter().zip(&input) {
+            assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn output_length_follows_speed() {
+        let input = sine(440.0, 44100, 44100, 1);
+        for (speed, expected) in [(0.5, 2.0), (2.0, 0.5)] {
+            let ratio = stretch(input.clone(), 1, speed).len() as f32 / input.len() as f32;
+            assert!(
+                (ratio - expected).abs() < expected * 0.05,
+                "速度 {} 时长度比 {}",
+                speed,
+                ratio
+            );
+        }
+    }
+
+    #[test]
+    fn format_change_starts_a_new_span() {
+        let mono = sine(440.0, 44100, 22050, 1);
+        let stereo = sine(440.0, 48000, 24000, 2);
+        let input = || Spans::new(vec![(1, 44100, mono.clone()), (2, 48000, stereo.clone())]);
+
+        // 原速时两段都原样输出，且各自带着正确的格式
+        let output = drain(TimeStretch::new(input(), StretchSpeed::new(1.0)));
+        assert_eq!(output.len(), mono.len() + stereo.len());
+        let (first, second) = output.split_at(mono.len());
+        assert!(first.iter().all(|&(c, r, _)| (c, r) == (1, 44100)));
+        assert!(second.iter().all(|&(c, r, _)| (c, r) == (2, 48000)));
+        for ((_, _, a), b) in output.iter().zip(mono.iter().chain(&stereo)) {
+            assert!((a - b).abs() < 1e-5);
+        }
+
+        // 变速时每段分别伸缩，立体声段仍是完整的帧
+        let output = drain(TimeStretch::new(input(), StretchSpeed::new(0.5)));
+        let split = output.iter().position(|&(c, _, _)| c == 2).unwrap();
+        assert!(
+            output[split..]
+                .iter()
+                .all(|&(c, r, _)| (c, r) == (2, 48000))
+        );
+        assert_eq!((output.len() - split) % 2, 0);
+        let ratio = split as f32 / mono.len() as f32;
+        assert!((ratio - 2.0).abs() < 0.1, "单声道段长度比 {}", ratio);
+        let ratio = (output.len() - split) as f32 / stereo.len() as f32;
+        assert!((ratio - 2.0).abs() < 0.1, "立体声段长度比 {}", ratio);
+    }
+}
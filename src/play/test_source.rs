@@ -0,0 +1,89 @@
+// 测试用的输入：按 span 切换格式，像解码器一样报告当前 span 剩余的采样数
+
+use rodio::{ChannelCount, SampleRate, Source};
+use std::time::Duration;
+
+pub struct Spans {
+    spans: Vec<(ChannelCount, SampleRate, Vec<f32>)>,
+    span: usize,
+    position: usize,
+}
+
+impl Spans {
+    /// 依次播放的 (声道数, 采样率, 交错采样)
+    pub fn new(spans: Vec<(ChannelCount, SampleRate, Vec<f32>)>) -> Self {
+        Self {
+            spans,
+            span: 0,
+            position: 0,
+        }
+    }
+
+    /// 当前 span 读完后报告下一个 span 的格式
+    fn current(&self) -> (ChannelCount, SampleRate) {
+        let (_, _, samples) = &self.spans[self.span];
+        let index = if self.position == samples.len() && self.span + 1 < self.spans.len() {
+            self.span + 1
+        } else {
+            self.span
+        };
+        let (channels, sample_rate, _) = self.spans[index];
+        (channels, sample_rate)
+    }
+}
+
+impl Iterator for Spans {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        while let Some((_, _, samples)) = self.spans.get(self.span) {
+            if let Some(&sample) = samples.get(self.position) {
+                self.position += 1;
+                return Some(sample);
+            }
+            self.span += 1;
+            self.position = 0;
+        }
+        None
+    }
+}
+
+impl Source for Spans {
+    fn current_span_len(&self) -> Option<usize> {
+        let Some((_, _, samples)) = self.spans.get(self.span) else {
+            return Some(0);
+        };
+        match samples.len() - self.position {
+            // 当前 span 已读完，下一次 next 才会切换
+            0 => Some(self.spans.get(self.span + 1).map_or(0, |(_, _, s)| s.len())),
+            remaining => Some(remaining),
+        }
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.current().0
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.current().1
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// 按 span 读完整个 source（与输出端一样只在 span 开头读取格式），返回每个采样及其格式
+pub fn drain(mut source: impl Source) -> Vec<(ChannelCount, SampleRate, f32)> {
+    let mut samples = Vec::new();
+    loop {
+        let (channels, sample_rate) = (source.channels(), source.sample_rate());
+        let span = source.current_span_len().unwrap_or(usize::MAX).max(1);
+        for _ in 0..span {
+            match source.next() {
+                Some(sample) => samples.push((channels, sample_rate, sample)),
+                None => return samples,
+            }
+        }
+    }
+}
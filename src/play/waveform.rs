@@ -0,0 +1,129 @@
+use rodio::{Decoder, Source};
+use tracing::debug_span;
+
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{self, BufReader},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+    time::UNIX_EPOCH,
+};
+
+/// 波形进度条的峰值数量
+pub const WAVEFORM_PEAKS: usize = 240;
+/// 解码时每块取一个峰值的帧数
+const BLOCK_FRAMES: usize = 1024;
+
+/// 文件的修改时间（毫秒）和大小，与波形一起缓存，任一变化时缓存作废
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FileStamp {
+    pub mtime: i64,
+    pub size: i64,
+}
+
+impl FileStamp {
+    pub fn of(path: &Path) -> io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        let mtime = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis() as i64);
+        Ok(Self {
+            mtime,
+            size: metadata.len() as i64,
+        })
+    }
+}
+
+/// 解码整首歌曲，计算波形峰值（按最大值归一化到 0 ~ 255）。
+/// 每解码一块检查一次 cancel，置位后停止解码并返回 None。
+pub fn compute_peaks(
+    path: &Path,
+    cancel: &AtomicBool,
+) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+    let _span = debug_span!("waveform", path = %path.display()).entered();
+    let decoder = Decoder::new(BufReader::new(File::open(path)?))?;
+    let block_len = BLOCK_FRAMES * decoder.channels().max(1) as usize;
+
+    // 先按固定块长取峰值，解码完成（总长度已知）后再合并为固定数量
+    let mut blocks = Vec::new();
+    let (mut peak, mut count) = (0f32, 0);
+    for sample in decoder {
+        peak = peak.max(sample.abs());
+        count += 1;
+        if count == block_len {
+            blocks.push(peak);
+            (peak, count) = (0.0, 0);
+            if cancel.load(Ordering::Relaxed) {
+                return Ok(None);
+            }
+        }
+    }
+    if count > 0 {
+        blocks.push(peak);
+    }
+    if blocks.is_empty() {
+        return Ok(Some(vec![0; WAVEFORM_PEAKS]));
+    }
+
+    let peaks: Vec<f32> = (0..WAVEFORM_PEAKS)
+        .map(|i| {
+            let from = (i * blocks.len() / WAVEFORM_PEAKS).min(blocks.len() - 1);
+            let to = ((i + 1) * blocks.len() / WAVEFORM_PEAKS).clamp(from + 1, blocks.len());
+            blocks[from..to].iter().copied().fold(0.0, f32::max)
+        })
+        .collect();
+    let max = peaks.iter().copied().fold(0.0, f32::max).max(1e-6);
+    Ok(Some(
+        peaks
+            .into_iter()
+            .map(|peak| (peak / max * 255.0).round() as u8)
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 写一个 16 位单声道 WAV 文件（正弦波，幅度 0.5）
+    fn write_wav(path: &Path, frames: usize) {
+        let rate = 44_100u32;
+        let data_len = (frames * 2) as u32;
+        let mut bytes = Vec::with_capacity(44 + frames * 2);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&rate.to_le_bytes());
+        bytes.extend_from_slice(&(rate * 2).to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for i in 0..frames {
+            let t = i as f32 / rate as f32;
+            let sample = (0.5 * (2.0 * std::f32::consts::PI * 440.0 * t).sin() * 32767.0) as i16;
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn peaks_are_normalized_and_cancel_stops_decoding() {
+        let path = std::env::temp_dir().join(format!("zotu-test-{}.wav", std::process::id()));
+        write_wav(&path, 44_100);
+
+        let peaks = compute_peaks(&path, &AtomicBool::new(false));
+        let cancelled = compute_peaks(&path, &AtomicBool::new(true));
+        fs::remove_file(&path).ok();
+
+        let peaks = peaks.unwrap().expect("not cancelled");
+        assert_eq!(peaks.len(), WAVEFORM_PEAKS);
+        assert_eq!(peaks.iter().max(), Some(&255));
+        assert!(cancelled.unwrap().is_none());
+    }
+}
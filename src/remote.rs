@@ -0,0 +1,403 @@
+// 单实例远程控制：第一个启动的实例在 Unix socket 上监听，之后的命令行调用把命令转发给它
+//
+// 协议为一行 JSON 请求、一行 JSON 响应。每个连接在独立线程中收发，命令由 UI 线程定时取出执行；
+// 扫描等耗时命令在后台进行并立即回复，不会阻塞之后的命令。
+// 非 Unix 平台没有 socket，命令行始终按“没有运行中的实例”处理。
+
+use gpui::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc::{Receiver, RecvTimeoutError, Sender},
+    time::Duration,
+};
+use tracing::warn;
+
+use crate::{
+    app::Zotu,
+    config::Config,
+    db::{database::DB, table::Table},
+    play::{
+        player::{LoopMode, PlayState, Player},
+        shuffle::ShuffleMode,
+    },
+};
+
+/// UI 线程检查远程命令的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 连接读写超时：客户端连上后不发请求，或不读取响应时放弃该连接
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 等待 UI 线程回复的最长时间（窗口卡住时不让连接线程一直挂着）
+const REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 命令行转发给运行中实例的命令
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum RemoteCommand {
+    /// 激活已有窗口（不带子命令启动时）
+    Raise,
+    /// 以文件或文件夹中的歌曲作为播放列表播放
+    Play {
+        path: PathBuf,
+    },
+    /// 把文件或文件夹中的歌曲加入播放队列
+    Enqueue {
+        path: PathBuf,
+    },
+    Toggle,
+    Next,
+    Status,
+    /// 扫描文件夹并把新歌曲加入曲库
+    Scan {
+        path: PathBuf,
+    },
+}
+
+/// 运行中实例的响应
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum RemoteResponse {
+    Done { message: String },
+    Status(PlayerStatus),
+    Error { message: String },
+}
+
+impl RemoteResponse {
+    pub fn done(message: impl Into<String>) -> Self {
+        RemoteResponse::Done {
+            message: message.into(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        RemoteResponse::Error {
+            message: message.into(),
+        }
+    }
+}
+
+/// 当前曲目（status 输出用）
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TrackStatus {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub path: PathBuf,
+    /// 时长（秒）
+    pub duration: u64,
+}
+
+/// 播放器状态快照
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlayerStatus {
+    /// 是否有运行中的实例（无头模式下为 false，只包含曲库信息）
+    pub running: bool,
+    /// playing / paused / stopped
+    pub state: String,
+    pub track: Option<TrackStatus>,
+    /// 已播放（秒）
+    pub elapsed: u64,
+    pub volume: f32,
+    pub loop_mode: LoopMode,
+    #[serde(default)]
+    pub shuffle_mode: ShuffleMode,
+    pub queue_len: usize,
+    pub library_size: i64,
+    pub favorite_count: usize,
+    pub history_count: usize,
+}
+
+impl PlayerStatus {
+    /// 从运行中的实例读取
+    pub fn from_app(cx: &App) -> Self {
+        let player = cx.global::<Player>();
+        let state = match player.play_state() {
+            PlayState::Play => "playing",
+            PlayState::Paused => "paused",
+            PlayState::Stopped => "stopped",
+        };
+        let track = player.current_track().map(|track| TrackStatus {
+            title: track.title().to_string(),
+            artist: track.artist().to_string(),
+            album: track.album().to_string(),
+            path: track.path().to_path_buf(),
+            duration: track.duration(),
+        });
+        Self {
+            running: true,
+            state: state.to_string(),
+            track,
+            elapsed: player.progress().map(|p| p.elapsed).unwrap_or(0),
+            volume: player.volume(),
+            loop_mode: player.loop_mode(),
+            shuffle_mode: player.shuffle_mode(),
+            queue_len: player.queue().len(),
+            ..Self::headless(cx.global::<Config>(), cx.global::<DB>())
+        }
+    }
+
+    /// 没有运行中的实例时，只根据配置和数据库生成
+    pub fn headless(config: &Config, db: &DB) -> Self {
+        Self {
+            running: false,
+            state: "stopped".to_string(),
+            track: None,
+            elapsed: 0,
+            volume: config.play_info.volume,
+            loop_mode: config.play_info.loop_mode,
+            shuffle_mode: config.play_info.shuffle_mode,
+            queue_len: 0,
+            library_size: db.get_album_count().unwrap_or(0),
+            favorite_count: db.get_all_uuids(Table::Favorite).len(),
+            history_count: db.get_all_uuids(Table::History).len(),
+        }
+    }
+}
+
+/// 使用 db_path 数据库的实例的 socket 路径
+///
+/// 文件名带有数据库路径的哈希，不同数据目录的实例可以同时运行，命令也只会转发给使用同一数据库的实例
+pub fn socket_path(db_path: &Path) -> PathBuf {
+    let hash = format!(
+        "{:x}",
+        Sha256::digest(db_path.as_os_str().as_encoded_bytes())
+    );
+    let name = format!("zotu-{}", &hash[..8]);
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join(format!("{}.sock", name)),
+        None => {
+            let user = std::env::var("USER").unwrap_or_else(|_| "default".to_string());
+            std::env::temp_dir().join(format!("{}-{}.sock", name, user))
+        }
+    }
+}
+
+/// 把命令发送给运行中的实例；没有实例在监听时返回 None
+pub fn send(path: &Path, command: &RemoteCommand) -> io::Result<Option<RemoteResponse>> {
+    #[cfg(unix)]
+    {
+        use std::io::{BufRead, BufReader, Write};
+        use std::os::unix::net::UnixStream;
+
+        let mut stream = match UnixStream::connect(path) {
+            Ok(stream) => stream,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused
+                ) =>
+            {
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+
+        stream.set_read_timeout(Some(REPLY_TIMEOUT + IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+        let mut request = serde_json::to_string(command)?;
+        request.push('\n');
+        stream.write_all(request.as_bytes())?;
+
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line)?;
+        Ok(Some(serde_json::from_str(&line)?))
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (path, command);
+        Ok(None)
+    }
+}
+
+/// 等待 UI 线程处理的请求
+struct Request {
+    command: RemoteCommand,
+    reply: Sender<RemoteResponse>,
+}
+
+/// 远程控制服务端：监听线程把请求放入通道，由 `init` 在 UI 线程处理
+pub struct RemoteServer {
+    path: PathBuf,
+    requests: Receiver<Request>,
+}
+
+impl RemoteServer {
+    /// 在给定路径上监听；已有实例在监听时返回 AddrInUse，残留的 socket 文件会被清理
+    pub fn bind(path: &Path) -> io::Result<Self> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::net::{UnixListener, UnixStream};
+
+            if path.exists() {
+                if UnixStream::connect(path).is_ok() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AddrInUse,
+                        "已有 Zotu 实例在运行",
+                    ));
+                }
+                std::fs::remove_file(path)?;
+            }
+
+            let listener = UnixListener::bind(path)?;
+            let (sender, requests) = std::sync::mpsc::channel();
+            std::thread::Builder::new()
+                .name("zotu-remote".into())
+                .spawn(move || {
+                    for stream in listener.incoming() {
+                        let stream = match stream {
+                            Ok(stream) => stream,
+                            Err(e) => {
+                                warn!("接受远程连接失败: {}", e);
+                                continue;
+                            }
+                        };
+                        // 每个连接单独一个线程，慢客户端不会挡住之后的命令
+                        let sender = sender.clone();
+                        let spawned = std::thread::Builder::new()
+                            .name("zotu-remote-conn".into())
+                            .spawn(move || {
+                                if let Err(e) = handle_connection(stream, &sender) {
+                                    warn!("处理远程命令失败: {}", e);
+                                }
+                            });
+                        if let Err(e) = spawned {
+                            warn!("创建远程连接线程失败: {}", e);
+                        }
+                    }
+                })?;
+
+            Ok(Self {
+                path: path.to_path_buf(),
+                requests,
+            })
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = path;
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "当前平台不支持远程控制",
+            ))
+        }
+    }
+
+    fn pending_requests(&self) -> Vec<Request> {
+        self.requests.try_iter().collect()
+    }
+}
+
+impl Drop for RemoteServer {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.path).ok();
+    }
+}
+
+/// 读取一条请求，交给 UI 线程并等待结果写回
+#[cfg(unix)]
+fn handle_connection(
+    mut stream: std::os::unix::net::UnixStream,
+    requests: &Sender<Request>,
+) -> io::Result<()> {
+    use std::io::{BufRead, BufReader, Write};
+
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+
+    let response = match serde_json::from_str::<RemoteCommand>(&line) {
+        Ok(command) => {
+            let (reply, response) = std::sync::mpsc::channel();
+            requests.send(Request { command, reply }).ok();
+            match response.recv_timeout(REPLY_TIMEOUT) {
+                Ok(response) => response,
+                Err(RecvTimeoutError::Timeout) => RemoteResponse::error("Zotu 没有响应"),
+                Err(RecvTimeoutError::Disconnected) => RemoteResponse::error("Zotu 正在退出"),
+            }
+        }
+        Err(e) => RemoteResponse::error(format!("无法解析命令: {}", e)),
+    };
+
+    let mut text = serde_json::to_string(&response)?;
+    text.push('\n');
+    stream.write_all(text.as_bytes())
+}
+
+/// 在 UI 线程定时处理远程命令（窗口关闭后停止监听并删除 socket）
+pub fn init(server: RemoteServer, window: WindowHandle<Zotu>, cx: &mut App) {
+    cx.spawn(async move |cx: &mut AsyncApp| {
+        loop {
+            cx.background_executor().timer(POLL_INTERVAL).await;
+
+            for request in server.pending_requests() {
+                let result = window.update(cx, |zotu, window, cx| {
+                    let response = zotu.handle_remote(request.command, window, cx);
+                    // 外部控制后刷新界面（播放栏只在播放中自动刷新）
+                    window.refresh();
+                    response
+                });
+                match result {
+                    Ok(response) => request.reply.send(response).ok(),
+                    Err(_) => {
+                        request
+                            .reply
+                            .send(RemoteResponse::error("Zotu 窗口已关闭"))
+                            .ok();
+                        return;
+                    }
+                };
+            }
+        }
+    })
+    .detach();
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{Duration, RemoteCommand, RemoteResponse, RemoteServer, send, socket_path};
+    use std::{os::unix::net::UnixStream, path::Path, time::Instant};
+
+    #[test]
+    fn socket_path_depends_on_database() {
+        let default = socket_path(Path::new("/home/user/.local/share/zotu/metadata.db"));
+        let other = socket_path(Path::new("/tmp/zotu-test/metadata.db"));
+        assert_ne!(default, other);
+        assert_eq!(other, socket_path(Path::new("/tmp/zotu-test/metadata.db")));
+        assert_eq!(default.parent(), other.parent());
+    }
+
+    #[test]
+    fn idle_client_does_not_block_other_commands() {
+        let path = std::env::temp_dir().join(format!("zotu-test-{}.sock", std::process::id()));
+        let server = RemoteServer::bind(&path).unwrap();
+
+        // 连上之后既不发请求也不读响应
+        let _idle = UnixStream::connect(&path).unwrap();
+
+        let client_path = path.clone();
+        let client = std::thread::spawn(move || send(&client_path, &RemoteCommand::Next));
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let request = loop {
+            if let Some(request) = server.pending_requests().pop() {
+                break request;
+            }
+            assert!(Instant::now() < deadline, "命令没有送达");
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(request.command, RemoteCommand::Next);
+        request.reply.send(RemoteResponse::done("ok")).unwrap();
+
+        let response = client.join().unwrap().unwrap();
+        assert!(matches!(response, Some(RemoteResponse::Done { message }) if message == "ok"));
+    }
+}
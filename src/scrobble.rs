@@ -0,0 +1,804 @@
+// 听歌记录上报（ListenBrainz 兼容接口，Config.scrobble.enabled 开启）
+//
+// UI 线程每秒把播放状态交给 `ScrobbleTracker`，按通用规则判断何时发送“正在播放”和正式记录：
+// 曲目长于 30 秒，且累计播放时长达到一半或 4 分钟（以先到者为准）。正式记录先写入 SQLite
+// 队列，由上报线程批量发送；网络不可用时按指数退避重试，重启后继续发送。
+
+use gpui::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    db::{database::DB, metadata::AlbumInfo},
+    play::player::Player,
+};
+
+/// 短于该时长的曲目不上报
+const MIN_TRACK_SECS: u64 = 30;
+/// 累计播放达到该时长即可上报（即使不到一半）
+const MAX_REQUIRED_SECS: u64 = 240;
+/// UI 线程检查播放状态的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// 每次最多发送的记录数（ListenBrainz 单次上限为 1000）
+const BATCH_SIZE: usize = 100;
+/// 重试退避：首次 30 秒，之后翻倍，最长 1 小时
+const RETRY_BASE_SECS: i64 = 30;
+const RETRY_MAX_SECS: i64 = 3600;
+/// 请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 一条听歌记录
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Listen {
+    pub artist: String,
+    pub title: String,
+    pub album: String,
+    /// 时长（秒）
+    pub duration: u64,
+    /// 开始播放的 Unix 时间戳（秒）
+    pub listened_at: i64,
+}
+
+impl Listen {
+    pub fn new(item: &AlbumInfo, listened_at: i64) -> Self {
+        Self {
+            artist: item.artist().to_string(),
+            title: item.title().to_string(),
+            album: item.album().to_string(),
+            duration: item.duration(),
+            listened_at,
+        }
+    }
+}
+
+/// 上报失败的原因
+#[derive(Debug)]
+pub enum ScrobbleError {
+    /// 网络错误、限流或服务端错误，稍后重试
+    Retry(String),
+    /// 服务端拒绝（如令牌无效、数据格式错误），重试也不会成功
+    Rejected(String),
+}
+
+impl std::fmt::Display for ScrobbleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScrobbleError::Retry(e) => write!(f, "暂时无法上报: {}", e),
+            ScrobbleError::Rejected(e) => write!(f, "服务端拒绝: {}", e),
+        }
+    }
+}
+
+/// 上报接口，可替换为其他服务或测试用的本地服务
+pub trait ScrobbleEndpoint: Send {
+    fn now_playing(&self, listen: &Listen) -> Result<(), ScrobbleError>;
+    fn submit(&self, listens: &[Listen]) -> Result<(), ScrobbleError>;
+}
+
+/// ListenBrainz 兼容接口（`POST {url}/1/submit-listens`）
+pub struct ListenBrainz {
+    url: String,
+    token: String,
+    agent: ureq::Agent,
+}
+
+impl ListenBrainz {
+    pub fn new(url: &str, token: &str) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .timeout_global(Some(REQUEST_TIMEOUT))
+            // 由下面统一按状态码分类
+            .http_status_as_error(false)
+            .build()
+            .into();
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            agent,
+        }
+    }
+
+    fn track_metadata(listen: &Listen) -> Value {
+        json!({
+            "artist_name": listen.artist,
+            "track_name": listen.title,
+            "release_name": listen.album,
+            "additional_info": {
+                "duration_ms": listen.duration * 1000,
+                "media_player": "Zotu",
+                "submission_client": "Zotu",
+                "submission_client_version": env!("CARGO_PKG_VERSION"),
+            },
+        })
+    }
+
+    fn post(&self, body: Value) -> Result<(), ScrobbleError> {
+        let response = self
+            .agent
+            .post(format!("{}/1/submit-listens", self.url))
+            .header("Authorization", format!("Token {}", self.token))
+            .send_json(body)
+            .map_err(|e| ScrobbleError::Retry(e.to_string()))?;
+
+        let status = response.status().as_u16();
+        match status {
+            200..=299 => Ok(()),
+            429 | 500..=599 => Err(ScrobbleError::Retry(format!("HTTP {}", status))),
+            _ => {
+                let message = response.into_body().read_to_string().unwrap_or_default();
+                Err(ScrobbleError::Rejected(format!(
+                    "HTTP {} {}",
+                    status, message
+                )))
+            }
+        }
+    }
+}
+
+impl ScrobbleEndpoint for ListenBrainz {
+    fn now_playing(&self, listen: &Listen) -> Result<(), ScrobbleError> {
+        self.post(json!({
+            "listen_type": "playing_now",
+            "payload": [{ "track_metadata": Self::track_metadata(listen) }],
+        }))
+    }
+
+    fn submit(&self, listens: &[Listen]) -> Result<(), ScrobbleError> {
+        let payload: Vec<Value> = listens
+            .iter()
+            .map(|listen| {
+                json!({
+                    "listened_at": listen.listened_at,
+                    "track_metadata": Self::track_metadata(listen),
+                })
+            })
+            .collect();
+        let listen_type = if payload.len() == 1 {
+            "single"
+        } else {
+            "import"
+        };
+        self.post(json!({ "listen_type": listen_type, "payload": payload }))
+    }
+}
+
+/// 需要上报的动作
+#[derive(Debug, PartialEq)]
+pub enum ScrobbleAction {
+    NowPlaying(Listen),
+    Scrobble(Listen),
+}
+
+/// 正在跟踪的曲目
+struct Tracked {
+    id: Uuid,
+    /// 开始播放时 `Player::plays_started` 的值，变化说明重新开始了一次播放
+    play: u64,
+    listen: Listen,
+    /// 实际播放的累计时长（暂停、拖动进度不计入）
+    played: Duration,
+    now_playing_sent: bool,
+    scrobbled: bool,
+}
+
+/// 按上报规则跟踪当前曲目的播放时长
+#[derive(Default)]
+pub struct ScrobbleTracker {
+    current: Option<Tracked>,
+}
+
+impl ScrobbleTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 根据最新播放状态更新
+    ///
+    /// `play` 为播放器已发出的 Started 事件数，`elapsed` 为当前曲目进度（秒），
+    /// `dt` 为距上次调用的时间，`now` 为当前 Unix 时间戳。
+    pub fn update(
+        &mut self,
+        track: Option<&AlbumInfo>,
+        play: u64,
+        playing: bool,
+        elapsed: u64,
+        dt: Duration,
+        now: i64,
+    ) -> Vec<ScrobbleAction> {
+        let Some(track) = track else {
+            self.current = None;
+            return Vec::new();
+        };
+
+        // 换歌，或单曲循环、重新点击时开始新的一次播放；拖动进度（包括拖回开头）不算
+        let restarted = |tracked: &Tracked| tracked.id != track.id() || tracked.play != play;
+        if self.current.as_ref().is_none_or(restarted) {
+            self.current = Some(Tracked {
+                id: track.id(),
+                play,
+                listen: Listen::new(track, now - elapsed as i64),
+                played: Duration::ZERO,
+                now_playing_sent: false,
+                scrobbled: false,
+            });
+        }
+
+        let mut actions = Vec::new();
+        let Some(tracked) = self.current.as_mut() else {
+            return actions;
+        };
+        if !playing {
+            return actions;
+        }
+
+        tracked.played += dt;
+        if !tracked.now_playing_sent {
+            tracked.now_playing_sent = true;
+            actions.push(ScrobbleAction::NowPlaying(tracked.listen.clone()));
+        }
+
+        let duration = tracked.listen.duration;
+        let required = (duration / 2).min(MAX_REQUIRED_SECS);
+        if !tracked.scrobbled && duration > MIN_TRACK_SECS && tracked.played.as_secs() >= required {
+            tracked.scrobbled = true;
+            actions.push(ScrobbleAction::Scrobble(tracked.listen.clone()));
+        }
+        actions
+    }
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// 第 attempts 次失败后的等待时间（秒）
+fn retry_delay(attempts: u32) -> i64 {
+    RETRY_BASE_SECS
+        .saturating_mul(1 << attempts.saturating_sub(1).min(16))
+        .min(RETRY_MAX_SECS)
+}
+
+/// 上报线程：持有独立的数据库连接，负责写入队列和发送
+pub struct ScrobbleWorker {
+    db: DB,
+    endpoint: Box<dyn ScrobbleEndpoint>,
+}
+
+impl ScrobbleWorker {
+    pub fn new(db: DB, endpoint: Box<dyn ScrobbleEndpoint>) -> Self {
+        Self { db, endpoint }
+    }
+
+    /// 处理动作直到通道关闭，空闲时按队列中的重试时间发送
+    pub fn run(self, actions: Receiver<ScrobbleAction>) {
+        loop {
+            self.flush();
+
+            let timeout = match self.db.next_scrobble_attempt() {
+                Ok(Some(next)) => {
+                    Duration::from_secs((next - unix_now()).clamp(1, RETRY_MAX_SECS) as u64)
+                }
+                Ok(None) => Duration::from_secs(RETRY_MAX_SECS as u64),
+                Err(e) => {
+                    warn!("读取上报队列失败: {}", e);
+                    Duration::from_secs(RETRY_BASE_SECS as u64)
+                }
+            };
+            match actions.recv_timeout(timeout) {
+                Ok(action) => self.handle(action),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    }
+
+    fn handle(&self, action: ScrobbleAction) {
+        match action {
+            // “正在播放”只在在线时有意义，失败不重试
+            ScrobbleAction::NowPlaying(listen) => {
+                if let Err(e) = self.endpoint.now_playing(&listen) {
+                    warn!("上报正在播放失败: {}", e);
+                }
+            }
+            ScrobbleAction::Scrobble(listen) => {
+                if let Err(e) = self.db.enqueue_scrobble(&listen) {
+                    warn!("写入上报队列失败: {}", e);
+                }
+            }
+        }
+    }
+
+    /// 发送所有到期的记录
+    pub fn flush(&self) {
+        self.flush_at(unix_now());
+    }
+
+    /// 以 now 为当前时间发送到期的记录，失败时按退避时间推迟
+    fn flush_at(&self, now: i64) {
+        loop {
+            let due = match self.db.load_due_scrobbles(now, BATCH_SIZE) {
+                Ok(due) if !due.is_empty() => due,
+                Ok(_) => return,
+                Err(e) => {
+                    warn!("读取上报队列失败: {}", e);
+                    return;
+                }
+            };
+
+            let listens: Vec<Listen> = due.iter().map(|(_, listen, _)| listen.clone()).collect();
+            let ids: Vec<i64> = due.iter().map(|(id, _, _)| *id).collect();
+            match self.endpoint.submit(&listens) {
+                Ok(()) => {
+                    if let Err(e) = self.db.remove_scrobbles(&ids) {
+                        warn!("清理上报队列失败: {}", e);
+                        return;
+                    }
+                }
+                Err(ScrobbleError::Rejected(e)) => {
+                    warn!("听歌记录被拒绝，已丢弃 {} 条: {}", ids.len(), e);
+                    if let Err(e) = self.db.remove_scrobbles(&ids) {
+                        warn!("清理上报队列失败: {}", e);
+                        return;
+                    }
+                }
+                Err(e) => {
+                    warn!("{}，稍后重试", e);
+                    for (id, _, attempts) in due {
+                        let attempts = attempts + 1;
+                        if let Err(e) =
+                            self.db
+                                .defer_scrobble(id, attempts, now + retry_delay(attempts))
+                        {
+                            warn!("更新上报队列失败: {}", e);
+                        }
+                    }
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// 按配置启动上报（未开启时什么也不做）
+pub fn init(db_path: &str, cx: &mut App) {
+    let config = &cx.global::<Config>().scrobble;
+    if !config.enabled {
+        return;
+    }
+    if config.token.is_empty() {
+        warn!("未配置上报令牌，听歌记录上报未启动");
+        return;
+    }
+    let endpoint = Box::new(ListenBrainz::new(&config.endpoint, &config.token));
+
+    // 上报线程使用独立连接，避免网络请求阻塞 UI 线程
+    let db = match DB::new(db_path) {
+        Ok(db) => db,
+        Err(e) => {
+            warn!("听歌记录上报启动失败: {}", e);
+            return;
+        }
+    };
+    let (sender, receiver) = mpsc::channel();
+    let worker = ScrobbleWorker::new(db, endpoint);
+    if let Err(e) = thread::Builder::new()
+        .name("zotu-scrobble".into())
+        .spawn(move || worker.run(receiver))
+    {
+        warn!("听歌记录上报启动失败: {}", e);
+        return;
+    }
+
+    cx.spawn(async move |cx: &mut AsyncApp| {
+        let mut tracker = ScrobbleTracker::new();
+        let mut last_tick = Instant::now();
+        loop {
+            cx.background_executor().timer(POLL_INTERVAL).await;
+
+            let now = Instant::now();
+            let dt = now - last_tick;
+            last_tick = now;
+            let result = cx.update(|cx: &mut App| {
+                let player = cx.global::<Player>();
+                let elapsed = player.progress().map(|p| p.elapsed).unwrap_or(0);
+                tracker.update(
+                    player.current_track(),
+                    player.plays_started(),
+                    player.is_playing(),
+                    elapsed,
+                    dt,
+                    unix_now(),
+                )
+            });
+            let Ok(actions) = result else {
+                break;
+            };
+            for action in actions {
+                if sender.send(action).is_err() {
+                    return;
+                }
+            }
+        }
+    })
+    .detach();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Listen, ListenBrainz, RETRY_BASE_SECS, ScrobbleAction, ScrobbleEndpoint, ScrobbleError,
+        ScrobbleTracker, ScrobbleWorker,
+    };
+    use crate::db::{database::DB, metadata::AlbumInfo};
+    use serde_json::{Value, json};
+    use std::{
+        path::PathBuf,
+        sync::{Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+    use tiny_http::{Response, Server};
+    use uuid::Uuid;
+
+    const NOW: i64 = 1_700_000_000;
+    /// 播放器的 Started 计数
+    const PLAY: u64 = 1;
+
+    fn track(duration: u64) -> AlbumInfo {
+        AlbumInfo::new(
+            Uuid::new_v4(),
+            "标题".into(),
+            "艺术家".into(),
+            "专辑".into(),
+            duration,
+            Arc::new(PathBuf::from("/music/track.flac")),
+            None,
+            None,
+        )
+    }
+
+    /// 连续播放 secs 秒（每秒更新一次），返回第几秒产生了正式记录
+    fn play(tracker: &mut ScrobbleTracker, item: &AlbumInfo, secs: u64) -> Option<u64> {
+        let mut scrobbled_at = None;
+        for elapsed in 1..=secs {
+            let actions = tracker.update(
+                Some(item),
+                PLAY,
+                true,
+                elapsed,
+                Duration::from_secs(1),
+                NOW + elapsed as i64,
+            );
+            if actions
+                .iter()
+                .any(|action| matches!(action, ScrobbleAction::Scrobble(_)))
+            {
+                assert!(scrobbled_at.is_none(), "同一次播放只应上报一次");
+                scrobbled_at = Some(elapsed);
+            }
+        }
+        scrobbled_at
+    }
+
+    #[test]
+    fn scrobbles_after_half_the_track() {
+        let item = track(200);
+        assert_eq!(play(&mut ScrobbleTracker::new(), &item, 200), Some(100));
+    }
+
+    #[test]
+    fn scrobbles_after_four_minutes_of_long_tracks() {
+        let item = track(3600);
+        assert_eq!(play(&mut ScrobbleTracker::new(), &item, 600), Some(240));
+    }
+
+    #[test]
+    fn short_tracks_are_not_scrobbled() {
+        let mut tracker = ScrobbleTracker::new();
+        let item = track(30);
+        let first = tracker.update(Some(&item), PLAY, true, 0, Duration::from_secs(1), NOW);
+        assert!(matches!(first.as_slice(), [ScrobbleAction::NowPlaying(_)]));
+        assert_eq!(play(&mut tracker, &item, 30), None);
+    }
+
+    #[test]
+    fn paused_time_is_not_counted() {
+        let mut tracker = ScrobbleTracker::new();
+        let item = track(200);
+        assert_eq!(play(&mut tracker, &item, 60), None);
+        for _ in 0..120 {
+            let actions = tracker.update(Some(&item), PLAY, false, 60, Duration::from_secs(1), NOW);
+            assert!(actions.is_empty());
+        }
+        let actions = tracker.update(Some(&item), PLAY, true, 61, Duration::from_secs(39), NOW);
+        assert!(actions.is_empty());
+        let actions = tracker.update(Some(&item), PLAY, true, 62, Duration::from_secs(1), NOW);
+        assert!(matches!(actions.as_slice(), [ScrobbleAction::Scrobble(_)]));
+    }
+
+    #[test]
+    fn seeking_back_to_start_is_not_a_new_listen() {
+        let mut tracker = ScrobbleTracker::new();
+        let item = track(200);
+        assert_eq!(play(&mut tracker, &item, 150), Some(100));
+        // 拖回开头继续听完，仍是同一次播放
+        assert_eq!(play(&mut tracker, &item, 200), None);
+    }
+
+    #[test]
+    fn repeating_a_track_is_a_new_listen() {
+        let mut tracker = ScrobbleTracker::new();
+        let item = track(200);
+        assert_eq!(play(&mut tracker, &item, 200), Some(100));
+
+        // 单曲循环：播放器再次发出 Started
+        let mut scrobbled = 0;
+        for elapsed in 0..=200 {
+            let actions = tracker.update(
+                Some(&item),
+                PLAY + 1,
+                true,
+                elapsed,
+                Duration::from_secs(1),
+                NOW + 200 + elapsed as i64,
+            );
+            for action in actions {
+                if let ScrobbleAction::Scrobble(listen) = action {
+                    assert_eq!(listen.listened_at, NOW + 200);
+                    scrobbled += 1;
+                }
+            }
+        }
+        assert_eq!(scrobbled, 1);
+    }
+
+    /// 记录收到的请求，可切换为失败或拒绝
+    #[derive(Clone, Default)]
+    struct MockEndpoint {
+        state: Arc<Mutex<MockState>>,
+    }
+
+    #[derive(Default)]
+    struct MockState {
+        failing: bool,
+        rejecting: bool,
+        attempts: usize,
+        submitted: Vec<Listen>,
+    }
+
+    impl MockEndpoint {
+        fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+            self.state.lock().unwrap()
+        }
+    }
+
+    impl ScrobbleEndpoint for MockEndpoint {
+        fn now_playing(&self, _listen: &Listen) -> Result<(), ScrobbleError> {
+            Ok(())
+        }
+
+        fn submit(&self, listens: &[Listen]) -> Result<(), ScrobbleError> {
+            let mut state = self.state();
+            state.attempts += 1;
+            if state.failing {
+                return Err(ScrobbleError::Retry("网络不可用".to_string()));
+            }
+            if state.rejecting {
+                return Err(ScrobbleError::Rejected("HTTP 400".to_string()));
+            }
+            state.submitted.extend_from_slice(listens);
+            Ok(())
+        }
+    }
+
+    fn worker(endpoint: &MockEndpoint) -> ScrobbleWorker {
+        ScrobbleWorker::new(DB::new(":memory:").unwrap(), Box::new(endpoint.clone()))
+    }
+
+    fn listen(title: &str, listened_at: i64) -> Listen {
+        Listen {
+            artist: "艺术家".to_string(),
+            title: title.to_string(),
+            album: "专辑".to_string(),
+            duration: 200,
+            listened_at,
+        }
+    }
+
+    #[test]
+    fn listens_stay_queued_while_endpoint_fails() {
+        let endpoint = MockEndpoint::default();
+        endpoint.state().failing = true;
+        let worker = worker(&endpoint);
+
+        worker.handle(ScrobbleAction::Scrobble(listen("一", NOW)));
+        worker.handle(ScrobbleAction::Scrobble(listen("二", NOW + 200)));
+        worker.flush_at(NOW);
+
+        assert_eq!(endpoint.state().attempts, 1);
+        let queued = worker.db.load_due_scrobbles(i64::MAX, 10).unwrap();
+        assert_eq!(queued.len(), 2);
+        assert!(queued.iter().all(|(_, _, attempts)| *attempts == 1));
+        assert_eq!(
+            worker.db.next_scrobble_attempt().unwrap(),
+            Some(NOW + RETRY_BASE_SECS)
+        );
+    }
+
+    #[test]
+    fn retries_back_off_then_drain_after_recovery() {
+        let endpoint = MockEndpoint::default();
+        endpoint.state().failing = true;
+        let worker = worker(&endpoint);
+        worker.handle(ScrobbleAction::Scrobble(listen("一", NOW)));
+        worker.handle(ScrobbleAction::Scrobble(listen("二", NOW + 200)));
+
+        // 第一次失败后等待 30 秒，未到期时不发送
+        worker.flush_at(NOW);
+        worker.flush_at(NOW + RETRY_BASE_SECS - 1);
+        assert_eq!(endpoint.state().attempts, 1);
+
+        // 第二次失败后等待时间翻倍
+        let second = NOW + RETRY_BASE_SECS;
+        worker.flush_at(second);
+        assert_eq!(endpoint.state().attempts, 2);
+        assert_eq!(
+            worker.db.next_scrobble_attempt().unwrap(),
+            Some(second + 2 * RETRY_BASE_SECS)
+        );
+
+        // 恢复后一次发出全部积压的记录并清空队列
+        endpoint.state().failing = false;
+        worker.flush_at(second + 2 * RETRY_BASE_SECS);
+        let state = endpoint.state();
+        assert_eq!(state.attempts, 3);
+        assert_eq!(
+            state.submitted,
+            vec![listen("一", NOW), listen("二", NOW + 200)]
+        );
+        assert_eq!(worker.db.next_scrobble_attempt().unwrap(), None);
+    }
+
+    #[test]
+    fn rejected_listens_are_dropped() {
+        let endpoint = MockEndpoint::default();
+        endpoint.state().rejecting = true;
+        let worker = worker(&endpoint);
+        worker.handle(ScrobbleAction::Scrobble(listen("一", NOW)));
+
+        worker.flush_at(NOW);
+        assert_eq!(endpoint.state().attempts, 1);
+        assert_eq!(worker.db.next_scrobble_attempt().unwrap(), None);
+    }
+
+    /// 本地 mock 服务收到的请求
+    #[derive(Debug)]
+    struct Received {
+        method: String,
+        path: String,
+        authorization: Option<String>,
+        body: Value,
+    }
+
+    /// 启动本地 HTTP 服务，按顺序以给定状态码回复，返回（地址, 收到的请求）
+    fn mock_server(statuses: Vec<u16>) -> (String, thread::JoinHandle<Vec<Received>>) {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let handle = thread::spawn(move || {
+            statuses
+                .into_iter()
+                .map(|status| {
+                    let mut request = server.recv().unwrap();
+                    let mut body = String::new();
+                    request.as_reader().read_to_string(&mut body).unwrap();
+                    let received = Received {
+                        method: request.method().to_string(),
+                        path: request.url().to_string(),
+                        authorization: request
+                            .headers()
+                            .iter()
+                            .find(|h| h.field.equiv("Authorization"))
+                            .map(|h| h.value.to_string()),
+                        body: serde_json::from_str(&body).unwrap(),
+                    };
+                    let response =
+                        Response::from_string("{\"error\": \"bad\"}").with_status_code(status);
+                    request.respond(response).unwrap();
+                    received
+                })
+                .collect()
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn listenbrainz_posts_listens_with_token() {
+        let (url, server) = mock_server(vec![200, 200, 200]);
+        // 末尾的斜杠不影响请求路径
+        let endpoint = ListenBrainz::new(&format!("{}/", url), "secret");
+
+        endpoint.now_playing(&listen("一", NOW)).unwrap();
+        endpoint.submit(&[listen("一", NOW)]).unwrap();
+        endpoint
+            .submit(&[listen("一", NOW), listen("二", NOW + 200)])
+            .unwrap();
+
+        let received = server.join().unwrap();
+        for request in &received {
+            assert_eq!(request.method, "POST");
+            assert_eq!(request.path, "/1/submit-listens");
+            assert_eq!(request.authorization.as_deref(), Some("Token secret"));
+        }
+
+        let playing_now = &received[0].body;
+        assert_eq!(playing_now["listen_type"], "playing_now");
+        assert!(playing_now["payload"][0].get("listened_at").is_none());
+
+        let single = &received[1].body;
+        assert_eq!(single["listen_type"], "single");
+        assert_eq!(single["payload"][0]["listened_at"], NOW);
+        let metadata = &single["payload"][0]["track_metadata"];
+        assert_eq!(metadata["artist_name"], "艺术家");
+        assert_eq!(metadata["track_name"], "一");
+        assert_eq!(metadata["release_name"], "专辑");
+        assert_eq!(metadata["additional_info"]["duration_ms"], json!(200_000));
+        assert_eq!(metadata["additional_info"]["media_player"], "Zotu");
+
+        let import = &received[2].body;
+        assert_eq!(import["listen_type"], "import");
+        assert_eq!(import["payload"].as_array().unwrap().len(), 2);
+        assert_eq!(import["payload"][1]["listened_at"], NOW + 200);
+    }
+
+    #[test]
+    fn listenbrainz_maps_status_codes() {
+        let statuses = vec![204, 429, 500, 503, 400, 401];
+        let (url, server) = mock_server(statuses.clone());
+        let endpoint = ListenBrainz::new(&url, "secret");
+
+        let results: Vec<_> = statuses
+            .iter()
+            .map(|_| endpoint.submit(&[listen("一", NOW)]))
+            .collect();
+        server.join().unwrap();
+
+        assert!(results[0].is_ok());
+        for result in &results[1..4] {
+            assert!(
+                matches!(result, Err(ScrobbleError::Retry(_))),
+                "{:?}",
+                result
+            );
+        }
+        for result in &results[4..] {
+            assert!(
+                matches!(result, Err(ScrobbleError::Rejected(_))),
+                "{:?}",
+                result
+            );
+        }
+        // 拒绝时附带服务端返回的内容
+        assert!(matches!(&results[4], Err(ScrobbleError::Rejected(m)) if m.contains("bad")));
+    }
+
+    #[test]
+    fn unreachable_endpoint_is_retried() {
+        // 绑定后立即释放端口，连接会被拒绝
+        let url = {
+            let server = Server::http("127.0.0.1:0").unwrap();
+            format!("http://{}", server.server_addr().to_ip().unwrap())
+        };
+        let result = ListenBrainz::new(&url, "secret").submit(&[listen("一", NOW)]);
+        assert!(matches!(result, Err(ScrobbleError::Retry(_))));
+    }
+}
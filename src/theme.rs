@@ -29,6 +29,14 @@ pub fn bg_input() -> Rgba {
 pub fn bg_playlist() -> Rgba {
     rgb(0xF5F5F5)
 }
+/// 弹窗遮罩
+pub fn bg_overlay() -> Rgba {
+    rgba(0x00000033)
+}
+/// 列表选中行
+pub fn bg_selected() -> Rgba {
+    rgb(0xE3F0FA)
+}
 
 /// 文字颜色
 pub fn text_primary() -> Rgba {
@@ -65,6 +73,12 @@ pub fn accent_blue() -> Rgba {
 pub fn accent_red() -> Rgba {
     rgb(0xFF6467)
 }
+pub fn accent_yellow() -> Rgba {
+    rgb(0xF59E0B)
+}
+pub fn accent_green() -> Rgba {
+    rgb(0x22C55E)
+}
 
 // ============================================================
 // 尺寸系统
@@ -80,7 +94,10 @@ pub const MENU_ITEM_HEIGHT: f32 = 50.0;
 pub const TITLEBAR_HEIGHT: f32 = 70.0;
 
 /// 播放栏高度
-pub const PLAYBAR_HEIGHT: f32 = 80.0;
+pub const PLAYBAR_HEIGHT: f32 = 96.0;
+
+/// 播放栏顶部进度条高度
+pub const SEEKBAR_HEIGHT: f32 = 16.0;
 
 /// 设置项高度
 pub const SETTING_ITEM_HEIGHT: f32 = 50.0;
@@ -1,2 +1,10 @@
+pub mod loop_region;
+pub mod menu;
+pub mod notification;
+pub mod palette;
+pub mod rating;
 pub mod search;
-pub mod menu;
\ No newline at end of file
+pub mod seekbar;
+pub mod smart_editor;
+pub mod tag_editor;
+pub mod visualizer;
@@ -0,0 +1,30 @@
+use gpui::*;
+
+use crate::{play::ab_loop::AbLoop, theme::*};
+
+/// 进度条上的 A–B 循环标记：完整区间显示为高亮色块，只有 A 点时显示一条竖线。
+/// 需放在 `relative()` 的进度条容器中。
+pub fn render_loop_region(ab_loop: Option<AbLoop>, duration: u64) -> Option<Div> {
+    let ab_loop = ab_loop?;
+    if duration == 0 {
+        return None;
+    }
+    let fraction =
+        |position: std::time::Duration| (position.as_secs_f32() / duration as f32).clamp(0.0, 1.0);
+
+    let mut color = accent_yellow();
+    let marker = div()
+        .absolute()
+        .top_0()
+        .h_full()
+        .left(relative(fraction(ab_loop.a)));
+    Some(match ab_loop.b {
+        Some(b) => {
+            color.a = 0.5;
+            marker
+                .w(relative(fraction(b) - fraction(ab_loop.a)))
+                .bg(color)
+        }
+        None => marker.w(px(2.0)).bg(color),
+    })
+}
@@ -1,17 +1,29 @@
 use gpui::{prelude::FluentBuilder, *};
 use uuid::Uuid;
 
-use crate::theme::*;
+use crate::{theme::*, ui::rating::render_rating};
 
-/// 右键菜单项的动作类型
+/// 右键菜单项的动作类型（作用于当前选中的全部歌曲）
 #[derive(Clone)]
 pub enum MenuAction {
     /// 添加到收藏
-    AddToFavorite(Uuid),
+    AddToFavorite(Vec<Uuid>),
     /// 从收藏中移除
-    RemoveFromFavorite(Uuid),
+    RemoveFromFavorite(Vec<Uuid>),
+    /// 设置评分（0 表示清除）
+    SetRating(Vec<Uuid>, u8),
     /// 下一首播放
-    PlayNext(Uuid),
+    PlayNext(Vec<Uuid>),
+    /// 添加到播放队列末尾
+    AddToQueue(Vec<Uuid>),
+    /// 仅播放选中的歌曲
+    PlaySelection(Vec<Uuid>),
+    /// 编辑标签
+    EditTags(Vec<Uuid>),
+    /// 从曲库移除（不删除文件）
+    RemoveFromLibrary(Vec<Uuid>),
+    /// 全选当前列表
+    SelectAll,
 }
 
 /// 菜单项配置
@@ -41,14 +53,16 @@ impl MenuItem {
 }
 
 pub struct MenuContext {
-    /// 当前上下文的目标 UUID
-    uuid: Option<Uuid>,
+    /// 当前上下文的目标 UUID 列表
+    uuids: Vec<Uuid>,
     /// 菜单是否显示
     visible: bool,
     /// 菜单位置
     position: Point<Pixels>,
-    /// 目标是否已收藏（用于动态显示菜单项）
+    /// 目标是否全部已收藏（用于动态显示菜单项）
     is_favorite: bool,
+    /// 目标的共同评分（评分不一致时为 0）
+    rating: u8,
 }
 
 impl EventEmitter<MenuAction> for MenuContext {}
@@ -56,25 +70,28 @@ impl EventEmitter<MenuAction> for MenuContext {}
 impl MenuContext {
     pub fn new() -> Self {
         Self {
-            uuid: None,
+            uuids: Vec::new(),
             visible: false,
             position: Point::default(),
             is_favorite: false,
+            rating: 0,
         }
     }
 
     /// 显示菜单
     pub fn show(
         &mut self,
-        uuid: &Uuid,
+        uuids: Vec<Uuid>,
         cx: &mut Context<Self>,
         pos: Point<Pixels>,
         is_favorite: bool,
+        rating: u8,
     ) {
-        self.uuid = Some(*uuid);
+        self.uuids = uuids;
         self.position = pos;
         self.visible = true;
         self.is_favorite = is_favorite;
+        self.rating = rating;
         cx.notify();
     }
 
@@ -86,19 +103,55 @@ impl MenuContext {
 
     /// 构建菜单项列表（根据当前状态动态生成）
     fn build_menu_items(&self) -> Vec<MenuItem> {
-        let uuid = self.uuid.unwrap_or_default();
+        let uuids = &self.uuids;
+        // 多选时在标签后显示数量
+        let label = |text: &str| -> SharedString {
+            if uuids.len() > 1 {
+                format!("{} ({})", text, uuids.len()).into()
+            } else {
+                SharedString::new(text)
+            }
+        };
         let mut items = Vec::new();
 
         if self.is_favorite {
             items.push(MenuItem::new(
-                "从收藏中移除",
-                MenuAction::RemoveFromFavorite(uuid),
+                label("从收藏中移除"),
+                MenuAction::RemoveFromFavorite(uuids.clone()),
             ));
         } else {
-            items.push(MenuItem::new("添加到收藏", MenuAction::AddToFavorite(uuid)));
+            items.push(MenuItem::new(
+                label("添加到收藏"),
+                MenuAction::AddToFavorite(uuids.clone()),
+            ));
         }
 
-        items.push(MenuItem::new("下一首播放", MenuAction::PlayNext(uuid)));
+        items.push(MenuItem::new(
+            label("下一首播放"),
+            MenuAction::PlayNext(uuids.clone()),
+        ));
+        items.push(MenuItem::new(
+            label("添加到播放队列"),
+            MenuAction::AddToQueue(uuids.clone()),
+        ));
+        if uuids.len() > 1 {
+            items.push(MenuItem::new(
+                label("播放选中歌曲"),
+                MenuAction::PlaySelection(uuids.clone()),
+            ));
+        }
+        items.push(MenuItem::new(
+            label("编辑标签"),
+            MenuAction::EditTags(uuids.clone()),
+        ));
+        items.push(MenuItem::new("全选", MenuAction::SelectAll));
+        items.push(
+            MenuItem::new(
+                label("从曲库移除"),
+                MenuAction::RemoveFromLibrary(uuids.clone()),
+            )
+            .danger(),
+        );
 
         items
     }
@@ -113,8 +166,9 @@ impl MenuContext {
 impl Render for MenuContext {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let menu_items = self.build_menu_items();
-        let menu_x = self.position.x;
-        let menu_y = self.position.y;
+        let position = self.position;
+        let uuids = self.uuids.clone();
+        let rating = self.rating;
 
         // 点击菜单外部时关闭（id() 先将 Div 转为 Stateful<Div>，使 when 闭包类型匹配）
         div().id("menu-backdrop").when(self.visible, |this| {
@@ -134,39 +188,60 @@ impl Render for MenuContext {
                         this.hide(cx);
                     }),
                 )
-                .child(
-                    div()
-                        .id("context-menu")
-                        .absolute()
-                        .left(menu_x)
-                        .top(menu_y)
-                        .min_w(px(180.0))
-                        .bg(bg_content())
-                        .rounded_lg()
-                        .shadow_md()
-                        .border_1()
-                        .border_color(border_default())
-                        .py_1()
-                        .children(menu_items.iter().map(|item| {
-                            let action = item.action.clone();
-                            let is_danger = item.danger;
-                            div()
-                                .px_3()
-                                .py_2()
-                                .text_sm()
-                                .cursor_pointer()
-                                .when(is_danger, |this| this.text_color(accent_red()))
-                                .when(!is_danger, |this| this.text_color(text_secondary()))
-                                .hover(|s| s.bg(bg_active()))
-                                .child(item.label.clone())
-                                .on_mouse_down(
-                                    MouseButton::Left,
-                                    cx.listener(move |menu, _evt, _window, cx| {
-                                        menu.execute_action(&action, cx);
-                                    }),
-                                )
-                        })),
-                )
+                .child(deferred(
+                    anchored().position(position).snap_to_window().child(
+                        div()
+                            .id("context-menu")
+                            .min_w(px(180.0))
+                            .bg(bg_content())
+                            .rounded_lg()
+                            .shadow_md()
+                            .border_1()
+                            .border_color(border_default())
+                            .py_1()
+                            // 评分
+                            .child(
+                                div()
+                                    .px_3()
+                                    .py_2()
+                                    .flex()
+                                    .flex_row()
+                                    .items_center()
+                                    .justify_between()
+                                    .gap_4()
+                                    .text_sm()
+                                    .text_color(text_secondary())
+                                    .child("评分")
+                                    .child(render_rating(rating, 14.0, |stars| {
+                                        let action = MenuAction::SetRating(uuids.clone(), stars);
+                                        cx.listener(
+                                            move |menu, _evt: &MouseDownEvent, _window, cx| {
+                                                menu.execute_action(&action, cx);
+                                            },
+                                        )
+                                    })),
+                            )
+                            .children(menu_items.iter().map(|item| {
+                                let action = item.action.clone();
+                                let is_danger = item.danger;
+                                div()
+                                    .px_3()
+                                    .py_2()
+                                    .text_sm()
+                                    .cursor_pointer()
+                                    .when(is_danger, |this| this.text_color(accent_red()))
+                                    .when(!is_danger, |this| this.text_color(text_secondary()))
+                                    .hover(|s| s.bg(bg_active()))
+                                    .child(item.label.clone())
+                                    .on_mouse_down(
+                                        MouseButton::Left,
+                                        cx.listener(move |menu, _evt, _window, cx| {
+                                            menu.execute_action(&action, cx);
+                                        }),
+                                    )
+                            })),
+                    ),
+                ))
         })
     }
 }
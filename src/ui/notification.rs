@@ -0,0 +1,342 @@
+use gpui::{prelude::FluentBuilder, *};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{
+    notify::{self, Level, Notification},
+    theme::*,
+};
+
+/// 自动消失的提示显示时长
+const TOAST_DURATION: Duration = Duration::from_secs(5);
+/// 同时显示的提示数量上限
+const MAX_TOASTS: usize = 4;
+/// 通知历史保留条数
+const MAX_HISTORY: usize = 100;
+/// 从通知通道取消息的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// 提示和历史面板的宽度
+const PANEL_WIDTH: f32 = 360.0;
+
+struct Toast {
+    notification: Notification,
+    /// 开始显示（或最后一次更新）的时间
+    shown_at: Instant,
+}
+
+impl Toast {
+    fn expired(&self, now: Instant) -> bool {
+        let notification = &self.notification;
+        notification.progress.is_none()
+            && !notification.level.is_persistent()
+            && now.duration_since(self.shown_at) >= TOAST_DURATION
+    }
+}
+
+/// 通知中心：右下角显示提示，可打开历史面板查看过去的通知
+pub struct NotificationCenter {
+    toasts: Vec<Toast>,
+    /// 通知历史（最新在前）
+    history: VecDeque<Notification>,
+    show_history: bool,
+    _poll_task: Task<()>,
+}
+
+impl NotificationCenter {
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        let task = cx.spawn(
+            async move |this: WeakEntity<NotificationCenter>, cx: &mut AsyncApp| {
+                loop {
+                    cx.background_executor().timer(POLL_INTERVAL).await;
+                    if this.update(cx, |this, cx| this.poll(cx)).is_err() {
+                        break;
+                    }
+                }
+            },
+        );
+
+        Self {
+            toasts: Vec::new(),
+            history: VecDeque::new(),
+            show_history: false,
+            _poll_task: task,
+        }
+    }
+
+    /// 取出新通知并移除过期的提示
+    fn poll(&mut self, cx: &mut Context<Self>) {
+        let now = Instant::now();
+        let incoming = notify::drain();
+        let count = self.toasts.len();
+        self.toasts.retain(|toast| !toast.expired(now));
+        if incoming.is_empty() && self.toasts.len() == count {
+            return;
+        }
+
+        for notification in incoming {
+            match self
+                .history
+                .iter_mut()
+                .find(|item| item.id == notification.id)
+            {
+                Some(item) => *item = notification.clone(),
+                None => {
+                    self.history.push_front(notification.clone());
+                    self.history.truncate(MAX_HISTORY);
+                }
+            }
+
+            let toast = Toast {
+                notification,
+                shown_at: now,
+            };
+            match self
+                .toasts
+                .iter_mut()
+                .find(|item| item.notification.id == toast.notification.id)
+            {
+                Some(item) => *item = toast,
+                None => self.toasts.push(toast),
+            }
+        }
+        cx.notify();
+    }
+
+    pub fn toggle_history(&mut self, cx: &mut Context<Self>) {
+        self.show_history = !self.show_history;
+        cx.notify();
+    }
+
+    fn dismiss(&mut self, id: u64, cx: &mut Context<Self>) {
+        self.toasts.retain(|toast| toast.notification.id != id);
+        cx.notify();
+    }
+
+    fn clear_history(&mut self, cx: &mut Context<Self>) {
+        self.history.clear();
+        cx.notify();
+    }
+}
+
+fn level_color(level: Level) -> Rgba {
+    match level {
+        Level::Info => accent_blue(),
+        Level::Success => accent_green(),
+        Level::Warning => accent_yellow(),
+        Level::Error => accent_red(),
+    }
+}
+
+/// 通知发出了多久（刚刚、N 分钟前……）
+fn format_age(time: SystemTime) -> String {
+    let secs = time.elapsed().map(|d| d.as_secs()).unwrap_or(0);
+    match secs {
+        0..60 => "刚刚".to_string(),
+        60..3600 => format!("{} 分钟前", secs / 60),
+        3600..86400 => format!("{} 小时前", secs / 3600),
+        _ => format!("{} 天前", secs / 86400),
+    }
+}
+
+/// 一条通知的内容：级别色条、标题、详情和进度
+fn render_notification(notification: &Notification) -> Div {
+    div()
+        .flex()
+        .flex_row()
+        .gap_3()
+        .child(
+            div()
+                .flex_shrink_0()
+                .w_1()
+                .rounded_full()
+                .bg(level_color(notification.level)),
+        )
+        .child(
+            div()
+                .flex_1()
+                .min_w_0()
+                .flex()
+                .flex_col()
+                .gap_1()
+                .child(
+                    div()
+                        .text_sm()
+                        .font_weight(FontWeight::MEDIUM)
+                        .child(notification.title.clone()),
+                )
+                .when_some(notification.detail.clone(), |this, detail| {
+                    this.child(div().text_xs().text_color(text_tertiary()).child(detail))
+                })
+                .when_some(notification.progress, |this, progress| {
+                    this.child(
+                        div()
+                            .mt_1()
+                            .h_1()
+                            .w_full()
+                            .rounded_full()
+                            .bg(bg_active())
+                            .child(
+                                div()
+                                    .h_full()
+                                    .w(relative(progress))
+                                    .rounded_full()
+                                    .bg(level_color(notification.level)),
+                            ),
+                    )
+                }),
+        )
+}
+
+impl NotificationCenter {
+    fn render_toasts(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let skip = self.toasts.len().saturating_sub(MAX_TOASTS);
+
+        div()
+            .absolute()
+            .right_4()
+            .bottom(px(PLAYBAR_HEIGHT + 16.0))
+            .w(px(PANEL_WIDTH))
+            .flex()
+            .flex_col()
+            .gap_2()
+            .children(self.toasts.iter().skip(skip).map(|toast| {
+                let id = toast.notification.id;
+                div()
+                    .id(ElementId::Name(format!("toast-{}", id).into()))
+                    .relative()
+                    .p_3()
+                    .pr_8()
+                    .rounded_lg()
+                    .border_1()
+                    .border_color(border_default())
+                    .bg(bg_card())
+                    .shadow_lg()
+                    .occlude()
+                    .child(render_notification(&toast.notification))
+                    .child(
+                        div()
+                            .id(ElementId::Name(format!("dismiss-toast-{}", id).into()))
+                            .absolute()
+                            .top_2()
+                            .right_2()
+                            .px_2()
+                            .rounded_md()
+                            .cursor_pointer()
+                            .text_sm()
+                            .text_color(text_tertiary())
+                            .hover(|s| s.bg(bg_hover()))
+                            .child("×")
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _evt, _window, cx| {
+                                    this.dismiss(id, cx);
+                                }),
+                            ),
+                    )
+            }))
+    }
+
+    fn render_history(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .id("notification-history")
+            .absolute()
+            .top(px(TITLEBAR_HEIGHT))
+            .right_4()
+            .w(px(PANEL_WIDTH))
+            .max_h(px(480.0))
+            .flex()
+            .flex_col()
+            .rounded_lg()
+            .border_1()
+            .border_color(border_default())
+            .bg(bg_content())
+            .shadow_lg()
+            .occlude()
+            .child(
+                div()
+                    .px_4()
+                    .py_3()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .justify_between()
+                    .border_b_1()
+                    .border_color(border_default())
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_weight(FontWeight::MEDIUM)
+                            .child("通知"),
+                    )
+                    .child(
+                        div()
+                            .id("clear-notifications")
+                            .px_2()
+                            .rounded_md()
+                            .cursor_pointer()
+                            .text_xs()
+                            .text_color(text_tertiary())
+                            .hover(|s| s.bg(bg_hover()))
+                            .child("清空")
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|this, _evt, _window, cx| {
+                                    this.clear_history(cx);
+                                }),
+                            ),
+                    ),
+            )
+            .child(
+                div()
+                    .id("notification-history-list")
+                    .flex()
+                    .flex_col()
+                    .overflow_y_scroll()
+                    .when(self.history.is_empty(), |this| {
+                        this.child(
+                            div()
+                                .px_4()
+                                .py_3()
+                                .text_sm()
+                                .text_color(text_placeholder())
+                                .child("没有通知"),
+                        )
+                    })
+                    .children(self.history.iter().map(|notification| {
+                        div()
+                            .px_4()
+                            .py_2()
+                            .flex()
+                            .flex_col()
+                            .gap_1()
+                            .border_b_1()
+                            .border_color(border_default())
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(text_placeholder())
+                                    .child(format_age(notification.time)),
+                            )
+                            .child(render_notification(notification))
+                    })),
+            )
+    }
+}
+
+impl Render for NotificationCenter {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .absolute()
+            .size_full()
+            .top_0()
+            .left_0()
+            .when(!self.toasts.is_empty(), |this| {
+                this.child(self.render_toasts(cx))
+            })
+            .when(self.show_history, |this| {
+                this.child(self.render_history(cx))
+            })
+    }
+}
@@ -0,0 +1,499 @@
+use gpui::{prelude::FluentBuilder, *};
+use std::{cmp::Reverse, collections::HashSet};
+use tracing::warn;
+
+use crate::{
+    components::sidebar::SidebarItem,
+    db::{database::DB, dbstate::LibraryState, metadata::AlbumInfo},
+    keymap::*,
+    theme::*,
+    ui::search::fuzzy_score,
+};
+
+/// 最多显示的结果数
+const MAX_RESULTS: usize = 50;
+const PALETTE_WIDTH: f32 = 560.0;
+const PALETTE_LIST_HEIGHT: f32 = 400.0;
+
+/// 需要由 Zotu 处理的选择结果（命令通过 action 直接派发）
+#[derive(Clone)]
+pub enum PaletteEvent {
+    PlayTrack(AlbumInfo),
+    PlayAlbum(Vec<AlbumInfo>),
+    OpenView(SidebarItem),
+}
+
+/// 候选项的目标
+enum PaletteTarget {
+    Action(Box<dyn Action>),
+    Track(AlbumInfo),
+    Album(SharedString),
+    View(SidebarItem),
+}
+
+impl Clone for PaletteTarget {
+    fn clone(&self) -> Self {
+        match self {
+            PaletteTarget::Action(action) => PaletteTarget::Action(action.boxed_clone()),
+            PaletteTarget::Track(item) => PaletteTarget::Track(item.clone()),
+            PaletteTarget::Album(album) => PaletteTarget::Album(album.clone()),
+            PaletteTarget::View(item) => PaletteTarget::View(*item),
+        }
+    }
+}
+
+/// 面板中的一项
+struct PaletteItem {
+    /// 分类标签（命令 / 歌单 / 专辑 / 歌曲）
+    category: &'static str,
+    label: SharedString,
+    detail: Option<SharedString>,
+    target: PaletteTarget,
+}
+
+/// 面板列出的全部命令
+fn commands() -> Vec<(&'static str, Box<dyn Action>)> {
+    vec![
+        ("播放 / 暂停", Box::new(PlayPause)),
+        ("下一首", Box::new(NextTrack)),
+        ("上一首", Box::new(PreviousTrack)),
+        ("快进 5 秒", Box::new(SeekForward)),
+        ("快退 5 秒", Box::new(SeekBackward)),
+        ("增大音量", Box::new(VolumeUp)),
+        ("减小音量", Box::new(VolumeDown)),
+        ("加快播放速度", Box::new(PlaybackFaster)),
+        ("减慢播放速度", Box::new(PlaybackSlower)),
+        ("恢复原速", Box::new(ResetPlaybackRate)),
+        ("切换随机播放", Box::new(ToggleShuffle)),
+        ("切换循环模式", Box::new(ToggleLoopMode)),
+        ("切换随机方式", Box::new(CycleShuffleMode)),
+        ("播完本首后停止", Box::new(ToggleStopAfterCurrent)),
+        ("设置 A–B 循环点", Box::new(CycleAbLoop)),
+        ("搜索歌曲", Box::new(FocusSearch)),
+        ("打开正在播放", Box::new(OpenNowPlaying)),
+        ("显示曲库", Box::new(ShowLibrary)),
+        ("显示收藏", Box::new(ShowFavorites)),
+        ("显示历史", Box::new(ShowHistory)),
+        ("显示统计", Box::new(ShowStats)),
+        ("打开设置", Box::new(OpenSettings)),
+        ("重新扫描曲库", Box::new(RescanLibrary)),
+        ("清空历史", Box::new(ClearHistory)),
+        ("通知历史", Box::new(ToggleNotifications)),
+    ]
+}
+
+/// 命令面板：模糊搜索命令、歌单、专辑和歌曲
+pub struct CommandPalette {
+    library_state: Entity<LibraryState>,
+    visible: bool,
+    query: String,
+    items: Vec<PaletteItem>,
+    /// 过滤后的结果（items 下标，按分数排序）
+    results: Vec<usize>,
+    /// 当前选中的结果下标
+    selected: usize,
+    /// 打开前的焦点，关闭时恢复
+    return_focus: Option<FocusHandle>,
+    focus_handle: FocusHandle,
+    scroll_handle: ScrollHandle,
+}
+
+impl EventEmitter<PaletteEvent> for CommandPalette {}
+
+impl CommandPalette {
+    pub fn new(library_state: Entity<LibraryState>, cx: &mut Context<Self>) -> Self {
+        Self {
+            library_state,
+            visible: false,
+            query: String::new(),
+            items: Vec::new(),
+            results: Vec::new(),
+            selected: 0,
+            return_focus: None,
+            focus_handle: cx.focus_handle(),
+            scroll_handle: ScrollHandle::new(),
+        }
+    }
+
+    pub fn toggle(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.visible {
+            self.close(window, cx);
+        } else {
+            self.open(window, cx);
+        }
+    }
+
+    fn open(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.return_focus = window.focused(cx);
+        self.items = self.build_items(cx);
+        self.query.clear();
+        self.update_results();
+        self.visible = true;
+        window.focus(&self.focus_handle);
+        cx.notify();
+    }
+
+    fn close(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.visible = false;
+        self.items.clear();
+        self.results.clear();
+        if let Some(focus) = self.return_focus.take() {
+            window.focus(&focus);
+        }
+        cx.notify();
+    }
+
+    /// 收集候选项：命令、歌单、专辑、歌曲
+    fn build_items(&self, cx: &App) -> Vec<PaletteItem> {
+        let mut items: Vec<PaletteItem> = commands()
+            .into_iter()
+            .map(|(label, action)| PaletteItem {
+                category: "命令",
+                label: label.into(),
+                detail: None,
+                target: PaletteTarget::Action(action),
+            })
+            .collect();
+
+        let db = cx.global::<DB>();
+        let smart = db
+            .load_smart_playlists()
+            .map(|playlists| {
+                playlists
+                    .into_iter()
+                    .map(|p| (p.name, SidebarItem::Smart(p.id)))
+                    .collect()
+            })
+            .unwrap_or_else(|e| {
+                warn!("加载智能歌单失败: {}", e);
+                Vec::new()
+            });
+        let custom = db
+            .load_playlists()
+            .map(|playlists| {
+                playlists
+                    .into_iter()
+                    .map(|(id, name)| (name, SidebarItem::Custom(id)))
+                    .collect()
+            })
+            .unwrap_or_else(|e| {
+                warn!("加载歌单失败: {}", e);
+                Vec::new()
+            });
+        items.extend(smart.into_iter().chain(custom).map(
+            |(name, item): (SharedString, SidebarItem)| PaletteItem {
+                category: "歌单",
+                label: name,
+                detail: None,
+                target: PaletteTarget::View(item),
+            },
+        ));
+
+        let library = self.library_state.read(cx).library();
+        let mut albums = HashSet::new();
+        for item in library.iter() {
+            let album = item.album();
+            if !album.is_empty() && albums.insert(album.clone()) {
+                items.push(PaletteItem {
+                    category: "专辑",
+                    label: album.clone(),
+                    detail: Some(item.artist()),
+                    target: PaletteTarget::Album(album),
+                });
+            }
+        }
+        items.extend(library.iter().map(|item| PaletteItem {
+            category: "歌曲",
+            label: item.title(),
+            detail: Some(format!("{} · {}", item.artist(), item.album()).into()),
+            target: PaletteTarget::Track(item.clone()),
+        }));
+
+        items
+    }
+
+    /// 按查询重新过滤并排序（标题匹配优先于附加信息）
+    fn update_results(&mut self) {
+        let query = self.query.to_lowercase();
+        let mut scored: Vec<(u32, usize)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, item)| {
+                let label = fuzzy_score(&item.label, &query);
+                let detail = item
+                    .detail
+                    .as_ref()
+                    .and_then(|detail| fuzzy_score(detail, &query))
+                    .map(|score| score / 2);
+                label.max(detail).map(|score| (score, idx))
+            })
+            .collect();
+        // 稳定排序：同分时保持命令 > 歌单 > 专辑 > 歌曲的顺序
+        scored.sort_by_key(|(score, _)| Reverse(*score));
+
+        self.results = scored
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|(_, idx)| idx)
+            .collect();
+        self.selected = 0;
+        self.scroll_handle.scroll_to_item(0);
+    }
+
+    fn move_selection(&mut self, delta: isize, cx: &mut Context<Self>) {
+        if self.results.is_empty() {
+            return;
+        }
+        self.selected = self
+            .selected
+            .saturating_add_signed(delta)
+            .min(self.results.len() - 1);
+        self.scroll_handle.scroll_to_item(self.selected);
+        cx.notify();
+    }
+
+    /// 执行选中的结果
+    fn confirm(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(target) = self
+            .results
+            .get(self.selected)
+            .map(|&idx| self.items[idx].target.clone())
+        else {
+            return;
+        };
+        // 先恢复焦点，命令沿原焦点路径派发
+        self.close(window, cx);
+
+        match target {
+            PaletteTarget::Action(action) => window.dispatch_action(action, cx),
+            PaletteTarget::Track(item) => cx.emit(PaletteEvent::PlayTrack(item)),
+            PaletteTarget::Album(album) => {
+                let tracks = self
+                    .library_state
+                    .read(cx)
+                    .library()
+                    .iter()
+                    .filter(|item| item.album() == album)
+                    .cloned()
+                    .collect();
+                cx.emit(PaletteEvent::PlayAlbum(tracks));
+            }
+            PaletteTarget::View(item) => cx.emit(PaletteEvent::OpenView(item)),
+        }
+    }
+
+    fn handle_key(&mut self, evt: &KeyDownEvent, window: &mut Window, cx: &mut Context<Self>) {
+        let keystroke = &evt.keystroke;
+        match keystroke.key.as_str() {
+            "escape" => self.close(window, cx),
+            "enter" => self.confirm(window, cx),
+            "up" => self.move_selection(-1, cx),
+            "down" => self.move_selection(1, cx),
+            "backspace" => {
+                self.query.pop();
+                self.update_results();
+                cx.notify();
+            }
+            _ => {
+                if keystroke.modifiers.control || keystroke.modifiers.platform {
+                    return;
+                }
+                if let Some(key_char) = &keystroke.key_char {
+                    self.query.push_str(key_char);
+                    self.update_results();
+                    cx.notify();
+                }
+            }
+        }
+    }
+
+    /// 命令对应的快捷键文本（按打开面板前的焦点位置查找）
+    fn shortcut_text(&self, action: &dyn Action, window: &Window) -> Option<String> {
+        let binding =
+            window.highest_precedence_binding_for_action_in(action, self.return_focus.as_ref()?)?;
+        Some(
+            binding
+                .keystrokes()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+}
+
+impl Render for CommandPalette {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let is_placeholder = self.query.is_empty();
+        let query_text = if is_placeholder {
+            "输入命令、歌曲、专辑或歌单...".to_string()
+        } else {
+            self.query.clone()
+        };
+
+        div()
+            .id("command-palette-backdrop")
+            .when(self.visible, |this| {
+                this.absolute()
+                    .size_full()
+                    .top_0()
+                    .left_0()
+                    .flex()
+                    .flex_col()
+                    .items_center()
+                    .pt(px(TITLEBAR_HEIGHT))
+                    .bg(bg_overlay())
+                    .occlude()
+                    .child(
+                        div()
+                            .id("command-palette")
+                            .key_context("CommandPalette")
+                            .track_focus(&self.focus_handle)
+                            .w(px(PALETTE_WIDTH))
+                            .flex()
+                            .flex_col()
+                            .bg(bg_content())
+                            .rounded_xl()
+                            .shadow_lg()
+                            .border_1()
+                            .border_color(border_default())
+                            .overflow_hidden()
+                            .on_key_down(cx.listener(|this, evt: &KeyDownEvent, window, cx| {
+                                this.handle_key(evt, window, cx);
+                            }))
+                            .on_mouse_down_out(cx.listener(|this, _evt, window, cx| {
+                                this.close(window, cx);
+                            }))
+                            // 输入行
+                            .child(
+                                div()
+                                    .h(px(SEARCH_BOX_HEIGHT + 8.0))
+                                    .px_4()
+                                    .flex()
+                                    .flex_row()
+                                    .items_center()
+                                    .gap_2()
+                                    .border_b_1()
+                                    .border_color(border_default())
+                                    .child(
+                                        svg()
+                                            .path("svg/search.svg")
+                                            .size_4()
+                                            .text_color(text_placeholder())
+                                            .flex_shrink_0(),
+                                    )
+                                    .child(
+                                        div()
+                                            .flex_1()
+                                            .text_sm()
+                                            .truncate()
+                                            .when(is_placeholder, |this| {
+                                                this.text_color(text_placeholder())
+                                            })
+                                            .when(!is_placeholder, |this| {
+                                                this.text_color(text_primary())
+                                            })
+                                            .child(query_text),
+                                    ),
+                            )
+                            // 结果列表
+                            .child(
+                                div()
+                                    .id("command-palette-results")
+                                    .max_h(px(PALETTE_LIST_HEIGHT))
+                                    .flex()
+                                    .flex_col()
+                                    .overflow_y_scroll()
+                                    .track_scroll(&self.scroll_handle)
+                                    .when(self.results.is_empty(), |this| {
+                                        this.child(
+                                            div()
+                                                .px_4()
+                                                .py_3()
+                                                .text_sm()
+                                                .text_color(text_placeholder())
+                                                .child("没有匹配的结果"),
+                                        )
+                                    })
+                                    .children(self.results.iter().enumerate().map(
+                                        |(pos, &idx)| {
+                                            let item = &self.items[idx];
+                                            let shortcut = match &item.target {
+                                                PaletteTarget::Action(action) => {
+                                                    self.shortcut_text(action.as_ref(), window)
+                                                }
+                                                _ => None,
+                                            };
+                                            div()
+                                                .id(ElementId::Name(
+                                                    format!("palette-{}", pos).into(),
+                                                ))
+                                                .px_4()
+                                                .py_2()
+                                                .flex()
+                                                .flex_row()
+                                                .items_center()
+                                                .gap_3()
+                                                .cursor_pointer()
+                                                .when(pos == self.selected, |this| {
+                                                    this.bg(bg_selected())
+                                                })
+                                                .hover(|style| style.bg(bg_hover()))
+                                                .child(
+                                                    div()
+                                                        .w(px(32.0))
+                                                        .flex_shrink_0()
+                                                        .text_xs()
+                                                        .text_color(text_tertiary())
+                                                        .child(item.category),
+                                                )
+                                                .child(
+                                                    div()
+                                                        .flex_1()
+                                                        .flex()
+                                                        .flex_col()
+                                                        .overflow_hidden()
+                                                        .child(
+                                                            div()
+                                                                .text_sm()
+                                                                .text_color(text_primary())
+                                                                .truncate()
+                                                                .child(item.label.clone()),
+                                                        )
+                                                        .when_some(
+                                                            item.detail.clone(),
+                                                            |this, detail| {
+                                                                this.child(
+                                                                    div()
+                                                                        .text_xs()
+                                                                        .text_color(text_tertiary())
+                                                                        .truncate()
+                                                                        .child(detail),
+                                                                )
+                                                            },
+                                                        ),
+                                                )
+                                                .when_some(shortcut, |this, shortcut| {
+                                                    this.child(
+                                                        div()
+                                                            .flex_shrink_0()
+                                                            .text_xs()
+                                                            .text_color(text_placeholder())
+                                                            .child(shortcut),
+                                                    )
+                                                })
+                                                .on_mouse_down(
+                                                    MouseButton::Left,
+                                                    cx.listener(move |this, _evt, window, cx| {
+                                                        this.selected = pos;
+                                                        this.confirm(window, cx);
+                                                    }),
+                                                )
+                                        },
+                                    )),
+                            ),
+                    )
+            })
+    }
+}
@@ -0,0 +1,42 @@
+use gpui::*;
+
+use crate::theme::*;
+
+/// 最高星级
+pub const MAX_RATING: u8 = 5;
+
+/// 星级评分控件：点击第 n 颗星评为 n 星，点击当前星级则清除评分。
+/// `on_rate` 为每个目标星级（0–5）生成点击回调；点击不会继续冒泡到外层（如歌曲行）。
+pub fn render_rating<F, L>(rating: u8, size: f32, mut on_rate: F) -> Div
+where
+    F: FnMut(u8) -> L,
+    L: Fn(&MouseDownEvent, &mut Window, &mut App) + 'static,
+{
+    div()
+        .flex()
+        .flex_row()
+        .items_center()
+        .gap(px(2.0))
+        .children((1..=MAX_RATING).map(|stars| {
+            let listener = on_rate(if stars == rating { 0 } else { stars });
+            let filled = stars <= rating;
+            svg()
+                .path(if filled {
+                    "svg/star-fill.svg"
+                } else {
+                    "svg/star.svg"
+                })
+                .size(px(size))
+                .flex_shrink_0()
+                .cursor_pointer()
+                .text_color(if filled {
+                    accent_yellow()
+                } else {
+                    text_muted()
+                })
+                .on_mouse_down(MouseButton::Left, move |evt, window, cx| {
+                    cx.stop_propagation();
+                    listener(evt, window, cx);
+                })
+        }))
+}
@@ -2,6 +2,40 @@ use gpui::{prelude::FluentBuilder, *};
 
 use crate::theme::*;
 
+/// 子串匹配（忽略大小写），query 需为小写
+pub fn matches(text: &str, query: &str) -> bool {
+    text.to_lowercase().contains(query)
+}
+
+/// 模糊匹配评分（忽略大小写），query 需为小写，不匹配时返回 None
+///
+/// 子串匹配优先（越靠前分数越高），其次是按顺序出现的字符（连续命中加分）
+pub fn fuzzy_score(text: &str, query: &str) -> Option<u32> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let text = text.to_lowercase();
+    if let Some(pos) = text.find(query) {
+        return Some(1000 - pos.min(500) as u32);
+    }
+
+    let mut score = 0;
+    let mut consecutive = false;
+    let mut pending = query.chars().peekable();
+    for c in text.chars() {
+        match pending.peek() {
+            Some(&q) if q == c => {
+                score += if consecutive { 3 } else { 1 };
+                consecutive = true;
+                pending.next();
+            }
+            Some(_) => consecutive = false,
+            None => break,
+        }
+    }
+    pending.peek().is_none().then_some(score.min(499))
+}
+
 /// 搜索事件
 #[derive(Clone)]
 pub struct SearchEvent {
@@ -31,6 +65,13 @@ impl SearchBox {
         }
     }
 
+    /// 获取焦点（快捷键调用）
+    pub fn focus(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.is_focused = true;
+        window.focus(&self.focus_handle);
+        cx.notify();
+    }
+
     /// 处理键盘输入
     fn handle_key_input(&mut self, input: &str, cx: &mut Context<Self>) {
         self.search_query.push_str(input);
@@ -0,0 +1,76 @@
+use gpui::*;
+
+use std::{cell::Cell, rc::Rc, sync::Arc};
+
+use crate::theme::*;
+
+/// 无波形数据时进度条的高度
+const PLAIN_BAR_HEIGHT: f32 = 3.0;
+/// 波形柱之间的间距
+const PEAK_GAP: f32 = 1.0;
+
+/// 进度条：有波形峰值时绘制波形（已播放部分高亮），否则绘制普通进度条。
+/// 绘制时把自身区域写入 bounds，供点击和拖动时换算位置。
+pub fn render_seekbar(
+    peaks: Option<Arc<Vec<u8>>>,
+    progress: f32,
+    bounds: Rc<Cell<Bounds<Pixels>>>,
+) -> impl IntoElement {
+    canvas(
+        move |area, _window, _cx| bounds.set(area),
+        move |area, _, window, _cx| {
+            let width = f32::from(area.size.width);
+            let height = f32::from(area.size.height);
+            let played_x = width * progress.clamp(0.0, 1.0);
+
+            let Some(peaks) = peaks.filter(|peaks| !peaks.is_empty()) else {
+                let top = area.origin.y + px((height - PLAIN_BAR_HEIGHT) / 2.0);
+                window.paint_quad(fill(
+                    Bounds::new(
+                        point(area.origin.x, top),
+                        size(area.size.width, px(PLAIN_BAR_HEIGHT)),
+                    ),
+                    bg_hover(),
+                ));
+                window.paint_quad(fill(
+                    Bounds::new(
+                        point(area.origin.x, top),
+                        size(px(played_x), px(PLAIN_BAR_HEIGHT)),
+                    ),
+                    accent_blue(),
+                ));
+                return;
+            };
+
+            let step = width / peaks.len() as f32;
+            let bar_width = (step - PEAK_GAP).max(1.0);
+            for (index, peak) in peaks.iter().enumerate() {
+                let x = index as f32 * step;
+                let bar_height = (*peak as f32 / 255.0 * height).max(1.0);
+                let origin = point(
+                    area.origin.x + px(x),
+                    area.origin.y + px((height - bar_height) / 2.0),
+                );
+                let color = if x + bar_width / 2.0 <= played_x {
+                    accent_blue()
+                } else {
+                    text_muted()
+                };
+                window.paint_quad(fill(
+                    Bounds::new(origin, size(px(bar_width), px(bar_height))),
+                    color,
+                ));
+            }
+        },
+    )
+    .size_full()
+}
+
+/// 鼠标位置对应的进度比例
+pub fn seek_fraction(bounds: Bounds<Pixels>, position: Point<Pixels>) -> f32 {
+    let width = f32::from(bounds.size.width);
+    if width <= 0.0 {
+        return 0.0;
+    }
+    (f32::from(position.x - bounds.origin.x) / width).clamp(0.0, 1.0)
+}
@@ -0,0 +1,843 @@
+use gpui::{prelude::FluentBuilder, *};
+
+use crate::{
+    db::smart::{Condition, Rule, SmartPlaylist, SortKey, SortOrder},
+    theme::*,
+};
+
+/// 智能歌单编辑器的保存、删除事件
+#[derive(Clone)]
+pub enum SmartPlaylistEvent {
+    /// 保存（ID 为 0 表示新建）
+    Saved(SmartPlaylist),
+    Deleted(i64),
+}
+
+/// 规则类型（对应 Rule 的各个变体）
+#[derive(Clone, Copy, PartialEq)]
+enum RuleKind {
+    Artist,
+    Genre,
+    AddedWithin,
+    PlayCount,
+    NeverPlayed,
+    DurationLess,
+    Favorite,
+    Rating,
+}
+
+impl RuleKind {
+    fn all() -> [RuleKind; 8] {
+        [
+            RuleKind::Artist,
+            RuleKind::Genre,
+            RuleKind::AddedWithin,
+            RuleKind::PlayCount,
+            RuleKind::NeverPlayed,
+            RuleKind::DurationLess,
+            RuleKind::Favorite,
+            RuleKind::Rating,
+        ]
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            RuleKind::Artist => "艺术家是",
+            RuleKind::Genre => "流派属于",
+            RuleKind::AddedWithin => "最近添加（天）",
+            RuleKind::PlayCount => "播放次数大于",
+            RuleKind::NeverPlayed => "从未播放",
+            RuleKind::DurationLess => "时长小于（分钟）",
+            RuleKind::Favorite => "已收藏",
+            RuleKind::Rating => "评分不低于（星）",
+        }
+    }
+
+    /// 输入框的提示文字，None 表示该规则没有参数
+    fn placeholder(&self) -> Option<&'static str> {
+        match self {
+            RuleKind::Artist => Some("艺术家名"),
+            RuleKind::Genre => Some("多个流派用逗号分隔"),
+            RuleKind::AddedWithin => Some("天数"),
+            RuleKind::PlayCount => Some("次数"),
+            RuleKind::DurationLess => Some("分钟"),
+            RuleKind::Rating => Some("1–5"),
+            RuleKind::NeverPlayed | RuleKind::Favorite => None,
+        }
+    }
+
+    /// 由输入的参数生成规则
+    fn to_rule(self, value: &str) -> Result<Rule, String> {
+        let value = value.trim();
+        let number = |name: &str| -> Result<u32, String> {
+            value
+                .parse::<u32>()
+                .map_err(|_| format!("“{}”需要填写整数", name))
+        };
+        match self {
+            RuleKind::Artist if value.is_empty() => Err("请填写艺术家".to_string()),
+            RuleKind::Artist => Ok(Rule::ArtistIs(value.to_string())),
+            RuleKind::Genre => {
+                let genres: Vec<String> = value
+                    .split([',', '，'])
+                    .map(|genre| genre.trim().to_string())
+                    .filter(|genre| !genre.is_empty())
+                    .collect();
+                if genres.is_empty() {
+                    return Err("请填写至少一个流派".to_string());
+                }
+                Ok(Rule::GenreIn(genres))
+            }
+            RuleKind::AddedWithin => Ok(Rule::AddedWithinDays(number(self.label())?)),
+            RuleKind::PlayCount => Ok(Rule::PlayCountGreaterThan(number(self.label())?)),
+            RuleKind::NeverPlayed => Ok(Rule::NeverPlayed),
+            RuleKind::DurationLess => match value.parse::<f64>() {
+                Ok(minutes) if minutes > 0.0 => {
+                    Ok(Rule::DurationLessThan((minutes * 60.0).round() as u64))
+                }
+                _ => Err(format!("“{}”需要填写正数", self.label())),
+            },
+            RuleKind::Favorite => Ok(Rule::IsFavorite),
+            RuleKind::Rating => match number(self.label())? {
+                stars @ 1..=5 => Ok(Rule::RatingAtLeast(stars as u8)),
+                _ => Err("评分需要在 1 到 5 之间".to_string()),
+            },
+        }
+    }
+
+    /// 拆分已有规则为类型和参数文本
+    fn from_rule(rule: &Rule) -> (RuleKind, String) {
+        match rule {
+            Rule::ArtistIs(artist) => (RuleKind::Artist, artist.clone()),
+            Rule::GenreIn(genres) => (RuleKind::Genre, genres.join(", ")),
+            Rule::AddedWithinDays(days) => (RuleKind::AddedWithin, days.to_string()),
+            Rule::PlayCountGreaterThan(count) => (RuleKind::PlayCount, count.to_string()),
+            Rule::NeverPlayed => (RuleKind::NeverPlayed, String::new()),
+            Rule::DurationLessThan(secs) => {
+                (RuleKind::DurationLess, (*secs as f64 / 60.0).to_string())
+            }
+            Rule::IsFavorite => (RuleKind::Favorite, String::new()),
+            Rule::RatingAtLeast(stars) => (RuleKind::Rating, stars.to_string()),
+        }
+    }
+}
+
+/// 编辑器中的一条规则
+struct RuleRow {
+    kind: RuleKind,
+    value: String,
+}
+
+/// 正在输入的字段
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Name,
+    Rule(usize),
+    Limit,
+}
+
+/// 智能歌单编辑弹窗：名称、规则（全部/任一满足）、排序和数量上限
+pub struct SmartPlaylistEditor {
+    /// 正在编辑的歌单 ID（0 表示新建）
+    id: i64,
+    visible: bool,
+    name: String,
+    /// true 为全部满足（AND），false 为任一满足（OR）
+    match_all: bool,
+    rules: Vec<RuleRow>,
+    sort: Option<SortKey>,
+    descending: bool,
+    limit: String,
+    active: Field,
+    /// 正在选择类型的规则
+    kind_menu: Option<usize>,
+    error: Option<SharedString>,
+    /// 打开后下一次渲染时获取焦点
+    pending_focus: bool,
+    focus_handle: FocusHandle,
+}
+
+impl EventEmitter<SmartPlaylistEvent> for SmartPlaylistEditor {}
+
+impl SmartPlaylistEditor {
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        Self {
+            id: 0,
+            visible: false,
+            name: String::new(),
+            match_all: true,
+            rules: Vec::new(),
+            sort: None,
+            descending: false,
+            limit: String::new(),
+            active: Field::Name,
+            kind_menu: None,
+            error: None,
+            pending_focus: false,
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    /// 新建智能歌单
+    pub fn open_new(&mut self, cx: &mut Context<Self>) {
+        let playlist =
+            SmartPlaylist::new("", Condition::All(vec![Condition::Rule(Rule::NeverPlayed)]));
+        self.open(&playlist, cx);
+    }
+
+    /// 编辑已有的智能歌单
+    pub fn open(&mut self, playlist: &SmartPlaylist, cx: &mut Context<Self>) {
+        let (match_all, rules, nested) = match &playlist.condition {
+            Condition::Rule(rule) => (true, vec![rule.clone()], false),
+            Condition::All(children) => (true, leaf_rules(children), has_nested(children)),
+            Condition::Any(children) => (false, leaf_rules(children), has_nested(children)),
+        };
+
+        self.id = playlist.id;
+        self.name = playlist.name.to_string();
+        self.match_all = match_all;
+        self.rules = rules
+            .iter()
+            .map(|rule| {
+                let (kind, value) = RuleKind::from_rule(rule);
+                RuleRow { kind, value }
+            })
+            .collect();
+        self.sort = playlist.sort.map(|sort| sort.key);
+        self.descending = playlist.sort.is_some_and(|sort| sort.descending);
+        self.limit = playlist
+            .limit
+            .map(|limit| limit.to_string())
+            .unwrap_or_default();
+        self.active = Field::Name;
+        self.kind_menu = None;
+        self.error = nested.then(|| "该歌单包含嵌套条件，保存后将只保留其中的规则".into());
+        self.visible = true;
+        self.pending_focus = true;
+        cx.notify();
+    }
+
+    fn close(&mut self, cx: &mut Context<Self>) {
+        self.visible = false;
+        cx.notify();
+    }
+
+    /// 由输入生成歌单定义，输入有误时返回错误说明
+    fn build(&self) -> Result<SmartPlaylist, String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("请填写歌单名称".to_string());
+        }
+        if self.rules.is_empty() {
+            return Err("请至少添加一条规则".to_string());
+        }
+        let rules = self
+            .rules
+            .iter()
+            .map(|row| row.kind.to_rule(&row.value).map(Condition::Rule))
+            .collect::<Result<Vec<_>, _>>()?;
+        let limit = match self.limit.trim() {
+            "" => None,
+            limit => match limit.parse::<u32>() {
+                Ok(limit) if limit > 0 => Some(limit),
+                _ => return Err("数量上限需要填写正整数".to_string()),
+            },
+        };
+
+        let condition = if self.match_all {
+            Condition::All(rules)
+        } else {
+            Condition::Any(rules)
+        };
+        let mut playlist = SmartPlaylist::new(name.to_string(), condition);
+        playlist.id = self.id;
+        playlist.sort = self.sort.map(|key| SortOrder {
+            key,
+            descending: self.descending,
+        });
+        playlist.limit = limit;
+        Ok(playlist)
+    }
+
+    fn save(&mut self, cx: &mut Context<Self>) {
+        match self.build() {
+            Ok(playlist) => {
+                cx.emit(SmartPlaylistEvent::Saved(playlist));
+                self.close(cx);
+            }
+            Err(e) => {
+                self.error = Some(e.into());
+                cx.notify();
+            }
+        }
+    }
+
+    fn delete(&mut self, cx: &mut Context<Self>) {
+        if self.id != 0 {
+            cx.emit(SmartPlaylistEvent::Deleted(self.id));
+        }
+        self.close(cx);
+    }
+
+    fn add_rule(&mut self, cx: &mut Context<Self>) {
+        self.rules.push(RuleRow {
+            kind: RuleKind::Artist,
+            value: String::new(),
+        });
+        self.active = Field::Rule(self.rules.len() - 1);
+        cx.notify();
+    }
+
+    fn remove_rule(&mut self, index: usize, cx: &mut Context<Self>) {
+        if index < self.rules.len() {
+            self.rules.remove(index);
+        }
+        self.active = Field::Name;
+        self.kind_menu = None;
+        cx.notify();
+    }
+
+    fn set_kind(&mut self, index: usize, kind: RuleKind, cx: &mut Context<Self>) {
+        if let Some(row) = self.rules.get_mut(index)
+            && row.kind != kind
+        {
+            row.kind = kind;
+            row.value.clear();
+        }
+        self.kind_menu = None;
+        if kind.placeholder().is_some() {
+            self.active = Field::Rule(index);
+        }
+        cx.notify();
+    }
+
+    /// 可输入的字段（按 Tab 顺序）
+    fn fields(&self) -> Vec<Field> {
+        let rules = self
+            .rules
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| row.kind.placeholder().is_some())
+            .map(|(i, _)| Field::Rule(i));
+        std::iter::once(Field::Name)
+            .chain(rules)
+            .chain(std::iter::once(Field::Limit))
+            .collect()
+    }
+
+    fn focus_next(&mut self, cx: &mut Context<Self>) {
+        let fields = self.fields();
+        let index = fields.iter().position(|field| *field == self.active);
+        self.active = fields[index.map_or(0, |i| (i + 1) % fields.len())];
+        cx.notify();
+    }
+
+    fn active_value(&mut self) -> Option<&mut String> {
+        match self.active {
+            Field::Name => Some(&mut self.name),
+            Field::Rule(i) => self.rules.get_mut(i).map(|row| &mut row.value),
+            Field::Limit => Some(&mut self.limit),
+        }
+    }
+
+    fn handle_key(&mut self, evt: &KeyDownEvent, cx: &mut Context<Self>) {
+        match evt.keystroke.key.as_str() {
+            "escape" => self.close(cx),
+            "enter" => self.save(cx),
+            "tab" => self.focus_next(cx),
+            "backspace" => {
+                if let Some(value) = self.active_value() {
+                    value.pop();
+                }
+                cx.notify();
+            }
+            _ => {
+                if let Some(key_char) = evt.keystroke.key_char.clone()
+                    && let Some(value) = self.active_value()
+                {
+                    value.push_str(&key_char);
+                    cx.notify();
+                }
+            }
+        }
+    }
+}
+
+/// 条件中的叶子规则（嵌套的组合条件按顺序展开）
+fn leaf_rules(children: &[Condition]) -> Vec<Rule> {
+    children
+        .iter()
+        .flat_map(|child| match child {
+            Condition::Rule(rule) => vec![rule.clone()],
+            Condition::All(children) | Condition::Any(children) => leaf_rules(children),
+        })
+        .collect()
+}
+
+fn has_nested(children: &[Condition]) -> bool {
+    children
+        .iter()
+        .any(|child| !matches!(child, Condition::Rule(_)))
+}
+
+/// 可点选的小标签
+fn render_chip(
+    id: impl Into<ElementId>,
+    label: impl Into<SharedString>,
+    selected: bool,
+) -> Stateful<Div> {
+    div()
+        .id(id)
+        .px_3()
+        .py_1()
+        .rounded_lg()
+        .cursor_pointer()
+        .text_sm()
+        .text_color(text_secondary())
+        .hover(|s| s.bg(bg_hover()))
+        .when(selected, |this| this.bg(bg_active()))
+        .child(label.into())
+}
+
+/// 输入框（内容为空时显示提示文字）
+fn render_input(
+    id: impl Into<ElementId>,
+    value: &str,
+    placeholder: &str,
+    active: bool,
+) -> Stateful<Div> {
+    let empty = value.is_empty();
+    div()
+        .id(id)
+        .h(px(SEARCH_BOX_HEIGHT))
+        .px_3()
+        .flex()
+        .items_center()
+        .bg(bg_input())
+        .rounded_lg()
+        .border_1()
+        .border_color(input_focus_ring(active))
+        .text_sm()
+        .truncate()
+        .cursor_text()
+        .text_color(if empty {
+            text_placeholder()
+        } else {
+            text_secondary()
+        })
+        .child(if empty {
+            placeholder.to_string()
+        } else {
+            value.to_string()
+        })
+}
+
+fn render_label(label: &'static str) -> Div {
+    div().text_xs().text_color(text_tertiary()).child(label)
+}
+
+impl SmartPlaylistEditor {
+    fn render_rule(&self, index: usize, row: &RuleRow, cx: &mut Context<Self>) -> Div {
+        let menu_open = self.kind_menu == Some(index);
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .child(
+                div()
+                    .flex()
+                    .flex_row()
+                    .items_center()
+                    .gap_2()
+                    .child(
+                        render_chip(
+                            ElementId::Name(format!("smart-rule-kind-{}", index).into()),
+                            format!("{} ▾", row.kind.label()),
+                            menu_open,
+                        )
+                        .flex_shrink_0()
+                        .bg(bg_input())
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |this, _evt, _window, cx| {
+                                this.kind_menu = if this.kind_menu == Some(index) {
+                                    None
+                                } else {
+                                    Some(index)
+                                };
+                                cx.notify();
+                            }),
+                        ),
+                    )
+                    .when_some(row.kind.placeholder(), |this, placeholder| {
+                        this.child(
+                            render_input(
+                                ElementId::Name(format!("smart-rule-value-{}", index).into()),
+                                &row.value,
+                                placeholder,
+                                self.active == Field::Rule(index),
+                            )
+                            .flex_1()
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _evt, window, cx| {
+                                    this.active = Field::Rule(index);
+                                    window.focus(&this.focus_handle);
+                                    cx.notify();
+                                }),
+                            ),
+                        )
+                    })
+                    .when(row.kind.placeholder().is_none(), |this| {
+                        this.child(div().flex_1())
+                    })
+                    .child(
+                        render_chip(
+                            ElementId::Name(format!("smart-rule-remove-{}", index).into()),
+                            "×",
+                            false,
+                        )
+                        .flex_shrink_0()
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(move |this, _evt, _window, cx| {
+                                this.remove_rule(index, cx);
+                            }),
+                        ),
+                    ),
+            )
+            .when(menu_open, |this| {
+                this.child(
+                    div()
+                        .flex()
+                        .flex_row()
+                        .flex_wrap()
+                        .gap_1()
+                        .p_2()
+                        .rounded_lg()
+                        .bg(bg_card())
+                        .children(RuleKind::all().into_iter().enumerate().map(|(k, kind)| {
+                            render_chip(
+                                ElementId::Name(format!("smart-rule-{}-kind-{}", index, k).into()),
+                                kind.label(),
+                                kind == row.kind,
+                            )
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(move |this, _evt, _window, cx| {
+                                    this.set_kind(index, kind, cx);
+                                }),
+                            )
+                        })),
+                )
+            })
+    }
+}
+
+impl Render for SmartPlaylistEditor {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if self.visible && self.pending_focus {
+            window.focus(&self.focus_handle);
+            self.pending_focus = false;
+        }
+        if !self.visible {
+            return div().id("smart-editor-backdrop");
+        }
+
+        let title = if self.id == 0 {
+            "新建智能歌单"
+        } else {
+            "编辑智能歌单"
+        };
+        let rules: Vec<Div> = self
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, row)| self.render_rule(i, row, cx))
+            .collect();
+
+        div()
+            .id("smart-editor-backdrop")
+            .absolute()
+            .size_full()
+            .top_0()
+            .left_0()
+            .flex()
+            .items_center()
+            .justify_center()
+            .bg(bg_overlay())
+            .occlude()
+            .child(
+                div()
+                    .id("smart-editor")
+                    .key_context("SmartPlaylistEditor")
+                    .track_focus(&self.focus_handle)
+                    .w(px(520.0))
+                    .max_h(px(640.0))
+                    .overflow_y_scroll()
+                    .p_6()
+                    .flex()
+                    .flex_col()
+                    .gap_3()
+                    .bg(bg_content())
+                    .rounded_xl()
+                    .shadow_lg()
+                    .border_1()
+                    .border_color(border_default())
+                    .on_key_down(cx.listener(|this, evt: &KeyDownEvent, _window, cx| {
+                        this.handle_key(evt, cx);
+                    }))
+                    .child(
+                        div()
+                            .text_lg()
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(text_primary())
+                            .child(title),
+                    )
+                    // 名称
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .gap_1()
+                            .child(render_label("名称"))
+                            .child(
+                                render_input(
+                                    "smart-name",
+                                    &self.name,
+                                    "歌单名称",
+                                    self.active == Field::Name,
+                                )
+                                .on_mouse_down(
+                                    MouseButton::Left,
+                                    cx.listener(|this, _evt, window, cx| {
+                                        this.active = Field::Name;
+                                        window.focus(&this.focus_handle);
+                                        cx.notify();
+                                    }),
+                                ),
+                            ),
+                    )
+                    // 规则
+                    .child(
+                        div()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap_2()
+                            .child(render_label("满足"))
+                            .child(
+                                render_chip("smart-match-all", "全部规则", self.match_all)
+                                    .on_mouse_down(
+                                        MouseButton::Left,
+                                        cx.listener(|this, _evt, _window, cx| {
+                                            this.match_all = true;
+                                            cx.notify();
+                                        }),
+                                    ),
+                            )
+                            .child(
+                                render_chip("smart-match-any", "任一规则", !self.match_all)
+                                    .on_mouse_down(
+                                        MouseButton::Left,
+                                        cx.listener(|this, _evt, _window, cx| {
+                                            this.match_all = false;
+                                            cx.notify();
+                                        }),
+                                    ),
+                            ),
+                    )
+                    .children(rules)
+                    .child(
+                        div().flex().flex_row().child(
+                            render_chip("smart-add-rule", "+ 添加规则", false)
+                                .bg(bg_input())
+                                .on_mouse_down(
+                                    MouseButton::Left,
+                                    cx.listener(|this, _evt, _window, cx| {
+                                        this.add_rule(cx);
+                                    }),
+                                ),
+                        ),
+                    )
+                    // 排序
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .gap_1()
+                            .child(render_label("排序"))
+                            .child(
+                                div()
+                                    .flex()
+                                    .flex_row()
+                                    .flex_wrap()
+                                    .gap_1()
+                                    .child(
+                                        render_chip(
+                                            "smart-sort-none",
+                                            "不排序",
+                                            self.sort.is_none(),
+                                        )
+                                        .on_mouse_down(
+                                            MouseButton::Left,
+                                            cx.listener(|this, _evt, _window, cx| {
+                                                this.sort = None;
+                                                cx.notify();
+                                            }),
+                                        ),
+                                    )
+                                    .children(SortKey::all().into_iter().map(|key| {
+                                        render_chip(
+                                            ElementId::Name(
+                                                format!("smart-sort-{}", key.label()).into(),
+                                            ),
+                                            key.label(),
+                                            self.sort == Some(key),
+                                        )
+                                        .on_mouse_down(
+                                            MouseButton::Left,
+                                            cx.listener(move |this, _evt, _window, cx| {
+                                                this.sort = Some(key);
+                                                cx.notify();
+                                            }),
+                                        )
+                                    })),
+                            )
+                            .when(
+                                self.sort.is_some_and(|key| key != SortKey::Random),
+                                |this| {
+                                    this.child(
+                                        div()
+                                            .flex()
+                                            .flex_row()
+                                            .gap_1()
+                                            .child(
+                                                render_chip(
+                                                    "smart-sort-asc",
+                                                    "升序",
+                                                    !self.descending,
+                                                )
+                                                .on_mouse_down(
+                                                    MouseButton::Left,
+                                                    cx.listener(|this, _evt, _window, cx| {
+                                                        this.descending = false;
+                                                        cx.notify();
+                                                    }),
+                                                ),
+                                            )
+                                            .child(
+                                                render_chip(
+                                                    "smart-sort-desc",
+                                                    "降序",
+                                                    self.descending,
+                                                )
+                                                .on_mouse_down(
+                                                    MouseButton::Left,
+                                                    cx.listener(|this, _evt, _window, cx| {
+                                                        this.descending = true;
+                                                        cx.notify();
+                                                    }),
+                                                ),
+                                            ),
+                                    )
+                                },
+                            ),
+                    )
+                    // 数量上限
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .gap_1()
+                            .child(render_label("最多歌曲数"))
+                            .child(
+                                render_input(
+                                    "smart-limit",
+                                    &self.limit,
+                                    "不限",
+                                    self.active == Field::Limit,
+                                )
+                                .on_mouse_down(
+                                    MouseButton::Left,
+                                    cx.listener(|this, _evt, window, cx| {
+                                        this.active = Field::Limit;
+                                        window.focus(&this.focus_handle);
+                                        cx.notify();
+                                    }),
+                                ),
+                            ),
+                    )
+                    .when_some(self.error.clone(), |this, error| {
+                        this.child(div().text_sm().text_color(accent_red()).child(error))
+                    })
+                    .child(
+                        div()
+                            .mt_2()
+                            .flex()
+                            .flex_row()
+                            .gap_3()
+                            .when(self.id != 0, |this| {
+                                this.child(
+                                    div()
+                                        .id("smart-editor-delete")
+                                        .px_4()
+                                        .py_2()
+                                        .rounded_lg()
+                                        .cursor_pointer()
+                                        .text_sm()
+                                        .text_color(accent_red())
+                                        .hover(|s| s.bg(bg_active()))
+                                        .child("删除歌单")
+                                        .on_mouse_down(
+                                            MouseButton::Left,
+                                            cx.listener(|this, _evt, _window, cx| {
+                                                this.delete(cx);
+                                            }),
+                                        ),
+                                )
+                            })
+                            .child(div().flex_1())
+                            .child(
+                                div()
+                                    .id("smart-editor-cancel")
+                                    .px_4()
+                                    .py_2()
+                                    .rounded_lg()
+                                    .cursor_pointer()
+                                    .text_sm()
+                                    .text_color(text_secondary())
+                                    .hover(|s| s.bg(bg_active()))
+                                    .child("取消")
+                                    .on_mouse_down(
+                                        MouseButton::Left,
+                                        cx.listener(|this, _evt, _window, cx| {
+                                            this.close(cx);
+                                        }),
+                                    ),
+                            )
+                            .child(
+                                div()
+                                    .id("smart-editor-save")
+                                    .px_4()
+                                    .py_2()
+                                    .rounded_lg()
+                                    .cursor_pointer()
+                                    .text_sm()
+                                    .font_weight(FontWeight::MEDIUM)
+                                    .text_color(text_primary())
+                                    .bg(accent_blue())
+                                    .hover(|s| s.bg(bg_active()))
+                                    .child("保存")
+                                    .on_mouse_down(
+                                        MouseButton::Left,
+                                        cx.listener(|this, _evt, _window, cx| {
+                                            this.save(cx);
+                                        }),
+                                    ),
+                            ),
+                    ),
+            )
+    }
+}
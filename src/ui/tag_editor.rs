@@ -0,0 +1,290 @@
+use gpui::{prelude::FluentBuilder, *};
+use uuid::Uuid;
+
+use crate::{
+    db::metadata::{AlbumInfo, TagEdit},
+    theme::*,
+};
+
+/// 标签编辑保存事件
+#[derive(Clone)]
+pub struct TagEditEvent {
+    pub ids: Vec<Uuid>,
+    pub edit: TagEdit,
+}
+
+/// 可编辑字段：标题、艺术家、专辑、流派
+const FIELD_LABELS: [&str; 4] = ["标题", "艺术家", "专辑", "流派"];
+const TITLE_FIELD: usize = 0;
+
+/// 标签编辑弹窗（支持多选批量编辑）
+pub struct TagEditor {
+    /// 正在编辑的歌曲
+    ids: Vec<Uuid>,
+    /// 弹窗是否显示
+    visible: bool,
+    /// 打开时的原值（多选且各不相同时为 None）
+    originals: [Option<String>; 4],
+    /// 当前输入值（None 表示保持不变）
+    values: [Option<String>; 4],
+    /// 当前输入的字段
+    active: usize,
+    /// 打开后下一次渲染时获取焦点
+    pending_focus: bool,
+    focus_handle: FocusHandle,
+}
+
+impl EventEmitter<TagEditEvent> for TagEditor {}
+
+impl TagEditor {
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        Self {
+            ids: Vec::new(),
+            visible: false,
+            originals: Default::default(),
+            values: Default::default(),
+            active: 0,
+            pending_focus: false,
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    /// 打开编辑弹窗
+    pub fn open(&mut self, items: &[AlbumInfo], cx: &mut Context<Self>) {
+        if items.is_empty() {
+            return;
+        }
+
+        // 所有选中歌曲取值相同时才显示原值
+        let common = |get: &dyn Fn(&AlbumInfo) -> Option<String>| -> Option<String> {
+            let first = get(&items[0]);
+            items[1..]
+                .iter()
+                .all(|item| get(item) == first)
+                .then_some(first)
+                .flatten()
+        };
+        self.originals = [
+            (items.len() == 1).then(|| items[0].title().to_string()),
+            common(&|item| Some(item.artist().to_string())),
+            common(&|item| Some(item.album().to_string())),
+            common(&|item| item.genre().map(|g| g.to_string())),
+        ];
+        self.values = self.originals.clone();
+        self.ids = items.iter().map(|item| item.id()).collect();
+        self.active = if items.len() == 1 { TITLE_FIELD } else { 1 };
+        self.visible = true;
+        self.pending_focus = true;
+        cx.notify();
+    }
+
+    fn close(&mut self, cx: &mut Context<Self>) {
+        self.visible = false;
+        self.ids.clear();
+        cx.notify();
+    }
+
+    /// 多选时标题不可编辑
+    fn is_editable(&self, field: usize) -> bool {
+        field != TITLE_FIELD || self.ids.len() == 1
+    }
+
+    fn focus_next(&mut self, cx: &mut Context<Self>) {
+        loop {
+            self.active = (self.active + 1) % FIELD_LABELS.len();
+            if self.is_editable(self.active) {
+                break;
+            }
+        }
+        cx.notify();
+    }
+
+    fn save(&mut self, cx: &mut Context<Self>) {
+        // 只提交与原值不同的字段
+        let changed = |i: usize| -> Option<String> {
+            self.values[i]
+                .clone()
+                .filter(|v| Some(v) != self.originals[i].as_ref())
+        };
+        let edit = TagEdit {
+            title: changed(0),
+            artist: changed(1),
+            album: changed(2),
+            genre: changed(3),
+        };
+        if !edit.is_empty() {
+            cx.emit(TagEditEvent {
+                ids: self.ids.clone(),
+                edit,
+            });
+        }
+        self.close(cx);
+    }
+
+    fn handle_key(&mut self, evt: &KeyDownEvent, cx: &mut Context<Self>) {
+        match evt.keystroke.key.as_str() {
+            "escape" => self.close(cx),
+            "enter" => self.save(cx),
+            "tab" => self.focus_next(cx),
+            "backspace" => {
+                if let Some(value) = &mut self.values[self.active] {
+                    value.pop();
+                }
+                cx.notify();
+            }
+            _ => {
+                if let Some(key_char) = &evt.keystroke.key_char {
+                    self.values[self.active]
+                        .get_or_insert_with(String::new)
+                        .push_str(key_char);
+                    cx.notify();
+                }
+            }
+        }
+    }
+}
+
+impl Render for TagEditor {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if self.visible && self.pending_focus {
+            window.focus(&self.focus_handle);
+            self.pending_focus = false;
+        }
+
+        let title = if self.ids.len() > 1 {
+            format!("编辑标签（{} 首）", self.ids.len())
+        } else {
+            "编辑标签".to_string()
+        };
+
+        div().id("tag-editor-backdrop").when(self.visible, |this| {
+            this.absolute()
+                .size_full()
+                .top_0()
+                .left_0()
+                .flex()
+                .items_center()
+                .justify_center()
+                .bg(bg_overlay())
+                .occlude()
+                .child(
+                    div()
+                        .id("tag-editor")
+                        .key_context("TagEditor")
+                        .track_focus(&self.focus_handle)
+                        .w(px(420.0))
+                        .p_6()
+                        .flex()
+                        .flex_col()
+                        .gap_3()
+                        .bg(bg_content())
+                        .rounded_xl()
+                        .shadow_lg()
+                        .border_1()
+                        .border_color(border_default())
+                        .on_key_down(cx.listener(|this, evt: &KeyDownEvent, _window, cx| {
+                            this.handle_key(evt, cx);
+                        }))
+                        .child(
+                            div()
+                                .text_lg()
+                                .font_weight(FontWeight::SEMIBOLD)
+                                .text_color(text_primary())
+                                .child(title),
+                        )
+                        .children(FIELD_LABELS.iter().enumerate().map(|(i, label)| {
+                            let editable = self.is_editable(i);
+                            let is_active = editable && self.active == i;
+                            let (text, placeholder) = match &self.values[i] {
+                                Some(value) => (value.clone(), false),
+                                None if !editable => ("多选时不可编辑".to_string(), true),
+                                None => ("多个值（保持不变）".to_string(), true),
+                            };
+                            div()
+                                .flex()
+                                .flex_col()
+                                .gap_1()
+                                .child(div().text_xs().text_color(text_tertiary()).child(*label))
+                                .child(
+                                    div()
+                                        .id(ElementId::Name(format!("tag-field-{}", i).into()))
+                                        .h(px(SEARCH_BOX_HEIGHT))
+                                        .px_3()
+                                        .flex()
+                                        .items_center()
+                                        .bg(bg_input())
+                                        .rounded_lg()
+                                        .border_1()
+                                        .border_color(input_focus_ring(is_active))
+                                        .text_sm()
+                                        .truncate()
+                                        .when(placeholder, |this| {
+                                            this.text_color(text_placeholder())
+                                        })
+                                        .when(!placeholder, |this| {
+                                            this.text_color(text_secondary())
+                                        })
+                                        .when(editable, |this| this.cursor_text())
+                                        .child(text)
+                                        .on_mouse_down(
+                                            MouseButton::Left,
+                                            cx.listener(move |this, _evt, window, cx| {
+                                                if this.is_editable(i) {
+                                                    this.active = i;
+                                                    window.focus(&this.focus_handle);
+                                                    cx.notify();
+                                                }
+                                            }),
+                                        ),
+                                )
+                        }))
+                        .child(
+                            div()
+                                .mt_2()
+                                .flex()
+                                .flex_row()
+                                .justify_end()
+                                .gap_3()
+                                .child(
+                                    div()
+                                        .id("tag-editor-cancel")
+                                        .px_4()
+                                        .py_2()
+                                        .rounded_lg()
+                                        .cursor_pointer()
+                                        .text_sm()
+                                        .text_color(text_secondary())
+                                        .hover(|s| s.bg(bg_active()))
+                                        .child("取消")
+                                        .on_mouse_down(
+                                            MouseButton::Left,
+                                            cx.listener(|this, _evt, _window, cx| {
+                                                this.close(cx);
+                                            }),
+                                        ),
+                                )
+                                .child(
+                                    div()
+                                        .id("tag-editor-save")
+                                        .px_4()
+                                        .py_2()
+                                        .rounded_lg()
+                                        .cursor_pointer()
+                                        .text_sm()
+                                        .font_weight(FontWeight::MEDIUM)
+                                        .text_color(text_primary())
+                                        .bg(accent_blue())
+                                        .hover(|s| s.bg(bg_active()))
+                                        .child("保存")
+                                        .on_mouse_down(
+                                            MouseButton::Left,
+                                            cx.listener(|this, _evt, _window, cx| {
+                                                this.save(cx);
+                                            }),
+                                        ),
+                                ),
+                        ),
+                )
+        })
+    }
+}
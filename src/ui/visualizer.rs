@@ -0,0 +1,66 @@
+use gpui::*;
+use tracing::warn;
+
+use crate::theme::*;
+
+/// 频谱柱之间的间距
+const BAR_GAP: f32 = 2.0;
+
+/// 频谱柱状图：levels 为各频段的高度比例（0.0 ~ 1.0），从低频到高频
+pub fn render_spectrum(levels: Vec<f32>) -> impl IntoElement {
+    canvas(
+        |_bounds, _window, _cx| {},
+        move |bounds, _, window, _cx| {
+            if levels.is_empty() {
+                return;
+            }
+            let width = f32::from(bounds.size.width);
+            let height = f32::from(bounds.size.height);
+            let bar_width = (width / levels.len() as f32 - BAR_GAP).max(1.0);
+            for (index, level) in levels.iter().enumerate() {
+                let bar_height = (level * height).max(1.0);
+                let origin = point(
+                    bounds.origin.x + px(index as f32 * (bar_width + BAR_GAP)),
+                    bounds.origin.y + px(height - bar_height),
+                );
+                window.paint_quad(fill(
+                    Bounds::new(origin, size(px(bar_width), px(bar_height))),
+                    accent_blue(),
+                ));
+            }
+        },
+    )
+    .size_full()
+}
+
+/// 示波器：按时间顺序绘制采样波形（-1.0 ~ 1.0）
+pub fn render_waveform(samples: Vec<f32>) -> impl IntoElement {
+    canvas(
+        |_bounds, _window, _cx| {},
+        move |bounds, _, window, _cx| {
+            if samples.len() < 2 {
+                return;
+            }
+            let width = f32::from(bounds.size.width);
+            let half = f32::from(bounds.size.height) / 2.0;
+            let step = width / (samples.len() - 1) as f32;
+            let at = |index: usize, sample: f32| {
+                point(
+                    bounds.origin.x + px(index as f32 * step),
+                    bounds.origin.y + px(half - sample.clamp(-1.0, 1.0) * half),
+                )
+            };
+
+            let mut builder = PathBuilder::stroke(px(1.5));
+            builder.move_to(at(0, samples[0]));
+            for (index, sample) in samples.iter().enumerate().skip(1) {
+                builder.line_to(at(index, *sample));
+            }
+            match builder.build() {
+                Ok(path) => window.paint_path(path, accent_blue()),
+                Err(e) => warn!("绘制波形失败: {}", e),
+            }
+        },
+    )
+    .size_full()
+}
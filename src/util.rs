@@ -1,7 +1,10 @@
-use std::{path::PathBuf,io::Error};
+use std::{
+    io::Error,
+    path::{Component, Path, PathBuf},
+};
 
 /// 列出指定路径下的所有文件
-pub fn list_file(path: &str) -> Result<Vec<PathBuf>,Error> {
+pub fn list_file(path: &str) -> Result<Vec<PathBuf>, Error> {
     let mut files = Vec::new();
     for entry in std::fs::read_dir(path)? {
         let entry = entry?;
@@ -19,3 +22,29 @@ pub fn format_duration(seconds: u64) -> String {
     let secs = seconds % 60;
     format!("{}:{:02}", mins, secs)
 }
+
+/// 计算 path 相对于 base 目录的相对路径（两者不在同一根下时返回 None）
+pub fn relative_path(path: &Path, base: &Path) -> Option<PathBuf> {
+    let path: Vec<Component> = path.components().collect();
+    let base: Vec<Component> = base.components().collect();
+
+    // 根（盘符）不同则无法相对化
+    if path.first() != base.first() {
+        return None;
+    }
+
+    let common = path
+        .iter()
+        .zip(base.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &path[common..] {
+        relative.push(component);
+    }
+    Some(relative)
+}